@@ -3,6 +3,21 @@ use std::path::Path;
 use std::process::Command;
 
 fn main() {
+    // 0. 记录当前 git commit，供 `--version-json` 输出
+    // 不在 git 仓库中（如从源码包构建）时为 "unknown"
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT_HASH={}", git_commit);
+
     // 1. 获取当前 profile (debug/release)
     // let profile = env::var("PROFILE").unwrap();
 
@@ -1,4 +1,4 @@
-import type { Transaction, FilterOptions, FetchTransactionsRequest, AccountUpdateRequest, HallticketUpdateRequest, AccountCookieResponse } from "./types";
+import type { Transaction, FilterOptions, FetchTransactionsRequest, BatchOp, BatchResponse, AccountUpdateRequest, HallticketUpdateRequest, CredentialsUpdateRequest, AccountCookieResponse } from "./types";
 
 const API_BASE_URL = "/api"; // Assuming the Vite proxy is set up or a relative path works
 
@@ -69,6 +69,18 @@ export const triggerFetchTransactions = async (request: FetchTransactionsRequest
   await handleResponse<void>(response); // Expecting no content on success
 };
 
+// Runs the operations in order, the database only changes if all of them succeed
+export const runBatch = async (ops: BatchOp[]): Promise<BatchResponse> => {
+  const response = await fetch(`${API_BASE_URL}/batch`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify(ops),
+  });
+  return handleResponse<BatchResponse>(response);
+};
+
 // Config APIs
 export const updateAccount = async (request: AccountUpdateRequest): Promise<void> => {
   const response = await fetch(`${API_BASE_URL}/config/account`, {
@@ -92,12 +104,31 @@ export const updateHallticket = async (request: HallticketUpdateRequest): Promis
   await handleResponse<void>(response);
 };
 
+export const updateCredentials = async (request: CredentialsUpdateRequest): Promise<void> => {
+  const response = await fetch(`${API_BASE_URL}/config/credentials`, {
+    method: "PUT",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify(request),
+  });
+  await handleResponse<void>(response);
+};
+
 export const getAccountCookie = async (): Promise<AccountCookieResponse> => {
   const response = await fetch(`${API_BASE_URL}/config/account-cookie`);
   // This endpoint might return 404 which handleResponse will throw as error, this is fine.
   return handleResponse<AccountCookieResponse>(response);
 };
 
+// Merchant APIs
+export const searchMerchants = async (prefix: string, limit?: number): Promise<string[]> => {
+  const searchParams = new URLSearchParams({ q: prefix });
+  if (limit !== undefined) searchParams.append('limit', limit.toString());
+  const response = await fetch(`${API_BASE_URL}/merchants/search?${searchParams.toString()}`);
+  return handleResponse<string[]>(response);
+};
+
 // CSV Export API
 export interface CsvExportParams {
   merchant?: string;
@@ -105,7 +136,8 @@ export interface CsvExportParams {
   max_amount?: number;
   time_start?: string; // YYYY-MM-DD format
   time_end?: string; // YYYY-MM-DD format
-  format?: 'csv' | 'json';
+  notes?: boolean;
+  format?: 'csv' | 'json' | 'xlsx';
 }
 
 export const exportCsv = async (params: CsvExportParams = {}): Promise<Blob> => {
@@ -116,6 +148,7 @@ export const exportCsv = async (params: CsvExportParams = {}): Promise<Blob> =>
   if (params.max_amount !== undefined) searchParams.append('max_amount', params.max_amount.toString());
   if (params.time_start) searchParams.append('time_start', params.time_start);
   if (params.time_end) searchParams.append('time_end', params.time_end);
+  if (params.notes) searchParams.append('notes', 'true');
   if (params.format) searchParams.append('format', params.format);
 
   const response = await fetch(`${API_BASE_URL}/export/csv?${searchParams.toString()}`);
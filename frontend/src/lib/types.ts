@@ -15,6 +15,20 @@ export interface FetchTransactionsRequest {
   start_date: string; // ISO 8601 date string
 }
 
+export type BatchOp =
+  | { op: "delete"; id: number }
+  | { op: "fetch"; start_date: string }; // ISO 8601 date string
+
+export type BatchOpResult =
+  | { status: "ok"; affected: number } // deleted or newly stored records
+  | { status: "failed"; error: string }
+  | { status: "not_applied" }; // rolled back because another operation failed
+
+export interface BatchResponse {
+  committed: boolean; // false means nothing was changed
+  results: BatchOpResult[]; // one per operation, in order
+}
+
 export interface AccountUpdateRequest {
   account: string;
 }
@@ -23,6 +37,11 @@ export interface HallticketUpdateRequest {
   hallticket: string;
 }
 
+export interface CredentialsUpdateRequest {
+  account: string;
+  hallticket: string;
+}
+
 export interface AccountCookieResponse {
   account: string;
   cookie: string;
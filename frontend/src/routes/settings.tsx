@@ -53,26 +53,15 @@ function SettingsPage() {
     setSuccessMessage(null);
 
     try {
-      // Update account
-      const accountRes = await fetch('/api/config/account', {
+      // Update account and hallticket together
+      const credentialsRes = await fetch('/api/config/credentials', {
         method: 'PUT',
         headers: { 'Content-Type': 'application/json' },
-        body: JSON.stringify({ account }),
+        body: JSON.stringify({ account, hallticket }),
       });
-      if (!accountRes.ok) {
-        const errorData = await accountRes.text();
-        throw new Error(`Failed to update account: ${accountRes.statusText} - ${errorData}`);
-      }
-
-      // Update hallticket
-      const hallticketRes = await fetch('/api/config/hallticket', {
-        method: 'PUT',
-        headers: { 'Content-Type': 'application/json' },
-        body: JSON.stringify({ hallticket }),
-      });
-      if (!hallticketRes.ok) {
-        const errorData = await hallticketRes.text();
-        throw new Error(`Failed to update hallticket: ${hallticketRes.statusText} - ${errorData}`);
+      if (!credentialsRes.ok) {
+        const errorData = await credentialsRes.text();
+        throw new Error(`Failed to update credentials: ${credentialsRes.statusText} - ${errorData}`);
       }
 
       setSuccessMessage('Settings updated successfully!');
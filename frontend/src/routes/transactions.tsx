@@ -135,7 +135,7 @@ function TransactionsPage() {
     },
   })
 
-  const handleExportCsv = async () => {
+  const handleExport = async (format: 'csv' | 'xlsx') => {
     try {
       setIsExporting(true)
 
@@ -145,7 +145,7 @@ function TransactionsPage() {
         ?.getFilterValue() as string
       // const timeFilter = table.getColumn('time')?.getFilterValue() as string
 
-      const exportParams: any = {}
+      const exportParams: any = { format }
       if (merchantFilter) {
         exportParams.merchant = merchantFilter
       }
@@ -159,7 +159,7 @@ function TransactionsPage() {
       const url = window.URL.createObjectURL(blob)
       const link = document.createElement('a')
       link.href = url
-      link.download = `transactions_export_${new Date().toISOString().split('T')[0]}.csv`
+      link.download = `transactions_export_${new Date().toISOString().split('T')[0]}.${format}`
       document.body.appendChild(link)
       link.click()
       document.body.removeChild(link)
@@ -212,15 +212,26 @@ function TransactionsPage() {
                 className="max-w-sm"
               />
             </div>
-            <Button
-              onClick={handleExportCsv}
-              disabled={isExporting}
-              variant="outline"
-              size="sm"
-            >
-              <Download className="mr-2 h-4 w-4" />
-              {isExporting ? 'Exporting...' : 'Export CSV'}
-            </Button>
+            <div className="flex items-center space-x-2">
+              <Button
+                onClick={() => handleExport('csv')}
+                disabled={isExporting}
+                variant="outline"
+                size="sm"
+              >
+                <Download className="mr-2 h-4 w-4" />
+                {isExporting ? 'Exporting...' : 'Export CSV'}
+              </Button>
+              <Button
+                onClick={() => handleExport('xlsx')}
+                disabled={isExporting}
+                variant="outline"
+                size="sm"
+              >
+                <Download className="mr-2 h-4 w-4" />
+                {isExporting ? 'Exporting...' : 'Export Excel'}
+              </Button>
+            </div>
           </div>
           <div className="rounded-md border">
             <Table>
@@ -20,7 +20,10 @@
 
 use color_eyre::eyre::Context;
 
-use crate::{libs::transactions::FilterOptions, utils::help_msg::HelpMsg};
+use crate::{
+    config::Config, libs::transactions::FilterOptions, page::fetch::FetchCommand,
+    utils::help_msg::HelpMsg,
+};
 
 /// 应用程序中的所有动作类型
 ///
@@ -35,6 +38,10 @@ pub enum Action {
     Quit,
     /// 触发界面重新渲染
     Render,
+    /// 替换应用程序配置，之后打开的页面使用新配置
+    UpdateConfig(Box<Config>),
+    /// 交给栈顶数据获取页面处理的动作，通常由确认对话框在关闭后发送
+    Fetch(FetchCommand),
 }
 
 /// 应用程序中的所有页面/层级类型
@@ -50,12 +57,28 @@ pub enum Layers {
     Fetch,
     /// 交易记录列表页面，可选的过滤选项
     Transaction(Option<FilterOptions>),
-    /// Cookie输入页面，用于配置认证信息
-    CookieInput,
+    /// Cookie输入页面，用于配置认证信息，可选的提示信息（如会话过期）
+    CookieInput(Option<String>),
     /// 帮助对话框，显示指定的帮助信息
     Help(HelpMsg),
-    /// 数据分析页面
-    Analysis,
+    /// 数据分析页面，可选的过滤选项
+    Analysis(Option<FilterOptions>),
+    /// 设置页面
+    Settings,
+    /// 确认对话框
+    ///
+    /// 按 `y` 确认后发送 `on_confirm`，按 `n` 或 `Esc` 取消后发送 `on_cancel`。
+    /// 对话框会先弹出自身，因此动作由下层页面处理。
+    Confirm {
+        /// 提示文本
+        prompt: String,
+        /// 确认时发送的动作
+        on_confirm: Box<Action>,
+        /// 取消时发送的动作
+        on_cancel: Box<Action>,
+    },
+    /// 错误对话框，显示指定的错误信息
+    Error(String),
 }
 
 impl std::fmt::Display for Layers {
@@ -64,9 +87,12 @@ impl std::fmt::Display for Layers {
             Layers::Home => write!(f, "Home"),
             Layers::Fetch => write!(f, "Fetch"),
             Layers::Transaction(_) => write!(f, "Transaction"),
-            Layers::CookieInput => write!(f, "CookieInput"),
+            Layers::CookieInput(_) => write!(f, "CookieInput"),
             Layers::Help(_) => write!(f, "Help"),
-            Layers::Analysis => write!(f, "Analysis"),
+            Layers::Analysis(_) => write!(f, "Analysis"),
+            Layers::Settings => write!(f, "Settings"),
+            Layers::Confirm { .. } => write!(f, "Confirm"),
+            Layers::Error(_) => write!(f, "Error"),
         }
     }
 }
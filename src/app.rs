@@ -28,20 +28,30 @@
 //! 3. LayerManager处理层级管理动作
 //! 4. 各个页面处理特定的业务动作
 //! 5. 状态更新触发界面重新渲染
+//!
+//! ## 全局快捷键
+//!
+//! `F1` 由 App 直接处理，不经过页面：汇总当前页面堆栈中各层的快捷键
+//! （见 [`Layer::help`](crate::page::Layer::help)），在帮助弹窗中一并列出。
+//!
+//! `Ctrl+C` 和 SIGINT 同样由 App 处理，在任意页面都会正常退出：终端在退出时恢复，
+//! 页面随 App 一同销毁，正在进行的获取也会随之取消。
 
 use crate::{
-    actions::Action,
+    actions::{Action, LayerManageAction, Layers},
     config::Config,
-    libs::transactions::TransactionManager,
-    page::home::Home,
+    libs::transactions::{FilterOptions, TransactionManager},
+    page::{Layer, home::Home},
     tui::{self, TuiEnum},
 };
 use color_eyre::eyre::{Context, Result};
+use crossterm::event::{KeyCode, KeyModifiers};
 use layer_manager::LayerManager;
 use tokio::sync::mpsc;
-use tracing::warn;
+use tracing::{info, warn};
 
 pub(crate) mod layer_manager;
+pub(crate) mod open_spec;
 
 /// 应用程序的根状态
 ///
@@ -78,40 +88,46 @@ impl RootState {
     ///
     /// 返回初始化完成的 `RootState` 实例
     ///
-    /// # Panics
+    /// # 错误
     ///
-    /// 如果数据库连接失败将会 panic
-    pub fn new(config: Config) -> Self {
+    /// 数据库连接失败（如文件损坏或被其他实例锁定）或写入配置失败时返回错误
+    pub fn new(config: Config) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
 
-        let manager = TransactionManager::new(config.config.db_path())
-            .with_context(|| {
-                format!(
-                    "Fail to connect to Database at {}",
-                    config
-                        .config
-                        .db_path()
-                        .as_ref()
-                        .map(|p| p.display().to_string())
-                        .unwrap_or("memory".into())
-                )
-            })
-            .unwrap();
+        let manager = TransactionManager::new(config.config.db_path()).with_context(|| {
+            format!(
+                "Fail to connect to Database at {}",
+                config
+                    .config
+                    .db_path()
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or("memory".into())
+            )
+        })?;
 
         if let Some(account) = &config.fetch.account {
-            manager.update_account(account).unwrap();
+            manager.update_account(account)?;
         }
         if let Some(hallticket) = &config.fetch.hallticket {
-            manager.update_hallticket(hallticket).unwrap();
+            manager.update_hallticket(hallticket)?;
+        }
+        if let Some(days) = config.config.retention_days {
+            let cutoff =
+                chrono::Local::now().fixed_offset() - chrono::Duration::days(days.get().into());
+            let purged = manager
+                .delete_filtered(&FilterOptions::default().end(cutoff))
+                .context("Failed to purge transactions past the retention period")?;
+            info!("Purged {} transactions older than {} days", purged, days);
         }
 
-        Self {
+        Ok(Self {
             should_quit: false,
             action_tx,
             action_rx,
             manager,
             config,
-        }
+        })
     }
 
     /// 发送动作到动作处理系统
@@ -132,14 +148,10 @@ impl RootState {
     ///
     /// * `action` - 要处理的动作
     pub(crate) fn update(&mut self, action: &Action) {
-        // match action {
-        //     Action::Quit => {
-        //         self.should_quit = true;
-        //     }
-        //     _ => {}
-        // }
-        if let Action::Quit = action {
-            self.should_quit = true;
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::UpdateConfig(config) => self.config = config.as_ref().clone(),
+            _ => {}
         }
     }
 }
@@ -177,14 +189,34 @@ impl App {
     ///
     /// 返回配置好的 `App` 实例，默认显示主页
     pub fn new(state: RootState, tui: TuiEnum) -> Self {
+        let mut home = Home::new(state.action_tx.clone().into())
+            .data_dir(state.config.config.data_dir.clone())
+            .manager(state.manager.clone());
+        home.init();
         Self {
-            layer_manager: LayerManager::new(Box::new(Home {
-                tx: state.action_tx.clone().into(),
-            })),
+            layer_manager: LayerManager::new(Box::new(home)),
             state,
             tui,
         }
     }
+
+    /// 在首页之上打开启动页面
+    ///
+    /// 格式见 [`open_spec`]。无效时记录警告并停留在首页。
+    ///
+    /// # 参数
+    ///
+    /// * `spec` - `--open` 参数的值
+    pub fn open(&mut self, spec: &str) {
+        match open_spec::parse_open_spec(spec) {
+            Ok(Layers::Home) => (),
+            Ok(layer) => self.layer_manager.handle_layer_action(
+                &LayerManageAction::Push(layer.into_push_config(false)),
+                &self.state,
+            ),
+            Err(e) => warn!("Ignoring --open \"{}\": {}", spec, e),
+        }
+    }
 }
 
 impl App {
@@ -264,11 +296,28 @@ impl App {
 
             // TODO impl these events
             tui::Event::Error => self.send_action(Action::Quit),
+            tui::Event::Interrupt => self.send_action(Action::Quit),
             tui::Event::FocusGained => (),
             tui::Event::FocusLost => (),
             tui::Event::Init => (),
             tui::Event::Resize(_, _) => self.send_action(Action::Render),
 
+            // raw mode delivers Ctrl+C as a key instead of SIGINT
+            tui::Event::Key(key)
+                if key.code == KeyCode::Char('c')
+                    && key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.send_action(Action::Quit)
+            }
+
+            // shortcut reference card, available on every page
+            tui::Event::Key(key) if key.code == KeyCode::F(1) => match self.layer_manager.help() {
+                Some(help) => self.send_action(LayerManageAction::Push(
+                    Layers::Help(help).into_push_config(true),
+                )),
+                None => self.layer_manager.handle_event(event),
+            },
+
             _ => self.layer_manager.handle_event(event),
         };
     }
@@ -287,6 +336,7 @@ impl App {
             Action::Layer(layer_action) => self
                 .layer_manager
                 .handle_layer_action(layer_action, &self.state),
+            Action::Fetch(_) => self.layer_manager.handle_action(&action),
             _ => {}
         }
         self.state.update(&action);
@@ -304,8 +354,8 @@ pub(super) mod test {
         cli::{ClapSource, Cli},
         libs::fetcher::MealFetcher,
         page::{
-            cookie_input::CookieInput, fetch::Fetch, help_popup::HelpPopup,
-            transactions::Transactions,
+            confirm_popup::ConfirmPopup, cookie_input::CookieInput, fetch::Fetch,
+            help_popup::HelpPopup, transactions::Transactions,
         },
         tui::Event,
         utils::help_msg::HelpEntry,
@@ -324,13 +374,14 @@ pub(super) mod test {
             default_args
         };
         let cli = Cli::parse_from(args);
-        crate::config::Config::new(Some(ClapSource::new(&cli))).expect("Failed to load config")
+        crate::config::Config::from_sources(Some(ClapSource::new(&cli)), None)
+            .expect("Failed to load config")
     }
     #[test]
     fn root_state_set_fetch_config() {
         let config = get_config(vec!["--account", "123456", "--hallticket", "543210"], true);
 
-        let root = RootState::new(config);
+        let root = RootState::new(config).unwrap();
         let (account, cookie) = root.manager.get_account_cookie().unwrap();
         assert_eq!(account, "123456");
         assert_eq!(cookie, "hallticket=543210");
@@ -338,7 +389,7 @@ pub(super) mod test {
 
     pub fn get_app() -> App {
         let config = get_config(vec!["--use-mock-data"], true);
-        let state = RootState::new(config);
+        let state = RootState::new(config).unwrap();
         let app = App::new(state, tui::TestTui::new().into());
         app
     }
@@ -354,7 +405,9 @@ pub(super) mod test {
         app.perform_action(Action::Layer(LayerManageAction::Swap(Layers::Fetch)));
         assert!(app.layer_manager.last().unwrap().is::<Fetch>());
 
-        app.perform_action(Action::Layer(LayerManageAction::Swap(Layers::CookieInput)));
+        app.perform_action(Action::Layer(LayerManageAction::Swap(Layers::CookieInput(
+            None,
+        ))));
         assert!(app.layer_manager.last().unwrap().is::<CookieInput>());
         app.perform_action(Action::Layer(LayerManageAction::Swap(Layers::Help(
             vec![HelpEntry::new('?', "Help")].into(),
@@ -385,6 +438,22 @@ pub(super) mod test {
         assert_eq!(app.state.should_quit, true);
     }
 
+    #[tokio::test]
+    async fn app_quit_on_interrupt() {
+        let mut app = get_app();
+        app.event_loop(tui::Event::Interrupt).unwrap();
+        assert!(app.state.should_quit);
+
+        let mut app = get_app();
+        app.perform_action(Action::Layer(LayerManageAction::Swap(Layers::Fetch)));
+        app.event_loop(tui::Event::Key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        )))
+        .unwrap();
+        assert!(app.state.should_quit);
+    }
+
     #[tokio::test]
     async fn app_quit_due_to_last_layer_pop() {
         let mut app = get_app();
@@ -408,6 +477,41 @@ pub(super) mod test {
         assert!(app.layer_manager.first().unwrap().is::<Home>());
     }
 
+    #[tokio::test]
+    async fn app_open() {
+        let mut app = get_app();
+        app.open("transactions?merchant=超市");
+        assert_eq!(app.layer_manager.len(), 2);
+        assert!(app.layer_manager.first().unwrap().is::<Home>());
+        assert!(app.layer_manager.last().unwrap().is::<Transactions>());
+
+        for spec in ["home", "nowhere", "transactions?start=tomorrow"] {
+            let mut app = get_app();
+            app.open(spec);
+            assert_eq!(app.layer_manager.len(), 1, "{}", spec);
+            assert!(app.layer_manager.last().unwrap().is::<Home>());
+        }
+    }
+
+    #[tokio::test]
+    async fn app_confirm_layer() {
+        let mut app = get_app();
+
+        app.perform_action(Action::Layer(LayerManageAction::Push(
+            Layers::Confirm {
+                prompt: "Open transactions?".to_string(),
+                on_confirm: Box::new(LayerManageAction::Swap(Layers::Transaction(None)).into()),
+                on_cancel: Box::new(Action::Render),
+            }
+            .into_push_config(true),
+        )));
+        assert!(app.layer_manager.last().unwrap().is::<ConfirmPopup>());
+
+        app.event_loop('y'.into()).unwrap();
+        assert_eq!(app.layer_manager.len(), 1);
+        assert!(app.layer_manager.last().unwrap().is::<Transactions>());
+    }
+
     #[tokio::test]
     async fn app_render() {
         let mut app = get_app();
@@ -429,4 +533,55 @@ pub(super) mod test {
         app.perform_action(Action::Render);
         assert_snapshot!(app.tui.backend());
     }
+
+    #[tokio::test]
+    async fn app_page_over_dialog_render() {
+        let mut app = get_app();
+        app.perform_action(Action::Layer(LayerManageAction::Push(
+            Layers::Confirm {
+                prompt: "Resume?".to_string(),
+                on_confirm: Box::new(Action::Render),
+                on_cancel: Box::new(Action::Render),
+            }
+            .into_push_config(true),
+        )));
+        app.perform_action(Action::Layer(LayerManageAction::Push(
+            Layers::CookieInput(None).into_push_config(false),
+        )));
+
+        // neither the dialog nor the home page shows through the cookie page
+        app.perform_action(Action::Render);
+        assert_snapshot!(app.tui.backend());
+
+        // closing it shows the dialog over the home page again
+        app.perform_action(Action::Layer(LayerManageAction::Pop));
+        app.perform_action(Action::Render);
+        assert_snapshot!(app.tui.backend());
+    }
+
+    #[tokio::test]
+    async fn app_shortcut_card() {
+        let mut app = get_app();
+        app.perform_action(Action::Layer(LayerManageAction::Push(
+            Layers::Transaction(None).into_push_config(false),
+        )));
+
+        app.event_loop(KeyCode::F(1).into()).unwrap();
+        let help = app
+            .layer_manager
+            .last()
+            .unwrap()
+            .downcast_ref::<HelpPopup>()
+            .expect("F1 should open the help popup");
+        let keys = help.help_msg().iter().map(|e| e.key()).collect::<Vec<_>>();
+        // shortcuts of the page below are listed too, without duplicates
+        assert_eq!(keys.first().unwrap(), "?");
+        assert!(keys.contains(&"A".to_string()));
+        assert!(keys.contains(&"q".to_string()));
+        assert_eq!(keys.iter().filter(|k| *k == "?").count(), 1);
+
+        // pressing it again closes the card instead of stacking another one
+        app.event_loop(KeyCode::F(1).into()).unwrap();
+        assert!(app.layer_manager.last().unwrap().is::<Transactions>());
+    }
 }
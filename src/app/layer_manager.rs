@@ -4,13 +4,19 @@ use std::{
 };
 
 use crate::{
-    actions::{LayerManageAction, Layers},
-    libs::fetcher::{MealFetcher, MockMealFetcher, RealMealFetcher},
+    actions::{Action, LayerManageAction, Layers},
+    config,
+    libs::{
+        fetcher::{MealFetcher, MockMealFetcher, RealMealFetcher},
+        filter_presets,
+    },
     page::{
-        Layer, analysis::Analysis, cookie_input::CookieInput, fetch::Fetch, help_popup::HelpPopup,
-        home::Home, transactions::Transactions,
+        Layer, analysis::Analysis, confirm_popup::ConfirmPopup, cookie_input::CookieInput,
+        error_popup::ErrorPopup, fetch::Fetch, help_popup::HelpPopup, home::Home,
+        settings::Settings, transactions::Transactions,
     },
     tui::Event,
+    utils::{help_msg::HelpMsg, merchant_normalize::MerchantAliases},
 };
 use ratatui::Frame;
 use tracing::{info, warn};
@@ -104,10 +110,18 @@ impl LayerManager {
         }
     }
 
+    /// Render the layers from the lowest one still shown up to the top
+    ///
+    /// A layer not rendered under the one above also hides everything below it,
+    /// e.g. a full page pushed over a dialog hides the page under the dialog too.
     pub(super) fn render(&mut self, f: &mut Frame) {
-        self.layers
+        let first = self
+            .layers
+            .iter()
+            .rposition(|page| !page.render)
+            .map_or(0, |hidden| hidden + 1);
+        self.layers[first..]
             .iter_mut()
-            .filter(|page| page.render)
             .for_each(|page| page.render(f, f.area()));
     }
 
@@ -120,6 +134,32 @@ impl LayerManager {
         }
     }
 
+    /// Hand a page action to the topmost layer
+    pub(super) fn handle_action(&mut self, action: &Action) {
+        if let Some(layer) = self.layers.last_mut() {
+            layer.handle_action(action);
+        }
+    }
+
+    /// Shortcuts of every layer in the stack for the F1 reference card, topmost first
+    ///
+    /// Keys already listed by a layer above are skipped, as that layer sees them first.
+    /// Returns `None` when a help popup is already on top or nothing is listed.
+    pub(super) fn help(&self) -> Option<HelpMsg> {
+        if self.layers.last()?.is::<HelpPopup>() {
+            return None;
+        }
+        let mut help = HelpMsg::default();
+        for layer in self.layers.iter().rev() {
+            for entry in layer.help().iter() {
+                if !help.iter().any(|e| e.key() == entry.key()) {
+                    help.push(entry.clone());
+                }
+            }
+        }
+        (!help.is_empty()).then_some(help)
+    }
+
     /// Handle LayerManageAction for root app, updating the layer stack
     pub(super) fn handle_layer_action(&mut self, action: &LayerManageAction, state: &RootState) {
         match action {
@@ -158,6 +198,8 @@ impl LayerManager {
                             .expect("Failed to get layer")
                             .into_layer_config(true),
                     );
+                } else {
+                    self.layers.last_mut().unwrap().layer.resume();
                 }
                 self.layers.last_mut().unwrap().render = true;
                 info!(
@@ -171,31 +213,49 @@ impl LayerManager {
     /// Get a new layer based on the given layer type
     fn get_layer(layer: &Layers, state: &RootState) -> Option<BoxedLayer> {
         let mut page = match layer.clone() {
-            Layers::Home => Box::new(Home {
-                tx: state.action_tx.clone().into(),
-            }) as Box<dyn Layer>,
-            Layers::Transaction(filter_opt) => Box::new(Transactions::new(
-                filter_opt,
-                state.action_tx.clone().into(),
-                state.manager.clone(),
-            )),
+            Layers::Home => Box::new(
+                Home::new(state.action_tx.clone().into())
+                    .data_dir(state.config.config.data_dir.clone())
+                    .manager(state.manager.clone()),
+            ) as Box<dyn Layer>,
+            Layers::Transaction(filter_opt) => Box::new(
+                Transactions::new(
+                    filter_opt,
+                    state.action_tx.clone().into(),
+                    state.manager.clone(),
+                )
+                .selected_row_style(&state.config.ui.selection)
+                .min_abs_amount(state.config.ui.min_abs_amount)
+                .spend_alert(state.config.ui.spend_alert)
+                .compact(state.config.ui.compact_table)
+                .wrap_navigation(state.config.ui.wrap_navigation)
+                .presets_path(filter_presets::presets_path(&state.config.config.data_dir)),
+            ),
             Layers::Fetch => Box::new(
-                Fetch::new(state.action_tx.clone().into(), state.manager.clone()).client(
-                    if state.config.fetch.use_mock_data {
+                Fetch::new(state.action_tx.clone().into(), state.manager.clone())
+                    .default_fetch_days(state.config.fetch.default_fetch_days)
+                    .confirm_fetch_pages(state.config.fetch.confirm_fetch_pages)
+                    .merchant(state.config.fetch.merchant.clone())
+                    .conflict_policy(state.config.fetch.conflict_policy)
+                    .fetch_balance(state.config.fetch.fetch_balance)
+                    .client(if state.config.fetch.use_mock_data {
                         MealFetcher::Mock(
                             MockMealFetcher::default()
                                 .set_sim_delay(Duration::from_secs(1))
                                 .per_page(50),
                         )
                     } else {
-                        MealFetcher::Real(RealMealFetcher::default())
-                    },
-                ),
+                        MealFetcher::Real(
+                            RealMealFetcher::default()
+                                .origin(config::get_api_origin())
+                                .proxy(state.config.fetch.proxy.clone()),
+                        )
+                    }),
+            ),
+            Layers::CookieInput(notice) => Box::new(
+                CookieInput::new(state.action_tx.clone().into(), state.manager.clone())
+                    .notice(notice),
             ),
-            Layers::CookieInput => Box::new(CookieInput::new(
-                state.action_tx.clone().into(),
-                state.manager.clone(),
-            )),
             Layers::Help(help_msg) => {
                 let help = HelpPopup::new(state.action_tx.clone().into(), help_msg.clone());
                 match help {
@@ -206,10 +266,36 @@ impl LayerManager {
                     }
                 }
             }
-            Layers::Analysis => Box::new(Analysis::new(
+            Layers::Analysis(filter_opt) => Box::new(
+                Analysis::new(
+                    filter_opt,
+                    state.action_tx.clone().into(),
+                    state.manager.clone(),
+                )
+                .min_abs_amount(state.config.ui.min_abs_amount)
+                .wrap_navigation(state.config.ui.wrap_navigation)
+                .merchant_top_n(state.config.ui.merchant_top_n as usize)
+                .merchant_aliases(MerchantAliases::new(
+                    state.config.ui.merchant_aliases.clone(),
+                )),
+            ),
+            Layers::Settings => Box::new(Settings::new(
                 state.action_tx.clone().into(),
-                state.manager.clone(),
+                state.config.clone(),
             )),
+            Layers::Confirm {
+                prompt,
+                on_confirm,
+                on_cancel,
+            } => Box::new(ConfirmPopup::new(
+                state.action_tx.clone().into(),
+                prompt,
+                *on_confirm,
+                *on_cancel,
+            )),
+            Layers::Error(message) => {
+                Box::new(ErrorPopup::new(state.action_tx.clone().into(), message))
+            }
         };
         page.init();
         Some(page.into())
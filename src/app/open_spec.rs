@@ -0,0 +1,160 @@
+//! # 启动页面
+//!
+//! 解析 `--open` 参数，使界面启动后直接打开指定页面，便于演示、测试和展示终端等场景。
+//!
+//! ## 格式
+//!
+//! `页面[?参数=值&参数=值...]`，页面名称不区分大小写：
+//!
+//! | 页面 | 说明 | 参数 |
+//! |------|------|------|
+//! | `home` | 首页 | - |
+//! | `fetch` | 数据获取页面 | - |
+//! | `transactions` | 交易记录列表 | 筛选参数 |
+//! | `analysis` | 数据分析页面 | 筛选参数 |
+//! | `settings` | 设置页面 | - |
+//!
+//! 筛选参数：
+//! - `merchant`: 商家名称，完全匹配
+//! - `contains`: 商家名称包含的文本
+//! - `start`: 起始日期 `YYYY-MM-DD`，包含
+//! - `end`: 结束日期 `YYYY-MM-DD`，包含当天
+//!
+//! ```bash
+//! ./xjtu-mealflow --open "transactions?merchant=超市"
+//! ./xjtu-mealflow --open "analysis?start=2025-03-01&end=2025-03-31"
+//! ```
+//!
+//! 参数值按原样使用，不做 URL 解码。
+
+use color_eyre::eyre::{Result, bail};
+
+use crate::{
+    actions::Layers,
+    libs::transactions::FilterOptions,
+    utils::date::{parse_day_end, parse_day_start},
+};
+
+/// 解析启动页面
+///
+/// # 参数
+///
+/// * `spec` - `--open` 参数的值
+///
+/// # 返回值
+///
+/// 要打开的页面。页面名称或参数无效时返回错误
+pub(crate) fn parse_open_spec(spec: &str) -> Result<Layers> {
+    let (page, query) = match spec.trim().split_once('?') {
+        Some((page, query)) => (page, Some(query)),
+        None => (spec.trim(), None),
+    };
+    let filter = query.map(parse_filter).transpose()?;
+
+    let layer = match page.to_ascii_lowercase().as_str() {
+        "transactions" => return Ok(Layers::Transaction(filter)),
+        "analysis" => return Ok(Layers::Analysis(filter)),
+        "home" => Layers::Home,
+        "fetch" => Layers::Fetch,
+        "settings" => Layers::Settings,
+        _ => bail!(
+            "Unknown page \"{}\", expected one of home, fetch, transactions, analysis, settings",
+            page
+        ),
+    };
+    if filter.is_some() {
+        bail!("Page \"{}\" takes no parameters", page);
+    }
+    Ok(layer)
+}
+
+/// Filter of the `key=value&...` part of a spec
+fn parse_filter(query: &str) -> Result<FilterOptions> {
+    let mut filter = FilterOptions::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let Some((key, value)) = pair.split_once('=') else {
+            bail!("Invalid parameter \"{}\", expected KEY=VALUE", pair);
+        };
+        if value.is_empty() {
+            bail!("Parameter \"{}\" has no value", key);
+        }
+        filter = match key {
+            "merchant" => filter.merchant(value),
+            "contains" => filter.merchant_contains(value),
+            "start" => filter.start(parse_day_start(value)?),
+            "end" => filter.end(parse_day_end(value)?),
+            _ => bail!(
+                "Unknown parameter \"{}\", expected one of merchant, contains, start, end",
+                key
+            ),
+        };
+    }
+    if let Some((start, end)) = filter.time
+        && start >= end
+    {
+        bail!("Start date is after end date");
+    }
+    Ok(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages() {
+        assert!(matches!(parse_open_spec("home").unwrap(), Layers::Home));
+        assert!(matches!(parse_open_spec(" Fetch ").unwrap(), Layers::Fetch));
+        assert!(matches!(
+            parse_open_spec("settings").unwrap(),
+            Layers::Settings
+        ));
+        assert!(matches!(
+            parse_open_spec("transactions").unwrap(),
+            Layers::Transaction(None)
+        ));
+        assert!(matches!(
+            parse_open_spec("analysis").unwrap(),
+            Layers::Analysis(None)
+        ));
+    }
+
+    #[test]
+    fn filters() {
+        let Layers::Transaction(Some(filter)) =
+            parse_open_spec("transactions?merchant=超市").unwrap()
+        else {
+            panic!("Expected a filtered transaction page");
+        };
+        assert_eq!(filter, FilterOptions::default().merchant("超市"));
+
+        let Layers::Analysis(Some(filter)) =
+            parse_open_spec("analysis?contains=餐厅&start=2025-03-01&end=2025-03-31").unwrap()
+        else {
+            panic!("Expected a filtered analysis page");
+        };
+        assert_eq!(
+            filter,
+            FilterOptions::default()
+                .merchant_contains("餐厅")
+                .start(parse_day_start("2025-03-01").unwrap())
+                .end(parse_day_end("2025-03-31").unwrap())
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        for spec in [
+            "",
+            "transaction",
+            "fetch?merchant=超市",
+            "transactions?merchant",
+            "transactions?merchant=",
+            "transactions?shop=超市",
+            "transactions?start=yesterday",
+            "analysis?start=2025-03-02&end=2025-03-01",
+        ] {
+            assert!(parse_open_spec(spec).is_err(), "{}", spec);
+        }
+    }
+}
@@ -16,13 +16,19 @@
 //! xjtu_mealflow [全局选项] [子命令]
 //! ├── clear-db          # 清理数据库
 //! ├── web              # 启动Web服务器
+//! ├── doctor           # 环境自检
+//! │   └── --online     # 向服务器发送测试请求
+//! ├── debug-info       # 打印诊断信息
 //! └── export-csv       # 导出CSV文件
 //!     ├── --output     # 输出文件路径
 //!     ├── --merchant   # 按商家筛选
 //!     ├── --min-amount # 最小金额
 //!     ├── --max-amount # 最大金额
 //!     ├── --time-start # 开始日期
-//!     └── --time-end   # 结束日期
+//!     ├── --time-end   # 结束日期
+//!     ├── --report     # 导出汇总报表
+//!     ├── --crlf       # 使用 CRLF 换行
+//!     └── --timestamp  # 默认文件名加上导出时间
 //! ```
 //!
 //! ## 配置集成
@@ -30,11 +36,19 @@
 //! CLI参数通过 `ClapSource` 集成到应用程序的配置系统中，
 //! 具有最高的配置优先级。
 
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{ArgAction, Parser, Subcommand};
 use color_eyre::Result;
 use config::Source;
 
-use crate::config::get_data_dir;
+use crate::{
+    config::get_data_dir,
+    libs::{
+        export_csv::{ReportKind, SplitBy},
+        transactions::ConflictPolicy,
+    },
+};
 
 /// XJTU MealFlow 命令行接口
 ///
@@ -87,6 +101,12 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub db_in_mem: bool,
 
+    /// 交易记录保留天数
+    ///
+    /// 启动界面时删除早于此天数的交易，不设置则全部保留
+    #[arg(long, value_name = "DAYS")]
+    pub retention_days: Option<u32>,
+
     /// 校园卡账号
     ///
     /// 用于获取交易记录。可以在 <https://card.xjtu.edu.cn> 获取
@@ -105,6 +125,136 @@ pub struct Cli {
     /// 注意：仍需要设置account或hallticket，但可以是假的占位符
     #[arg(long, default_value_t = false)]
     pub use_mock_data: bool,
+
+    /// 获取页面默认的回溯天数
+    ///
+    /// 为 30、90 或 365 时初始选中对应的预设，其他值预填到自定义日期输入框
+    #[arg(long, value_name = "DAYS")]
+    pub default_fetch_days: Option<u32>,
+
+    /// 预计请求页数超过此值时，获取前先确认
+    ///
+    /// 按回溯天数粗略估计，默认为 50，设为 0 则不确认
+    #[arg(long, value_name = "PAGES")]
+    pub confirm_fetch_pages: Option<u32>,
+
+    /// 获取时只保存商家名称包含此文本的交易
+    ///
+    /// 不区分 ASCII 大小写。仍会下载所有页，只是不保存其他商家的记录
+    #[arg(long, value_name = "MERCHANT")]
+    pub fetch_merchant: Option<String>,
+
+    /// 请求校园卡服务器时使用的代理
+    ///
+    /// 支持 http、https 和 socks5，如 socks5://127.0.0.1:1080。不设置时使用 HTTP_PROXY 等环境变量
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// 获取到与已有记录 id 相同但数据不同的交易时的处理方式
+    ///
+    /// abort 报错并停止写入（默认），skip 保留已有记录，overwrite 以新记录覆盖
+    #[arg(long, value_name = "POLICY")]
+    pub on_conflict: Option<ConflictPolicy>,
+
+    /// 获取交易后一并查询校园卡余额
+    ///
+    /// 余额保存在数据库中，显示在首页和获取页面
+    #[arg(long, default_value_t = false)]
+    pub fetch_balance: bool,
+
+    /// 表格选中行的前景色
+    ///
+    /// 支持颜色名称（如 red）、#RRGGBB 以及 256 色索引
+    #[arg(long, value_name = "COLOR")]
+    pub selection_fg: Option<String>,
+
+    /// 表格选中行的背景色
+    ///
+    /// 格式同 --selection-fg
+    #[arg(long, value_name = "COLOR")]
+    pub selection_bg: Option<String>,
+
+    /// 表格选中行的文字修饰，逗号分隔
+    ///
+    /// 可选值：bold、underline、reversed。默认为 reversed
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    pub selection_modifiers: Option<Vec<String>>,
+
+    /// 隐藏金额绝对值低于此值的交易
+    ///
+    /// 同时作用于交易列表和统计分析，默认为 0，即显示全部交易
+    #[arg(long, value_name = "FLOAT")]
+    pub min_abs_amount: Option<f64>,
+
+    /// 高亮金额绝对值超过此值的交易
+    ///
+    /// 超过阈值的行在交易列表中以红色粗体显示，默认不高亮
+    #[arg(long, value_name = "FLOAT")]
+    pub spend_alert: Option<f64>,
+
+    /// 交易表格使用紧凑布局
+    ///
+    /// 每条记录只占一行，适合较小的终端。也可在交易列表中按 c 切换
+    #[arg(long, default_value_t = false)]
+    pub compact_table: bool,
+
+    /// 在列表首尾停止，而不是循环跳转到另一端
+    ///
+    /// 作用于交易列表的上下移动和分析页的标签切换
+    #[arg(long, default_value_t = false)]
+    pub no_wrap_navigation: bool,
+
+    /// 启动后直接打开的页面，可附带筛选条件
+    ///
+    /// 如 "transactions?merchant=超市"、"analysis?start=2025-03-01"。无效时停留在首页
+    #[arg(long, value_name = "SPEC")]
+    pub open: Option<String>,
+
+    /// 不使用备用屏幕，退出后界面保留在终端中
+    ///
+    /// 便于调试渲染时向上翻看输出
+    #[arg(long, default_value_t = false)]
+    pub no_alt_screen: bool,
+
+    /// 提高日志详细程度，可重复使用
+    ///
+    /// -v 为 debug，-vv 为 trace。指定后忽略 RUST_LOG 等环境变量
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// 只记录警告和错误日志
+    #[arg(short, long, default_value_t = false)]
+    pub quiet: bool,
+
+    /// 商家别名，格式为 ALIAS=CANONICAL，可重复指定
+    ///
+    /// 统计分析时将别名合并为规范名称。名称的首尾空白和全角字符差异会自动忽略
+    #[arg(long, value_name = "ALIAS=CANONICAL")]
+    pub merchant_alias: Option<Vec<String>>,
+
+    /// 商家分析中单独列出的商家数，其余合并为 "Others"
+    ///
+    /// 默认为 20，设为 0 则列出全部商家
+    #[arg(long, value_name = "N")]
+    pub merchant_top_n: Option<u32>,
+
+    /// 打印最终生效的配置并退出
+    ///
+    /// 以 JSON 格式输出合并所有配置源后的结果，用于排查配置优先级问题
+    #[arg(long, default_value_t = false)]
+    pub print_config: bool,
+
+    /// 以 JSON 格式打印版本和构建信息并退出
+    ///
+    /// 包含版本号、构建时的 git commit 和数据目录，便于在反馈问题时附上
+    #[arg(long, default_value_t = false)]
+    pub version_json: bool,
+
+    /// 以单行紧凑格式输出 JSON
+    ///
+    /// 作用于 --print-config 和 --version-json，默认带缩进
+    #[arg(long, default_value_t = false)]
+    pub compact_json: bool,
 }
 
 /// 应用程序子命令
@@ -124,19 +274,69 @@ pub enum Commands {
     /// - 数据库损坏需要重建时
     ClearDb,
 
+    /// 压缩本地数据库
+    ///
+    /// 删除记录后 SQLite 不会自动缩小数据库文件，此命令重建数据库文件以释放空间，
+    /// 并输出压缩前后的文件大小。使用内存数据库时不做任何操作。
+    VacuumDb,
+
+    /// 删除旧的交易记录
+    ///
+    /// 删除早于截止时间的交易并输出删除的条数。
+    Purge {
+        /// 截止时间，早于此时间的交易会被删除
+        ///
+        /// 格式：YYYY-MM-DD（当天 00:00），或 Nd 表示 N 天前，如 180d
+        #[arg(long, value_name = "DATE|Nd")]
+        older_than: String,
+    },
+
+    /// 重新计算所有交易的商家类别
+    ///
+    /// 更新分类规则后运行，使已有记录的类别与新规则一致，并输出类别发生变化的条数。
+    Recategorize,
+
     /// 启动Web服务器模式
     ///
     /// 启动HTTP服务器，提供Web界面和REST API。
     /// 默认监听在 http://localhost:8080
     Web,
 
+    /// 检查运行环境
+    ///
+    /// 检查数据目录是否可写、数据库能否打开、账号和 Cookie 是否存在，
+    /// 并打印检查清单。有检查失败时以非零状态退出。
+    Doctor {
+        /// 额外向校园卡服务器发送一次测试请求
+        ///
+        /// 用于确认 Cookie 仍然有效，服务器返回可解析的数据
+        #[arg(long, default_value_t = false)]
+        online: bool,
+
+        /// 不执行检查，打印获取第一页交易记录的等价 curl 命令
+        ///
+        /// 用于在应用外复现请求，Cookie 默认隐去
+        #[arg(long, default_value_t = false)]
+        print_curl: bool,
+
+        /// 在 --print-curl 的输出中保留 Cookie
+        #[arg(long, default_value_t = false, requires = "print_curl")]
+        include_cookie: bool,
+    },
+
+    /// 打印诊断信息，便于附在问题反馈中
+    ///
+    /// 包括数据库路径、`PRAGMA user_version`、各表行数、商家数、交易时间范围，
+    /// 以及隐去敏感字段后的生效配置。
+    DebugInfo,
+
     /// 导出交易记录为CSV文件
     ///
     /// 支持多种筛选条件和输出格式。
     ExportCsv {
         /// 输出CSV文件路径
         ///
-        /// 如果不指定，默认为 "transactions_export.csv"
+        /// 如果不指定，默认为 "transactions_export.csv"，见 `--timestamp`
         #[arg(short, long, value_name = "FILE_PATH")]
         output: Option<String>,
 
@@ -167,17 +367,76 @@ pub enum Commands {
         #[arg(long, value_name = "DATE")]
         time_start: Option<String>,
 
-        /// 结束日期筛选（不包含）
+        /// 结束日期筛选（包含当天）
         ///
         /// 格式：YYYY-MM-DD
         /// 示例：2023-12-31
         #[arg(long, value_name = "DATE")]
         time_end: Option<String>,
+
+        /// 导出汇总报表而不是逐条交易
+        ///
+        /// merchant：每个商家一行，包含交易笔数和金额合计。
+        /// 未指定输出路径时默认为 "merchant_report.csv"
+        #[arg(long, value_name = "KIND")]
+        report: Option<ReportKind>,
+
+        /// 只导出最近的 N 条交易
+        ///
+        /// 在其他筛选条件之后生效，按时间从新到旧取前 N 条
+        #[arg(long, value_name = "N", conflicts_with = "report")]
+        limit: Option<usize>,
+
+        /// 在末尾追加一行合计：`,,<总金额>,"TOTAL (<条数> rows)"`
+        #[arg(long, conflicts_with = "report")]
+        summary: bool,
+
+        /// 使用 CRLF (`\r\n`) 换行，便于 Windows 上的 Excel 等软件打开
+        ///
+        /// 默认使用 LF (`\n`)
+        #[arg(long)]
+        crlf: bool,
+
+        /// 未指定输出路径时，在默认文件名后加上导出时间
+        ///
+        /// 例如 "transactions_export_20250301_120000.csv"，避免覆盖之前的导出
+        #[arg(long)]
+        timestamp: bool,
+
+        /// 按时间段拆分为多个文件，写入 `--output` 指定的目录
+        ///
+        /// month：每月一个 "transactions_YYYY-MM.csv"。
+        /// 未指定输出路径时默认为 "transactions_export" 目录
+        #[arg(long, value_name = "PERIOD", conflicts_with = "report")]
+        split_by: Option<SplitBy>,
+
+        /// 追加 Note 列，导出在交易列表中添加的备注
+        #[arg(long, conflicts_with = "report")]
+        notes: bool,
     },
 }
 
+impl Cli {
+    /// 由 `--verbose` 与 `--quiet` 决定的日志级别
+    ///
+    /// # 返回值
+    ///
+    /// 两者都未指定时返回 `None`，此时由环境变量决定日志级别
+    pub fn log_level(&self) -> Option<tracing::Level> {
+        match (self.quiet, self.verbose) {
+            (true, _) => Some(tracing::Level::WARN),
+            (false, 0) => None,
+            (false, 1) => Some(tracing::Level::DEBUG),
+            (false, _) => Some(tracing::Level::TRACE),
+        }
+    }
+}
+
 const VERSION_MESSAGE: &str = concat!(env!("CARGO_PKG_VERSION"));
 
+/// 构建时的 git commit，由 build.rs 写入，不在 git 仓库中构建时为 "unknown"
+const GIT_COMMIT_HASH: &str = env!("GIT_COMMIT_HASH");
+
 /// 生成详细的版本信息字符串
 ///
 /// 包含以下信息：
@@ -204,6 +463,24 @@ Data directory: {data_dir_path}"
     )
 }
 
+/// 生成 JSON 格式的版本和构建信息
+///
+/// # 参数
+///
+/// * `pretty` - 是否带缩进，见 [`crate::utils::json`]
+///
+/// # 返回值
+///
+/// 包含 `version`、`git_commit` 和 `data_dir` 字段的 JSON 字符串
+pub fn version_json(pretty: bool) -> String {
+    let info = serde_json::json!({
+        "version": VERSION_MESSAGE,
+        "git_commit": GIT_COMMIT_HASH,
+        "data_dir": get_data_dir().display().to_string(),
+    });
+    crate::utils::json::to_json(&info, pretty).expect("version info should serialize")
+}
+
 /// Clap配置源
 ///
 /// 实现 `config::Source` trait，用于将命令行参数集成到配置系统中。
@@ -212,12 +489,33 @@ Data directory: {data_dir_path}"
 pub(crate) struct ClapSource {
     data_dir: Option<String>,
     db_in_men: bool,
+    retention_days: Option<u32>,
     account: Option<String>,
     hallticket: Option<String>,
     use_mock_data: bool,
+    default_fetch_days: Option<u32>,
+    confirm_fetch_pages: Option<u32>,
+    fetch_merchant: Option<String>,
+    proxy: Option<String>,
+    on_conflict: Option<ConflictPolicy>,
+    fetch_balance: bool,
+    selection_fg: Option<String>,
+    selection_bg: Option<String>,
+    selection_modifiers: Option<Vec<String>>,
+    min_abs_amount: Option<f64>,
+    spend_alert: Option<f64>,
+    compact_table: bool,
+    no_wrap_navigation: bool,
+    merchant_alias: Option<Vec<String>>,
+    merchant_top_n: Option<u32>,
 }
 
 impl ClapSource {
+    /// `--data-dir` 指定的数据目录，用于确定配置文件的位置
+    pub fn data_dir(&self) -> Option<PathBuf> {
+        self.data_dir.as_ref().map(PathBuf::from)
+    }
+
     /// 从CLI参数创建配置源
     ///
     /// # 参数
@@ -231,9 +529,25 @@ impl ClapSource {
         Self {
             data_dir: cli.data_dir.clone(),
             db_in_men: cli.db_in_mem,
+            retention_days: cli.retention_days,
             account: cli.account.clone(),
             hallticket: cli.hallticket.clone(),
             use_mock_data: cli.use_mock_data,
+            default_fetch_days: cli.default_fetch_days,
+            confirm_fetch_pages: cli.confirm_fetch_pages,
+            fetch_merchant: cli.fetch_merchant.clone(),
+            proxy: cli.proxy.clone(),
+            on_conflict: cli.on_conflict,
+            fetch_balance: cli.fetch_balance,
+            selection_fg: cli.selection_fg.clone(),
+            selection_bg: cli.selection_bg.clone(),
+            selection_modifiers: cli.selection_modifiers.clone(),
+            min_abs_amount: cli.min_abs_amount,
+            spend_alert: cli.spend_alert,
+            compact_table: cli.compact_table,
+            no_wrap_navigation: cli.no_wrap_navigation,
+            merchant_alias: cli.merchant_alias.clone(),
+            merchant_top_n: cli.merchant_top_n,
         }
     }
 }
@@ -264,6 +578,10 @@ impl Source for ClapSource {
             config::Value::new(None, self.db_in_men),
         );
 
+        if let Some(days) = self.retention_days {
+            map.insert("retention_days".to_string(), config::Value::new(None, days));
+        }
+
         if self.account.is_some() {
             map.insert(
                 "fetch.account".to_string(),
@@ -281,6 +599,99 @@ impl Source for ClapSource {
             "fetch.use_mock_data".to_string(),
             config::Value::new(None, self.use_mock_data),
         );
+
+        if let Some(days) = self.default_fetch_days {
+            map.insert(
+                "fetch.default_fetch_days".to_string(),
+                config::Value::new(None, days),
+            );
+        }
+        if let Some(pages) = self.confirm_fetch_pages {
+            map.insert(
+                "fetch.confirm_fetch_pages".to_string(),
+                config::Value::new(None, pages),
+            );
+        }
+        if let Some(merchant) = &self.fetch_merchant {
+            map.insert(
+                "fetch.merchant".to_string(),
+                config::Value::new(None, merchant.clone()),
+            );
+        }
+        if let Some(proxy) = &self.proxy {
+            map.insert(
+                "fetch.proxy".to_string(),
+                config::Value::new(None, proxy.clone()),
+            );
+        }
+        if let Some(policy) = self.on_conflict {
+            map.insert(
+                "fetch.conflict_policy".to_string(),
+                config::Value::new(None, policy.to_string()),
+            );
+        }
+        if self.fetch_balance {
+            map.insert(
+                "fetch.fetch_balance".to_string(),
+                config::Value::new(None, true),
+            );
+        }
+
+        if self.selection_fg.is_some() {
+            map.insert(
+                "ui.selection.fg".to_string(),
+                config::Value::new(None, self.selection_fg.clone()),
+            );
+        }
+        if self.selection_bg.is_some() {
+            map.insert(
+                "ui.selection.bg".to_string(),
+                config::Value::new(None, self.selection_bg.clone()),
+            );
+        }
+        if let Some(modifiers) = &self.selection_modifiers {
+            map.insert(
+                "ui.selection.modifiers".to_string(),
+                config::Value::new(None, modifiers.clone()),
+            );
+        }
+        if let Some(min_abs_amount) = self.min_abs_amount {
+            map.insert(
+                "ui.min_abs_amount".to_string(),
+                config::Value::new(None, min_abs_amount),
+            );
+        }
+        if let Some(spend_alert) = self.spend_alert {
+            map.insert(
+                "ui.spend_alert".to_string(),
+                config::Value::new(None, spend_alert),
+            );
+        }
+        // only when set, so that the flag does not override a config file
+        if self.compact_table {
+            map.insert(
+                "ui.compact_table".to_string(),
+                config::Value::new(None, true),
+            );
+        }
+        if self.no_wrap_navigation {
+            map.insert(
+                "ui.wrap_navigation".to_string(),
+                config::Value::new(None, false),
+            );
+        }
+        if let Some(aliases) = &self.merchant_alias {
+            map.insert(
+                "ui.merchant_aliases".to_string(),
+                config::Value::new(None, aliases.clone()),
+            );
+        }
+        if let Some(top_n) = self.merchant_top_n {
+            map.insert(
+                "ui.merchant_top_n".to_string(),
+                config::Value::new(None, top_n),
+            );
+        }
         Ok(map)
     }
 }
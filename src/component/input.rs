@@ -170,6 +170,7 @@
 //!
 //! ### 粘贴支持
 //! 组件支持文本粘贴事件，在自动提交模式下会立即触发提交。
+//! 粘贴的多行文本会被合并为单行：每行去除首尾空白后以空格连接，制表符替换为空格。
 //!
 //! ### 状态查询
 //! ```rust
@@ -182,11 +183,43 @@
 //!
 //! ### 文本重置
 //! 在取消输入时，组件会自动恢复到初始文本值。
+//!
+//! ### 输入历史
+//! 通过 `with_history(true)` 启用后，每次提交的内容会被记录（最多保留
+//! [`HISTORY_CAPACITY`] 条），在输入模式下可使用 ↑/↓ 键回溯之前提交的内容：
+//!
+//! ```rust
+//! let input = InputComp::new()
+//!     .title("Custom Start Date")
+//!     .with_history(true);
+//! ```
+//!
+//! ### 长度限制
+//! 通过 `max_len(n)` 限制输入的字符数。达到上限后继续输入的字符会被忽略，
+//! 粘贴的文本会被截断到剩余长度；输入框达到上限时右下角会以灰色显示 `n/n` 作为提示：
+//!
+//! ```rust
+//! let input = InputComp::new()
+//!     .title("Hallticket")
+//!     .max_len(4096);
+//! ```
+//!
+//! ### 输入格式化
+//! 通过 `formatter(f)` 在输入时整理文本，如日期输入框自动插入 `-`。
+//! 输入字符或粘贴使文本变长后，组件用 `f` 的结果替换文本；结果不同时光标移到末尾。
+//! 删除字符时不调用 `f`，以免刚删除的分隔符又被补回：
+//!
+//! ```rust
+//! let input = InputComp::new()
+//!     .title("Custom Start Date")
+//!     .formatter(format_date_input);
+//! ```
 
 use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
     style::{Color, Style},
+    text::Line,
     widgets::{Block, BorderType, Borders, Paragraph},
 };
 use tui_input::{Input, backend::crossterm::EventHandler};
@@ -201,6 +234,9 @@ use crate::{
     },
 };
 
+/// Max number of submitted values kept in the input history
+pub(crate) const HISTORY_CAPACITY: usize = 20;
+
 #[derive(Clone, Debug)]
 /// A input Component
 
@@ -212,6 +248,12 @@ pub(crate) struct InputComp {
 
     auto_submit: bool,
     control_keys: InputCompCtrlKeys,
+
+    history: Option<InputHistory>,
+    /// Max number of chars accepted, `None` for unlimited
+    max_len: Option<usize>,
+    /// Applied to the text whenever typing or pasting makes it longer
+    formatter: Option<fn(&str) -> String>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -239,6 +281,63 @@ impl Default for InputCompCtrlKeys {
     }
 }
 
+/// Previously submitted values of an [`InputComp`]
+#[derive(Clone, Debug, Default)]
+struct InputHistory {
+    entries: Vec<String>,
+    /// Index of the entry currently recalled, `None` if not browsing
+    cursor: Option<usize>,
+    /// Text in the input before browsing started, restored when going past the newest entry
+    draft: String,
+}
+
+impl InputHistory {
+    fn push(&mut self, value: &str) {
+        self.reset_cursor();
+        if value.is_empty() || self.entries.last().is_some_and(|last| last == value) {
+            return;
+        }
+        self.entries.push(value.to_string());
+        if self.entries.len() > HISTORY_CAPACITY {
+            self.entries.remove(0);
+        }
+    }
+
+    fn prev(&mut self, current: &str) -> Option<&str> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let idx = match self.cursor {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(i) => i.saturating_sub(1),
+        };
+        self.cursor = Some(idx);
+        Some(&self.entries[idx])
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        match self.cursor {
+            None => None,
+            Some(i) if i + 1 < self.entries.len() => {
+                self.cursor = Some(i + 1);
+                Some(&self.entries[i + 1])
+            }
+            Some(_) => {
+                self.cursor = None;
+                Some(&self.draft)
+            }
+        }
+    }
+
+    fn reset_cursor(&mut self) {
+        self.cursor = None;
+        self.draft.clear();
+    }
+}
+
 impl InputComp {
     pub fn new() -> Self {
         Self {
@@ -247,6 +346,9 @@ impl InputComp {
             title: Default::default(),
             auto_submit: false,
             control_keys: Default::default(),
+            history: None,
+            max_len: None,
+            formatter: None,
         }
     }
 
@@ -287,6 +389,30 @@ impl InputComp {
         }
     }
 
+    /// Enable recalling previously submitted values with Up/Down
+    pub fn with_history(self, b: bool) -> Self {
+        Self {
+            history: b.then(InputHistory::default),
+            ..self
+        }
+    }
+
+    /// Reject typed chars and truncate pasted text beyond `len` chars
+    pub fn max_len(self, len: usize) -> Self {
+        Self {
+            max_len: Some(len),
+            ..self
+        }
+    }
+
+    /// Reformat the text with `formatter` as it is typed, e.g. to insert separators
+    pub fn formatter(self, formatter: fn(&str) -> String) -> Self {
+        Self {
+            formatter: Some(formatter),
+            ..self
+        }
+    }
+
     pub fn get_help_msg(&self) -> HelpMsg {
         let mut msg = HelpMsg::default();
         match self.mode {
@@ -313,6 +439,9 @@ impl InputComp {
                         "submit input",
                     ));
                 }
+                if self.history.is_some() {
+                    msg.push(HelpEntry::new_plain("↑↓", "input history"));
+                }
             }
         };
         msg
@@ -340,15 +469,33 @@ impl InputComp {
             InputMode::Inputting => match event {
                 Event::Key(key) => {
                     if self.control_keys.submit_keys.contains(&(*key).into()) {
+                        if let Some(history) = self.history.as_mut() {
+                            history.push(self.input.value());
+                        }
                         output_string = Some(self.input.value().to_string());
                         self.mode = InputMode::Focused;
                         status.consumed();
                     } else if self.control_keys.exit_keys.contains(&(*key).into()) {
+                        if let Some(history) = self.history.as_mut() {
+                            history.reset_cursor();
+                        }
                         self.input.reset();
                         self.mode = InputMode::Focused;
                         status.consumed();
+                    } else if let Some(recalled) = self.recall_history(key.code) {
+                        self.input = std::mem::take(&mut self.input).with_value(recalled);
+                        if self.auto_submit {
+                            output_string = Some(self.input.value().to_string());
+                        }
+                        status.consumed();
                     } else {
+                        let before = self.input.clone();
                         self.input.handle_event(&crossterm::event::Event::Key(*key));
+                        if self.remaining().is_some_and(|n| n < 0) {
+                            self.input = before;
+                        } else if self.input.value().len() > before.value().len() {
+                            self.apply_formatter();
+                        }
                         if self.auto_submit {
                             output_string = Some(self.input.value().to_string());
                         }
@@ -356,9 +503,15 @@ impl InputComp {
                     }
                 }
                 Event::Paste(s) => {
-                    s.chars().for_each(|c| {
+                    let pasted = sanitize_paste(s);
+                    let take = match self.remaining() {
+                        Some(n) => n.max(0) as usize,
+                        None => usize::MAX,
+                    };
+                    pasted.chars().take(take).for_each(|c| {
                         self.input.handle(tui_input::InputRequest::InsertChar(c));
                     });
+                    self.apply_formatter();
                     if self.auto_submit {
                         output_string = Some(self.input.value().to_string());
                     }
@@ -370,6 +523,41 @@ impl InputComp {
         (status, output_string)
     }
 
+    /// Replace the text with its formatted version, leaving the cursor alone if unchanged
+    fn apply_formatter(&mut self) {
+        if let Some(formatter) = self.formatter {
+            let formatted = formatter(self.input.value());
+            if formatted != self.input.value() {
+                self.input = std::mem::take(&mut self.input).with_value(formatted);
+            }
+        }
+    }
+
+    /// Chars left before reaching `max_len`, negative if already over it
+    fn remaining(&self) -> Option<isize> {
+        self.max_len
+            .map(|max| max as isize - self.input.value().chars().count() as isize)
+    }
+
+    /// Get the history entry to show for an Up/Down key, if history is enabled
+    fn recall_history(&mut self, code: KeyCode) -> Option<String> {
+        let history = self.history.as_mut()?;
+        match code {
+            KeyCode::Up => history.prev(self.input.value()).map(str::to_string),
+            KeyCode::Down => history.next().map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// Replace the text, e.g. with a value picked outside the input
+    pub fn set_text<T: Into<String>>(&mut self, text: T) {
+        self.input = Input::new(text.into());
+    }
+
+    pub fn get_text(&self) -> String {
+        self.input.value().to_string()
+    }
+
     pub fn set_mode(&mut self, mode: InputMode) {
         self.mode = mode;
     }
@@ -378,6 +566,20 @@ impl InputComp {
     }
 }
 
+/// Flatten pasted text into a single line
+///
+/// Each line is trimmed and non-empty lines are joined with a space, tabs are
+/// replaced with spaces, so that a multi-line blob (e.g. a whole cookie header)
+/// does not end up as raw control characters in the single-line input.
+fn sanitize_paste(s: &str) -> String {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('\t', " ")
+}
+
 impl WidgetExt for InputComp {
     fn render(&mut self, frame: &mut Frame, area: ratatui::prelude::Rect) {
         let width = area.width.max(3) - 3;
@@ -388,17 +590,25 @@ impl WidgetExt for InputComp {
             InputMode::Idle => Style::default(),
         };
 
+        let mut block = match &self.title {
+            Some(title) => Block::default().title(title.as_str()),
+            None => Block::default(),
+        }
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+        if let Some(max) = self.max_len
+            && self.remaining().is_some_and(|n| n <= 0)
+        {
+            // hint that further input is dropped
+            block = block.title_bottom(
+                Line::styled(format!("{}/{}", max, max), Color::DarkGray).right_aligned(),
+            );
+        }
+
         let input_widget = Paragraph::new(self.input.value())
             .style(style)
             .scroll((0, scroll as u16))
-            .block(
-                match &self.title {
-                    Some(title) => Block::default().title(title.as_str()),
-                    None => Block::default(),
-                }
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded),
-            );
+            .block(block);
         frame.render_widget(input_widget, area);
 
         if matches!(self.mode, InputMode::Inputting) {
@@ -409,12 +619,6 @@ impl WidgetExt for InputComp {
         }
     }
 }
-#[cfg(test)]
-impl InputComp {
-    pub fn get_text(&self) -> String {
-        self.input.value().to_string()
-    }
-}
 
 #[cfg(test)]
 pub mod test {
@@ -522,6 +726,79 @@ pub mod test {
         assert_eq!(input.handle_seq(seq.to_vec()), Some("acccb".to_string()));
     }
 
+    #[test]
+    fn test_input_paste_multiline() {
+        let mut input = get_input(false);
+        input.set_mode(InputMode::Focused);
+
+        let seq = [
+            KeyCode::Enter.into(),
+            Event::Paste("  hallticket=abc\r\n\n\tpath=/\n".into()),
+            KeyCode::Enter.into(),
+        ];
+        assert_eq!(
+            input.handle_seq(seq.to_vec()),
+            Some("hallticket=abc path=/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_input_paste_max_len() {
+        let mut input = get_input(false).max_len(5);
+        input.set_mode(InputMode::Focused);
+
+        let seq = [
+            KeyCode::Enter.into(),
+            'a'.into(),
+            Event::Paste("bcdefgh".into()),
+            // typing at the limit is ignored, editing still works
+            'x'.into(),
+            KeyCode::Backspace.into(),
+            'y'.into(),
+            KeyCode::Enter.into(),
+        ];
+        assert_eq!(input.handle_seq(seq.to_vec()), Some("abcdy".to_string()));
+
+        // nothing fits in a full input
+        let seq = [
+            KeyCode::Enter.into(),
+            Event::Paste("zz".into()),
+            KeyCode::Enter.into(),
+        ];
+        assert_eq!(input.handle_seq(seq.to_vec()), Some("abcdy".to_string()));
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+        terminal.draw(|f| input.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_input_formatter() {
+        let mut input = get_input(true).formatter(crate::utils::date::format_date_input);
+        input.set_mode(InputMode::Focused);
+
+        let mut seq: Vec<Event> = vec![KeyCode::Enter.into()];
+        seq.extend("20240901".chars().map(Event::from));
+        assert_eq!(input.handle_seq(seq), Some("2024-09-01".to_string()));
+
+        // deleting is left alone, so the dash can be removed
+        let seq = vec![KeyCode::Backspace.into(); 3];
+        assert_eq!(input.handle_seq(seq), Some("2024-09".to_string()));
+        let seq = vec![KeyCode::Backspace.into(); 3];
+        assert_eq!(input.handle_seq(seq), Some("2024".to_string()));
+
+        input.set_text("");
+        let output = input.handle_seq(vec![Event::Paste("20250301".to_string())]);
+        assert_eq!(output, Some("2025-03-01".to_string()));
+
+        // inputs without a formatter are unaffected
+        let mut input = get_input(true);
+        input.set_mode(InputMode::Focused);
+        let mut seq: Vec<Event> = vec![KeyCode::Enter.into()];
+        seq.extend("20240901".chars().map(Event::from));
+        assert_eq!(input.handle_seq(seq), Some("20240901".to_string()));
+    }
+
     #[test]
     fn test_input_paste_auto_commit() {
         let mut input = get_input(true);
@@ -532,6 +809,81 @@ pub mod test {
         assert_eq!(input.handle_seq(seq.to_vec()), Some("ccc".to_string()));
     }
 
+    #[test]
+    fn test_input_history() {
+        let mut input = get_input(false).with_history(true);
+        input.set_mode(InputMode::Focused);
+
+        input.handle_seq(vec![
+            KeyCode::Enter.into(),
+            'a'.into(),
+            KeyCode::Enter.into(),
+        ]);
+        input.handle_seq(vec![
+            KeyCode::Enter.into(),
+            KeyCode::Backspace.into(),
+            'b'.into(),
+            KeyCode::Enter.into(),
+        ]);
+
+        // browse back through history, stopping at the oldest entry
+        input.handle_seq(vec![
+            KeyCode::Enter.into(),
+            KeyCode::Backspace.into(),
+            'c'.into(),
+        ]);
+        input.handle_seq(vec![KeyCode::Up.into()]);
+        assert_eq!(input.get_text(), "b");
+        input.handle_seq(vec![KeyCode::Up.into(), KeyCode::Up.into()]);
+        assert_eq!(input.get_text(), "a");
+
+        // going past the newest entry restores the draft
+        input.handle_seq(vec![KeyCode::Down.into()]);
+        assert_eq!(input.get_text(), "b");
+        input.handle_seq(vec![KeyCode::Down.into()]);
+        assert_eq!(input.get_text(), "c");
+
+        let output = input.handle_seq(vec![KeyCode::Up.into(), KeyCode::Enter.into()]);
+        assert_eq!(output, Some("b".to_string()));
+        // submitting the same value again is not duplicated
+        assert_eq!(input.history.as_ref().unwrap().entries, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_input_history_capacity() {
+        let mut input = get_input(false).with_history(true);
+        input.set_mode(InputMode::Focused);
+
+        for i in 0..HISTORY_CAPACITY + 5 {
+            input.handle_seq(vec![
+                // clear the previous value before pasting a new one
+                KeyCode::Enter.into(),
+                KeyCode::Esc.into(),
+                KeyCode::Enter.into(),
+                Event::Paste(i.to_string()),
+                KeyCode::Enter.into(),
+            ]);
+        }
+        let entries = &input.history.as_ref().unwrap().entries;
+        assert_eq!(entries.len(), HISTORY_CAPACITY);
+        assert_eq!(entries.last().unwrap(), &(HISTORY_CAPACITY + 4).to_string());
+    }
+
+    #[test]
+    fn test_input_without_history() {
+        let mut input = get_input(false);
+        input.set_mode(InputMode::Focused);
+
+        input.handle_seq(vec![
+            KeyCode::Enter.into(),
+            'a'.into(),
+            KeyCode::Enter.into(),
+        ]);
+        input.handle_seq(vec![KeyCode::Enter.into(), KeyCode::Up.into()]);
+        assert_eq!(input.get_text(), "a");
+        assert!(input.history.is_none());
+    }
+
     fn get_buffer_color(t: &Terminal<TestBackend>) -> Color {
         let cell = t
             .backend()
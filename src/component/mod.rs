@@ -13,6 +13,8 @@
 //! ## 组件列表
 //!
 //! - [`input`]: 输入组件，提供文本输入和编辑功能
+//! - [`table`]: 表格组件，提供可滚动、可选中的列表
+//! - [`scroll_list`]: 滚动列表组件，统一列表的滚动按键和滚动条，内容由页面绘制
 //!
 //! ## 使用示例
 //!
@@ -35,3 +37,13 @@
 ///
 /// 提供文本输入和编辑功能，支持多种输入模式和自定义配置。
 pub(crate) mod input;
+
+/// 表格组件模块
+///
+/// 提供带斑马纹、选中高亮和滚动条的列表表格，列宽按内容自动计算。
+pub(crate) mod table;
+
+/// 滚动列表组件模块
+///
+/// 记录滚动位置和选中项，处理 `j`/`k`/`g`/`G` 按键并绘制滚动条。
+pub(crate) mod scroll_list;
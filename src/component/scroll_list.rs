@@ -0,0 +1,257 @@
+//! # 滚动列表组件模块
+//!
+//! 记录列表的滚动位置（以及可选的选中项），统一处理 `j`/`k`/`g`/`G` 按键，并绘制滚动条。
+//! 列表内容由页面自行绘制：渲染时通过 [`ScrollList::visible`] 取得当前可见的条目范围。
+//!
+//! ## 两种模式
+//!
+//! - **选中模式**（默认）: 按键移动选中项，滚动位置随之调整，保证选中项可见
+//! - **滚动模式**（[`ScrollList::scroll_only`]）: 没有选中项，按键直接滚动列表
+//!
+//! ## 键盘快捷键
+//!
+//! | 按键 | 功能 |
+//! |------|------|
+//! | `j`/`↓` | 下一项 |
+//! | `k`/`↑` | 上一项 |
+//! | `g` | 第一项 |
+//! | `G` | 最后一项 |
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::component::scroll_list::ScrollList;
+//!
+//! let mut list = ScrollList::new(items.len());
+//!
+//! // 事件处理
+//! let status = list.handle_events(&event);
+//!
+//! // 渲染：每项占一行时，可见条目数即区域高度
+//! let range = list.visible(area.height as usize);
+//! // ……绘制 items[range]，高亮 list.selected() ……
+//! list.render_scrollbar(frame, area);
+//! ```
+
+use std::ops::Range;
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState},
+};
+
+use crate::{app::layer_manager::EventHandlingStatus, tui::Event};
+
+/// 可滚动列表的状态
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ScrollList {
+    len: usize,
+    /// First visible item
+    offset: usize,
+    /// Highlighted item, `None` for lists that only scroll or have no items
+    selected: Option<usize>,
+    /// Whether the keys move a selection or scroll the list
+    selectable: bool,
+    /// Items that fit in the last rendered area, 0 before the first render
+    viewport: usize,
+}
+
+impl ScrollList {
+    /// 创建选中模式的列表，选中第一项
+    ///
+    /// # 参数
+    ///
+    /// * `len` - 条目数量
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            selected: (len > 0).then_some(0),
+            selectable: true,
+            ..Default::default()
+        }
+    }
+
+    /// Only scroll the list, without a selected item
+    pub fn scroll_only(self) -> Self {
+        Self {
+            selected: None,
+            selectable: false,
+            ..self
+        }
+    }
+
+    /// Change the number of items, keeping the position where it is still valid
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        if self.selectable {
+            self.selected = len
+                .checked_sub(1)
+                .map(|last| self.selected.unwrap_or(0).min(last));
+        }
+        self.offset = self.offset.min(self.max_offset());
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    #[cfg(test)]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Largest offset that still fills the viewport, or shows the last item before a render
+    fn max_offset(&self) -> usize {
+        match self.viewport {
+            0 => self.len.saturating_sub(1),
+            viewport => self.len.saturating_sub(viewport),
+        }
+    }
+
+    /// Move the selection, or the first visible item when only scrolling, to `index`
+    fn go_to(&mut self, index: usize) {
+        if self.selectable {
+            self.selected = self.len.checked_sub(1).map(|last| index.min(last));
+        } else {
+            self.offset = index.min(self.max_offset());
+        }
+    }
+
+    /// 下一项
+    pub fn next(&mut self) {
+        self.go_to(self.selected.unwrap_or(self.offset).saturating_add(1));
+    }
+
+    /// 上一项
+    pub fn previous(&mut self) {
+        self.go_to(self.selected.unwrap_or(self.offset).saturating_sub(1));
+    }
+
+    /// 第一项
+    pub fn first(&mut self) {
+        self.go_to(0);
+        self.offset = 0;
+    }
+
+    /// 最后一项
+    pub fn last(&mut self) {
+        self.go_to(usize::MAX);
+    }
+
+    /// 处理 `j`/`k`/`g`/`G` 及方向键
+    ///
+    /// # 返回值
+    ///
+    /// 处理了按键时返回 [`EventHandlingStatus::Consumed`]
+    pub fn handle_events(&mut self, event: &Event) -> EventHandlingStatus {
+        let mut status = EventHandlingStatus::default();
+        let Event::Key(key) = event else {
+            return status;
+        };
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char('g') => self.first(),
+            KeyCode::Char('G') => self.last(),
+            _ => return status,
+        }
+        status.consumed();
+        status
+    }
+
+    /// 渲染前调用，取得可见的条目范围
+    ///
+    /// # 参数
+    ///
+    /// * `viewport` - 可显示的条目数
+    ///
+    /// # 返回值
+    ///
+    /// 可见条目的下标范围，选中模式下包含选中项
+    pub fn visible(&mut self, viewport: usize) -> Range<usize> {
+        self.viewport = viewport;
+        if let Some(selected) = self.selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if viewport > 0 && selected >= self.offset + viewport {
+                self.offset = selected + 1 - viewport;
+            }
+        }
+        self.offset = self.offset.min(self.max_offset());
+        self.offset..(self.offset + viewport).min(self.len)
+    }
+
+    /// 在 `area` 右侧绘制滚动条，所有条目都可见时不绘制
+    pub fn render_scrollbar(&self, frame: &mut Frame, area: Rect) {
+        if self.len <= self.viewport {
+            return;
+        }
+        let mut state = ScrollbarState::new(self.max_offset())
+            .position(self.offset)
+            .viewport_content_length(self.viewport);
+        frame.render_stateful_widget(
+            Scrollbar::default().orientation(ScrollbarOrientation::VerticalRight),
+            area,
+            &mut state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection() {
+        let mut list = ScrollList::new(5);
+        assert_eq!(list.selected(), Some(0));
+        assert_eq!(list.visible(2), 0..2);
+
+        list.last();
+        assert_eq!(list.selected(), Some(4));
+        assert_eq!(list.visible(2), 3..5);
+        list.next();
+        assert_eq!(list.selected(), Some(4), "Should stop at the last item");
+
+        list.previous();
+        list.previous();
+        assert_eq!(list.visible(2), 2..4);
+        list.first();
+        assert_eq!(list.visible(2), 0..2);
+
+        list.set_len(1);
+        assert_eq!(list.selected(), Some(0));
+        list.set_len(0);
+        assert_eq!(list.selected(), None);
+        assert_eq!(list.visible(2), 0..0);
+    }
+
+    #[test]
+    fn scroll_only() {
+        let mut list = ScrollList::new(5).scroll_only();
+        assert_eq!(list.selected(), None);
+        for key in ['j', 'j', 'j', 'j', 'j'] {
+            assert!(matches!(
+                list.handle_events(&key.into()),
+                EventHandlingStatus::Consumed
+            ));
+        }
+        // before the first render only the last item is known to be the end
+        assert_eq!(list.offset(), 4);
+        assert_eq!(list.visible(2), 3..5);
+
+        let _ = list.handle_events(&'k'.into());
+        assert_eq!(list.visible(2), 2..4);
+        let _ = list.handle_events(&'G'.into());
+        assert_eq!(list.offset(), 3);
+        let _ = list.handle_events(&'g'.into());
+        assert_eq!(list.offset(), 0);
+
+        assert!(matches!(
+            list.handle_events(&'x'.into()),
+            EventHandlingStatus::ShouldPropagate
+        ));
+    }
+}
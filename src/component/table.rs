@@ -0,0 +1,443 @@
+//! # 表格组件模块
+//!
+//! 提供可重用的列表表格组件，封装了表头样式、斑马纹行、选中行高亮、滚动条和列宽计算。
+//! 页面只需给出列定义和将一条数据格式化为各列文本的函数。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::component::table::{Column, TableComp};
+//!
+//! let mut table = TableComp::new(
+//!     vec![Column::new("金额").fixed().right(), Column::new("商家")],
+//!     |t: &Transaction| vec![format_amount(t.amount), t.merchant.clone()],
+//! );
+//! table.set_items(transactions);
+//!
+//! // 渲染时可以在斑马纹的基础上调整行样式
+//! table.render(frame, area, |index, _| TableComp::<Transaction>::stripe_style(index));
+//! ```
+//!
+//! ## 紧凑模式
+//!
+//! 默认每行高 3 行（上下各留一行空白）。紧凑模式下每行只占 1 行，
+//! 小终端上可以显示约三倍的记录。
+//!
+//! ## 列宽
+//!
+//! 每列宽度取表头和所有单元格中最宽者（按 CJK 宽度计算）再加 2。
+//! 固定列（[`Column::fixed`]）使用该宽度，其余列以该宽度为下限并平分剩余空间。
+//! 通过 [`TableComp::set_format_row`] 更换格式化函数时会重新计算列宽。
+
+use std::cmp::max;
+
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Margin, Rect},
+    style::{Color, Style, Stylize, palette::tailwind},
+    text::Text,
+    widgets::{
+        Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table,
+        TableState,
+    },
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::SelectionStyle;
+
+/// 表格配色
+pub(crate) struct TableColors {
+    pub buffer_bg: Color,
+    pub header_bg: Color,
+    pub header_fg: Color,
+    pub row_fg: Color,
+    pub normal_row_color: Color,
+    pub alt_row_color: Color,
+}
+
+pub(crate) const TABLE_COLORS: TableColors = TableColors {
+    buffer_bg: Color::Reset,
+    header_bg: tailwind::INDIGO.c950,
+    header_fg: tailwind::GRAY.c100,
+    row_fg: tailwind::GRAY.c200,
+    normal_row_color: Color::Reset,
+    alt_row_color: tailwind::GRAY.c950,
+};
+
+/// Row height with blank padding above and below
+const ITEM_HEIGHT: usize = 3;
+/// Row height in compact mode
+const COMPACT_ITEM_HEIGHT: usize = 1;
+
+/// 表格的一列
+#[derive(Clone, Debug)]
+pub(crate) struct Column {
+    header: &'static str,
+    alignment: Alignment,
+    fixed: bool,
+}
+
+impl Column {
+    pub fn new(header: &'static str) -> Self {
+        Self {
+            header,
+            alignment: Alignment::Left,
+            fixed: false,
+        }
+    }
+
+    /// Right-align the cells of this column
+    pub fn right(self) -> Self {
+        Self {
+            alignment: Alignment::Right,
+            ..self
+        }
+    }
+
+    /// Keep the column at its content width instead of sharing the spare space
+    pub fn fixed(self) -> Self {
+        Self {
+            fixed: true,
+            ..self
+        }
+    }
+}
+
+/// 可滚动、可选中的表格
+#[derive(Clone, Debug)]
+pub(crate) struct TableComp<T> {
+    columns: Vec<Column>,
+    format_row: fn(&T) -> Vec<String>,
+
+    items: Vec<T>,
+    /// Formatted cells of `items`, cached so that rendering does not format every frame
+    rows: Vec<Vec<String>>,
+    widths: Vec<usize>,
+
+    table_state: TableState,
+    scroll_state: ScrollbarState,
+    selected_row_style: Style,
+    compact: bool,
+    wrap: bool,
+}
+
+impl<T> TableComp<T> {
+    /// 创建表格
+    ///
+    /// # 参数
+    ///
+    /// * `columns` - 列定义
+    /// * `format_row` - 将一条数据格式化为各列文本，返回值长度应与 `columns` 相同
+    pub fn new(columns: Vec<Column>, format_row: fn(&T) -> Vec<String>) -> Self {
+        let mut table = Self {
+            columns,
+            format_row,
+            items: Vec::new(),
+            rows: Vec::new(),
+            widths: Vec::new(),
+            table_state: TableState::default(),
+            scroll_state: ScrollbarState::default(),
+            selected_row_style: (&SelectionStyle::default()).into(),
+            compact: false,
+            wrap: true,
+        };
+        table.set_items(Vec::new());
+        table
+    }
+
+    /// Set the style of the selected row
+    pub fn selected_row_style<S: Into<Style>>(mut self, style: S) -> Self {
+        self.selected_row_style = style.into();
+        self
+    }
+
+    /// Use 1-line rows without blank padding
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self.update_scroll_state();
+        self
+    }
+
+    /// Wrap around at the first and last row instead of stopping there
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Switch between compact and spacious rows, keeping the selection
+    pub fn toggle_compact(&mut self) {
+        self.compact = !self.compact;
+        self.update_scroll_state();
+    }
+
+    pub fn is_compact(&self) -> bool {
+        self.compact
+    }
+
+    fn item_height(&self) -> usize {
+        if self.compact {
+            COMPACT_ITEM_HEIGHT
+        } else {
+            ITEM_HEIGHT
+        }
+    }
+
+    /// Sync the scrollbar with the item count, row height and selection
+    fn update_scroll_state(&mut self) {
+        let height = self.item_height();
+        self.scroll_state = self
+            .scroll_state
+            .content_length(self.items.len() * height)
+            .position(self.selected().unwrap_or(0) * height);
+    }
+
+    /// Wrap a cell in blank lines unless in compact mode
+    fn pad(&self, cell: &str) -> String {
+        if self.compact {
+            cell.to_string()
+        } else {
+            format!("\n{}\n", cell)
+        }
+    }
+
+    /// Format the rows with `format_row` from now on, keeping the items and selection
+    pub fn set_format_row(&mut self, format_row: fn(&T) -> Vec<String>) {
+        self.format_row = format_row;
+        self.rows = self.items.iter().map(self.format_row).collect();
+        self.widths = column_widths(&self.columns, &self.rows);
+    }
+
+    /// Replace the items, selecting the first one and scrolling to the top
+    pub fn set_items(&mut self, items: Vec<T>) {
+        self.rows = items.iter().map(self.format_row).collect();
+        self.widths = column_widths(&self.columns, &self.rows);
+        self.items = items;
+        self.table_state
+            .select(if self.items.is_empty() { None } else { Some(0) });
+        self.update_scroll_state();
+    }
+
+    /// Change the selected item in place, keeping the selection and scroll position
+    pub fn update_selected(&mut self, f: impl FnOnce(&mut T)) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let Some(item) = self.items.get_mut(index) else {
+            return;
+        };
+        f(item);
+        self.rows[index] = (self.format_row)(item);
+        self.widths = column_widths(&self.columns, &self.rows);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    #[cfg(test)]
+    pub fn widths(&self) -> &[usize] {
+        &self.widths
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.table_state.selected()
+    }
+
+    pub fn selected_item(&self) -> Option<&T> {
+        self.selected().and_then(|i| self.items.get(i))
+    }
+
+    /// Select the item at `index`, ignored if out of range
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.table_state.select(Some(index));
+            self.update_scroll_state();
+        }
+    }
+
+    /// Move the selection by `delta` rows, wrapping around at both ends unless disabled
+    pub fn change_focus(&mut self, delta: isize) {
+        let max = self.items.len();
+        if max == 0 {
+            return;
+        }
+        let cur_index = self.table_state.selected().unwrap_or(0);
+        let new_index = if self.wrap {
+            (cur_index as isize + delta).rem_euclid(max as isize) as usize
+        } else {
+            (cur_index as isize + delta).clamp(0, max as isize - 1) as usize
+        };
+        self.table_state.select(Some(new_index));
+        self.update_scroll_state();
+    }
+
+    /// Default style of the row at `index`, alternating the background
+    pub fn stripe_style(index: usize) -> Style {
+        let color = match index % 2 {
+            0 => TABLE_COLORS.normal_row_color,
+            _ => TABLE_COLORS.alt_row_color,
+        };
+        Style::new().fg(TABLE_COLORS.row_fg).bg(color)
+    }
+
+    /// 渲染表格和滚动条
+    ///
+    /// # 参数
+    ///
+    /// * `row_style` - 根据行号和数据返回该行样式，通常在 [`Self::stripe_style`] 的基础上修改
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        row_style: impl Fn(usize, &T) -> Style,
+    ) {
+        let header_style = Style::default()
+            .fg(TABLE_COLORS.header_fg)
+            .bg(TABLE_COLORS.header_bg);
+
+        let header = self
+            .columns
+            .iter()
+            .map(|c| Cell::from(self.pad(c.header)))
+            .collect::<Row>()
+            .style(header_style)
+            .height(self.item_height() as u16);
+
+        let rows =
+            self.rows
+                .iter()
+                .zip(&self.items)
+                .enumerate()
+                .map(|(i, (cells, item))| {
+                    Row::new(cells.iter().zip(&self.columns).map(|(cell, column)| {
+                        Text::from(self.pad(cell)).alignment(column.alignment)
+                    }))
+                    .style(row_style(i, item))
+                    .height(self.item_height() as u16)
+                });
+        let bar = " █ ";
+        let highlight_symbol = if self.compact {
+            Text::from(bar)
+        } else {
+            Text::from(vec!["".into(), bar.into(), "".into()])
+        };
+
+        let constraints = self.columns.iter().zip(&self.widths).map(|(c, w)| {
+            let width = (w + 2).try_into().unwrap_or(u16::MAX);
+            if c.fixed {
+                Constraint::Length(width)
+            } else {
+                Constraint::Min(width)
+            }
+        });
+
+        let t = Table::new(rows, constraints)
+            .header(header)
+            .row_highlight_style(self.selected_row_style)
+            .highlight_symbol(highlight_symbol)
+            .bg(TABLE_COLORS.buffer_bg)
+            .highlight_spacing(HighlightSpacing::Always)
+            .column_spacing(4);
+
+        frame.render_stateful_widget(t, area, &mut self.table_state);
+
+        frame.render_stateful_widget(
+            Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 1,
+            }),
+            &mut self.scroll_state,
+        );
+    }
+}
+
+/// Widest of the header and every cell, for each column
+fn column_widths(columns: &[Column], rows: &[Vec<String>]) -> Vec<usize> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| UnicodeWidthStr::width_cjk(cell.as_str()))
+                .fold(UnicodeWidthStr::width_cjk(column.header), max)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_table() -> TableComp<(&'static str, u32)> {
+        TableComp::new(
+            vec![Column::new("名称"), Column::new("Count").fixed().right()],
+            |(name, count)| vec![name.to_string(), count.to_string()],
+        )
+    }
+
+    #[test]
+    fn widths() {
+        let mut table = get_table();
+        assert_eq!(table.widths, vec![4, 5]);
+        assert_eq!(table.selected(), None);
+
+        table.set_items(vec![("梧桐苑餐厅", 3), ("a", 1234567)]);
+        assert_eq!(table.widths, vec![10, 7]);
+        assert_eq!(table.selected_item(), Some(&("梧桐苑餐厅", 3)));
+    }
+
+    #[test]
+    fn change_focus() {
+        let mut table = get_table();
+        // no items, nothing to select
+        table.change_focus(1);
+        assert_eq!(table.selected(), None);
+
+        table.set_items(vec![("a", 1), ("b", 2), ("c", 3)]);
+        table.change_focus(-1);
+        assert_eq!(table.selected(), Some(2));
+        table.change_focus(1);
+        assert_eq!(table.selected(), Some(0));
+        table.change_focus(4);
+        assert_eq!(table.selected(), Some(1));
+    }
+
+    #[test]
+    fn change_focus_no_wrap() {
+        let mut table = get_table().wrap(false);
+        table.set_items(vec![("a", 1), ("b", 2), ("c", 3)]);
+        table.change_focus(-1);
+        assert_eq!(table.selected(), Some(0));
+        table.change_focus(1);
+        assert_eq!(table.selected(), Some(1));
+        table.change_focus(4);
+        assert_eq!(table.selected(), Some(2));
+        table.change_focus(1);
+        assert_eq!(table.selected(), Some(2));
+    }
+
+    #[test]
+    fn compact() {
+        let mut table = get_table();
+        table.set_items(vec![("a", 1), ("b", 2), ("c", 3)]);
+        table.change_focus(2);
+        assert_eq!(table.scroll_state, ScrollbarState::new(9).position(6));
+
+        table.toggle_compact();
+        assert!(table.is_compact());
+        assert_eq!(table.selected(), Some(2));
+        assert_eq!(table.scroll_state, ScrollbarState::new(3).position(2));
+
+        table.set_items(vec![("a", 1), ("b", 2)]);
+        assert_eq!(table.scroll_state, ScrollbarState::new(2).position(0));
+    }
+}
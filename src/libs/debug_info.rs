@@ -0,0 +1,154 @@
+//! # 诊断信息模块
+//!
+//! 为 `debug-info` 子命令汇总数据库和配置信息，生成一份可以直接粘贴到问题反馈中的报告。
+//!
+//! ## 报告内容
+//!
+//! - 数据库路径，使用内存数据库时为 `(in memory)`
+//! - `PRAGMA user_version`
+//! - 各表行数与不同商家的数量
+//! - 最早和最晚的交易时间
+//! - 最终生效的配置，与 `--print-config` 相同，Cookie 等敏感字段已隐去
+//!
+//! ## 输出示例
+//!
+//! ```text
+//! DB path: /home/user/.local/share/xjtu_mealflow/transactions.db
+//! Schema version: 0
+//! Rows:
+//!   cookies: 1
+//!   fetch_dates: 2
+//!   meta: 1
+//!   transactions: 1234
+//! Distinct merchants: 56
+//! Transaction dates: 2024-09-01 07:32:10 .. 2025-03-01 12:05:44
+//! Config:
+//! {
+//!   ...
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use color_eyre::{Result, eyre::Context};
+
+use crate::{
+    config::Config,
+    libs::transactions::{LOCAL_TIME_FORMAT, TransactionManager},
+};
+
+/// 生成诊断报告
+///
+/// # 参数
+///
+/// * `config` - 合并所有配置源后的配置
+/// * `manager` - 按 `config` 打开的数据库
+///
+/// # 返回值
+///
+/// 多行文本报告。本函数不会修改数据库
+pub fn build_report(config: &Config, manager: &TransactionManager) -> Result<String> {
+    let stats = manager
+        .db_stats()
+        .context("Failed to read database stats")?;
+    let db_path = config
+        .config
+        .db_path()
+        .map_or("(in memory)".to_string(), |p| p.display().to_string());
+
+    // writing to a String never fails
+    let mut report = String::new();
+    writeln!(report, "DB path: {}", db_path).unwrap();
+    writeln!(report, "Schema version: {}", stats.user_version).unwrap();
+    writeln!(report, "Rows:").unwrap();
+    for (table, count) in &stats.table_counts {
+        writeln!(report, "  {}: {}", table, count).unwrap();
+    }
+    writeln!(report, "Distinct merchants: {}", stats.merchant_count).unwrap();
+    match stats.time_range {
+        Some((min, max)) => writeln!(
+            report,
+            "Transaction dates: {} .. {}",
+            min.format(LOCAL_TIME_FORMAT),
+            max.format(LOCAL_TIME_FORMAT)
+        )
+        .unwrap(),
+        None => writeln!(report, "Transaction dates: none").unwrap(),
+    }
+    writeln!(report, "Config:").unwrap();
+    report.push_str(&config.to_json(true)?);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        cli::{ClapSource, Cli},
+        libs::transactions::Transaction,
+    };
+
+    fn get_config() -> Config {
+        let args = Cli::parse_from([
+            "test-debug-info",
+            "--db-in-mem",
+            "--account",
+            "123456",
+            "--hallticket",
+            "secret",
+        ]);
+        Config::from_sources(Some(ClapSource::new(&args)), None).unwrap()
+    }
+
+    #[test]
+    fn empty_db() {
+        let manager = TransactionManager::new(None).unwrap();
+        let report = build_report(&get_config(), &manager).unwrap();
+
+        assert!(report.starts_with(
+            "DB path: (in memory)\n\
+             Schema version: 0\n\
+             Rows:\n  \
+             cookies: 0\n  \
+             fetch_dates: 0\n  \
+             meta: 0\n  \
+             notes: 0\n  \
+             transactions: 0\n\
+             Distinct merchants: 0\n\
+             Transaction dates: none\n\
+             Config:\n{"
+        ));
+    }
+
+    #[test]
+    fn report() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-5.5, "Canteen", "2025-01-02 08:30:00").unwrap(),
+                Transaction::from_local(-3.0, "Shop", "2025-02-10 18:00:00").unwrap(),
+            ])
+            .unwrap();
+        manager
+            .set_credentials("123456", "hallticket=secret")
+            .unwrap();
+
+        let report = build_report(&get_config(), &manager).unwrap();
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[3], "  cookies: 1");
+        assert_eq!(lines[7], "  transactions: 3");
+        assert_eq!(lines[8], "Distinct merchants: 2");
+        assert_eq!(
+            lines[9],
+            "Transaction dates: 2025-01-02 08:30:00 .. 2025-03-01 12:00:00"
+        );
+
+        let (_, json) = report.split_once("Config:\n").unwrap();
+        let json: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(json["fetch"]["hallticket"], "<redacted>");
+        assert!(!report.contains("secret"));
+    }
+}
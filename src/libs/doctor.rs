@@ -0,0 +1,337 @@
+//! # 自检模块
+//!
+//! 为 `doctor` 子命令提供环境自检，帮助用户区分网络、Cookie 与配置问题。
+//!
+//! ## 检查项
+//!
+//! | 检查项 | 内容 |
+//! |--------|------|
+//! | Data directory | 数据目录存在且可写 |
+//! | Database | 数据库可以打开，并统计交易记录条数 |
+//! | Account & cookie | 命令行参数或数据库中存在账号和 Cookie |
+//! | Server | （仅 `--online`）向校园卡服务器发送一次请求，确认返回可解析的 JSON |
+//!
+//! ## 输出示例
+//!
+//! ```text
+//! [PASS] Data directory: /home/user/.local/share/xjtu_mealflow is writable
+//! [PASS] Database: 1234 transactions
+//! [FAIL] Account & cookie: No account and cookie found
+//! [SKIP] Server: Pass --online to send a test request
+//! ```
+//!
+//! ## 复现请求
+//!
+//! `doctor --print-curl` 不执行检查，而是打印获取第一页交易记录的等价 `curl` 命令，
+//! 便于在应用外复现请求。Cookie 默认隐去，加上 `--include-cookie` 时原样输出。
+
+use std::{fmt::Display, fs, path::Path};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+};
+
+use crate::{
+    config::Config,
+    libs::{fetcher::RealMealFetcher, transactions::TransactionManager},
+};
+
+/// 单项检查的结果状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// 未执行，如未指定 `--online` 或前置检查失败
+    Skip,
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+            CheckStatus::Skip => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn from_result(name: &'static str, result: Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                status: CheckStatus::Pass,
+                detail,
+            },
+            Err(e) => Self {
+                name,
+                status: CheckStatus::Fail,
+                detail: format!("{:#}", e),
+            },
+        }
+    }
+
+    fn skip<T: Into<String>>(name: &'static str, detail: T) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Skip,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl Display for CheckResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.status, self.name, self.detail)
+    }
+}
+
+/// 依次执行所有检查
+///
+/// # 参数
+///
+/// * `config` - 合并所有配置源后的配置
+/// * `online` - 用于发送测试请求的获取器，为 `None` 时跳过服务器检查。
+///   账号和 Cookie 由本函数设置
+///
+/// # 返回值
+///
+/// 按检查顺序排列的结果。本函数不会修改数据库
+pub fn run_checks(config: &Config, online: Option<RealMealFetcher>) -> Vec<CheckResult> {
+    let mut results = vec![CheckResult::from_result(
+        "Data directory",
+        check_data_dir(&config.config.data_dir),
+    )];
+
+    let (db_result, manager) = match TransactionManager::new(config.config.db_path()) {
+        Ok(manager) => (
+            manager
+                .fetch_count()
+                .map(|count| format!("{} transactions", count)),
+            Some(manager),
+        ),
+        Err(e) => (Err(e).context("Failed to open the database"), None),
+    };
+    results.push(CheckResult::from_result("Database", db_result));
+
+    let credentials = match resolve_credentials(config, manager.as_ref()) {
+        Ok((account, cookie)) => {
+            results.push(CheckResult::from_result(
+                "Account & cookie",
+                Ok(format!("Using account {}", account)),
+            ));
+            Some((account, cookie))
+        }
+        Err(e) => {
+            results.push(CheckResult::from_result("Account & cookie", Err(e)));
+            None
+        }
+    };
+
+    results.push(match (online, credentials) {
+        (None, _) => CheckResult::skip("Server", "Pass --online to send a test request"),
+        (Some(_), None) => CheckResult::skip("Server", "Account or cookie missing"),
+        (Some(fetcher), Some((account, cookie))) => CheckResult::from_result(
+            "Server",
+            fetcher
+                .account(account)
+                .cookie(cookie)
+                .validate()
+                .map(|count| format!("Got {} transactions on the first page", count)),
+        ),
+    });
+
+    results
+}
+
+/// 生成获取第一页交易记录的等价 `curl` 命令
+///
+/// # 参数
+///
+/// * `config` - 合并所有配置源后的配置，账号和 Cookie 的取法与 [`run_checks`] 相同
+/// * `fetcher` - 决定服务器地址和代理的获取器，账号和 Cookie 由本函数设置
+/// * `include_cookie` - 是否原样输出 Cookie，否则替换为 `<redacted>`
+///
+/// # 返回值
+///
+/// `curl` 命令，找不到账号或 Cookie 时返回错误。数据库以只读方式打开，不存在时不会被创建
+pub fn curl_command(
+    config: &Config,
+    fetcher: RealMealFetcher,
+    include_cookie: bool,
+) -> Result<String> {
+    let manager = config
+        .config
+        .db_path()
+        .and_then(|path| TransactionManager::open_read_only(&path).ok());
+    let (account, cookie) = resolve_credentials(config, manager.as_ref())?;
+    fetcher
+        .account(account)
+        .cookie(cookie)
+        .to_curl(1, include_cookie)
+}
+
+/// 打印检查清单
+///
+/// # 返回值
+///
+/// 有检查失败时返回错误
+pub fn print_report(results: &[CheckResult]) -> Result<()> {
+    for result in results {
+        println!("{}", result);
+    }
+    let failed = results
+        .iter()
+        .filter(|r| r.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+fn check_data_dir(data_dir: &Path) -> Result<String> {
+    fs::create_dir_all(data_dir)
+        .with_context(|| format!("Failed to create {}", data_dir.display()))?;
+    let probe = data_dir.join(".doctor-write-test");
+    fs::write(&probe, b"ok").with_context(|| format!("{} is not writable", data_dir.display()))?;
+    fs::remove_file(&probe)?;
+    Ok(format!("{} is writable", data_dir.display()))
+}
+
+/// Command line values take priority over the ones saved in the database, the same as in the TUI
+fn resolve_credentials(
+    config: &Config,
+    manager: Option<&TransactionManager>,
+) -> Result<(String, String)> {
+    let (saved_account, saved_cookie) = manager
+        .and_then(|m| m.get_account_cookie_may_empty().ok())
+        .unwrap_or_default();
+    let account = config.fetch.account.clone().unwrap_or(saved_account);
+    let cookie = config
+        .fetch
+        .hallticket
+        .as_ref()
+        .map_or(saved_cookie, |h| format!("hallticket={}", h));
+
+    match (account.is_empty(), cookie.is_empty()) {
+        (false, false) => Ok((account, cookie)),
+        (true, true) => bail!("No account and cookie found"),
+        (true, false) => bail!("No account found"),
+        (false, true) => bail!("No cookie found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::{ClapSource, Cli};
+
+    fn get_config(data_dir: &Path, extra: &[&str]) -> Config {
+        let mut args = vec!["test-doctor", "--data-dir", data_dir.to_str().unwrap()];
+        args.extend(extra);
+        Config::new(Some(ClapSource::new(&Cli::parse_from(args)))).unwrap()
+    }
+
+    fn statuses(results: &[CheckResult]) -> Vec<CheckStatus> {
+        results.iter().map(|r| r.status).collect()
+    }
+
+    #[test]
+    fn offline_checks() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = get_config(dir.path(), &[]);
+        let results = run_checks(&config, None);
+        assert_eq!(
+            statuses(&results),
+            vec![
+                CheckStatus::Pass,
+                CheckStatus::Pass,
+                CheckStatus::Fail,
+                CheckStatus::Skip
+            ]
+        );
+        assert_eq!(results[1].detail, "0 transactions");
+        assert!(print_report(&results).is_err());
+
+        let config = get_config(dir.path(), &["--account", "123456", "--hallticket", "abc"]);
+        let results = run_checks(&config, None);
+        assert_eq!(results[2].status, CheckStatus::Pass);
+        assert!(print_report(&results).is_ok());
+        // doctor must not persist the credentials
+        let manager = TransactionManager::new(config.config.db_path()).unwrap();
+        assert!(manager.get_account_cookie().is_err());
+    }
+
+    #[test]
+    fn curl_command_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = get_config(dir.path(), &[]);
+        let err = curl_command(&config, RealMealFetcher::default(), false).unwrap_err();
+        assert_eq!(err.to_string(), "No account and cookie found");
+        assert!(!config.config.db_path().unwrap().exists());
+
+        let config = get_config(dir.path(), &["--account", "123456", "--hallticket", "abc"]);
+        let curl = curl_command(&config, RealMealFetcher::default(), false).unwrap();
+        assert!(curl.contains("account=123456&page=1"));
+        assert!(curl.contains("-H 'cookie: <redacted>'"));
+        let curl = curl_command(&config, RealMealFetcher::default(), true).unwrap();
+        assert!(curl.contains("-H 'cookie: hallticket=abc'"));
+
+        // credentials saved in an existing database are still found
+        let manager = TransactionManager::new(config.config.db_path()).unwrap();
+        manager.set_credentials("654321", "hallticket=def").unwrap();
+        let config = get_config(dir.path(), &[]);
+        let curl = curl_command(&config, RealMealFetcher::default(), true).unwrap();
+        assert!(curl.contains("account=654321&page=1"));
+        assert!(curl.contains("-H 'cookie: hallticket=def'"));
+    }
+
+    #[test]
+    fn online_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = get_config(dir.path(), &["--account", "123456", "--hallticket", "abc"]);
+
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/Report/GetPersonTrjn")
+            .match_header("cookie", "hallticket=abc")
+            .with_status(200)
+            .with_body(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test/mock-data/api-resp.json"
+            )))
+            .create();
+        let results = run_checks(
+            &config,
+            Some(RealMealFetcher::default().origin(server.url())),
+        );
+        mock.assert();
+        assert_eq!(results[3].status, CheckStatus::Pass, "{}", results[3]);
+
+        let mut server = mockito::Server::new();
+        server
+            .mock("POST", "/Report/GetPersonTrjn")
+            .with_status(200)
+            .with_body("<html>Login</html>")
+            .create();
+        let results = run_checks(
+            &config,
+            Some(RealMealFetcher::default().origin(server.url())),
+        );
+        assert_eq!(results[3].status, CheckStatus::Fail);
+        assert!(results[3].detail.contains("session expired"));
+    }
+}
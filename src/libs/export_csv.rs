@@ -19,6 +19,15 @@
 //! cargo run -- export-csv --output "data/my_transactions.csv"
 //! ```
 //!
+//! ### 避免覆盖之前的导出
+//!
+//! 未指定 `--output` 时，`--timestamp` 在默认文件名后加上导出时间：
+//!
+//! ```bash
+//! # 导出到 transactions_export_20250301_120000.csv
+//! cargo run -- export-csv --timestamp
+//! ```
+//!
 //! ## 筛选功能
 //!
 //! ### 按消费金额筛选
@@ -30,6 +39,9 @@
 //! cargo run -- export-csv --min-amount=10.00 --max-amount=50.00
 //! ```
 //!
+//! 金额按分比较，浮点误差不会让恰好等于边界的交易被漏掉，参见
+//! [`AMOUNT_EPSILON`](crate::libs::transactions::AMOUNT_EPSILON)。
+//!
 //! ### 按商家筛选
 //!
 //! 导出特定商家的交易数据：
@@ -67,6 +79,36 @@
 //!   --output "filtered_transactions.csv"
 //! ```
 //!
+//! ### 只导出最近的记录
+//!
+//! `--limit` 与上述筛选条件组合使用，在筛选后按时间从新到旧取前 N 条，
+//! 导出的文件也按时间降序排列：
+//!
+//! ```bash
+//! # 导出"超市"最近的 100 条交易
+//! cargo run -- export-csv --merchant "超市" --limit 100
+//! ```
+//!
+//! ### 商家汇总报表
+//!
+//! 每个商家输出一行汇总，而不是逐条交易，同样支持上述筛选条件：
+//!
+//! ```bash
+//! # 默认路径 merchant_report.csv
+//! cargo run -- export-csv --report merchant --time-start "2024-09-01"
+//! ```
+//!
+//! ### 按月拆分
+//!
+//! `--split-by month` 把筛选后的交易按月份（UTC+8）分别写入 `--output` 指定的目录，
+//! 每月一个 `transactions_YYYY-MM.csv`，没有交易的月份不生成文件。目录不存在时会自动创建，
+//! 未指定 `--output` 时默认为 `transactions_export` 目录：
+//!
+//! ```bash
+//! # 导出到 archive/transactions_2024-09.csv、archive/transactions_2024-10.csv ……
+//! cargo run -- export-csv --split-by month --output archive --time-start "2024-09-01"
+//! ```
+//!
 //! ## 日期格式
 //!
 //! 所有日期参数必须使用 `YYYY-MM-DD` 格式，例如：
@@ -78,17 +120,47 @@
 //! 导出的 CSV 文件包含以下列：
 //! - `ID`: 交易唯一标识符
 //! - `Time`: 交易时间（格式：YYYY-MM-DD HH:MM:SS +ZZZZ）
-//! - `Amount`: 交易金额（负数表示消费，正数表示充值）
+//! - `Amount`: 交易金额（负数表示消费，正数表示充值），保留两位小数，如 `-15.50`
+//! - `Merchant`: 商家名称
+//! - `Note`: 交易备注，仅在使用 `--notes` 时输出，没有备注的交易留空
+//!
+//! 使用 `--summary` 时末尾追加一行合计，只填写金额和商家两列，便于在表格软件中查看：
+//!
+//! ```text
+//! ,,-25.30,"TOTAL (2 rows)"
+//! ```
+//!
+//! 默认使用 LF (`\n`) 换行。Windows 上的 Excel 等软件需要 CRLF 时，加上 `--crlf`：
+//!
+//! ```bash
+//! cargo run -- export-csv --crlf
+//! ```
+//!
+//! 商家汇总报表包含以下列，按金额升序排列（消费最多的商家在前）：
 //! - `Merchant`: 商家名称
+//! - `Count`: 交易笔数
+//! - `Total`: 交易金额之和，保留两位小数
+//!
+//! ## Excel 工作簿
+//!
+//! Web API 还可以导出 `.xlsx` 工作簿（见 [`CsvExporter::export_to_xlsx`]），列与 CSV 相同，
+//! 但 `Time` 为 Excel 日期时间（UTC+8，不含时区），`Amount` 为数字，
+//! 在 Excel 中打开时不会遇到 CSV 的编码问题，也可以直接排序和求和。
 
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use chrono::{DateTime, FixedOffset, NaiveDate};
+use chrono::NaiveDateTime;
 use color_eyre::eyre::{Context, Result};
+use rust_xlsxwriter::{Format, Workbook};
 
 use super::transactions::{FilterOptions, Transaction, TransactionManager};
+use crate::utils::{
+    amount::{format_amount_plain, round_amount},
+    date::parse_range,
+};
 
 /// CSV 导出器
 ///
@@ -111,6 +183,75 @@ pub struct ExportOptions {
     pub time_start: Option<String>,
     /// 结束日期筛选
     pub time_end: Option<String>,
+    /// 导出汇总报表而不是逐条交易
+    pub report: Option<ReportKind>,
+    /// 只导出最近的 N 条交易
+    pub limit: Option<usize>,
+    /// 在末尾追加合计行，对汇总报表无效
+    pub summary_row: bool,
+    /// 行尾换行符
+    pub line_ending: LineEnding,
+    /// 未指定输出路径时，在默认文件名后加上导出时间，避免覆盖之前的导出
+    pub timestamp: bool,
+    /// 按时间段拆分为多个文件，此时 `output` 为输出目录
+    pub split_by: Option<SplitBy>,
+    /// 追加备注列，对汇总报表无效
+    pub notes: bool,
+}
+
+/// CSV 行尾换行符
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`，Windows 上的 Excel 等软件需要
+    Crlf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// 未指定输出路径时使用的文件名
+///
+/// # 参数
+///
+/// * `stem` - 不含扩展名的默认文件名
+/// * `timestamp` - 导出时间，为 `None` 时不加时间后缀
+///
+/// # 返回值
+///
+/// `<stem>.csv` 或 `<stem>_YYYYMMDD_HHMMSS.csv`
+fn default_output_path(stem: &str, timestamp: Option<NaiveDateTime>) -> String {
+    format!("{}.csv", default_output_stem(stem, timestamp))
+}
+
+/// `stem`, with the export time appended if given, also used for the default split directory
+fn default_output_stem(stem: &str, timestamp: Option<NaiveDateTime>) -> String {
+    match timestamp {
+        Some(time) => format!("{}_{}", stem, time.format("%Y%m%d_%H%M%S")),
+        None => stem.to_string(),
+    }
+}
+
+/// 汇总报表类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportKind {
+    /// 每个商家一行：Merchant,Count,Total
+    Merchant,
+}
+
+/// 拆分导出的时间段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SplitBy {
+    /// 每月一个文件：transactions_YYYY-MM.csv
+    Month,
 }
 
 impl CsvExporter {
@@ -127,6 +268,53 @@ impl CsvExporter {
     ///
     /// 成功时返回导出的记录数量
     pub fn execute_export(manager: &TransactionManager, options: &ExportOptions) -> Result<usize> {
+        let timestamp = options
+            .timestamp
+            .then(|| chrono::Local::now().naive_local());
+
+        if options.report == Some(ReportKind::Merchant) {
+            let output_path = options
+                .output
+                .clone()
+                .unwrap_or_else(|| default_output_path("merchant_report", timestamp));
+            let (csv_content, count) = Self::export_merchant_report(manager, options)?;
+            std::fs::write(&output_path, csv_content)
+                .with_context(|| format!("Failed to write report to {}", output_path))?;
+            println!(
+                "Successfully exported report of {} merchants to {}",
+                count, output_path
+            );
+            return Ok(count);
+        }
+
+        let notes = Self::fetch_notes(manager, options)?;
+
+        if let Some(split_by) = options.split_by {
+            let dir = options
+                .output
+                .clone()
+                .unwrap_or_else(|| default_output_stem("transactions_export", timestamp));
+            let transactions = Self::select_transactions(manager, options)?;
+            let files = Self::export_split(
+                &transactions,
+                &dir,
+                split_by,
+                options.summary_row,
+                options.line_ending,
+                notes.as_ref(),
+            )?;
+            for (path, count) in &files {
+                println!("Exported {} transactions to {}", count, path.display());
+            }
+            println!(
+                "Successfully exported {} transactions into {} files in {}",
+                transactions.len(),
+                files.len(),
+                dir
+            );
+            return Ok(transactions.len());
+        }
+
         // 构建筛选条件
         let filter_opt = Self::build_filter_options(options)?;
 
@@ -134,13 +322,36 @@ impl CsvExporter {
         let output_path = options
             .output
             .clone()
-            .unwrap_or_else(|| "transactions_export.csv".to_string());
+            .unwrap_or_else(|| default_output_path("transactions_export", timestamp));
 
         // 执行导出
-        let count = if Self::has_any_filter(options) {
-            Self::export_filtered_transactions(manager, &output_path, &filter_opt)?
+        let count = if let Some(limit) = options.limit {
+            Self::export_recent_transactions(
+                manager,
+                &output_path,
+                &filter_opt,
+                limit,
+                options.summary_row,
+                options.line_ending,
+                notes.as_ref(),
+            )?
+        } else if Self::has_any_filter(options) {
+            Self::export_filtered_transactions(
+                manager,
+                &output_path,
+                &filter_opt,
+                options.summary_row,
+                options.line_ending,
+                notes.as_ref(),
+            )?
         } else {
-            Self::export_all_transactions(manager, &output_path)?
+            Self::export_all_transactions(
+                manager,
+                &output_path,
+                options.summary_row,
+                options.line_ending,
+                notes.as_ref(),
+            )?
         };
 
         println!(
@@ -162,13 +373,14 @@ impl CsvExporter {
         }
 
         // (2) 金额筛选
-        // 用户输入正数范围，转换为数据库中的负数范围
+        // 用户输入正数的闭区间，转换为数据库中的负数范围。数据库按分比较左闭右开区间，
+        // 因此闭区间 [a, b] 等价于 [a, b + 0.01)
         if let Some(min) = options.min_amount {
             if min < 0.0 {
                 println!("Warning: min_amount should be positive, converting absolute value");
             }
-            // 用户最小值 -> 数据库最大值（逻辑反转）
-            let db_max = -min.abs();
+            // 用户最小值 -> 数据库最大值（逻辑反转），最小值本身也包含在内
+            let db_max = -min.abs() + 0.01;
             filter_opt = filter_opt.max(db_max);
         }
 
@@ -181,36 +393,26 @@ impl CsvExporter {
             filter_opt = filter_opt.min(db_min);
         }
 
-        // (3) 日期筛选
-        if let Some(start_str) = &options.time_start {
-            match Self::parse_date(start_str) {
-                Ok(start_date) => {
-                    println!("Setting start date to: {}", start_date);
-                    filter_opt = filter_opt.start(start_date);
-                }
-                Err(e) => {
-                    println!("Warning: Invalid start date format '{}': {}", start_str, e);
-                    println!("Date format should be YYYY-MM-DD");
-                }
-            }
+        // (3) 日期筛选：结束日期当天的交易也包含在内
+        let (start, end) = parse_range(options.time_start.as_deref(), options.time_end.as_deref())?;
+        if let Some(start) = start {
+            filter_opt = filter_opt.start(start);
         }
-
-        if let Some(end_str) = &options.time_end {
-            match Self::parse_end_date(end_str) {
-                Ok(end_date) => {
-                    println!("Setting end date to: {}", end_date);
-                    filter_opt = filter_opt.end(end_date);
-                }
-                Err(e) => {
-                    println!("Warning: Invalid end date format '{}': {}", end_str, e);
-                    println!("Date format should be YYYY-MM-DD");
-                }
-            }
+        if let Some(end) = end {
+            filter_opt = filter_opt.end(end);
         }
 
         Ok(filter_opt)
     }
 
+    /// Notes of all transactions when `options.notes` is set
+    fn fetch_notes(
+        manager: &TransactionManager,
+        options: &ExportOptions,
+    ) -> Result<Option<HashMap<i64, String>>> {
+        options.notes.then(|| manager.fetch_notes()).transpose()
+    }
+
     /// 检查是否有任何筛选条件
     fn has_any_filter(options: &ExportOptions) -> bool {
         options.merchant.is_some()
@@ -220,45 +422,18 @@ impl CsvExporter {
             || options.time_end.is_some()
     }
 
-    /// 解析日期字符串，将时间设为当天开始 (00:00:00)
-    ///
-    /// [之前的文档注释保持不变...]
-    pub fn parse_date(date_str: &str) -> Result<DateTime<FixedOffset>> {
-        let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-            .context(format!("Failed to parse date: {}", date_str))?;
-
-        let naive_datetime = date.and_hms_opt(0, 0, 0).unwrap();
-        let tz_offset = FixedOffset::east_opt(8 * 3600).unwrap(); // UTC+8
-        let datetime =
-            DateTime::<FixedOffset>::from_naive_utc_and_offset(naive_datetime, tz_offset);
-
-        Ok(datetime)
-    }
-
-    /// 解析日期字符串，将时间设为当天结束 (23:59:59)
-    ///
-    /// [之前的文档注释保持不变...]
-    pub fn parse_end_date(date_str: &str) -> Result<DateTime<FixedOffset>> {
-        let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-            .context(format!("Failed to parse date: {}", date_str))?;
-
-        let naive_datetime = date.and_hms_opt(23, 59, 59).unwrap();
-        let tz_offset = FixedOffset::east_opt(8 * 3600).unwrap(); // UTC+8
-        let datetime =
-            DateTime::<FixedOffset>::from_naive_utc_and_offset(naive_datetime, tz_offset);
-
-        Ok(datetime)
-    }
-
     /// 导出所有交易记录到 CSV 文件
     ///
     /// [之前的文档注释和实现保持不变...]
     pub fn export_all_transactions<P: AsRef<Path>>(
         manager: &TransactionManager,
         file_path: P,
+        summary_row: bool,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
     ) -> Result<usize> {
         let transactions = manager.fetch_all()?;
-        Self::write_transactions_to_csv(&transactions, file_path)?;
+        Self::write_transactions_to_csv(&transactions, file_path, summary_row, line_ending, notes)?;
         Ok(transactions.len())
     }
 
@@ -269,37 +444,169 @@ impl CsvExporter {
         manager: &TransactionManager,
         file_path: P,
         filter_opt: &FilterOptions,
+        summary_row: bool,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
     ) -> Result<usize> {
         let transactions = manager.fetch_filtered(filter_opt)?;
         println!(
             "Found {} transactions matching the filters",
             transactions.len()
         );
-        Self::write_transactions_to_csv(&transactions, file_path)?;
+        Self::write_transactions_to_csv(&transactions, file_path, summary_row, line_ending, notes)?;
         Ok(transactions.len())
     }
 
-    /// 将交易记录写入 CSV 文件
+    /// 导出筛选后最近的 `limit` 条交易记录到 CSV 文件
     ///
-    /// [之前的实现保持不变...]
-    fn write_transactions_to_csv<P: AsRef<Path>>(
-        transactions: &[Transaction],
+    /// # 参数
+    ///
+    /// * `manager` - 交易管理器实例
+    /// * `file_path` - 输出文件路径
+    /// * `filter_opt` - 筛选条件
+    /// * `limit` - 最多导出的条数
+    /// * `summary_row` - 是否在末尾追加合计行
+    /// * `line_ending` - 行尾换行符
+    /// * `notes` - 交易备注，为 `Some` 时追加备注列
+    ///
+    /// # 返回值
+    ///
+    /// 成功时返回导出的记录数量
+    pub fn export_recent_transactions<P: AsRef<Path>>(
+        manager: &TransactionManager,
         file_path: P,
-    ) -> Result<()> {
-        let mut file = File::create(file_path)?;
+        filter_opt: &FilterOptions,
+        limit: usize,
+        summary_row: bool,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
+    ) -> Result<usize> {
+        let transactions = manager.fetch_recent(filter_opt, limit)?;
+        Self::write_transactions_to_csv(&transactions, file_path, summary_row, line_ending, notes)?;
+        Ok(transactions.len())
+    }
 
-        writeln!(file, "ID,Time,Amount,Merchant")?;
+    /// 按时间段把交易记录分别写入 `dir` 下的多个 CSV 文件
+    ///
+    /// 每个文件通过 [`Self::write_to`] 写入，格式与单个文件导出相同；文件内保持传入的顺序。
+    ///
+    /// # 参数
+    ///
+    /// * `transactions` - 交易记录数组
+    /// * `dir` - 输出目录，不存在时会创建
+    /// * `split_by` - 拆分的时间段
+    /// * `summary_row` - 是否在每个文件末尾追加合计行
+    /// * `line_ending` - 行尾换行符
+    /// * `notes` - 交易备注，为 `Some` 时追加备注列
+    ///
+    /// # 返回值
+    ///
+    /// 按时间先后排列的 (文件路径, 记录数量)，没有交易的时间段不生成文件
+    pub fn export_split<P: AsRef<Path>>(
+        transactions: &[Transaction],
+        dir: P,
+        split_by: SplitBy,
+        summary_row: bool,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
+    ) -> Result<Vec<(PathBuf, usize)>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
 
+        let mut groups: BTreeMap<String, Vec<Transaction>> = BTreeMap::new();
         for transaction in transactions {
-            writeln!(
-                file,
+            let key = match split_by {
+                SplitBy::Month => transaction.time.format("%Y-%m").to_string(),
+            };
+            groups.entry(key).or_default().push(transaction.clone());
+        }
+
+        groups
+            .into_iter()
+            .map(|(key, group)| {
+                let path = dir.join(format!("transactions_{}.csv", key));
+                Self::write_transactions_to_csv(&group, &path, summary_row, line_ending, notes)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                Ok((path, group.len()))
+            })
+            .collect()
+    }
+
+    /// 将交易记录以 CSV 格式写入任意输出
+    ///
+    /// 文件导出和字符串导出都基于此方法，保证格式一致。
+    /// 方法内部不做缓冲，写入文件或网络流时建议传入 [`BufWriter`]。
+    ///
+    /// # 参数
+    ///
+    /// * `transactions` - 交易记录数组
+    /// * `writer` - 输出目标，如文件、标准输出或内存缓冲区
+    /// * `line_ending` - 行尾换行符
+    /// * `notes` - 交易备注，为 `Some` 时追加 `Note` 列
+    pub fn write_to<W: Write>(
+        transactions: &[Transaction],
+        writer: &mut W,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
+    ) -> Result<()> {
+        let eol = line_ending.as_str();
+        let note_header = if notes.is_some() { ",Note" } else { "" };
+        write!(writer, "ID,Time,Amount,Merchant{}{}", note_header, eol)?;
+
+        for transaction in transactions {
+            write!(
+                writer,
                 "{},{},{},\"{}\"",
                 transaction.id,
                 transaction.time.format("%Y-%m-%d %H:%M:%S %z"),
-                transaction.amount,
-                transaction.merchant.replace("\"", "\"\"")
+                format_amount_plain(transaction.amount),
+                transaction.merchant.replace("\"", "\"\""),
             )?;
+            if let Some(notes) = notes {
+                match notes.get(&transaction.id) {
+                    Some(note) => write!(writer, ",\"{}\"", note.replace("\"", "\"\""))?,
+                    None => write!(writer, ",")?,
+                }
+            }
+            write!(writer, "{}", eol)?;
+        }
+        Ok(())
+    }
+
+    /// 写入合计行：金额之和与记录条数，`note_column` 为真时备注列留空
+    fn write_summary_row<W: Write>(
+        transactions: &[Transaction],
+        writer: &mut W,
+        line_ending: LineEnding,
+        note_column: bool,
+    ) -> Result<()> {
+        let total: f64 = transactions.iter().map(|t| t.amount).sum();
+        write!(
+            writer,
+            ",,{},\"TOTAL ({} rows)\"{}{}",
+            format_amount_plain(total),
+            transactions.len(),
+            if note_column { "," } else { "" },
+            line_ending.as_str()
+        )?;
+        Ok(())
+    }
+
+    /// 将交易记录写入 CSV 文件，`summary_row` 为真时追加合计行
+    fn write_transactions_to_csv<P: AsRef<Path>>(
+        transactions: &[Transaction],
+        file_path: P,
+        summary_row: bool,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(file_path)?);
+        Self::write_to(transactions, &mut writer, line_ending, notes)?;
+        if summary_row {
+            Self::write_summary_row(transactions, &mut writer, line_ending, notes.is_some())?;
         }
+        writer.flush()?;
         Ok(())
     }
 
@@ -328,6 +635,13 @@ impl CsvExporter {
     ///     max_amount: Some(50.0),
     ///     time_start: None,
     ///     time_end: None,
+    ///     report: None,
+    ///     limit: None,
+    ///     summary_row: false,
+    ///     line_ending: LineEnding::Lf,
+    ///     timestamp: false,
+    ///     split_by: None,
+    ///     notes: false,
     /// };
     ///
     /// let (csv_content, count) = CsvExporter::export_to_string(&manager, &options)?;
@@ -337,22 +651,92 @@ impl CsvExporter {
         manager: &TransactionManager,
         options: &ExportOptions,
     ) -> Result<(String, usize)> {
-        // 复用现有的筛选条件构建逻辑
-        let filter_opt = Self::build_filter_options(options)?;
-
-        // 获取交易记录（复用现有逻辑）
-        let transactions = if Self::has_any_filter(options) {
-            manager.fetch_filtered(&filter_opt)?
-        } else {
-            manager.fetch_all()?
-        };
+        let transactions = Self::select_transactions(manager, options)?;
+        let notes = Self::fetch_notes(manager, options)?;
 
         // 生成 CSV 字符串
-        let csv_content = Self::transactions_to_csv_string(&transactions)?;
+        let csv_content = Self::transactions_to_csv_string(
+            &transactions,
+            options.summary_row,
+            options.line_ending,
+            notes.as_ref(),
+        )?;
 
         Ok((csv_content, transactions.len()))
     }
 
+    /// 导出交易记录为 Excel 工作簿（用于 Web API）
+    ///
+    /// 筛选逻辑与 [`export_to_string`](Self::export_to_string) 相同，`summary_row` 同样有效。
+    ///
+    /// # 参数
+    ///
+    /// * `manager` - 交易管理器实例
+    /// * `options` - 导出选项的引用，`line_ending` 会被忽略
+    ///
+    /// # 返回值
+    ///
+    /// 成功时返回 (`.xlsx` 文件内容, 记录数量)
+    pub fn export_to_xlsx(
+        manager: &TransactionManager,
+        options: &ExportOptions,
+    ) -> Result<(Vec<u8>, usize)> {
+        let transactions = Self::select_transactions(manager, options)?;
+        let notes = Self::fetch_notes(manager, options)?;
+        let content =
+            Self::transactions_to_xlsx(&transactions, options.summary_row, notes.as_ref())?;
+        Ok((content, transactions.len()))
+    }
+
+    /// Transactions matching the filters and limit of `options`
+    fn select_transactions(
+        manager: &TransactionManager,
+        options: &ExportOptions,
+    ) -> Result<Vec<Transaction>> {
+        let filter_opt = Self::build_filter_options(options)?;
+        if let Some(limit) = options.limit {
+            manager.fetch_recent(&filter_opt, limit)
+        } else if Self::has_any_filter(options) {
+            manager.fetch_filtered(&filter_opt)
+        } else {
+            manager.fetch_all()
+        }
+    }
+
+    /// 导出按商家汇总的 CSV 报表
+    ///
+    /// 筛选条件与逐条导出相同，`options.output` 和 `options.report` 会被忽略。
+    ///
+    /// # 参数
+    ///
+    /// * `manager` - 交易管理器实例
+    /// * `options` - 导出选项的引用
+    ///
+    /// # 返回值
+    ///
+    /// 成功时返回 (CSV字符串内容, 商家数量)
+    pub fn export_merchant_report(
+        manager: &TransactionManager,
+        options: &ExportOptions,
+    ) -> Result<(String, usize)> {
+        let filter_opt = Self::build_filter_options(options)?;
+        let totals = manager.fetch_merchant_totals(&filter_opt)?;
+
+        let eol = options.line_ending.as_str();
+        let mut csv_content = format!("Merchant,Count,Total{}", eol);
+        for total in &totals {
+            csv_content.push_str(&format!(
+                "\"{}\",{},{}{}",
+                total.merchant.replace("\"", "\"\""),
+                total.count,
+                format_amount_plain(total.total),
+                eol
+            ));
+        }
+
+        Ok((csv_content, totals.len()))
+    }
+
     /// 将交易记录转换为 CSV 字符串
     ///
     /// 与 write_transactions_to_csv 保持相同的格式，但输出到字符串而不是文件
@@ -360,27 +744,494 @@ impl CsvExporter {
     /// # 参数
     ///
     /// * `transactions` - 交易记录数组
+    /// * `summary_row` - 是否在末尾追加合计行
+    /// * `line_ending` - 行尾换行符
+    /// * `notes` - 交易备注，为 `Some` 时追加备注列
     ///
     /// # 返回值
     ///
     /// CSV 格式的字符串
-    fn transactions_to_csv_string(transactions: &[Transaction]) -> Result<String> {
-        let mut csv_content = String::new();
+    fn transactions_to_csv_string(
+        transactions: &[Transaction],
+        summary_row: bool,
+        line_ending: LineEnding,
+        notes: Option<&HashMap<i64, String>>,
+    ) -> Result<String> {
+        let mut buf = Vec::new();
+        Self::write_to(transactions, &mut buf, line_ending, notes)?;
+        if summary_row {
+            Self::write_summary_row(transactions, &mut buf, line_ending, notes.is_some())?;
+        }
+        // every field is either ASCII or taken from a valid UTF-8 string
+        Ok(String::from_utf8(buf)?)
+    }
 
-        // 写入表头（与文件版本格式一致）
-        csv_content.push_str("ID,Time,Amount,Merchant\n");
+    /// A workbook with a bold header row, typed Time and Amount cells,
+    /// and optional Note column and total row
+    fn transactions_to_xlsx(
+        transactions: &[Transaction],
+        summary_row: bool,
+        notes: Option<&HashMap<i64, String>>,
+    ) -> Result<Vec<u8>> {
+        let header_format = Format::new().set_bold();
+        let time_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+        let amount_format = Format::new().set_num_format("0.00");
 
-        // 写入数据行（与文件版本格式一致）
-        for transaction in transactions {
-            csv_content.push_str(&format!(
-                "{},{},{},\"{}\"\n",
-                transaction.id,
-                transaction.time.format("%Y-%m-%d %H:%M:%S %z"),
-                transaction.amount,
-                transaction.merchant.replace("\"", "\"\"")
-            ));
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Transactions")?;
+        for (col, title) in (0..).zip(["ID", "Time", "Amount", "Merchant"]) {
+            sheet.write_string_with_format(0, col, title, &header_format)?;
+        }
+        if notes.is_some() {
+            sheet.write_string_with_format(0, 4, "Note", &header_format)?;
         }
+        for (row, transaction) in (1..).zip(transactions) {
+            // ids have more digits than an Excel number keeps
+            sheet.write_string(row, 0, transaction.id.to_string())?;
+            sheet.write_datetime_with_format(
+                row,
+                1,
+                transaction.time.naive_local(),
+                &time_format,
+            )?;
+            sheet.write_number_with_format(
+                row,
+                2,
+                round_amount(transaction.amount),
+                &amount_format,
+            )?;
+            sheet.write_string(row, 3, &transaction.merchant)?;
+            if let Some(note) = notes.and_then(|notes| notes.get(&transaction.id)) {
+                sheet.write_string(row, 4, note)?;
+            }
+        }
+        if summary_row {
+            let row = transactions.len() as u32 + 1;
+            let total: f64 = transactions.iter().map(|t| t.amount).sum();
+            sheet.write_number_with_format(row, 2, round_amount(total), &amount_format)?;
+            sheet.write_string(row, 3, format!("TOTAL ({} rows)", transactions.len()))?;
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+
+        workbook
+            .save_to_buffer()
+            .context("Failed to build the xlsx file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merchant_report() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.1, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-15.2, "Canteen", "2025-03-02 12:00:00").unwrap(),
+                Transaction::from_local(-30.0, "\"Big\" Market", "2025-03-03 12:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        let options = ExportOptions {
+            output: None,
+            merchant: None,
+            min_amount: None,
+            max_amount: None,
+            time_start: None,
+            time_end: None,
+            report: Some(ReportKind::Merchant),
+            limit: None,
+            summary_row: false,
+            line_ending: LineEnding::Lf,
+            timestamp: false,
+            split_by: None,
+            notes: false,
+        };
+        let (csv_content, count) = CsvExporter::export_merchant_report(&manager, &options).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            csv_content,
+            "Merchant,Count,Total\n\"\"\"Big\"\" Market\",1,-30.00\n\"Canteen\",2,-25.30\n"
+        );
+
+        let options = ExportOptions {
+            merchant: Some("Canteen".to_string()),
+            ..options
+        };
+        let (_, count) = CsvExporter::export_merchant_report(&manager, &options).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn export_xlsx() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-12.5, "Market", "2025-03-02 12:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        let options = ExportOptions {
+            output: None,
+            merchant: Some("Canteen".to_string()),
+            min_amount: None,
+            max_amount: None,
+            time_start: None,
+            time_end: None,
+            report: None,
+            limit: None,
+            summary_row: true,
+            line_ending: LineEnding::Lf,
+            timestamp: false,
+            split_by: None,
+            notes: false,
+        };
+        let (content, count) = CsvExporter::export_to_xlsx(&manager, &options).unwrap();
+        assert_eq!(count, 1);
+        // an xlsx file is a zip archive
+        assert!(content.starts_with(b"PK\x03\x04"));
+    }
+
+    #[test]
+    fn export_limit() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-12.0, "Market", "2025-03-02 12:00:00").unwrap(),
+                Transaction::from_local(-14.0, "Canteen", "2025-03-03 12:00:00").unwrap(),
+                Transaction::from_local(-16.0, "Canteen", "2025-03-04 12:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        let options = ExportOptions {
+            output: None,
+            merchant: None,
+            min_amount: None,
+            max_amount: None,
+            time_start: None,
+            time_end: None,
+            report: None,
+            limit: Some(2),
+            summary_row: false,
+            line_ending: LineEnding::Lf,
+            timestamp: false,
+            split_by: None,
+            notes: false,
+        };
+        let amounts = |csv: &str| {
+            csv.lines()
+                .skip(1)
+                .map(|l| l.split(',').nth(2).unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let (csv_content, count) = CsvExporter::export_to_string(&manager, &options).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(amounts(&csv_content), vec!["-16.00", "-14.00"]);
+
+        // applied after the other filters
+        let options = ExportOptions {
+            merchant: Some("Canteen".to_string()),
+            time_end: Some("2025-03-03".to_string()),
+            ..options
+        };
+        let (csv_content, count) = CsvExporter::export_to_string(&manager, &options).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(amounts(&csv_content), vec!["-14.00", "-10.00"]);
+    }
+
+    #[test]
+    fn amount_range_inclusive() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-9.99, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-10.000000001, "Canteen", "2025-03-02 12:00:00").unwrap(),
+                Transaction::from_local(-50.0, "Canteen", "2025-03-03 12:00:00").unwrap(),
+                Transaction::from_local(-50.01, "Canteen", "2025-03-04 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let count = |min_amount, max_amount| {
+            let options = ExportOptions {
+                output: None,
+                merchant: None,
+                min_amount,
+                max_amount,
+                time_start: None,
+                time_end: None,
+                report: None,
+                limit: None,
+                summary_row: false,
+                line_ending: LineEnding::Lf,
+                timestamp: false,
+                split_by: None,
+                notes: false,
+            };
+            CsvExporter::export_to_string(&manager, &options).unwrap().1
+        };
+
+        // both bounds are inclusive
+        assert_eq!(count(Some(10.0), Some(50.0)), 2);
+        assert_eq!(count(Some(10.0), None), 3);
+        assert_eq!(count(None, Some(50.0)), 3);
+    }
+
+    #[test]
+    fn write_to() {
+        let transactions = vec![
+            Transaction::from_local(-10.5, "Canteen", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(20.0, "\"Big\" Market", "2025-03-02 08:30:00").unwrap(),
+        ];
+        let mut buf = Vec::new();
+        CsvExporter::write_to(&transactions, &mut buf, LineEnding::Lf, None).unwrap();
+        let written = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            written,
+            CsvExporter::transactions_to_csv_string(&transactions, false, LineEnding::Lf, None)
+                .unwrap()
+        );
+        insta::assert_snapshot!(written);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        CsvExporter::write_transactions_to_csv(&transactions, &path, false, LineEnding::Lf, None)
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), written);
+    }
+
+    #[test]
+    fn note_column() {
+        let manager = TransactionManager::new(None).unwrap();
+        let transactions = vec![
+            Transaction::from_local(-10.5, "Canteen", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-20.0, "Market", "2025-03-02 08:30:00").unwrap(),
+        ];
+        manager.insert(&transactions).unwrap();
+        manager
+            .set_note(transactions[0].id, "lunch with \"Bob\"")
+            .unwrap();
+
+        let mut options = ExportOptions {
+            output: None,
+            merchant: None,
+            min_amount: None,
+            max_amount: None,
+            time_start: None,
+            time_end: None,
+            report: None,
+            limit: None,
+            summary_row: true,
+            line_ending: LineEnding::Lf,
+            timestamp: false,
+            split_by: None,
+            notes: false,
+        };
+        let (csv_content, _) = CsvExporter::export_to_string(&manager, &options).unwrap();
+        assert!(csv_content.starts_with("ID,Time,Amount,Merchant\n"));
+        assert!(!csv_content.contains("Bob"));
+
+        options.notes = true;
+        let (csv_content, _) = CsvExporter::export_to_string(&manager, &options).unwrap();
+        let lines = csv_content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "ID,Time,Amount,Merchant,Note");
+        let noted = lines
+            .iter()
+            .find(|l| l.starts_with(&transactions[0].id.to_string()))
+            .unwrap();
+        assert!(noted.ends_with(",\"Canteen\",\"lunch with \"\"Bob\"\"\""));
+        let plain = lines
+            .iter()
+            .find(|l| l.starts_with(&transactions[1].id.to_string()))
+            .unwrap();
+        assert!(plain.ends_with(",\"Market\","));
+        assert_eq!(lines[3], ",,-30.50,\"TOTAL (2 rows)\",");
+    }
+
+    #[test]
+    fn summary_row() {
+        let transactions = vec![
+            Transaction::from_local(-10.1, "Canteen", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-15.2, "Market", "2025-03-02 12:00:00").unwrap(),
+        ];
+        let csv_content =
+            CsvExporter::transactions_to_csv_string(&transactions, true, LineEnding::Lf, None)
+                .unwrap();
+        assert_eq!(csv_content.lines().count(), 4);
+        assert_eq!(
+            csv_content.lines().last().unwrap(),
+            ",,-25.30,\"TOTAL (2 rows)\""
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        CsvExporter::write_transactions_to_csv(&transactions, &path, true, LineEnding::Lf, None)
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), csv_content);
+
+        let empty =
+            CsvExporter::transactions_to_csv_string(&[], true, LineEnding::Lf, None).unwrap();
+        assert_eq!(
+            empty,
+            "ID,Time,Amount,Merchant\n,,0.00,\"TOTAL (0 rows)\"\n"
+        );
+    }
+
+    #[test]
+    fn date_range() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-1.0, "Early", "2025-02-28 23:59:59").unwrap(),
+                Transaction::from_local(-2.0, "First", "2025-03-01 00:00:00").unwrap(),
+                Transaction::from_local(-3.0, "Last", "2025-03-01 23:59:59").unwrap(),
+                Transaction::from_local(-4.0, "Late", "2025-03-02 00:00:00").unwrap(),
+            ])
+            .unwrap();
+        let mut options = ExportOptions {
+            output: None,
+            merchant: None,
+            min_amount: None,
+            max_amount: None,
+            time_start: Some("2025-03-01".to_string()),
+            time_end: Some("2025-03-01".to_string()),
+            report: None,
+            limit: None,
+            summary_row: false,
+            line_ending: LineEnding::Lf,
+            timestamp: false,
+            split_by: None,
+            notes: false,
+        };
+        // both ends are whole days in UTC+8
+        let (csv_content, count) = CsvExporter::export_to_string(&manager, &options).unwrap();
+        assert_eq!(count, 2);
+        assert!(csv_content.contains("First") && csv_content.contains("Last"));
+
+        options.time_end = Some("2025-02-30".to_string());
+        assert!(CsvExporter::export_to_string(&manager, &options).is_err());
+        options.time_end = Some("2025-02-28".to_string());
+        assert!(CsvExporter::export_to_string(&manager, &options).is_err());
+    }
 
-        Ok(csv_content)
+    #[test]
+    fn default_output() {
+        let time = chrono::NaiveDate::from_ymd_opt(2025, 3, 1)
+            .unwrap()
+            .and_hms_opt(9, 5, 7)
+            .unwrap();
+        assert_eq!(
+            default_output_path("transactions_export", None),
+            "transactions_export.csv"
+        );
+        assert_eq!(
+            default_output_path("transactions_export", Some(time)),
+            "transactions_export_20250301_090507.csv"
+        );
+        assert_eq!(
+            default_output_path("merchant_report", Some(time)),
+            "merchant_report_20250301_090507.csv"
+        );
+        assert_eq!(
+            default_output_stem("transactions_export", Some(time)),
+            "transactions_export_20250301_090507"
+        );
+    }
+
+    #[test]
+    fn split_by_month() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-01-31 23:30:00").unwrap(),
+                Transaction::from_local(-5.5, "Market", "2025-02-01 08:00:00").unwrap(),
+                Transaction::from_local(-3.0, "Canteen", "2025-02-15 12:00:00").unwrap(),
+                Transaction::from_local(-8.0, "Canteen", "2025-04-02 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("archive");
+        let options = ExportOptions {
+            output: Some(out.to_str().unwrap().to_string()),
+            merchant: Some("Canteen".to_string()),
+            min_amount: None,
+            max_amount: None,
+            time_start: None,
+            time_end: None,
+            report: None,
+            limit: None,
+            summary_row: true,
+            line_ending: LineEnding::Lf,
+            timestamp: false,
+            split_by: Some(SplitBy::Month),
+            notes: false,
+        };
+        assert_eq!(CsvExporter::execute_export(&manager, &options).unwrap(), 3);
+
+        let mut files: Vec<_> = std::fs::read_dir(&out)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        // months without matching transactions get no file
+        assert_eq!(
+            files,
+            [
+                "transactions_2025-01.csv",
+                "transactions_2025-02.csv",
+                "transactions_2025-04.csv"
+            ]
+        );
+        let feb = std::fs::read_to_string(out.join("transactions_2025-02.csv")).unwrap();
+        let lines: Vec<_> = feb.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(",-3.00,\"Canteen\""));
+        assert_eq!(lines[2], ",,-3.00,\"TOTAL (1 rows)\"");
+    }
+
+    #[test]
+    fn crlf() {
+        let transactions = vec![
+            Transaction::from_local(-10.1, "Canteen", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-15.2, "Market", "2025-03-02 12:00:00").unwrap(),
+        ];
+        let lf = CsvExporter::transactions_to_csv_string(&transactions, true, LineEnding::Lf, None)
+            .unwrap();
+        let crlf =
+            CsvExporter::transactions_to_csv_string(&transactions, true, LineEnding::Crlf, None)
+                .unwrap();
+        assert_eq!(crlf, lf.replace('\n', "\r\n"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        CsvExporter::write_transactions_to_csv(&transactions, &path, true, LineEnding::Crlf, None)
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), crlf);
+
+        let manager = TransactionManager::new(None).unwrap();
+        manager.insert(&transactions).unwrap();
+        let options = ExportOptions {
+            output: None,
+            merchant: None,
+            min_amount: None,
+            max_amount: None,
+            time_start: None,
+            time_end: None,
+            report: Some(ReportKind::Merchant),
+            limit: None,
+            summary_row: false,
+            line_ending: LineEnding::Crlf,
+            timestamp: false,
+            split_by: None,
+            notes: false,
+        };
+        let (report, _) = CsvExporter::export_merchant_report(&manager, &options).unwrap();
+        assert_eq!(
+            report,
+            "Merchant,Count,Total\r\n\"Market\",1,-15.20\r\n\"Canteen\",1,-10.10\r\n"
+        );
     }
 }
@@ -37,8 +37,9 @@
 //! ### 生产环境（真实数据获取）
 //!
 //! ```rust
+//! use std::num::NonZeroU32;
 //! use chrono::{DateTime, FixedOffset};
-//! use crate::libs::fetcher::{MealFetcher, RealMealFetcher, fetch};
+//! use crate::libs::fetcher::{MealFetcher, RealMealFetcher, fetch_flushing};
 //! use crate::page::fetch::FetchProgress;
 //!
 //! // 配置获取器
@@ -48,12 +49,23 @@
 //!         .account("your_student_id")
 //! );
 //!
-//! // 获取交易记录
+//! // 获取交易记录（从最新一直获取到 end_time，包含 end_time 时刻的交易），每 5 页交出一批
 //! let end_time = chrono::Utc::now().with_timezone(&FixedOffset::east(8 * 3600));
-//! let transactions = fetch(end_time, fetcher, |progress| {
-//!     println!("Progress: {:?}", progress);
-//!     Ok(())
-//! })?;
+//! let mut transactions = vec![];
+//! fetch_flushing(
+//!     end_time,
+//!     fetcher,
+//!     1,
+//!     |progress| {
+//!         println!("Progress: {:?}", progress);
+//!         Ok(())
+//!     },
+//!     NonZeroU32::new(5).unwrap(),
+//!     |batch| {
+//!         transactions.extend(batch);
+//!         Ok(())
+//!     },
+//! )?;
 //! ```
 //!
 //! ### 测试环境（模拟数据）
@@ -70,12 +82,45 @@
 //! );
 //! ```
 //!
+//! ### 测试中按脚本返回每页响应
+//!
+//! 仅在测试中可用的 [`ScriptedMealFetcher`] 按顺序返回给定的每页响应，
+//! 便于构造空页、格式错误、Cookie 失效或请求出错等边界情况：
+//!
+//! ```rust
+//! let fetcher = MealFetcher::Scripted(ScriptedMealFetcher::new(vec![
+//!     Ok(ScriptedMealFetcher::page_of(&transactions)),
+//!     Err(eyre!("connection reset")),
+//! ]));
+//! ```
+//!
 //! ## 错误处理
 //!
 //! - **认证失败**: Cookie 过期或无效时会返回解析错误，建议重新登录
 //! - **网络错误**: 自动重试机制（最多3次），每次间隔1秒
 //! - **数据解析**: 严格的 JSON 格式验证，字段缺失时提供详细错误信息
 //!
+//! ## 代理
+//!
+//! 通过 [`RealMealFetcher::proxy`] 设置 http、https 或 socks5 代理，便于在校外通过代理访问；
+//! 未设置时 reqwest 会读取 `HTTP_PROXY`、`HTTPS_PROXY`、`ALL_PROXY` 等环境变量。
+//!
+//! ## 阻塞与异步
+//!
+//! [`fetch_flushing`] 使用阻塞的 HTTP 客户端，适用于命令行和 `spawn_blocking`；
+//! [`fetch_async`] 使用异步客户端，可以在 tokio 任务中直接 `.await`，
+//! 两者共用请求构造和分页逻辑。
+//!
+//! ## 取消
+//!
+//! [`fetch_async`] 接收一个 [`CancellationToken`]，取消后不再请求后续页面，
+//! 正在进行的请求也会被放弃，并返回取消前已获取的记录。Web 接口在客户端断开连接时借此停止获取。
+//!
+//! ## 增量保存
+//!
+//! [`fetch_flushing`] 每获取若干页就通过回调交出这些记录，出错时也先交出已获取的部分，
+//! 调用方可以边获取边写入数据库，长时间获取中途失败时不会丢失已获取的页。
+//!
 //! ## 进度回调
 //!
 //! 获取过程支持进度回调，用于 UI 更新：
@@ -103,12 +148,23 @@ use color_eyre::{
 use reqwest::{blocking::Client, header};
 use serde::{Deserialize, Serialize};
 use std::{
+    num::NonZeroU32,
     str::{self},
     thread::sleep,
-    time::Duration,
+    time::{Duration, Instant},
+};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(test)]
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
 };
 
-use crate::{libs::transactions::Transaction, page::fetch::FetchProgress};
+use crate::{
+    libs::transactions::{LOCAL_TIME_FORMAT, Transaction},
+    page::fetch::FetchProgress,
+};
 
 /// XJTU 校园卡系统 API 基础地址
 pub const API_ORIGIN: &str = "http://card.xjtu.edu.cn";
@@ -116,6 +172,24 @@ pub const API_ORIGIN: &str = "http://card.xjtu.edu.cn";
 /// 获取个人交易记录的 API 路径
 pub const API_PATH: &str = "/Report/GetPersonTrjn";
 
+/// 查询校园卡信息（含余额）的 API 路径
+pub const BALANCE_PATH: &str = "/User/GetCardInfoByAccountNoParm";
+
+/// 服务器返回了 HTML 等非 JSON 内容
+///
+/// 通常是 Cookie 失效后被重定向到了登录页。可通过
+/// `report.chain().any(|e| e.is::<SessionExpired>())` 与网络错误等其他错误区分。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionExpired;
+
+impl std::fmt::Display for SessionExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Your session expired, please re-enter your cookie")
+    }
+}
+
+impl std::error::Error for SessionExpired {}
+
 /// XJTU 校园卡 API 响应数据结构
 ///
 /// 包含交易记录列表的 JSON 响应格式
@@ -133,7 +207,7 @@ struct TransactionRow {
     /// 交易发生时间（字符串格式）
     ///
     /// API 字段名: `OCCTIME`
-    /// 格式: "YYYY-MM-DD HH:MM:SS"
+    /// 格式: "YYYY-MM-DD HH:MM:SS"，其他变体见 [`Transaction::parse_api_time`]
     #[serde(rename = "OCCTIME")]
     time: String,
 
@@ -152,11 +226,22 @@ struct TransactionRow {
     merchant: String,
 }
 
+impl From<&Transaction> for TransactionRow {
+    fn from(t: &Transaction) -> Self {
+        Self {
+            time: t.time.format(LOCAL_TIME_FORMAT).to_string(),
+            amount: t.amount,
+            merchant: t.merchant.clone(),
+        }
+    }
+}
+
 /// 校园卡数据获取器的统一接口
 ///
 /// 提供生产环境和测试环境两种数据获取模式：
 /// - `Real`: 从 XJTU 校园卡系统获取真实数据
 /// - `Mock`: 生成模拟数据用于测试和开发
+/// - `Scripted`: 按顺序返回给定的每页响应，仅用于测试
 ///
 /// ## 设计模式
 ///
@@ -173,6 +258,12 @@ pub enum MealFetcher {
     ///
     /// 生成随机的模拟交易数据，用于测试和演示
     Mock(MockMealFetcher),
+
+    /// 脚本获取器
+    ///
+    /// 按顺序返回预先给定的每页响应，用于测试边界情况
+    #[cfg(test)]
+    Scripted(ScriptedMealFetcher),
 }
 
 impl From<RealMealFetcher> for MealFetcher {
@@ -185,6 +276,12 @@ impl From<MockMealFetcher> for MealFetcher {
         MealFetcher::Mock(fetcher)
     }
 }
+#[cfg(test)]
+impl From<ScriptedMealFetcher> for MealFetcher {
+    fn from(fetcher: ScriptedMealFetcher) -> Self {
+        MealFetcher::Scripted(fetcher)
+    }
+}
 
 impl Default for MealFetcher {
     fn default() -> Self {
@@ -192,6 +289,46 @@ impl Default for MealFetcher {
     }
 }
 
+impl MealFetcher {
+    /// Number of records requested per page
+    pub fn page_size(&self) -> u32 {
+        match self {
+            MealFetcher::Real(c) => c.per_page,
+            MealFetcher::Mock(c) => c.per_page,
+            #[cfg(test)]
+            MealFetcher::Scripted(c) => c.per_page,
+        }
+    }
+
+    /// 查询校园卡当前余额（元），见 [`RealMealFetcher::fetch_balance`]
+    pub fn fetch_balance(&self) -> Result<f64> {
+        match self {
+            MealFetcher::Real(c) => c.fetch_balance(),
+            MealFetcher::Mock(_) => Ok(MockMealFetcher::BALANCE),
+            #[cfg(test)]
+            MealFetcher::Scripted(_) => bail!("No balance scripted"),
+        }
+    }
+
+    fn fetch_transaction_one_page(&self, page: u32) -> Result<String> {
+        match self {
+            MealFetcher::Real(c) => c.fetch_transaction_one_page(page),
+            MealFetcher::Mock(c) => c.fetch_transaction_one_page(page),
+            #[cfg(test)]
+            MealFetcher::Scripted(c) => c.fetch_transaction_one_page(page),
+        }
+    }
+
+    async fn fetch_transaction_one_page_async(&self, page: u32) -> Result<String> {
+        match self {
+            MealFetcher::Real(c) => c.fetch_transaction_one_page_async(page).await,
+            MealFetcher::Mock(c) => c.fetch_transaction_one_page_async(page).await,
+            #[cfg(test)]
+            MealFetcher::Scripted(c) => c.fetch_transaction_one_page_async(page).await,
+        }
+    }
+}
+
 /// 真实数据获取器
 ///
 /// 负责与 XJTU 校园卡系统进行 HTTP 通信，获取用户的真实交易记录。
@@ -232,6 +369,11 @@ pub struct RealMealFetcher {
     /// 控制单次 API 请求获取的交易记录数量，
     /// 影响请求频率和内存使用
     per_page: u32,
+
+    /// 代理地址
+    ///
+    /// 为 `None` 时由 reqwest 读取 `HTTP_PROXY` 等环境变量
+    proxy: Option<String>,
 }
 
 impl Default for RealMealFetcher {
@@ -241,6 +383,7 @@ impl Default for RealMealFetcher {
             account: Default::default(),
             origin: API_ORIGIN.into(),
             per_page: 50,
+            proxy: None,
         }
     }
 }
@@ -292,14 +435,13 @@ impl RealMealFetcher {
         }
     }
 
-    /// 设置 API 服务器地址（仅测试用）
+    /// 设置 API 服务器地址
     ///
-    /// 允许在测试环境中指向 Mock 服务器
+    /// 允许指向 Mock 服务器，或通过 `XJTU_MEALFLOW_API_ORIGIN` 指向本地回放服务器调试
     ///
     /// # 参数
     ///
     /// * `origin` - 服务器地址，如 "http://localhost:3000"
-    #[cfg(test)]
     pub fn origin<T: Into<String>>(self, origin: T) -> Self {
         Self {
             origin: origin.into(),
@@ -307,6 +449,16 @@ impl RealMealFetcher {
         }
     }
 
+    /// 设置代理
+    ///
+    /// # 参数
+    ///
+    /// * `proxy` - 代理地址，支持 http、https 和 socks5，如 "socks5://127.0.0.1:1080"。
+    ///   为 `None` 时使用 `HTTP_PROXY`、`HTTPS_PROXY`、`ALL_PROXY` 等环境变量
+    pub fn proxy(self, proxy: Option<String>) -> Self {
+        Self { proxy, ..self }
+    }
+
     /// 设置每页记录数
     ///
     /// 控制单次 API 请求获取的交易记录数量。
@@ -327,9 +479,59 @@ impl RealMealFetcher {
         }
     }
 
-    fn fetch_transaction_one_page(&self, page: u32) -> Result<String> {
-        let client = Client::new();
+    /// 发送一次请求，验证账号和 Cookie 是否可用
+    ///
+    /// 只请求第一页，并确认返回内容可以解析为交易记录。
+    ///
+    /// # 返回值
+    ///
+    /// 成功时返回第一页中的消费记录条数。Cookie 失效时错误链中包含 [`SessionExpired`]
+    pub fn validate(&self) -> Result<usize> {
+        let page = self.fetch_transaction_one_page(1)?;
+        Ok(api_response_to_transactions(&page)?.len())
+    }
+
+    /// The configured proxy, `None` to leave it to reqwest's environment lookup
+    fn build_proxy(&self) -> Result<Option<reqwest::Proxy>> {
+        self.proxy
+            .as_deref()
+            .map(|proxy| {
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy: {}", proxy))
+            })
+            .transpose()
+    }
+
+    fn build_client(&self) -> Result<Client> {
+        let builder = Client::builder();
+        let builder = match self.build_proxy()? {
+            Some(proxy) => builder.proxy(proxy),
+            None => builder,
+        };
+        builder.build().context("Failed to build HTTP client")
+    }
+
+    fn build_async_client(&self) -> Result<reqwest::Client> {
+        let builder = reqwest::Client::builder();
+        let builder = match self.build_proxy()? {
+            Some(proxy) => builder.proxy(proxy),
+            None => builder,
+        };
+        builder.build().context("Failed to build HTTP client")
+    }
 
+    /// Build the url, headers and form body for one page
+    fn build_request(&self, page: u32) -> Result<(String, header::HeaderMap, String)> {
+        let (account, headers) = self.build_headers()?;
+        let body = format!(
+            "account={}&page={}&json=true&rows={}",
+            account, page, self.per_page
+        );
+
+        Ok((format!("{}{}", &self.origin, API_PATH), headers, body))
+    }
+
+    /// The account and the headers shared by every request
+    fn build_headers(&self) -> Result<(String, header::HeaderMap)> {
         let cookie = self.cookie.clone().ok_or(eyre!("Cookie not set"))?;
         let account = self.account.clone().ok_or(eyre!("Account not set"))?;
 
@@ -364,10 +566,76 @@ impl RealMealFetcher {
         headers.insert(header::USER_AGENT, "".parse().unwrap());
         headers.insert(header::COOKIE, cookie.parse().context("Invalid cookie")?);
 
-        let body = format!(
-            "account={}&page={}&json=true&rows={}",
-            account, page, self.per_page
-        );
+        Ok((account, headers))
+    }
+
+    /// 查询校园卡当前余额
+    ///
+    /// 与交易记录使用不同的接口（[`BALANCE_PATH`]），但使用相同的账号、Cookie 和代理。
+    ///
+    /// # 返回值
+    ///
+    /// 余额（元）。Cookie 失效时错误链中包含 [`SessionExpired`]
+    pub fn fetch_balance(&self) -> Result<f64> {
+        let client = self.build_client()?;
+        let (account, headers) = self.build_headers()?;
+
+        let response = client
+            .post(format!("{}{}", &self.origin, BALANCE_PATH))
+            .headers(headers)
+            .body(format!("account={}&json=true", account))
+            .send()
+            .map_err(|e| eyre!("Request error: {}", e))?;
+        if !response.status().is_success() {
+            bail!("Request failed with status: {}", response.status());
+        }
+        let response = response
+            .text()
+            .map_err(|e| eyre!("Failed to parse response: {}", e))?;
+        api_response_to_balance(&response)
+    }
+
+    /// 生成与请求一页交易记录等价的 `curl` 命令，便于在应用外复现请求
+    ///
+    /// 与实际请求使用相同的 URL、请求头和请求体，设置了代理时一并带上。
+    ///
+    /// # 参数
+    ///
+    /// * `page` - 页码，从 1 开始
+    /// * `include_cookie` - 为 `false` 时 Cookie 替换为 `<redacted>`，便于直接贴到问题反馈中
+    ///
+    /// # 返回值
+    ///
+    /// 多行的 `curl` 命令，未设置账号或 Cookie 时返回错误
+    pub fn to_curl(&self, page: u32, include_cookie: bool) -> Result<String> {
+        let (url, headers, body) = self.build_request(page)?;
+        let quote = |s: &str| format!("'{}'", s.replace('\'', r"'\''"));
+
+        let mut args = vec![format!("curl -X POST {}", quote(&url))];
+        for (name, value) in &headers {
+            let value = if name == header::COOKIE && !include_cookie {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or_default()
+            };
+            // `-H 'name:'` would drop the header, `name;` sends it empty
+            args.push(if value.is_empty() {
+                format!("-H {}", quote(&format!("{};", name)))
+            } else {
+                format!("-H {}", quote(&format!("{}: {}", name, value)))
+            });
+        }
+        if let Some(proxy) = &self.proxy {
+            args.push(format!("--proxy {}", quote(proxy)));
+        }
+        args.push("--compressed".to_string());
+        args.push(format!("--data-raw {}", quote(&body)));
+        Ok(args.join(" \\\n  "))
+    }
+
+    fn fetch_transaction_one_page(&self, page: u32) -> Result<String> {
+        let client = self.build_client()?;
+        let (url, headers, body) = self.build_request(page)?;
 
         // Attempt request with retry logic
         let mut attempts = 0;
@@ -376,7 +644,7 @@ impl RealMealFetcher {
 
         while attempts < max_attempts {
             match client
-                .post(format!("{}{}", &self.origin, API_PATH))
+                .post(&url)
                 .headers(headers.clone())
                 .body(body.clone())
                 .send()
@@ -409,9 +677,47 @@ impl RealMealFetcher {
         // If we get here, all attempts failed
         bail!(last_error.unwrap_or_else(|| eyre!("Failed to fetch transactions")))
     }
+
+    /// Async version of `fetch_transaction_one_page`, retries without blocking a thread
+    async fn fetch_transaction_one_page_async(&self, page: u32) -> Result<String> {
+        let client = self.build_async_client()?;
+        let (url, headers, body) = self.build_request(page)?;
+
+        let max_attempts = 3;
+        let mut last_error = None;
+
+        for _ in 0..max_attempts {
+            match client
+                .post(&url)
+                .headers(headers.clone())
+                .body(body.clone())
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => match response.text().await {
+                    Ok(api_response) => return Ok(api_response),
+                    Err(e) => last_error = Some(eyre!("Failed to parse response: {}", e)),
+                },
+                Ok(response) => {
+                    last_error = Some(eyre!("Request failed with status: {}", response.status()));
+                }
+                Err(e) => last_error = Some(eyre!("Request error: {}", e)),
+            }
+
+            tokio::time::sleep(Duration::new(1, 0)).await;
+        }
+
+        bail!(last_error.unwrap_or_else(|| eyre!("Failed to fetch transactions")))
+    }
 }
 
 fn api_response_to_transactions(s: &str) -> Result<Vec<Transaction>> {
+    // A login redirect comes back as an HTML page instead of JSON
+    if s.trim_start().starts_with('<') {
+        return Err(eyre!(SessionExpired))
+            .with_section(|| s.to_string().header("Incorrect API response:"));
+    }
+
     let api_response = serde_json::from_str::<ApiResponse>(s).map_err(|e| {
         if e.is_data() && format!("{}", e).contains("missing field `rows`") {
             eyre!("{}. This may indicate that your cookie has expired.", e).with_note(
@@ -423,17 +729,17 @@ fn api_response_to_transactions(s: &str) -> Result<Vec<Transaction>> {
         .with_section(|| s.to_string().header("Incorrect API response:"))
     })?;
 
-    let row_map = |row: TransactionRow| {
-        // Parse the date
-        let time_str = &row.time.trim();
-        let Ok(time) = Transaction::parse_to_fixed_utc_plus8(time_str, "%Y-%m-%d %H:%M:%S") else {
-            return None;
-        };
-
-        let amount = row.amount;
-        let merchant = row.merchant.trim().to_string();
-
-        Some(Transaction::new(amount, merchant, time))
+    let row_map = |row: TransactionRow| match Transaction::parse_api_time(row.time.trim()) {
+        Ok(time) => Some(Transaction::new(
+            row.amount,
+            row.merchant.trim().to_string(),
+            time,
+        )),
+        Err(e) => {
+            // Rows with an unparsable time are skipped
+            tracing::warn!("Skipping transaction at {}: {:#}", row.merchant.trim(), e);
+            None
+        }
     };
 
     Ok(api_response
@@ -444,63 +750,253 @@ fn api_response_to_transactions(s: &str) -> Result<Vec<Transaction>> {
         .collect())
 }
 
-pub fn fetch<F>(
+/// Parse the card info response, whose `Msg` field is itself a JSON string holding
+/// `query_card.card[0].db_balance` in cents
+fn api_response_to_balance(s: &str) -> Result<f64> {
+    if s.trim_start().starts_with('<') {
+        return Err(eyre!(SessionExpired))
+            .with_section(|| s.to_string().header("Incorrect API response:"));
+    }
+
+    let parse = || -> Result<f64> {
+        let response = serde_json::from_str::<serde_json::Value>(s)?;
+        let msg = response["Msg"]
+            .as_str()
+            .ok_or(eyre!("Missing field `Msg`"))?;
+        let msg = serde_json::from_str::<serde_json::Value>(msg)?;
+        let balance = &msg["query_card"]["card"][0]["db_balance"];
+        let cents = match balance {
+            serde_json::Value::Number(n) => n.as_f64(),
+            serde_json::Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .ok_or(eyre!("Missing or invalid field `db_balance`"))?;
+        Ok(cents / 100.0)
+    };
+    parse().map_err(|e| {
+        eyre!("Failed to parse the card balance: {}", e)
+            .with_section(|| s.to_string().header("Incorrect API response:"))
+    })
+}
+
+/// 获取的最大页数
+const MAX_PAGES: u32 = 200;
+
+/// Page bookkeeping shared by [`fetch`], [`fetch_flushing`] and [`fetch_async`]
+struct FetchCollector<F> {
     end_time: DateTime<FixedOffset>,
-    client: MealFetcher,
     progress_cb: F,
-) -> Result<Vec<Transaction>>
+    start: Instant,
+    /// Transactions not yet handed out by [`FetchCollector::take`]
+    transactions: Vec<Transaction>,
+    /// Number of transactions fetched so far, including those already taken
+    fetched: u32,
+}
+
+impl<F> FetchCollector<F>
 where
     F: Fn(FetchProgress) -> Result<()>,
 {
-    let mut all_transactions: Vec<Transaction> = Vec::new();
-    let max_pages = 200;
+    /// Start a fetch whose first requested page is `first_page`
+    fn new(end_time: DateTime<FixedOffset>, first_page: u32, progress_cb: F) -> Result<Self> {
+        let collector = Self {
+            end_time,
+            progress_cb,
+            start: Instant::now(),
+            transactions: Vec::new(),
+            fetched: 0,
+        };
+        (collector.progress_cb)(FetchProgress {
+            current_page: first_page.saturating_sub(1),
+            total_entries_fetched: 0,
+            oldest_date: None,
+            elapsed: collector.start.elapsed(),
+        })?;
+        Ok(collector)
+    }
 
-    progress_cb(FetchProgress {
-        current_page: 0,
-        total_entries_fetched: 0,
-        oldest_date: None,
-    })?;
+    /// Add the raw response of `page`, returns `true` once no more pages are needed
+    fn add_page(&mut self, page: u32, response: Result<String>) -> Result<bool> {
+        let response = response.with_context(|| format!("Error when fetching on page {}", page))?;
 
-    for page in 1..=max_pages {
-        let page_transactions = match &client {
-            MealFetcher::Real(c) => c.fetch_transaction_one_page(page),
-            MealFetcher::Mock(c) => c.fetch_transaction_one_page(page),
+        let page_transactions = api_response_to_transactions(&response).with_context(|| {
+            format!(
+                "Error when parsing data returned from XJTU server on page {}",
+                page
+            )
+        })?;
+        if page_transactions.is_empty() {
+            return Ok(true);
         }
-        .with_context(|| format!("Error when fetching on page {}", page))?;
 
-        let page_transactions =
-            api_response_to_transactions(&page_transactions).with_context(|| {
-                format!(
-                    "Error when parsing data returned from XJTU server on page {}",
-                    page
-                )
-            })?;
-        if page_transactions.is_empty() {
+        self.fetched += page_transactions.len() as u32;
+        self.transactions.extend(page_transactions);
+
+        // Check if we've reached transactions older than the end timestamp
+        let Some(last_transaction) = self.transactions.last() else {
+            bail!("No transactions fetched");
+        };
+        (self.progress_cb)(FetchProgress {
+            current_page: page,
+            total_entries_fetched: self.fetched,
+            oldest_date: Some(last_transaction.time),
+            elapsed: self.start.elapsed(),
+        })?;
+
+        // `end_time` is inclusive. Stop only once we are strictly past it, as the
+        // next page may still hold transactions at exactly `end_time`.
+        let end_timestamp = self.end_time.timestamp();
+        if last_transaction.time.timestamp() < end_timestamp {
+            // Filter out transactions older than the end timestamp
+            self.transactions
+                .retain(|t| t.time.timestamp() >= end_timestamp);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Hand out the transactions collected since the last call
+    fn take(&mut self) -> Vec<Transaction> {
+        Transaction::dedup(std::mem::take(&mut self.transactions))
+    }
+
+    fn finish(mut self) -> Vec<Transaction> {
+        self.take()
+    }
+}
+
+/// 从最新的交易开始逐页向前获取，直到 `end_time`
+///
+/// 一次性返回所有记录，仅供测试使用；应用中使用 [`fetch_flushing`] 或 [`fetch_async`]。
+///
+/// # 参数
+///
+/// * `end_time` - 要获取的最早时间（包含）。时间恰好等于 `end_time` 的交易会被保留
+/// * `client` - 真实或模拟的获取器
+/// * `progress_cb` - 每获取一页后调用的进度回调，返回错误时终止获取
+///
+/// # 返回值
+///
+/// 成功时返回 `end_time` 及之后的交易记录，已按内容去重
+#[cfg(test)]
+pub fn fetch<F>(
+    end_time: DateTime<FixedOffset>,
+    client: MealFetcher,
+    progress_cb: F,
+) -> Result<Vec<Transaction>>
+where
+    F: Fn(FetchProgress) -> Result<()>,
+{
+    let mut collector = FetchCollector::new(end_time, 1, progress_cb)?;
+    for page in 1..=MAX_PAGES {
+        if collector.add_page(page, client.fetch_transaction_one_page(page))? {
             break;
         }
+    }
+    Ok(collector.finish())
+}
 
-        all_transactions.extend(page_transactions);
+/// 从最新的交易开始逐页向前获取，直到 `end_time`，边获取边把记录交给 `flush_cb` 保存
+///
+/// 使用阻塞的 HTTP 客户端，在异步上下文中需要放入 `spawn_blocking`。
+///
+/// 每获取 `flush_every` 页调用一次 `flush_cb`，传入这几页的记录；获取结束时传入剩余的记录。
+/// 获取出错时也会先交出已获取的记录再返回错误，因此长时间获取中途失败或程序被终止时，
+/// 之前的页不会丢失。
+///
+/// 出错时，进度回调最后收到的 `current_page` 及之前的页都已交给 `flush_cb`，
+/// 以 `current_page + 1` 作为 `first_page` 再次调用即可从出错的页继续，例如更新失效的 Cookie 之后。
+///
+/// # 参数
+///
+/// * `end_time` - 要获取的最早时间（包含）。时间恰好等于 `end_time` 的交易会被保留
+/// * `client` - 真实或模拟的获取器
+/// * `first_page` - 从第几页开始获取，从头获取时为 1
+/// * `progress_cb` - 每获取一页后调用的进度回调，返回错误时终止获取。
+///   开始前会先以 `current_page: first_page - 1` 调用一次
+/// * `flush_every` - 每隔多少页保存一次
+/// * `flush_cb` - 保存一批记录，返回错误时终止获取
+///
+/// # 返回值
+///
+/// 成功时返回交给 `flush_cb` 的记录总数
+pub fn fetch_flushing<F, G>(
+    end_time: DateTime<FixedOffset>,
+    client: MealFetcher,
+    first_page: u32,
+    progress_cb: F,
+    flush_every: NonZeroU32,
+    mut flush_cb: G,
+) -> Result<usize>
+where
+    F: Fn(FetchProgress) -> Result<()>,
+    G: FnMut(Vec<Transaction>) -> Result<()>,
+{
+    let first_page = first_page.max(1);
+    let mut collector = FetchCollector::new(end_time, first_page, progress_cb)?;
+    let mut flushed = 0;
+    for page in first_page..=MAX_PAGES {
+        let done = match collector.add_page(page, client.fetch_transaction_one_page(page)) {
+            Ok(done) => done,
+            Err(e) => {
+                // keep the pages fetched before the error
+                flush_cb(collector.take())?;
+                return Err(e);
+            }
+        };
+        if done {
+            break;
+        }
+        if page % flush_every == 0 {
+            let batch = collector.take();
+            flushed += batch.len();
+            flush_cb(batch)?;
+        }
+    }
+    let rest = collector.finish();
+    flushed += rest.len();
+    flush_cb(rest)?;
+    Ok(flushed)
+}
 
-        // Check if we've reached transactions older than the end timestamp
-        if let Some(last_transaction) = all_transactions.last() {
-            progress_cb(FetchProgress {
-                current_page: page,
-                total_entries_fetched: all_transactions.len() as u32,
-                oldest_date: Some(last_transaction.time),
-            })?;
-
-            let last_timestamp = last_transaction.time.timestamp();
-            if last_timestamp <= end_time.timestamp() {
-                // Filter out transactions older than the end timestamp
-                all_transactions.retain(|t| (t.time.timestamp()) > end_time.timestamp());
+/// 从最新的交易开始逐页向前获取，直到 `end_time`，异步版本
+///
+/// 使用异步 HTTP 客户端，重试间隔通过 `tokio::time::sleep` 等待，不占用阻塞线程。
+///
+/// # 参数
+///
+/// * `end_time` - 要获取的最早时间（包含）。时间恰好等于 `end_time` 的交易会被保留
+/// * `client` - 真实或模拟的获取器
+/// * `progress_cb` - 每获取一页后调用的进度回调，返回错误时终止获取
+/// * `cancel` - 取消后放弃正在进行的请求，返回已获取的记录而不是错误
+///
+/// # 返回值
+///
+/// 成功时返回 `end_time` 及之后的交易记录，已按内容去重
+pub async fn fetch_async<F>(
+    end_time: DateTime<FixedOffset>,
+    client: MealFetcher,
+    progress_cb: F,
+    cancel: &CancellationToken,
+) -> Result<Vec<Transaction>>
+where
+    F: Fn(FetchProgress) -> Result<()>,
+{
+    let mut collector = FetchCollector::new(end_time, 1, progress_cb)?;
+    for page in 1..=MAX_PAGES {
+        let response = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                tracing::info!("Fetch cancelled before page {} was done", page);
                 break;
             }
-        } else {
-            bail!("No transactions fetched");
+            response = client.fetch_transaction_one_page_async(page) => response,
+        };
+        if collector.add_page(page, response)? {
+            break;
         }
     }
-
-    Ok(all_transactions)
+    Ok(collector.finish())
 }
 
 #[derive(Debug, Clone)]
@@ -516,12 +1012,21 @@ impl Default for MockMealFetcher {
             env!("CARGO_MANIFEST_DIR"),
             "/test/mock-data/mock-transactions.json"
         ));
-        let mut  data = serde_json::from_str::<Vec<TransactionRow>>(data).context(
+        let data = serde_json::from_str::<Vec<TransactionRow>>(data).context(
             "Failed to parse mock data. This may indicate that the mock data file is missing or corrupted.",
         ).unwrap();
+        Self::from_rows(data)
+    }
+}
 
+impl MockMealFetcher {
+    /// Card balance reported by the mock fetcher
+    pub const BALANCE: f64 = 86.5;
+
+    /// Serve `rows` newest first, as the API does
+    fn from_rows(mut data: Vec<TransactionRow>) -> Self {
         let parse_date = |date_str: &str| {
-            Transaction::parse_to_fixed_utc_plus8(date_str, "%Y-%m-%d %H:%M:%S").unwrap()
+            Transaction::parse_to_fixed_utc_plus8(date_str, LOCAL_TIME_FORMAT).unwrap()
         };
 
         data.sort_by(|a, b| {
@@ -536,9 +1041,13 @@ impl Default for MockMealFetcher {
             data,
         }
     }
-}
 
-impl MockMealFetcher {
+    /// Serve `transactions` instead of the bundled mock data, for tests needing exact datasets
+    #[cfg(test)]
+    pub fn with_transactions(transactions: Vec<Transaction>) -> Self {
+        Self::from_rows(transactions.iter().map(TransactionRow::from).collect())
+    }
+
     #[allow(dead_code)]
     pub fn set_sim_delay(self, duration: Duration) -> Self {
         Self {
@@ -556,7 +1065,17 @@ impl MockMealFetcher {
         if let Some(d) = self.sim_delay {
             sleep(d);
         }
+        self.page_response(page)
+    }
 
+    async fn fetch_transaction_one_page_async(&self, page: u32) -> Result<String> {
+        if let Some(d) = self.sim_delay {
+            tokio::time::sleep(d).await;
+        }
+        self.page_response(page)
+    }
+
+    fn page_response(&self, page: u32) -> Result<String> {
         let start = std::cmp::min((page - 1) * self.per_page, self.data.len() as u32);
         let end = std::cmp::min(start + self.per_page, self.data.len() as u32);
 
@@ -569,6 +1088,73 @@ impl MockMealFetcher {
     }
 }
 
+/// 按顺序返回给定每页响应的获取器，仅用于测试
+///
+/// 每次请求（包括重试）取出下一个响应，与请求的页码无关；响应用完后返回空页，获取随之结束。
+/// 克隆的获取器共享剩余的响应。
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct ScriptedMealFetcher {
+    responses: Arc<Mutex<VecDeque<Result<String>>>>,
+    /// Waits before each response in order, no wait once these run out
+    delays: Arc<Mutex<VecDeque<Duration>>>,
+    per_page: u32,
+}
+
+#[cfg(test)]
+impl ScriptedMealFetcher {
+    pub fn new(responses: Vec<Result<String>>) -> Self {
+        Self {
+            responses: Arc::new(Mutex::new(responses.into())),
+            delays: Default::default(),
+            per_page: 20,
+        }
+    }
+
+    /// Wait `delays[i]` before the i-th response, e.g. to script slow then fast pages
+    pub fn delays(self, delays: Vec<Duration>) -> Self {
+        *self.delays.lock().unwrap() = delays.into();
+        self
+    }
+
+    /// API response holding `transactions`
+    pub fn page_of(transactions: &[Transaction]) -> String {
+        let rows = transactions.iter().map(TransactionRow::from).collect();
+        serde_json::to_string(&ApiResponse { rows }).unwrap()
+    }
+
+    /// Number of responses not requested yet
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        self.delays.lock().unwrap().pop_front()
+    }
+
+    fn next_response(&self) -> Result<String> {
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Ok(Self::page_of(&[])))
+    }
+
+    fn fetch_transaction_one_page(&self, _page: u32) -> Result<String> {
+        if let Some(d) = self.next_delay() {
+            sleep(d);
+        }
+        self.next_response()
+    }
+
+    async fn fetch_transaction_one_page_async(&self, _page: u32) -> Result<String> {
+        if let Some(d) = self.next_delay() {
+            tokio::time::sleep(d).await;
+        }
+        self.next_response()
+    }
+}
+
 #[cfg(test)]
 pub mod test_utils {
     use crate::libs::transactions::Transaction;
@@ -615,6 +1201,56 @@ mod tests {
         println!("{:?}", transactions);
     }
 
+    #[test]
+    fn test_to_curl() {
+        let fetcher = RealMealFetcher::default()
+            .account("123456")
+            .cookie("hallticket=it's-secret")
+            .per_page(20);
+        let curl = fetcher.to_curl(2, false).unwrap();
+        insta::assert_snapshot!(curl);
+        assert!(!curl.contains("secret"));
+
+        let curl = fetcher
+            .proxy(Some("socks5://127.0.0.1:1080".to_string()))
+            .to_curl(1, true)
+            .unwrap();
+        assert!(curl.contains(r"-H 'cookie: hallticket=it'\''s-secret'"));
+        assert!(curl.contains("--proxy 'socks5://127.0.0.1:1080'"));
+
+        assert!(RealMealFetcher::default().to_curl(1, false).is_err());
+    }
+
+    #[test]
+    fn test_api_response_time_formats() {
+        let response = r#"{"rows": [
+            {"OCCTIME": "2025-03-01 12:30:00", "TRANAMT": -10.0, "MERCNAME": "A"},
+            {"OCCTIME": "2025-03-01 12:31", "TRANAMT": -11.0, "MERCNAME": "B"},
+            {"OCCTIME": "2025-03-01T12:32:00", "TRANAMT": -12.0, "MERCNAME": "C"},
+            {"OCCTIME": "yesterday", "TRANAMT": -13.0, "MERCNAME": "D"}
+        ]}"#;
+        let transactions = api_response_to_transactions(response).unwrap();
+        let merchants: Vec<&str> = transactions.iter().map(|t| t.merchant.as_str()).collect();
+        assert_eq!(merchants, ["A", "B", "C"]);
+        assert_eq!(
+            transactions[1].time.format("%H:%M:%S").to_string(),
+            "12:31:00"
+        );
+    }
+
+    #[test]
+    fn test_api_response_html() {
+        let html = "\r\n<!DOCTYPE html>\n<html><head><title>统一身份认证</title></head></html>";
+        let err = api_response_to_transactions(html)
+            .wrap_err("Error when parsing data returned from XJTU server on page 1")
+            .unwrap_err();
+        assert!(err.chain().any(|e| e.is::<SessionExpired>()));
+
+        // malformed JSON is still a generic parse error
+        let err = api_response_to_transactions("{\"rows\": [").unwrap_err();
+        assert!(!err.chain().any(|e| e.is::<SessionExpired>()));
+    }
+
     #[test]
     fn test_fetch_mock() {
         let fetcher = MockMealFetcher::default();
@@ -625,10 +1261,132 @@ mod tests {
         let transactions = fetch(end_time, MealFetcher::Mock(fetcher), |_| Ok(())).unwrap();
         assert!(!transactions.is_empty());
         transactions.iter().for_each(|t| {
-            assert!(t.time.timestamp() > end_time.timestamp());
+            assert!(t.time.timestamp() >= end_time.timestamp());
         });
     }
 
+    #[test]
+    fn test_mock_with_transactions() {
+        let start = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 8, 0, 0)
+            .unwrap();
+        let data: Vec<Transaction> = (0..45)
+            .map(|i| {
+                let time = start + CDuration::hours(i);
+                Transaction::new(-(i as f64) - 1.0, "Canteen".to_string(), time)
+            })
+            .collect();
+
+        let pages = std::sync::Mutex::new(Vec::new());
+        let client = MealFetcher::Mock(MockMealFetcher::with_transactions(data.clone()));
+        let mut transactions = fetch(start, client, |p| {
+            pages.lock().unwrap().push(p.current_page);
+            Ok(())
+        })
+        .unwrap();
+        transactions.sort_by_key(|t| t.time);
+        assert_eq!(transactions, data);
+        assert_eq!(pages.into_inner().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_fetch_end_time_inclusive() {
+        let all = test_utils::get_mock_data(1000);
+        let boundary = all[16].clone();
+        let expected: Vec<Transaction> = Transaction::dedup(
+            all.into_iter()
+                .filter(|t| t.time >= boundary.time)
+                .collect(),
+        );
+
+        // vary page sizes so the boundary lands both inside and at the end of a page.
+        // Pages holding only top-ups parse to no transactions and end the fetch, so skip 1.
+        for per_page in 2..=6 {
+            let fetcher = MockMealFetcher::default().per_page(per_page);
+            let transactions =
+                fetch(boundary.time, MealFetcher::Mock(fetcher), |_| Ok(())).unwrap();
+            assert!(
+                transactions.contains(&boundary),
+                "transaction at end_time should be kept, per_page = {}",
+                per_page
+            );
+            assert_eq!(transactions, expected, "per_page = {}", per_page);
+        }
+    }
+
+    #[test]
+    fn test_fetch_flushing() {
+        let end_time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+            .unwrap();
+        let client = MealFetcher::Mock(MockMealFetcher::default().per_page(5));
+        let expected = fetch(end_time, client.clone(), |_| Ok(())).unwrap();
+
+        let mut batches = Vec::new();
+        let flushed = fetch_flushing(
+            end_time,
+            client.clone(),
+            1,
+            |_| Ok(()),
+            NonZeroU32::new(2).unwrap(),
+            |batch| {
+                batches.push(batch);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert!(batches.len() > 2, "should flush while fetching");
+        assert_eq!(flushed, batches.iter().map(Vec::len).sum::<usize>());
+        assert_eq!(Transaction::dedup(batches.concat()), expected);
+
+        // pages fetched before an error are still handed out
+        let mut batches = Vec::new();
+        let result = fetch_flushing(
+            end_time,
+            client.clone(),
+            1,
+            |p| {
+                if p.current_page == 3 {
+                    bail!("stop")
+                }
+                Ok(())
+            },
+            NonZeroU32::new(2).unwrap(),
+            |batch| {
+                batches.push(batch);
+                Ok(())
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            Transaction::dedup(batches.concat()),
+            test_utils::get_mock_data(15)
+        );
+
+        // resuming after the last reported page fetches the rest
+        let first_page = std::cell::Cell::new(None);
+        fetch_flushing(
+            end_time,
+            client,
+            4,
+            |p| {
+                if first_page.get().is_none() {
+                    first_page.set(Some(p.current_page));
+                }
+                Ok(())
+            },
+            NonZeroU32::new(2).unwrap(),
+            |batch| {
+                batches.push(batch);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(first_page.get(), Some(3));
+        assert_eq!(Transaction::dedup(batches.concat()), expected);
+    }
+
     #[tokio::test]
     async fn test_fetch_mock_progress() {
         let (tx, mut rx) = tokio::sync::mpsc::channel::<FetchProgress>(1);
@@ -659,6 +1417,15 @@ mod tests {
         }
 
         assert_ne!(received.len(), 0);
+        assert!(
+            received
+                .windows(2)
+                .all(|w| w[1].0.elapsed >= w[0].0.elapsed),
+            "elapsed time should never decrease"
+        );
+        let last = &received.last().unwrap().0;
+        assert!(last.elapsed >= Duration::from_millis(200));
+        assert!(last.records_per_sec().is_some());
 
         loop {
             match tokio::time::timeout(Duration::from_secs(5), c_rx.recv()).await {
@@ -731,6 +1498,251 @@ mod tests {
         mock.assert();
     }
 
+    #[test]
+    fn test_request_proxy() {
+        // the mock server acts as an HTTP proxy, receiving the request for the real origin
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/Report/GetPersonTrjn")
+            .with_status(200)
+            .with_body(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test/mock-data/api-resp.json"
+            )))
+            .create();
+
+        let fetch = RealMealFetcher::default()
+            .account("Account")
+            .cookie("Cookie")
+            .origin("http://card.invalid")
+            .proxy(Some(server.url()));
+        let t = fetch.fetch_transaction_one_page(1).unwrap();
+        assert!(!api_response_to_transactions(&t).unwrap().is_empty());
+        mock.assert();
+
+        let err = RealMealFetcher::default()
+            .account("Account")
+            .cookie("Cookie")
+            .proxy(Some("not a proxy".to_string()))
+            .validate()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid proxy: not a proxy");
+    }
+
+    #[test]
+    fn test_api_response_to_balance() {
+        let response = r#"{"IsSucceed":true,"Msg":"{\"query_card\":{\"retcode\":\"0\",\"card\":[{\"account\":\"123456\",\"db_balance\":12345}]}}"}"#;
+        assert_eq!(api_response_to_balance(response).unwrap(), 123.45);
+
+        let response = r#"{"Msg":"{\"query_card\":{\"card\":[{\"db_balance\":\"500\"}]}}"}"#;
+        assert_eq!(api_response_to_balance(response).unwrap(), 5.0);
+
+        let err = api_response_to_balance("<html></html>").unwrap_err();
+        assert!(err.chain().any(|e| e.is::<SessionExpired>()));
+
+        let err = api_response_to_balance(r#"{"Msg":"{}"}"#).unwrap_err();
+        assert!(!err.chain().any(|e| e.is::<SessionExpired>()));
+        assert!(err.to_string().contains("db_balance"), "{}", err);
+    }
+
+    #[test]
+    fn test_request_balance() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", BALANCE_PATH)
+            .match_header("cookie", "Cookie")
+            .match_body("account=Account&json=true")
+            .with_status(200)
+            .with_body(r#"{"Msg":"{\"query_card\":{\"card\":[{\"db_balance\":2050}]}}"}"#)
+            .create();
+
+        let fetch = RealMealFetcher::default()
+            .account("Account")
+            .cookie("Cookie")
+            .origin(server.url());
+        assert_eq!(fetch.fetch_balance().unwrap(), 20.5);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_request_async() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/Report/GetPersonTrjn")
+            .match_header("cookie", "Cookie")
+            .match_body("account=Account&page=2&json=true&rows=50")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test/mock-data/api-resp.json"
+            )))
+            .create_async()
+            .await;
+
+        let fetch = RealMealFetcher::default()
+            .account("Account")
+            .cookie("Cookie")
+            .origin(server.url());
+
+        let t = fetch.fetch_transaction_one_page_async(2).await.unwrap();
+        assert!(!api_response_to_transactions(&t).unwrap().is_empty());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_fetch_async_mock() {
+        let end_time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+            .unwrap();
+        let client = MealFetcher::Mock(MockMealFetcher::default().per_page(7));
+
+        let expected = fetch(end_time, client.clone(), |_| Ok(())).unwrap();
+        let pages = std::sync::Mutex::new(Vec::new());
+        let transactions = fetch_async(
+            end_time,
+            client,
+            |p| {
+                pages.lock().unwrap().push(p.current_page);
+                Ok(())
+            },
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(!transactions.is_empty());
+        assert_eq!(transactions, expected);
+        let pages = pages.into_inner().unwrap();
+        assert_eq!(pages, (0..pages.len() as u32).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_async_cancel() {
+        let end_time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+            .unwrap();
+        let client = MealFetcher::Mock(MockMealFetcher::default().per_page(7));
+        let cancel = CancellationToken::new();
+
+        // cancel as soon as the second page arrives
+        let transactions = fetch_async(
+            end_time,
+            client.clone(),
+            |p| {
+                if p.current_page == 2 {
+                    cancel.cancel();
+                }
+                Ok(())
+            },
+            &cancel,
+        )
+        .await
+        .unwrap();
+        let all = fetch(end_time, client, |_| Ok(())).unwrap();
+        // only the first two pages of 7 are kept
+        assert!(!transactions.is_empty() && transactions.len() <= 14);
+        assert!(transactions.len() < all.len());
+        assert!(transactions.iter().all(|t| all.contains(t)));
+    }
+
+    #[test]
+    fn test_scripted_fetch() {
+        let end_time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+            .unwrap();
+        let data = test_utils::get_mock_data(6);
+
+        // an empty page ends the fetch, the rest of the script is left alone
+        let client = ScriptedMealFetcher::new(vec![
+            Ok(ScriptedMealFetcher::page_of(&data[..3])),
+            Ok(ScriptedMealFetcher::page_of(&[])),
+            Ok(ScriptedMealFetcher::page_of(&data[3..])),
+        ]);
+        let pages = std::sync::Mutex::new(Vec::new());
+        let transactions = fetch(end_time, client.clone().into(), |p| {
+            pages.lock().unwrap().push(p.current_page);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(transactions, data[..3]);
+        assert_eq!(pages.into_inner().unwrap(), vec![0, 1]);
+        assert_eq!(client.remaining(), 1);
+
+        // errors carry the page they happened on
+        let client = ScriptedMealFetcher::new(vec![
+            Ok(ScriptedMealFetcher::page_of(&data[..3])),
+            Err(eyre!("connection reset")),
+        ]);
+        let err = fetch(end_time, client.into(), |_| Ok(())).unwrap_err();
+        assert_eq!(err.to_string(), "Error when fetching on page 2");
+
+        // a login page means the session expired
+        let client = ScriptedMealFetcher::new(vec![Ok("<html></html>".to_string())]);
+        let err = fetch(end_time, client.into(), |_| Ok(())).unwrap_err();
+        assert!(err.chain().any(|e| e.is::<SessionExpired>()));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_async_cancel_slow_page() {
+        let end_time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+            .unwrap();
+        let data = test_utils::get_mock_data(4);
+        let client = ScriptedMealFetcher::new(vec![
+            Ok(ScriptedMealFetcher::page_of(&data[..2])),
+            Ok(ScriptedMealFetcher::page_of(&data[2..])),
+        ])
+        .delays(vec![Duration::ZERO, Duration::from_secs(60)]);
+
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+        let start = Instant::now();
+        let transactions = fetch_async(end_time, client.into(), |_| Ok(()), &cancel)
+            .await
+            .unwrap();
+        // the slow second page is abandoned instead of waited for
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(transactions, data[..2]);
+    }
+
+    #[test]
+    fn test_scripted_fetch_flushing_error() {
+        let end_time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+            .unwrap();
+        let data = test_utils::get_mock_data(4);
+        let client = ScriptedMealFetcher::new(vec![
+            Ok(ScriptedMealFetcher::page_of(&data[..2])),
+            Ok(ScriptedMealFetcher::page_of(&data[2..])),
+            Ok("{\"rows\": 1}".to_string()),
+        ]);
+
+        let mut flushed = Vec::new();
+        let err = fetch_flushing(
+            end_time,
+            client.into(),
+            1,
+            |_| Ok(()),
+            NonZeroU32::new(5).unwrap(),
+            |batch| {
+                flushed.push(batch);
+                Ok(())
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error when parsing data returned from XJTU server on page 3"
+        );
+        // the pages before the malformed one are still handed out
+        assert_eq!(flushed, vec![data]);
+    }
+
     #[test]
     #[ignore]
     fn test_fetch_transactions() {
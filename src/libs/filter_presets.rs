@@ -0,0 +1,162 @@
+//! # 筛选预设模块
+//!
+//! 保存带名称的 [`FilterOptions`]，便于在交易列表中反复套用相同的筛选条件，
+//! 如“只看早餐”或“只看超市”。
+//!
+//! ## 存储格式
+//!
+//! 预设按保存顺序存放在数据目录下的 [`PRESETS_FILE`] 中：
+//!
+//! ```json
+//! [
+//!   { "name": "超市", "filter": { "merchant_contains": "超市", ... } }
+//! ]
+//! ```
+//!
+//! 每次保存或删除都会先重新读取文件，因此多个页面同时打开时不会覆盖彼此的修改。
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::transactions::FilterOptions;
+
+/// 筛选预设文件名，位于数据目录下
+pub const PRESETS_FILE: &str = "filter_presets.json";
+
+/// 一个带名称的筛选条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+    pub filter: FilterOptions,
+}
+
+/// 获取数据目录下的预设文件路径
+///
+/// # 参数
+///
+/// * `data_dir` - 数据目录
+pub fn presets_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(PRESETS_FILE)
+}
+
+/// 读取所有预设
+///
+/// # 参数
+///
+/// * `path` - 预设文件路径
+///
+/// # 返回值
+///
+/// 按保存顺序排列的预设，文件不存在时返回空列表
+pub fn load_presets(path: &Path) -> Result<Vec<FilterPreset>> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Error parsing filter presets {}", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => {
+            Err(e).with_context(|| format!("Error reading filter presets {}", path.display()))
+        }
+    }
+}
+
+/// 保存预设
+///
+/// 已有同名预设时原位替换其筛选条件，否则追加到末尾。
+///
+/// # 参数
+///
+/// * `path` - 预设文件路径，不存在时创建
+/// * `preset` - 要保存的预设
+///
+/// # 返回值
+///
+/// 保存后的所有预设
+pub fn save_preset(path: &Path, preset: FilterPreset) -> Result<Vec<FilterPreset>> {
+    let mut presets = load_presets(path)?;
+    match presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+    write_presets(path, &presets)?;
+    Ok(presets)
+}
+
+/// 删除预设
+///
+/// # 参数
+///
+/// * `path` - 预设文件路径
+/// * `name` - 要删除的预设名称
+///
+/// # 返回值
+///
+/// 删除后的所有预设，没有该名称的预设时不修改文件
+pub fn delete_preset(path: &Path, name: &str) -> Result<Vec<FilterPreset>> {
+    let mut presets = load_presets(path)?;
+    let len = presets.len();
+    presets.retain(|p| p.name != name);
+    if presets.len() != len {
+        write_presets(path, &presets)?;
+    }
+    Ok(presets)
+}
+
+fn write_presets(path: &Path, presets: &[FilterPreset]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(presets)?)
+        .with_context(|| format!("Error writing filter presets {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn preset(name: &str, merchant: &str) -> FilterPreset {
+        FilterPreset {
+            name: name.to_string(),
+            filter: FilterOptions::default().merchant_contains(merchant),
+        }
+    }
+
+    #[test]
+    fn save_and_delete() {
+        let dir = tempdir().unwrap();
+        let path = presets_path(&dir.path().join("data"));
+        assert!(load_presets(&path).unwrap().is_empty());
+
+        save_preset(&path, preset("超市", "超市")).unwrap();
+        save_preset(&path, preset("早餐", "餐厅")).unwrap();
+        // same name replaces in place
+        let presets = save_preset(&path, preset("超市", "文治超市")).unwrap();
+        assert_eq!(
+            presets,
+            vec![preset("超市", "文治超市"), preset("早餐", "餐厅")]
+        );
+        assert_eq!(load_presets(&path).unwrap(), presets);
+
+        let presets = delete_preset(&path, "超市").unwrap();
+        assert_eq!(presets, vec![preset("早餐", "餐厅")]);
+        assert_eq!(delete_preset(&path, "不存在").unwrap(), presets);
+        assert_eq!(load_presets(&path).unwrap(), presets);
+    }
+
+    #[test]
+    fn invalid_file() {
+        let dir = tempdir().unwrap();
+        let path = presets_path(dir.path());
+        fs::write(&path, "not json").unwrap();
+        let err = load_presets(&path).unwrap_err();
+        assert!(err.to_string().contains("Error parsing filter presets"));
+        assert!(save_preset(&path, preset("超市", "超市")).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not json");
+    }
+}
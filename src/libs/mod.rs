@@ -5,8 +5,11 @@
 //!
 //! ## 模块组织
 //!
+//! - debug_info: 问题反馈用的诊断信息
+//! - doctor: 运行环境自检
 //! - export_csv: CSV数据导出功能
 //! - fetcher: 从XJTU服务器获取交易数据
+//! - filter_presets: 带名称的筛选预设
 //! - transactions: 交易数据管理和数据库操作
 //!
 //! ## 数据流程
@@ -30,6 +33,16 @@
 //! - **并发安全**: 支持多线程访问
 //! - **错误处理**: 完善的错误处理和恢复机制
 
+/// 诊断信息模块
+///
+/// 为 `debug-info` 子命令汇总数据库统计和生效的配置。
+pub mod debug_info;
+
+/// 运行环境自检模块
+///
+/// 为 `doctor` 子命令检查数据目录、数据库、认证信息以及服务器连通性。
+pub mod doctor;
+
 /// CSV数据导出功能模块
 ///
 /// 提供将交易记录导出为CSV格式的功能，支持多种筛选条件和导出选项。
@@ -40,6 +53,11 @@ pub mod export_csv;
 /// 负责从XJTU校园卡服务器获取交易记录，支持真实数据和模拟数据两种模式。
 pub mod fetcher;
 
+/// 筛选预设模块
+///
+/// 将带名称的筛选条件保存到数据目录下的文件中，供交易列表循环切换。
+pub mod filter_presets;
+
 /// 交易数据管理模块
 ///
 /// 提供交易记录的数据库操作、筛选查询和数据管理功能。
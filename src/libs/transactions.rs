@@ -6,7 +6,7 @@
 //!
 //! - **数据模型**: `Transaction` 结构体定义交易记录的标准格式
 //! - **数据库管理**: `TransactionManager` 提供数据的增删改查操作
-//! - **数据筛选**: `FilterOptions` 支持按时间、商家、金额范围筛选
+//! - **数据筛选**: `FilterOptions` 支持按时间、商家、商家类别、金额范围筛选
 //! - **账户管理**: Cookie 和账户信息的持久化存储
 //!
 //! ## 数据库架构
@@ -17,14 +17,37 @@
 //!     id INTEGER PRIMARY KEY,           -- 交易唯一标识（基于内容哈希）
 //!     time TEXT NOT NULL,              -- 交易时间（ISO 8601 格式）
 //!     amount REAL NOT NULL,            -- 交易金额（负数=消费，正数=充值）
-//!     merchant TEXT NOT NULL           -- 商家名称
+//!     merchant TEXT NOT NULL,          -- 商家名称
+//!     timestamp INTEGER,               -- 交易时间的 Unix 时间戳（秒），用于按时间筛选
+//!     category TEXT                    -- 商家类别，如 "食堂食物"，插入时按商家名称分类
 //! );
+//! CREATE INDEX idx_transactions_timestamp ON transactions (timestamp);
+//! CREATE INDEX idx_transactions_category ON transactions (category);
 //!
 //! -- 账户信息表
 //! CREATE TABLE cookies (
 //!     account TEXT PRIMARY KEY,        -- 学号/账号
 //!     cookie TEXT NOT NULL            -- 会话 Cookie
 //! );
+//!
+//! -- 最近使用的自定义获取开始日期，每个账号最多保留 10 条，按 rowid 从新到旧排列
+//! CREATE TABLE fetch_dates (
+//!     account TEXT NOT NULL,
+//!     date TEXT NOT NULL,              -- YYYY-MM-DD
+//!     PRIMARY KEY (account, date)
+//! );
+//!
+//! -- 其他零散的键值数据，如上次获取的时间、校园卡余额及其查询时间
+//! CREATE TABLE meta (
+//!     key TEXT PRIMARY KEY,
+//!     value TEXT NOT NULL
+//! );
+//!
+//! -- 用户为交易添加的备注，随交易一起删除
+//! CREATE TABLE notes (
+//!     id INTEGER PRIMARY KEY,          -- 交易的 id
+//!     note TEXT NOT NULL
+//! );
 //! ```
 //!
 //! ## 冲突处理机制
@@ -33,6 +56,24 @@
 //! - **相同记录**: 静默跳过（IGNORE）
 //! - **ID 冲突但数据不同**: 抛出错误（ABORT）
 //!
+//! [`TransactionManager::insert`] 遵循上述规则，用于去重写入；校园卡系统更正了某条记录
+//! （如退款调整金额）时，使用 [`TransactionManager::upsert`] 以新数据覆盖同 `id` 的旧记录。
+//! [`TransactionManager::insert_with_policy`] 按 [`ConflictPolicy`] 在报错、跳过和覆盖之间选择，
+//! 获取页面使用配置项 `fetch.conflict_policy` 决定。
+//!
+//! ## 时间戳列
+//!
+//! `time` 列保存带时区的时间字符串，用于展示和导出；按时间范围筛选时则使用 `timestamp` 列，
+//! 避免字符串比较在时区或格式不一致时出错。旧版本创建的数据库在打开时会自动添加该列，
+//! 并为缺少时间戳的记录补齐。
+//!
+//! ## 类别列
+//!
+//! 插入时用 [`classify_merchant`] 计算商家类别并存入 `category` 列，
+//! 分析页面和按类别筛选直接在 SQL 中使用该列。旧数据库同样会在打开时补齐。
+//! 分类表更新后，调用 [`TransactionManager::recategorize_all`]（即 `recategorize` 子命令）
+//! 重新计算所有记录的类别。
+//!
 //! ## 时区处理
 //!
 //! 所有时间均使用 UTC+8 (中国标准时间)：
@@ -106,18 +147,29 @@
 //! // [min_amount, max_amount) 左闭右开区间
 //! // 注意：消费金额为负数
 //! let filter = FilterOptions::default()
-//!     .min(-100.0)  // 消费金额 >= 100 元
-//!     .max(-10.0);  // 消费金额 < 10 元
+//!     .min(-100.0)  // 消费金额 <= 100 元
+//!     .max(-10.0);  // 消费金额 > 10 元
 //! ```
+//!
+//! 金额以 `f64` 存储，`-10.0` 可能实际存为 `-10.000000001`。因此金额按分比较：
+//! 与边界相差不到半分（[`AMOUNT_EPSILON`]）的金额视为等于边界，左边界包含在内，右边界不包含。
+//! 只设置一侧时另一侧为无穷大，不参与比较。`min_abs_amount` 同样按分比较。
 
 use std::{
-    path::PathBuf,
+    collections::HashMap,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use chrono::{DateTime, FixedOffset, TimeZone};
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
 use color_eyre::eyre::{Context, ContextCompat, Result, bail};
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, ErrorCode, OpenFlags, OptionalExtension, params};
+
+use crate::utils::{
+    amount::round_amount,
+    merchant_class::{MerchantType, classify_merchant},
+};
 use serde::{Deserialize, Serialize}; // Added import
 
 /// 交易记录数据结构
@@ -136,6 +188,10 @@ use serde::{Deserialize, Serialize}; // Added import
 ///
 /// 交易 ID 通过对 `时间戳 + 金额 + 商家名称` 进行哈希计算生成，
 /// 确保相同内容的交易具有相同的 ID，便于去重和冲突检测。
+///
+/// ## 相等性
+///
+/// `PartialEq`/`Eq`/`Hash` 基于交易内容（见 [`Transaction::content_key`]）而非 `id`。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     /// 交易唯一标识符
@@ -166,11 +222,46 @@ pub struct Transaction {
 pub const OFFSET_UTC_PLUS8: FixedOffset =
     FixedOffset::east_opt(8 * 3600).expect("Failed to create FixedOffset +8");
 
+/// 默认的本地时间格式，与 XJTU 校园卡 API 返回的时间格式一致
+pub const LOCAL_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 解析 API 时间时依次尝试的格式
+///
+/// 校园卡系统更新后曾返回过不带秒、带毫秒或 ISO 风格的时间，
+/// 都不匹配时再按带时区的 RFC 3339 解析。
+const API_TIME_FORMATS: &[&str] = &[
+    LOCAL_TIME_FORMAT,
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+];
+
+/// Number of recent custom fetch dates kept for each account
+const MAX_FETCH_DATES: usize = 10;
+
+/// 金额比较的容差，相差不到半分的金额视为相等
+///
+/// 交易金额精确到分，浮点误差远小于半分，因此按此容差比较等同于按分比较。
+pub const AMOUNT_EPSILON: f64 = 0.005;
+
+/// Key in the `meta` table holding when the last successful fetch was stored
+const LAST_FETCHED_AT_KEY: &str = "last_fetched_at";
+
+/// Keys in the `meta` table holding the last fetched card balance and when it was fetched
+const BALANCE_KEY: &str = "balance";
+const BALANCE_UPDATED_AT_KEY: &str = "balance_updated_at";
+
+/// Insert statement shared by [`TransactionManager::insert`] and [`TransactionManager::upsert`]
+const INSERT_TRANSACTION: &str = "INSERT INTO transactions (id, time, amount, merchant, timestamp, category) VALUES (?, ?, ?, ?, ?, ?)";
+
 impl Transaction {
     /// 创建新的交易记录
     ///
     /// 根据提供的金额、商家和时间信息创建交易记录，
-    /// 自动计算基于内容的唯一 ID。
+    /// 自动计算基于内容的唯一 ID。金额先舍入到分，见 [`round_amount`]。
     ///
     /// # 参数
     ///
@@ -197,6 +288,7 @@ impl Transaction {
     /// );
     /// ```
     pub fn new(amount: f64, merchant: String, time: DateTime<FixedOffset>) -> Self {
+        let amount = round_amount(amount);
         Transaction {
             id: Transaction::hash(&format!("{}&{}&{}", time.timestamp(), amount, &merchant)),
             time,
@@ -205,6 +297,34 @@ impl Transaction {
         }
     }
 
+    /// 使用本地时间字符串创建交易记录
+    ///
+    /// 按 [`LOCAL_TIME_FORMAT`] 解析时间并视为 UTC+8，省去调用方手动构造
+    /// `DateTime<FixedOffset>` 的步骤。
+    ///
+    /// # 参数
+    ///
+    /// * `amount` - 交易金额（负数表示消费，正数表示充值）
+    /// * `merchant` - 商家名称
+    /// * `time` - 本地时间字符串，如 `"2024-01-15 12:30:00"`
+    ///
+    /// # 返回值
+    ///
+    /// 成功时返回 `Transaction`，时间格式不匹配时返回解析错误
+    ///
+    /// # 示例
+    ///
+    /// ```rust
+    /// use crate::libs::transactions::Transaction;
+    ///
+    /// let transaction = Transaction::from_local(-15.50, "梧桐苑餐厅", "2024-01-15 12:30:00")?;
+    /// ```
+    #[cfg(test)]
+    pub fn from_local<T: Into<String>>(amount: f64, merchant: T, time: &str) -> Result<Self> {
+        let time = Transaction::parse_to_fixed_utc_plus8(time, LOCAL_TIME_FORMAT)?;
+        Ok(Transaction::new(amount, merchant.into(), time))
+    }
+
     /// 解析日期字符串为 UTC+8 时区的 DateTime
     ///
     /// 将字符串格式的日期时间解析为带有 UTC+8 时区信息的 DateTime 对象。
@@ -246,6 +366,69 @@ impl Transaction {
             .with_context(|| format!("Ambiguous result when adding TZ info to {}", naive_dt))
     }
 
+    /// 解析 XJTU 校园卡 API 返回的时间字符串
+    ///
+    /// 按顺序尝试多种格式，而不是只接受 [`LOCAL_TIME_FORMAT`]，
+    /// 避免 API 格式变化后所有记录都被丢弃。使用默认格式以外的格式时会记录日志。
+    ///
+    /// # 参数
+    ///
+    /// * `s` - API 返回的时间字符串
+    ///
+    /// # 返回值
+    ///
+    /// UTC+8 时区的时间，所有格式都不匹配时返回错误
+    pub fn parse_api_time(s: &str) -> Result<DateTime<FixedOffset>> {
+        for format in API_TIME_FORMATS {
+            if let Ok(time) = Transaction::parse_to_fixed_utc_plus8(s, format) {
+                if *format != LOCAL_TIME_FORMAT {
+                    tracing::debug!("Parsed time {:?} with format {}", s, format);
+                }
+                return Ok(time);
+            }
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            tracing::debug!("Parsed time {:?} as RFC 3339", s);
+            return Ok(time.with_timezone(&OFFSET_UTC_PLUS8));
+        }
+        bail!("Unrecognized time format: {:?}", s)
+    }
+
+    /// 交易内容键
+    ///
+    /// 由时间戳、金额和商家名称组成，与可能发生哈希碰撞的 `id` 无关。
+    /// `PartialEq`/`Eq`/`Hash` 均基于此键实现，因此可以直接使用 `HashSet` 去重。
+    ///
+    /// # 返回值
+    ///
+    /// 返回 `(时间戳, 金额的位表示, 商家名称)` 元组
+    pub fn content_key(&self) -> (i64, u64, &str) {
+        (
+            self.time.timestamp(),
+            self.amount.to_bits(),
+            self.merchant.as_str(),
+        )
+    }
+
+    /// 按交易内容去重
+    ///
+    /// 保留每组相同内容交易中第一次出现的记录，并保持原有顺序。
+    ///
+    /// # 参数
+    ///
+    /// * `transactions` - 待去重的交易记录
+    ///
+    /// # 返回值
+    ///
+    /// 返回去重后的交易记录
+    pub fn dedup(transactions: Vec<Transaction>) -> Vec<Transaction> {
+        let mut seen = std::collections::HashSet::new();
+        transactions
+            .into_iter()
+            .filter(|t| seen.insert(t.clone()))
+            .collect()
+    }
+
     /// 计算字符串的哈希值
     ///
     /// 使用 Rust 默认的哈希算法计算字符串的 64 位哈希值，
@@ -266,6 +449,44 @@ impl Transaction {
     }
 }
 
+impl PartialEq for Transaction {
+    fn eq(&self, other: &Self) -> bool {
+        self.content_key() == other.content_key()
+    }
+}
+
+impl Eq for Transaction {}
+
+impl std::hash::Hash for Transaction {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.content_key().hash(state);
+    }
+}
+
+/// Actionable guidance for errors caused by a locked or corrupt database file
+///
+/// Returns `None` if the error is not one of these cases.
+fn db_error_hint(err: &rusqlite::Error, db_path: &Path) -> Option<String> {
+    match err.sqlite_error_code()? {
+        ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => Some(format!(
+            "The local cache DB at {} is locked. Another instance of the app may be running, close it and try again.",
+            db_path.display()
+        )),
+        ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt => Some(format!(
+            "The local cache DB at {} is corrupt or not a SQLite database. Move or delete it to start with a fresh cache.",
+            db_path.display()
+        )),
+        _ => None,
+    }
+}
+
+/// Escape the wildcards of `LIKE` so that `s` is matched literally
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 #[derive(Debug, Clone)]
 pub struct TransactionManager {
     conn: Arc<Mutex<Connection>>,
@@ -275,8 +496,17 @@ impl TransactionManager {
     pub fn new(db_path: Option<PathBuf>) -> Result<Self> {
         let conn = match db_path.as_ref() {
             Some(db_path) => {
-                std::fs::create_dir_all(db_path.parent().unwrap())
-                    .context("Failed to create dir for local cache DB")?;
+                // a bare file name has an empty parent, meaning the current directory
+                if let Some(dir) = db_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(dir).map_err(|e| {
+                        let kind = e.kind();
+                        color_eyre::eyre::eyre!(e).wrap_err(format!(
+                            "Cannot create data directory at {}: {}",
+                            dir.display(),
+                            kind
+                        ))
+                    })?;
+                }
                 Connection::open(db_path.clone()).with_context(|| {
                     format!(
                         "Failed to open local cache DB at {}",
@@ -289,6 +519,12 @@ impl TransactionManager {
 
         // Initialize the database
         TransactionManager::init_db(&conn)
+            .map_err(
+                |e| match db_path.as_deref().and_then(|p| db_error_hint(&e, p)) {
+                    Some(hint) => color_eyre::eyre::eyre!(e).wrap_err(hint),
+                    None => color_eyre::eyre::eyre!(e),
+                },
+            )
             .with_context(|| "Failed to initialize local cache DB")?;
 
         Ok(TransactionManager {
@@ -296,16 +532,58 @@ impl TransactionManager {
         })
     }
 
+    /// 以只读方式打开已有的数据库
+    ///
+    /// 与 [`new`](Self::new) 不同，不会创建目录或数据库文件，也不会建表、迁移或切换日志模式。
+    ///
+    /// # 参数
+    ///
+    /// * `db_path` - 数据库文件路径
+    ///
+    /// # 返回值
+    ///
+    /// 文件不存在或无法打开时返回错误
+    pub fn open_read_only(db_path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("Failed to open local cache DB at {}", db_path.display()))?;
+        conn.busy_timeout(Duration::from_millis(5000))?;
+        Ok(TransactionManager {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
     fn init_db(conn: &Connection) -> Result<(), rusqlite::Error> {
+        // `path()` is `Some("")` for in-memory databases, where WAL does not apply
+        if conn.path().is_some_and(|path| !path.is_empty()) {
+            // WAL lets readers (e.g. the web server) run alongside the fetcher's writes,
+            // and the busy timeout makes short lock contention wait instead of failing
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            conn.busy_timeout(Duration::from_millis(5000))?;
+        }
         conn.execute(
             "CREATE TABLE IF NOT EXISTS transactions (
                 id INTEGER PRIMARY KEY,
                 time TEXT NOT NULL,
                 amount REAL NOT NULL,
-                merchant TEXT NOT NULL
+                merchant TEXT NOT NULL,
+                timestamp INTEGER,
+                category TEXT
             )",
             [],
         )?;
+        Self::migrate_timestamp(conn)?;
+        Self::migrate_category(conn)?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transactions_timestamp ON transactions (timestamp)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transactions_category ON transactions (category)",
+            [],
+        )?;
         conn.execute(
             "CREATE TRIGGER IF NOT EXISTS prevent_transaction_conflict 
                 BEFORE INSERT ON transactions
@@ -336,31 +614,258 @@ impl TransactionManager {
         )",
             [],
         )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS fetch_dates (
+            account TEXT NOT NULL,
+            date TEXT NOT NULL,
+            PRIMARY KEY (account, date)
+        )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY,
+            note TEXT NOT NULL
+        )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Add the `timestamp` column to databases created before it existed, and fill it in
+    /// for rows that lack it
+    fn migrate_timestamp(conn: &Connection) -> Result<(), rusqlite::Error> {
+        let has_column = conn
+            .prepare("SELECT 1 FROM pragma_table_info('transactions') WHERE name = 'timestamp'")?
+            .exists([])?;
+        if !has_column {
+            conn.execute("ALTER TABLE transactions ADD COLUMN timestamp INTEGER", [])?;
+        }
+
+        let missing = conn
+            .prepare("SELECT id, time FROM transactions WHERE timestamp IS NULL")?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, DateTime<FixedOffset>>(1)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE transactions SET timestamp = ? WHERE id = ?")?;
+            for (id, time) in missing {
+                stmt.execute(params![time.timestamp(), id])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Add the `category` column to databases created before it existed, and classify
+    /// rows that lack a category
+    fn migrate_category(conn: &Connection) -> Result<(), rusqlite::Error> {
+        let has_column = conn
+            .prepare("SELECT 1 FROM pragma_table_info('transactions') WHERE name = 'category'")?
+            .exists([])?;
+        if !has_column {
+            conn.execute("ALTER TABLE transactions ADD COLUMN category TEXT", [])?;
+        }
+        Self::categorize(conn, true)?;
         Ok(())
     }
 
+    /// Classify the merchant of each row and store the category where it differs
+    ///
+    /// Only looks at rows without a category when `only_missing` is set.
+    /// Returns the number of rows updated.
+    fn categorize(conn: &Connection, only_missing: bool) -> Result<usize, rusqlite::Error> {
+        let query = if only_missing {
+            "SELECT id, merchant, category FROM transactions WHERE category IS NULL"
+        } else {
+            "SELECT id, merchant, category FROM transactions"
+        };
+        let changed = conn
+            .prepare(query)?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .filter_map(|row| match row {
+                Ok((id, merchant, category)) => {
+                    let new = classify_merchant(&merchant).to_string();
+                    (category.as_ref() != Some(&new)).then_some(Ok((id, new)))
+                }
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare("UPDATE transactions SET category = ? WHERE id = ?")?;
+            for (id, category) in &changed {
+                stmt.execute(params![category, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(changed.len())
+    }
+
+    /// 重新计算所有交易的商家类别
+    ///
+    /// 分类规则更新后调用，使已有记录与新规则一致。
+    ///
+    /// # 返回值
+    ///
+    /// 类别发生变化的记录数
+    pub fn recategorize_all(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        Self::categorize(&conn, false).context("Failed to recategorize transactions")
+    }
+
     pub fn insert(&self, transactions: &Vec<Transaction>) -> Result<()> {
+        self.insert_with_policy(transactions, ConflictPolicy::Abort)
+            .map(|_| ())
+    }
+
+    /// 按指定的冲突处理方式写入交易记录
+    ///
+    /// 完全相同的记录总是被静默跳过。
+    ///
+    /// # 参数
+    ///
+    /// * `transactions` - 要写入的交易记录
+    /// * `policy` - 遇到 `id` 相同但数据不同的已有记录时的处理方式
+    ///
+    /// # 返回值
+    ///
+    /// 实际新增或改变的记录数，跳过的记录不计入。
+    /// [`ConflictPolicy::Abort`] 时遇到冲突返回错误，之前的记录已经写入
+    pub fn insert_with_policy(
+        &self,
+        transactions: &Vec<Transaction>,
+        policy: ConflictPolicy,
+    ) -> Result<usize> {
+        if policy == ConflictPolicy::Overwrite {
+            return self.upsert(transactions);
+        }
         let conn = self.conn.lock().unwrap();
+        Self::insert_rows(&conn, transactions, policy)
+    }
 
+    /// Insert `transactions` on `conn`, returns the number of rows actually written
+    ///
+    /// [`ConflictPolicy::Overwrite`] is handled by [`upsert`](Self::upsert) and acts as
+    /// [`ConflictPolicy::Abort`] here.
+    fn insert_rows(
+        conn: &Connection,
+        transactions: &Vec<Transaction>,
+        policy: ConflictPolicy,
+    ) -> Result<usize> {
         // insert at once
-        let mut stmt = conn
-            .prepare("INSERT INTO transactions (id, time, amount, merchant) VALUES (?, ?, ?, ?)")?;
+        let mut stmt = conn.prepare(INSERT_TRANSACTION)?;
+        let mut exists = conn.prepare("SELECT EXISTS(SELECT 1 FROM transactions WHERE id = ?)")?;
 
+        let mut inserted = 0;
         for transaction in transactions {
-            stmt.execute(params![
-                transaction.id,
-                transaction.time,
-                transaction.amount,
-                transaction.merchant
-            ])
-            .with_context(|| {
-                format!(
-                    "Error when inserting transactions into Database, transaction: {:?}",
-                    transaction
-                )
-            })?;
+            if policy == ConflictPolicy::Skip
+                && exists.query_row([transaction.id], |row| row.get::<_, bool>(0))?
+            {
+                continue;
+            }
+            inserted += stmt
+                .execute(params![
+                    transaction.id,
+                    transaction.time,
+                    round_amount(transaction.amount),
+                    transaction.merchant,
+                    transaction.time.timestamp(),
+                    classify_merchant(&transaction.merchant).to_string()
+                ])
+                .with_context(|| {
+                    format!(
+                        "Error when inserting transactions into Database, transaction: {:?}",
+                        transaction
+                    )
+                })?;
         }
-        Ok(())
+        Ok(inserted)
+    }
+
+    /// 写入交易记录，覆盖 `id` 相同但数据不同的已有记录
+    ///
+    /// 与 [`insert`](Self::insert) 不同，更正过的记录不会触发冲突错误。
+    /// 重复写入相同数据不会产生变化。所有记录在同一个事务中写入，出错时全部回滚。
+    ///
+    /// # 参数
+    ///
+    /// * `transactions` - 要写入的交易记录
+    ///
+    /// # 返回值
+    ///
+    /// 新增或被覆盖的记录数，数据未变的记录不计入
+    pub fn upsert(&self, transactions: &[Transaction]) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let mut written = 0;
+        {
+            // The conflict trigger runs before `ON CONFLICT` is considered, so overwrite the
+            // existing row first; the insert then finds an identical row and is ignored
+            let mut update = tx.prepare(
+                "UPDATE transactions SET time = ?2, amount = ?3, merchant = ?4, timestamp = ?5, category = ?6 WHERE id = ?1",
+            )?;
+            let mut insert = tx.prepare(INSERT_TRANSACTION)?;
+            let mut identical = tx.prepare(
+                "SELECT EXISTS(SELECT 1 FROM transactions WHERE id = ? AND time = ? AND amount = ? AND merchant = ?)",
+            )?;
+            for transaction in transactions {
+                let amount = round_amount(transaction.amount);
+                let params = params![
+                    transaction.id,
+                    transaction.time,
+                    amount,
+                    transaction.merchant,
+                    transaction.time.timestamp(),
+                    classify_merchant(&transaction.merchant).to_string()
+                ];
+                if !identical.query_row(
+                    params![
+                        transaction.id,
+                        transaction.time,
+                        amount,
+                        transaction.merchant
+                    ],
+                    |row| row.get::<_, bool>(0),
+                )? {
+                    written += 1;
+                }
+                update
+                    .execute(params)
+                    .and_then(|_| insert.execute(params))
+                    .with_context(|| {
+                        format!(
+                            "Error when upserting transactions into Database, transaction: {:?}",
+                            transaction
+                        )
+                    })?;
+            }
+        }
+        tx.commit()?;
+        Ok(written)
     }
 
     /// Fetch all transactions from the database
@@ -381,46 +886,79 @@ impl TransactionManager {
         Ok(transactions.filter_map(|t| t.ok()).collect())
     }
 
+    /// Fetch a single transaction by its id
+    ///
+    /// Returns `Ok(None)` if no transaction with the given id exists.
+    pub fn fetch_by_id(&self, id: i64) -> Result<Option<Transaction>> {
+        let conn = self.conn.lock().unwrap();
+        let transaction = conn
+            .query_row(
+                "SELECT id, time, amount, merchant FROM transactions WHERE id = ?",
+                params![id],
+                |row| {
+                    Ok(Transaction {
+                        id: row.get(0)?,
+                        time: row.get(1)?,
+                        amount: row.get(2)?,
+                        merchant: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(transaction)
+    }
+
     pub fn fetch_filtered(&self, filter_opt: &FilterOptions) -> Result<Vec<Transaction>> {
         let conn = self.conn.lock().unwrap();
 
-        let mut conditions = Vec::new();
-        let mut params = Vec::new();
+        let (where_clause, params) = Self::where_clause(filter_opt);
+        let query = format!(
+            "SELECT id, time, amount, merchant FROM transactions {}",
+            where_clause
+        );
 
-        if let Some((start, end)) = &filter_opt.time {
-            conditions.push("time >= ? AND time < ?");
-            params.push(start.to_string());
-            params.push(end.to_string());
-        }
+        let mut stmt = conn.prepare(&query)?;
 
-        if let Some(merchant) = &filter_opt.merchant {
-            conditions.push("merchant = ?");
-            params.push(merchant.to_string());
-        }
+        let transactions = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok(Transaction {
+                id: row.get(0)?,
+                time: row.get(1)?,
+                amount: row.get(2)?,
+                merchant: row.get(3)?,
+            })
+        })?;
 
-        if let Some((min, max)) = &filter_opt.amount {
-            conditions.push("amount >= ? AND amount < ?");
-            params.push(min.to_string());
-            params.push(max.to_string());
-        }
+        Ok(transactions.filter_map(|t| t.ok()).collect())
+    }
 
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+    /// 按时间从新到旧取出最多 `limit` 条符合筛选条件的交易
+    ///
+    /// # 参数
+    ///
+    /// * `filter_opt` - 筛选条件，与 `fetch_filtered` 相同
+    /// * `limit` - 最多返回的条数
+    ///
+    /// # 返回值
+    ///
+    /// 按时间降序排列的交易，时间相同时按 `id` 排序以保证结果稳定
+    pub fn fetch_recent(
+        &self,
+        filter_opt: &FilterOptions,
+        limit: usize,
+    ) -> Result<Vec<Transaction>> {
+        let conn = self.conn.lock().unwrap();
 
+        let (where_clause, mut params) = Self::where_clause(filter_opt);
         let query = format!(
-            "SELECT id, time, amount, merchant FROM transactions {}",
+            "SELECT id, time, amount, merchant FROM transactions {}
+                ORDER BY timestamp DESC, id LIMIT CAST(? AS INTEGER)",
             where_clause
         );
+        params.push(limit.to_string());
 
         let mut stmt = conn.prepare(&query)?;
 
-        let param_refs: Vec<&dyn rusqlite::ToSql> =
-            params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
-
-        let transactions = stmt.query_map(rusqlite::params_from_iter(param_refs), |row| {
+        let transactions = stmt.query_map(rusqlite::params_from_iter(params), |row| {
             Ok(Transaction {
                 id: row.get(0)?,
                 time: row.get(1)?,
@@ -429,31 +967,343 @@ impl TransactionManager {
             })
         })?;
 
-        Ok(transactions.filter_map(|t| t.ok()).collect())
+        Ok(transactions.collect::<Result<_, _>>()?)
     }
 
-    pub fn fetch_count(&self) -> Result<u64> {
+    /// 按商家汇总交易笔数与金额
+    ///
+    /// # 参数
+    ///
+    /// * `filter_opt` - 筛选条件，与 `fetch_filtered` 相同
+    ///
+    /// # 返回值
+    ///
+    /// 每个商家一条汇总，按金额升序排列，即消费最多的商家在前
+    pub fn fetch_merchant_totals(&self, filter_opt: &FilterOptions) -> Result<Vec<MerchantTotal>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT COUNT(*) FROM transactions")?;
-        let count: i64 = stmt.query_row([], |row| row.get(0))?;
-        Ok(count as u64)
+
+        let (where_clause, params) = Self::where_clause(filter_opt);
+        let query = format!(
+            "SELECT merchant, COUNT(*), SUM(amount) FROM transactions {}
+                GROUP BY merchant ORDER BY SUM(amount), merchant",
+            where_clause
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+
+        let totals = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok(MerchantTotal {
+                merchant: row.get(0)?,
+                count: row.get(1)?,
+                total: row.get(2)?,
+            })
+        })?;
+
+        Ok(totals.collect::<Result<_, _>>()?)
     }
 
-    #[allow(dead_code)]
-    pub fn clear_db(&self) -> Result<(), rusqlite::Error> {
+    /// 按前缀搜索商家名称，用于自动补全
+    ///
+    /// 前缀按字面匹配，`%` 和 `_` 不作为通配符；与 `LIKE` 相同，只对 ASCII 字母不区分大小写。
+    ///
+    /// # 参数
+    ///
+    /// * `prefix` - 商家名称前缀，为空时匹配所有商家
+    /// * `limit` - 最多返回的商家数
+    ///
+    /// # 返回值
+    ///
+    /// 按交易笔数从多到少排列的商家名称，笔数相同时按名称排列
+    pub fn search_merchants(&self, prefix: &str, limit: usize) -> Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM transactions", [])?;
-        Ok(())
+        let mut stmt = conn.prepare(
+            "SELECT merchant FROM transactions WHERE merchant LIKE ? ESCAPE '\\'
+                GROUP BY merchant ORDER BY COUNT(*) DESC, merchant LIMIT ?",
+        )?;
+        let merchants = stmt.query_map(
+            params![format!("{}%", escape_like(prefix)), limit as i64],
+            |row| row.get(0),
+        )?;
+        Ok(merchants.collect::<Result<_, _>>()?)
     }
 
-    /// Update account(optional) and cookie in cookies table
+    /// 按商家类别统计交易笔数
     ///
-    /// If there is already a record, update it. Otherwise, insert a new record.
-    /// There should always be only one records in cookies table
-    pub fn update_account(&self, account: &str) -> Result<()> {
+    /// # 参数
+    ///
+    /// * `filter_opt` - 筛选条件
+    ///
+    /// # 返回值
+    ///
+    /// 每个出现过的类别及其交易笔数
+    pub fn fetch_category_counts(
+        &self,
+        filter_opt: &FilterOptions,
+    ) -> Result<Vec<(MerchantType, u32)>> {
         let conn = self.conn.lock().unwrap();
 
-        // Check if there are existing records
+        let (where_clause, params) = Self::where_clause(filter_opt);
+        let query = format!(
+            "SELECT category, COUNT(*) FROM transactions {} GROUP BY category ORDER BY COUNT(*) DESC, category",
+            where_clause
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let counts = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            let category: Option<String> = row.get(0)?;
+            Ok((
+                category.map_or(MerchantType::Unknown, |c| MerchantType::from_type_str(&c)),
+                row.get(1)?,
+            ))
+        })?;
+
+        Ok(counts.collect::<Result<_, _>>()?)
+    }
+
+    /// 统计支出总额
+    ///
+    /// # 参数
+    ///
+    /// * `filter_opt` - 筛选条件
+    ///
+    /// # 返回值
+    ///
+    /// 符合条件的支出（金额为负的交易）之和，以正数表示
+    pub fn fetch_spent(&self, filter_opt: &FilterOptions) -> Result<f64> {
+        let conn = self.conn.lock().unwrap();
+        let (where_clause, params) = Self::where_clause(filter_opt);
+        let query = format!(
+            "SELECT COALESCE(SUM(CASE WHEN amount < 0 THEN -amount ELSE 0 END), 0) FROM transactions {}",
+            where_clause
+        );
+        let spent = conn.query_row(&query, rusqlite::params_from_iter(params), |row| row.get(0))?;
+        Ok(round_amount(spent))
+    }
+
+    /// 按天（UTC+8）统计交易笔数与金额之和
+    ///
+    /// 按 `timestamp` 列划分日期，而不是 `date(time)`：后者会把带时区的时间换算为 UTC，
+    /// 使凌晨 8 点前的交易被算到前一天。
+    ///
+    /// # 参数
+    ///
+    /// * `filter` - 筛选条件
+    ///
+    /// # 返回值
+    ///
+    /// 每个有交易的日期及其 (日期, 交易笔数, 金额之和)，按日期升序排列
+    pub fn daily_counts(&self, filter: &FilterOptions) -> Result<Vec<(NaiveDate, u64, f64)>> {
+        let conn = self.conn.lock().unwrap();
+
+        let (where_clause, params) = Self::where_clause(filter);
+        let query = format!(
+            "SELECT date(timestamp, 'unixepoch', '+8 hours') AS day, COUNT(*), SUM(amount)
+                FROM transactions {} GROUP BY day HAVING day IS NOT NULL ORDER BY day",
+            where_clause
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let days = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+        Ok(days.collect::<Result<_, _>>()?)
+    }
+
+    /// 与暂存数据库对比，找出本数据库中尚不存在的交易
+    ///
+    /// 按交易内容（见 [`Transaction::content_key`]）比较，而不是按 `id`。
+    ///
+    /// # 参数
+    ///
+    /// * `staging` - 存放待合并交易的数据库，通常是内存数据库
+    ///
+    /// # 返回值
+    ///
+    /// 成功时返回 (本数据库中没有的交易, 已存在的交易数量)
+    pub fn diff(&self, staging: &TransactionManager) -> Result<(Vec<Transaction>, usize)> {
+        let existing: std::collections::HashSet<Transaction> =
+            self.fetch_all()?.into_iter().collect();
+        let (duplicates, new): (Vec<_>, Vec<_>) = staging
+            .fetch_all()?
+            .into_iter()
+            .partition(|t| existing.contains(t));
+        Ok((new, duplicates.len()))
+    }
+
+    /// Build the `WHERE` clause and its params for `filter_opt`
+    fn where_clause(filter_opt: &FilterOptions) -> (String, Vec<String>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        if let Some((start, end)) = &filter_opt.time {
+            // the column has integer affinity, so the text params are compared as numbers
+            conditions.push("timestamp >= ? AND timestamp < ?");
+            params.push(start.timestamp().to_string());
+            params.push(end.timestamp().to_string());
+        }
+
+        if let Some(merchant) = &filter_opt.merchant {
+            conditions.push("merchant = ?");
+            params.push(merchant.to_string());
+        }
+
+        if let Some(part) = &filter_opt.merchant_contains {
+            conditions.push("merchant LIKE ? ESCAPE '\\'");
+            params.push(format!("%{}%", escape_like(part)));
+        }
+
+        // amounts are compared in cents, see `AMOUNT_EPSILON`
+        if let Some((min, max)) = &filter_opt.amount {
+            // an infinite bound would be bound as the text "inf", which never compares as a number
+            if min.is_finite() {
+                conditions.push("amount >= ?");
+                params.push((min - AMOUNT_EPSILON).to_string());
+            }
+            if max.is_finite() {
+                conditions.push("amount < ?");
+                params.push((max - AMOUNT_EPSILON).to_string());
+            }
+        }
+
+        if let Some(threshold) = &filter_opt.min_abs_amount {
+            // params are bound as text, which an expression without column affinity would not convert
+            conditions.push("ABS(amount) >= CAST(? AS REAL)");
+            params.push((threshold - AMOUNT_EPSILON).to_string());
+        }
+
+        if let Some(category) = &filter_opt.category {
+            conditions.push("category = ?");
+            params.push(category.to_string());
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        (where_clause, params)
+    }
+
+    /// 删除符合筛选条件的交易记录及其备注
+    ///
+    /// # 参数
+    ///
+    /// * `filter_opt` - 筛选条件，为默认值时删除全部记录
+    ///
+    /// # 返回值
+    ///
+    /// 删除的记录数
+    pub fn delete_filtered(&self, filter_opt: &FilterOptions) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let (where_clause, params) = Self::where_clause(filter_opt);
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            &format!(
+                "DELETE FROM notes WHERE id IN (SELECT id FROM transactions {})",
+                where_clause
+            ),
+            rusqlite::params_from_iter(&params),
+        )
+        .context("Failed to delete notes")?;
+        let deleted = tx
+            .execute(
+                &format!("DELETE FROM transactions {}", where_clause),
+                rusqlite::params_from_iter(params),
+            )
+            .context("Failed to delete transactions")?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// 在同一个数据库事务中按顺序执行多步操作
+    ///
+    /// 任意一步出错时回滚全部操作，之前已执行的步骤也不会生效。
+    ///
+    /// # 参数
+    ///
+    /// * `steps` - 按顺序执行的操作
+    ///
+    /// # 返回值
+    ///
+    /// 每一步影响的记录数，即删除或新写入的记录数。出错时返回出错步骤的序号和错误，
+    /// 提交事务失败算作最后一步出错
+    pub fn apply_batch(
+        &self,
+        steps: &[BatchStep],
+    ) -> std::result::Result<Vec<usize>, (usize, color_eyre::Report)> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn
+            .unchecked_transaction()
+            .context("Failed to start a database transaction")
+            .map_err(|e| (0, e))?;
+        let mut affected = Vec::with_capacity(steps.len());
+        for (i, step) in steps.iter().enumerate() {
+            let result = match step {
+                BatchStep::Delete(id) => tx
+                    .execute("DELETE FROM notes WHERE id = ?", [id])
+                    .and_then(|_| tx.execute("DELETE FROM transactions WHERE id = ?", [id]))
+                    .context("Failed to delete transaction"),
+                BatchStep::Insert(transactions) => {
+                    Self::insert_rows(&tx, transactions, ConflictPolicy::Abort)
+                }
+            };
+            // dropping `tx` without committing rolls back the earlier steps
+            affected.push(result.map_err(|e| (i, e))?);
+        }
+        tx.commit()
+            .context("Failed to commit the batch")
+            .map_err(|e| (steps.len().saturating_sub(1), e))?;
+        Ok(affected)
+    }
+
+    /// Drop `table`, so that queries on it fail
+    #[cfg(test)]
+    pub fn drop_table(&self, table: &str) {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(&format!("DROP TABLE {}", table), []).unwrap();
+    }
+
+    pub fn fetch_count(&self) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT COUNT(*) FROM transactions")?;
+        let count: i64 = stmt.query_row([], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+
+    #[allow(dead_code)]
+    pub fn clear_db(&self) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM notes", [])?;
+        tx.execute("DELETE FROM transactions", [])?;
+        tx.commit()
+    }
+
+    /// Rebuild the database file to give the space of deleted rows back to the OS
+    ///
+    /// In WAL mode the rebuilt pages first land in the `-wal` file, so it is
+    /// checkpointed and truncated afterwards for the main file to actually shrink.
+    /// Does nothing for an in-memory database.
+    pub fn vacuum(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if conn.path().is_none_or(str::is_empty) {
+            return Ok(());
+        }
+        conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+            .context("Failed to vacuum local cache DB")?;
+        Ok(())
+    }
+
+    /// Update account(optional) and cookie in cookies table
+    ///
+    /// If there is already a record, update it. Otherwise, insert a new record.
+    /// There should always be only one records in cookies table
+    pub fn update_account(&self, account: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        // Check if there are existing records
         let existing = conn.query_row("SELECT cookie FROM cookies LIMIT 1", [], |row| {
             row.get::<_, String>(0)
         });
@@ -491,6 +1341,232 @@ impl TransactionManager {
         self.update_cookie(&cookie)
     }
 
+    /// 同时设置账号和 Cookie
+    ///
+    /// 与分别调用 [`update_account`](Self::update_account) 和
+    /// [`update_cookie`](Self::update_cookie) 不同，两者在同一次加锁、同一个事务中写入，
+    /// 其他调用方不会读到只更新了一半的认证信息。
+    ///
+    /// # 参数
+    ///
+    /// * `account` - 校园卡账号
+    /// * `cookie` - 完整的 Cookie 字符串，如 `hallticket=...`
+    pub fn set_credentials(&self, account: &str, cookie: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM cookies", [])?;
+        tx.execute(
+            "INSERT INTO cookies (account, cookie) VALUES (?, ?)",
+            params![account, cookie],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 记录一次使用过的自定义获取开始日期
+    ///
+    /// 已存在的日期会移到最前，每个账号只保留最近的 10 条。
+    ///
+    /// # 参数
+    ///
+    /// * `account` - 校园卡账号，不同账号的记录互不影响
+    /// * `date` - 获取开始日期
+    pub fn record_fetch_date(&self, account: &str, date: NaiveDate) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        // re-insert so the date gets the newest rowid
+        tx.execute(
+            "DELETE FROM fetch_dates WHERE account = ? AND date = ?",
+            params![account, date],
+        )?;
+        tx.execute(
+            "INSERT INTO fetch_dates (account, date) VALUES (?, ?)",
+            params![account, date],
+        )?;
+        tx.execute(
+            "DELETE FROM fetch_dates WHERE account = ?1 AND rowid NOT IN (
+                SELECT rowid FROM fetch_dates WHERE account = ?1 ORDER BY rowid DESC LIMIT ?2
+            )",
+            params![account, MAX_FETCH_DATES],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 获取最近使用过的自定义获取开始日期，从新到旧排列
+    ///
+    /// # 参数
+    ///
+    /// * `account` - 校园卡账号
+    pub fn recent_fetch_dates(&self, account: &str) -> Result<Vec<NaiveDate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT date FROM fetch_dates WHERE account = ? ORDER BY rowid DESC")?;
+        let dates = stmt.query_map(params![account], |row| row.get(0))?;
+        Ok(dates.collect::<Result<_, _>>()?)
+    }
+
+    /// 获取有交易的日期（UTC+8），从早到晚排列
+    pub fn fetch_covered_days(&self) -> Result<Vec<NaiveDate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT date(timestamp, 'unixepoch', '+8 hours') FROM transactions
+                WHERE timestamp IS NOT NULL ORDER BY 1",
+        )?;
+        let days = stmt.query_map([], |row| row.get(0))?;
+        Ok(days.collect::<Result<_, _>>()?)
+    }
+
+    /// 查找已保存的交易之间连续多天没有交易的区间
+    ///
+    /// 只查找最早和最晚一笔交易之间的空缺，这些日期可能是漏获取的，也可能只是假期。
+    ///
+    /// # 参数
+    ///
+    /// * `min_days` - 至少连续这么多天没有交易才算作空缺
+    ///
+    /// # 返回值
+    ///
+    /// 按时间从早到晚排列的空缺
+    pub fn find_date_gaps(&self, min_days: i64) -> Result<Vec<DateGap>> {
+        Ok(date_gaps(&self.fetch_covered_days()?, min_days))
+    }
+
+    /// 获取上次成功获取并保存交易的时间
+    ///
+    /// # 返回值
+    ///
+    /// 从未获取过时返回 `None`
+    pub fn get_last_fetched_at(&self) -> Result<Option<DateTime<FixedOffset>>> {
+        let conn = self.conn.lock().unwrap();
+        let time = conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?",
+                params![LAST_FETCHED_AT_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read the last fetch time")?;
+        Ok(time)
+    }
+
+    /// 记录获取并保存交易的时间
+    ///
+    /// # 参数
+    ///
+    /// * `time` - 获取完成的时间
+    pub fn set_last_fetched_at(&self, time: DateTime<FixedOffset>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?, ?)",
+            params![LAST_FETCHED_AT_KEY, time],
+        )
+        .context("Failed to save the last fetch time")?;
+        Ok(())
+    }
+
+    /// 获取一笔交易的备注
+    ///
+    /// # 参数
+    ///
+    /// * `id` - 交易的 id
+    ///
+    /// # 返回值
+    ///
+    /// 没有备注时返回 `None`
+    #[allow(dead_code)]
+    pub fn get_note(&self, id: i64) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT note FROM notes WHERE id = ?", params![id], |row| {
+            row.get(0)
+        })
+        .optional()
+        .context("Failed to read the note")
+    }
+
+    /// 设置一笔交易的备注
+    ///
+    /// # 参数
+    ///
+    /// * `id` - 交易的 id
+    /// * `note` - 备注内容，为空（或只有空白）时删除备注
+    pub fn set_note(&self, id: i64, note: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let note = note.trim();
+        if note.is_empty() {
+            conn.execute("DELETE FROM notes WHERE id = ?", params![id])
+        } else {
+            conn.execute(
+                "INSERT OR REPLACE INTO notes (id, note) VALUES (?, ?)",
+                params![id, note],
+            )
+        }
+        .context("Failed to save the note")?;
+        Ok(())
+    }
+
+    /// 获取所有备注，用于批量显示或导出
+    ///
+    /// # 返回值
+    ///
+    /// 交易 id 到备注的映射
+    pub fn fetch_notes(&self) -> Result<HashMap<i64, String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, note FROM notes")?;
+        let notes = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(notes.collect::<Result<_, _>>()?)
+    }
+
+    /// 获取上次查询到的校园卡余额
+    ///
+    /// # 返回值
+    ///
+    /// 余额（元）及查询时间，从未查询过时返回 `None`
+    pub fn get_balance(&self) -> Result<Option<(f64, DateTime<FixedOffset>)>> {
+        let conn = self.conn.lock().unwrap();
+        let get = |key: &str| -> Result<Option<String>> {
+            conn.query_row(
+                "SELECT value FROM meta WHERE key = ?",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to read the card balance")
+        };
+        let (Some(balance), Some(time)) = (get(BALANCE_KEY)?, get(BALANCE_UPDATED_AT_KEY)?) else {
+            return Ok(None);
+        };
+        let balance = balance
+            .parse()
+            .with_context(|| format!("Invalid card balance: {}", balance))?;
+        let time = DateTime::parse_from_rfc3339(&time)
+            .with_context(|| format!("Invalid card balance time: {}", time))?;
+        Ok(Some((balance, time)))
+    }
+
+    /// 记录查询到的校园卡余额
+    ///
+    /// # 参数
+    ///
+    /// * `balance` - 余额（元）
+    /// * `time` - 查询的时间
+    pub fn set_balance(&self, balance: f64, time: DateTime<FixedOffset>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        for (key, value) in [
+            (BALANCE_KEY, balance.to_string()),
+            (BALANCE_UPDATED_AT_KEY, time.to_rfc3339()),
+        ] {
+            tx.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES (?, ?)",
+                params![key, value],
+            )
+            .context("Failed to save the card balance")?;
+        }
+        tx.commit().context("Failed to save the card balance")?;
+        Ok(())
+    }
+
     pub fn get_account_cookie(&self) -> Result<(String, String)> {
         let (account, cookie) = self.get_account_cookie_may_empty()?;
 
@@ -515,6 +1591,140 @@ impl TransactionManager {
             None => bail!("No account and cookie found"),
         }
     }
+
+    /// 统计数据库的基本信息，用于问题反馈
+    ///
+    /// # 返回值
+    ///
+    /// `PRAGMA user_version`、各表行数、商家数和交易时间范围
+    pub fn db_stats(&self) -> Result<DbStats> {
+        let conn = self.conn.lock().unwrap();
+        let user_version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        let tables = conn
+            .prepare(
+                "SELECT name FROM sqlite_master
+                    WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            )?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let table_counts = tables
+            .into_iter()
+            .map(|table| {
+                let count: i64 = conn.query_row(
+                    &format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")),
+                    [],
+                    |row| row.get(0),
+                )?;
+                Ok((table, count as u64))
+            })
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        let (merchant_count, min, max): (i64, Option<i64>, Option<i64>) = conn.query_row(
+            "SELECT COUNT(DISTINCT merchant), MIN(timestamp), MAX(timestamp) FROM transactions",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let to_local =
+            |ts: i64| DateTime::from_timestamp(ts, 0).map(|t| t.with_timezone(&OFFSET_UTC_PLUS8));
+        let time_range = match (min.and_then(to_local), max.and_then(to_local)) {
+            (Some(min), Some(max)) => Some((min, max)),
+            _ => None,
+        };
+
+        Ok(DbStats {
+            user_version,
+            table_counts,
+            merchant_count: merchant_count as u64,
+            time_range,
+        })
+    }
+}
+
+/// 数据库的基本信息，见 [`TransactionManager::db_stats`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbStats {
+    /// `PRAGMA user_version` 的值
+    pub user_version: i64,
+    /// 各表名称及行数，按表名排序
+    pub table_counts: Vec<(String, u64)>,
+    /// 不同商家的数量
+    pub merchant_count: u64,
+    /// 最早和最晚的交易时间，没有交易时为 `None`
+    pub time_range: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+}
+
+/// 写入时遇到 `id` 相同但数据不同的已有记录时的处理方式
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+    strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// 报错并停止写入，之前的记录已经写入
+    #[default]
+    Abort,
+    /// 保留已有记录，跳过新记录
+    Skip,
+    /// 以新记录覆盖已有记录
+    Overwrite,
+}
+
+/// 批量操作中的一步，见 [`TransactionManager::apply_batch`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchStep {
+    /// 删除指定 `id` 的交易记录及其备注，不存在时不做任何事
+    Delete(i64),
+    /// 写入交易记录，完全相同的记录被跳过，`id` 相同但数据不同时出错
+    Insert(Vec<Transaction>),
+}
+
+/// 连续没有交易的日期区间，见 [`TransactionManager::find_date_gaps`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateGap {
+    /// 空缺的第一天
+    pub first: NaiveDate,
+    /// 空缺的最后一天，包含在空缺内
+    pub last: NaiveDate,
+}
+
+impl DateGap {
+    /// 空缺的天数
+    pub fn days(&self) -> i64 {
+        (self.last - self.first).num_days() + 1
+    }
+}
+
+/// Runs of at least `min_days` missing days between the sorted `days`
+fn date_gaps(days: &[NaiveDate], min_days: i64) -> Vec<DateGap> {
+    days.windows(2)
+        .filter_map(|pair| {
+            let gap = DateGap {
+                first: pair[0].succ_opt()?,
+                last: pair[1].pred_opt()?,
+            };
+            (gap.days() >= min_days.max(1)).then_some(gap)
+        })
+        .collect()
+}
+
+/// 单个商家的交易汇总
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MerchantTotal {
+    pub merchant: String,
+    /// 交易笔数
+    pub count: u64,
+    /// 交易金额之和（负数表示消费）
+    pub total: f64,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)] // Added Serialize, Deserialize, made pub
@@ -524,8 +1734,15 @@ pub struct FilterOptions {
     pub time: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>, // Made pub
     /// Merchant name
     pub merchant: Option<String>, // Made pub
+    /// Part of the merchant name, matched anywhere in the name (ASCII case-insensitive)
+    pub merchant_contains: Option<String>,
     /// Amount range, closed on left, open on right
     pub amount: Option<(f64, f64)>, // Made pub
+    /// Hide transactions whose absolute amount is below this value
+    pub min_abs_amount: Option<f64>,
+    /// Merchant category
+    #[serde(default)]
+    pub category: Option<MerchantType>,
 }
 
 impl FilterOptions {
@@ -562,6 +1779,11 @@ impl FilterOptions {
         self.merchant = Some(merchant.into());
         self
     }
+    /// Only keep merchants whose name contains `part`, e.g. "浴室" matches "西14西15东12浴室"
+    pub fn merchant_contains<T: Into<String>>(mut self, part: T) -> Self {
+        self.merchant_contains = Some(part.into());
+        self
+    }
     #[allow(dead_code)]
     pub fn min(mut self, amount: f64) -> Self {
         // Made pub
@@ -580,6 +1802,56 @@ impl FilterOptions {
         });
         self
     }
+    /// Only keep transactions with `|amount| >= threshold`
+    pub fn min_abs_amount(mut self, threshold: f64) -> Self {
+        self.min_abs_amount = Some(threshold);
+        self
+    }
+    /// Only keep transactions whose merchant falls into `category`
+    #[allow(dead_code)]
+    pub fn category(mut self, category: MerchantType) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    /// 判断一条交易是否符合筛选条件
+    ///
+    /// 与数据库查询的筛选规则一致，用于筛选尚未写入数据库的记录，如刚获取的交易。
+    ///
+    /// # 参数
+    ///
+    /// * `transaction` - 要判断的交易记录
+    ///
+    /// # 返回值
+    ///
+    /// 符合全部筛选条件时返回 `true`
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let timestamp = transaction.time.timestamp();
+        let amount = transaction.amount;
+        self.time
+            .is_none_or(|(start, end)| start.timestamp() <= timestamp && timestamp < end.timestamp())
+            && self
+                .merchant
+                .as_ref()
+                .is_none_or(|merchant| transaction.merchant == *merchant)
+            // LIKE is case-insensitive for ASCII characters only
+            && self.merchant_contains.as_ref().is_none_or(|part| {
+                transaction
+                    .merchant
+                    .to_ascii_lowercase()
+                    .contains(&part.to_ascii_lowercase())
+            })
+            && self.amount.is_none_or(|(min, max)| {
+                min - AMOUNT_EPSILON <= amount && amount < max - AMOUNT_EPSILON
+            })
+            && self
+                .min_abs_amount
+                .is_none_or(|threshold| amount.abs() >= threshold - AMOUNT_EPSILON)
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| classify_merchant(&transaction.merchant) == *category)
+    }
 }
 
 impl std::fmt::Display for FilterOptions {
@@ -591,9 +1863,18 @@ impl std::fmt::Display for FilterOptions {
         if let Some(merchant) = &self.merchant {
             result.push_str(&format!("Merchant: {}\n", merchant));
         }
+        if let Some(part) = &self.merchant_contains {
+            result.push_str(&format!("Merchant contains: {}\n", part));
+        }
         if let Some((min, max)) = &self.amount {
             result.push_str(&format!("Amount: {} - {}\n", min, max));
         }
+        if let Some(threshold) = &self.min_abs_amount {
+            result.push_str(&format!("Hide below: {}\n", threshold));
+        }
+        if let Some(category) = &self.category {
+            result.push_str(&format!("Category: {}\n", category));
+        }
         if result.is_empty() {
             result.push_str("No filters applied\n");
         }
@@ -603,10 +1884,26 @@ impl std::fmt::Display for FilterOptions {
 
 #[cfg(test)]
 mod tests {
-    use chrono::TimeZone;
+    use chrono::{TimeZone, Utc};
 
     use super::*;
 
+    #[test]
+    fn transaction_from_local() {
+        let transaction = Transaction::from_local(-15.5, "Amazon", "2025-03-01 12:30:00").unwrap();
+        let expected = Transaction::new(
+            -15.5,
+            "Amazon".to_string(),
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 3, 1, 12, 30, 0)
+                .unwrap(),
+        );
+        assert_eq!(transaction, expected);
+        assert_eq!(transaction.id, expected.id);
+
+        assert!(Transaction::from_local(-15.5, "Amazon", "2025/03/01 12:30").is_err());
+    }
+
     #[test]
     fn transaction_parse_time() {
         let time_str = "2025-03-01 00:00:00";
@@ -620,6 +1917,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transaction_parse_api_time() {
+        let expected = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 12, 30, 0)
+            .unwrap();
+        for input in [
+            "2025-03-01 12:30:00",
+            "2025-03-01 12:30:00.000",
+            "2025-03-01 12:30",
+            "2025-03-01T12:30:00",
+            "2025-03-01T12:30",
+            "2025/03/01 12:30:00",
+            "2025/03/01 12:30",
+            "2025-03-01T04:30:00Z",
+            "2025-03-01T12:30:00+08:00",
+        ] {
+            assert_eq!(
+                Transaction::parse_api_time(input).unwrap(),
+                expected,
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            Transaction::parse_api_time("2025-03-01 12:30:05.250")
+                .unwrap()
+                .timestamp_subsec_millis(),
+            250
+        );
+        for input in ["", "2025-03-01", "12:30:00", "2025-13-01 12:30:00"] {
+            assert!(Transaction::parse_api_time(input).is_err(), "{}", input);
+        }
+    }
+
     #[test]
     fn transaction_new() {
         let time = OFFSET_UTC_PLUS8
@@ -632,6 +1963,27 @@ mod tests {
         assert_eq!(transaction.id, 2865793625909541060);
     }
 
+    #[test]
+    fn transaction_content_eq() {
+        let time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 12, 0, 0)
+            .unwrap();
+        let a = Transaction::new(-12.5, "Amazon".to_string(), time);
+        let b = Transaction {
+            id: 42,
+            ..a.clone()
+        };
+        let c = Transaction::new(-12.5, "Google".to_string(), time);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let deduped = Transaction::dedup(vec![a.clone(), c.clone(), b]);
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[0].id, a.id);
+        assert_eq!(deduped[1].merchant, "Google");
+    }
+
     #[test]
     fn test_transaction_manager() {
         let manager = TransactionManager::new(None).unwrap();
@@ -665,29 +2017,339 @@ mod tests {
         assert_eq!(fetched[0].amount, -100.0);
         assert_eq!(fetched[0].merchant, "Amazon");
 
-        assert_eq!(fetched[1].id, 2);
-        assert_eq!(fetched[1].amount, -200.0);
-        assert_eq!(fetched[1].merchant, "Google");
+        assert_eq!(fetched[1].id, 2);
+        assert_eq!(fetched[1].amount, -200.0);
+        assert_eq!(fetched[1].merchant, "Google");
+    }
+
+    #[test]
+    fn test_account_cookie() {
+        let manager = TransactionManager::new(None).unwrap();
+
+        manager.update_account("test_account").unwrap();
+        let (account, cookie) = manager.get_account_cookie_may_empty().unwrap();
+        assert_eq!(account, "test_account");
+        assert_eq!(cookie, "");
+
+        manager.update_cookie("test_cookie").unwrap();
+        let (account, cookie) = manager.get_account_cookie().unwrap();
+        assert_eq!(account, "test_account");
+        assert_eq!(cookie, "test_cookie");
+
+        manager.update_account("test_account2").unwrap();
+        let (account, cookie) = manager.get_account_cookie().unwrap();
+        assert_eq!(account, "test_account2");
+        assert_eq!(cookie, "test_cookie");
+    }
+
+    #[test]
+    fn test_set_credentials() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager.update_account("old").unwrap();
+        manager.update_hallticket("old").unwrap();
+
+        manager
+            .set_credentials("2021123456", "hallticket=new")
+            .unwrap();
+        assert_eq!(
+            manager.get_account_cookie().unwrap(),
+            ("2021123456".to_string(), "hallticket=new".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fetch_dates() {
+        let manager = TransactionManager::new(None).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 9, day).unwrap();
+        assert!(manager.recent_fetch_dates("a").unwrap().is_empty());
+
+        manager.record_fetch_date("a", date(1)).unwrap();
+        manager.record_fetch_date("a", date(2)).unwrap();
+        manager.record_fetch_date("b", date(3)).unwrap();
+        manager.record_fetch_date("a", date(1)).unwrap();
+        assert_eq!(
+            manager.recent_fetch_dates("a").unwrap(),
+            vec![date(1), date(2)]
+        );
+        assert_eq!(manager.recent_fetch_dates("b").unwrap(), vec![date(3)]);
+
+        for day in 10..25 {
+            manager.record_fetch_date("a", date(day)).unwrap();
+        }
+        let dates = manager.recent_fetch_dates("a").unwrap();
+        assert_eq!(dates.len(), MAX_FETCH_DATES);
+        assert_eq!(dates.first(), Some(&date(24)));
+        assert_eq!(dates.last(), Some(&date(15)));
+        assert_eq!(manager.recent_fetch_dates("b").unwrap(), vec![date(3)]);
+    }
+
+    #[test]
+    fn test_date_gaps() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let days = [date(1, 1), date(1, 2), date(1, 5), date(2, 20), date(2, 22)];
+        assert_eq!(
+            date_gaps(&days, 1),
+            vec![
+                DateGap {
+                    first: date(1, 3),
+                    last: date(1, 4)
+                },
+                DateGap {
+                    first: date(1, 6),
+                    last: date(2, 19)
+                },
+                DateGap {
+                    first: date(2, 21),
+                    last: date(2, 21)
+                },
+            ]
+        );
+        let gaps = date_gaps(&days, 3);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].days(), 45);
+        assert!(date_gaps(&days[..1], 1).is_empty());
+        assert!(date_gaps(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn test_find_date_gaps() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-01-01 23:30:00").unwrap(),
+                // still the previous day in UTC
+                Transaction::from_local(-10.0, "Canteen", "2025-01-05 07:00:00").unwrap(),
+                Transaction::from_local(-10.0, "Canteen", "2025-01-05 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        assert_eq!(
+            manager.fetch_covered_days().unwrap(),
+            vec![
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 1, 5).unwrap()
+            ]
+        );
+        assert_eq!(
+            manager.find_date_gaps(3).unwrap(),
+            vec![DateGap {
+                first: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+                last: NaiveDate::from_ymd_opt(2025, 1, 4).unwrap(),
+            }]
+        );
+        assert!(manager.find_date_gaps(4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_last_fetched_at() {
+        let manager = TransactionManager::new(None).unwrap();
+        assert_eq!(manager.get_last_fetched_at().unwrap(), None);
+
+        let first = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 12, 0, 0)
+            .unwrap();
+        manager.set_last_fetched_at(first).unwrap();
+        assert_eq!(manager.get_last_fetched_at().unwrap(), Some(first));
+
+        let second = first + chrono::Duration::hours(3);
+        manager.set_last_fetched_at(second).unwrap();
+        assert_eq!(manager.get_last_fetched_at().unwrap(), Some(second));
+    }
+
+    #[test]
+    fn test_balance() {
+        let manager = TransactionManager::new(None).unwrap();
+        assert_eq!(manager.get_balance().unwrap(), None);
+
+        let time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 12, 0, 0)
+            .unwrap();
+        manager.set_balance(123.45, time).unwrap();
+        assert_eq!(manager.get_balance().unwrap(), Some((123.45, time)));
+
+        let later = time + chrono::Duration::hours(1);
+        manager.set_balance(0.0, later).unwrap();
+        assert_eq!(manager.get_balance().unwrap(), Some((0.0, later)));
+    }
+
+    #[test]
+    fn test_corrupt_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("transactions.db");
+        std::fs::write(&db_path, "definitely not a sqlite database, just some text").unwrap();
+
+        let err = TransactionManager::new(Some(db_path)).unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("corrupt or not a SQLite database"), "{}", msg);
+    }
+
+    #[test]
+    fn test_uncreatable_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        // a regular file where a parent directory should be
+        let file = dir.path().join("not-a-dir");
+        std::fs::write(&file, "").unwrap();
+        let data_dir = file.join("data");
+
+        let err = TransactionManager::new(Some(data_dir.join("transactions.db"))).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Cannot create data directory at {}: not a directory",
+                data_dir.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_journal_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = TransactionManager::new(Some(dir.path().join("transactions.db"))).unwrap();
+        let mode: String = manager
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        // in-memory databases keep their default journal mode
+        let manager = TransactionManager::new(None).unwrap();
+        let mode: String = manager
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "memory");
+        manager.insert(&vec![]).unwrap();
+        assert_eq!(manager.fetch_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_db_error_hint() {
+        let path = Path::new("transactions.db");
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert!(
+            db_error_hint(&busy, path)
+                .unwrap()
+                .contains("Another instance of the app may be running")
+        );
+        assert!(db_error_hint(&rusqlite::Error::QueryReturnedNoRows, path).is_none());
+    }
+
+    #[test]
+    fn test_fetch_by_id() {
+        let manager = TransactionManager::new(None).unwrap();
+        let transaction = Transaction {
+            id: 42,
+            time: OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 3, 1, 12, 0, 0)
+                .unwrap(),
+            amount: -12.5,
+            merchant: "Canteen".to_string(),
+        };
+        manager.insert(&vec![transaction.clone()]).unwrap();
+
+        let fetched = manager.fetch_by_id(42).unwrap().unwrap();
+        assert_eq!(fetched.id, 42);
+        assert_eq!(fetched, transaction);
+        assert!(manager.fetch_by_id(43).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_insert_with_policy() {
+        let original = Transaction::from_local(-12.5, "炸吧", "2025-03-01 12:00:00").unwrap();
+        let corrected = Transaction {
+            amount: -2.5,
+            ..original.clone()
+        };
+        let new = Transaction::from_local(-3.0, "东区浴室-和风", "2025-03-02 12:00:00").unwrap();
+        let setup = || {
+            let manager = TransactionManager::new(None).unwrap();
+            manager.insert(&vec![original.clone()]).unwrap();
+            manager
+        };
+
+        let manager = setup();
+        assert!(
+            manager
+                .insert_with_policy(&vec![corrected.clone(), new.clone()], ConflictPolicy::Abort)
+                .is_err()
+        );
+        assert_eq!(manager.fetch_count().unwrap(), 1);
+
+        let manager = setup();
+        // only the new one is written, identical rows are not counted either
+        assert_eq!(
+            manager
+                .insert_with_policy(
+                    &vec![corrected.clone(), new.clone(), original.clone()],
+                    ConflictPolicy::Skip
+                )
+                .unwrap(),
+            1
+        );
+        assert_eq!(manager.fetch_count().unwrap(), 2);
+        assert_eq!(
+            manager.fetch_by_id(original.id).unwrap(),
+            Some(original.clone())
+        );
+
+        let manager = setup();
+        assert_eq!(
+            manager
+                .insert_with_policy(
+                    &vec![corrected.clone(), new.clone()],
+                    ConflictPolicy::Overwrite,
+                )
+                .unwrap(),
+            2
+        );
+        assert_eq!(manager.fetch_count().unwrap(), 2);
+        assert_eq!(manager.fetch_by_id(original.id).unwrap(), Some(corrected));
     }
 
     #[test]
-    fn test_account_cookie() {
+    fn test_upsert() {
         let manager = TransactionManager::new(None).unwrap();
+        let original = Transaction::from_local(-12.5, "炸吧", "2025-03-01 12:00:00").unwrap();
+        manager.insert(&vec![original.clone()]).unwrap();
 
-        manager.update_account("test_account").unwrap();
-        let (account, cookie) = manager.get_account_cookie_may_empty().unwrap();
-        assert_eq!(account, "test_account");
-        assert_eq!(cookie, "");
+        let corrected = Transaction {
+            amount: -2.5,
+            ..original.clone()
+        };
+        assert!(manager.insert(&vec![corrected.clone()]).is_err());
+        let new = Transaction::from_local(-3.0, "东区浴室-和风", "2025-03-02 12:00:00").unwrap();
+        assert_eq!(
+            manager.upsert(&[corrected.clone(), new.clone()]).unwrap(),
+            2
+        );
+        // idempotent
+        assert_eq!(
+            manager.upsert(&[corrected.clone(), new.clone()]).unwrap(),
+            0
+        );
 
-        manager.update_cookie("test_cookie").unwrap();
-        let (account, cookie) = manager.get_account_cookie().unwrap();
-        assert_eq!(account, "test_account");
-        assert_eq!(cookie, "test_cookie");
+        assert_eq!(manager.fetch_count().unwrap(), 2);
+        assert_eq!(manager.fetch_by_id(original.id).unwrap(), Some(corrected));
+        assert_eq!(manager.fetch_by_id(new.id).unwrap(), Some(new));
 
-        manager.update_account("test_account2").unwrap();
-        let (account, cookie) = manager.get_account_cookie().unwrap();
-        assert_eq!(account, "test_account2");
-        assert_eq!(cookie, "test_cookie");
+        // the stored category follows a corrected merchant
+        let moved = Transaction {
+            merchant: "东区浴室-和风".to_string(),
+            ..original
+        };
+        manager.upsert(&[moved]).unwrap();
+        assert_eq!(
+            manager
+                .fetch_category_counts(&FilterOptions::default())
+                .unwrap(),
+            vec![(MerchantType::Bathhouse, 2)]
+        );
     }
 
     #[test]
@@ -842,5 +2504,641 @@ mod tests {
         let results = manager.fetch_filtered(&filter).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].merchant, "Amazon");
+
+        // bounds in another offset compare by instant, not by string
+        let filter = FilterOptions::default()
+            .start(
+                Utc.with_ymd_and_hms(2025, 3, 1, 16, 0, 0)
+                    .unwrap()
+                    .fixed_offset(),
+            )
+            .end(
+                Utc.with_ymd_and_hms(2025, 3, 2, 16, 0, 0)
+                    .unwrap()
+                    .fixed_offset(),
+            );
+        let results = manager.fetch_filtered(&filter).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].merchant, "Google");
+        assert_eq!(results[0].amount, -200.0);
+    }
+
+    #[test]
+    fn test_migrate_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("transactions.db");
+        let time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 12, 0, 0)
+            .unwrap();
+        {
+            // schema before the timestamp column was added
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute(
+                "CREATE TABLE transactions (
+                    id INTEGER PRIMARY KEY,
+                    time TEXT NOT NULL,
+                    amount REAL NOT NULL,
+                    merchant TEXT NOT NULL
+                )",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO transactions (id, time, amount, merchant) VALUES (1, ?, -10.0, 'Amazon')",
+                params![time],
+            )
+            .unwrap();
+        }
+
+        let manager = TransactionManager::new(Some(db_path)).unwrap();
+        let timestamp: i64 = manager
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT timestamp FROM transactions WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(timestamp, time.timestamp());
+        let category: String = manager
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT category FROM transactions WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(category, MerchantType::Unknown.to_string());
+
+        let filter = FilterOptions::default()
+            .start(time)
+            .end(time + chrono::Duration::seconds(1));
+        assert_eq!(manager.fetch_filtered(&filter).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_daily_counts() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-15.5, "Canteen", "2025-03-01 18:00:00").unwrap(),
+                // before 08:00, still the local day rather than the UTC one
+                Transaction::from_local(-3.0, "Shower", "2025-03-03 01:30:00").unwrap(),
+                Transaction::from_local(50.0, "Recharge", "2025-03-03 23:59:59").unwrap(),
+            ])
+            .unwrap();
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        assert_eq!(
+            manager.daily_counts(&FilterOptions::default()).unwrap(),
+            vec![
+                (date("2025-03-01"), 2, -25.5),
+                (date("2025-03-03"), 2, 47.0),
+            ]
+        );
+        assert_eq!(
+            manager
+                .daily_counts(&FilterOptions::default().merchant("Canteen"))
+                .unwrap(),
+            vec![(date("2025-03-01"), 2, -25.5)]
+        );
+        assert!(
+            manager
+                .daily_counts(&FilterOptions::default().merchant("None"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_fetch_spent() {
+        let manager = TransactionManager::new(None).unwrap();
+        assert_eq!(manager.fetch_spent(&FilterOptions::default()).unwrap(), 0.0);
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.1, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-15.2, "Canteen", "2025-03-02 18:00:00").unwrap(),
+                Transaction::from_local(50.0, "Recharge", "2025-03-03 09:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        // income is not spending
+        assert_eq!(
+            manager.fetch_spent(&FilterOptions::default()).unwrap(),
+            25.3
+        );
+        let start = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 2, 0, 0, 0)
+            .unwrap();
+        assert_eq!(
+            manager
+                .fetch_spent(&FilterOptions::default().start(start))
+                .unwrap(),
+            15.2
+        );
+    }
+
+    #[test]
+    fn test_fetch_merchant_totals() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-15.5, "Canteen", "2025-03-02 12:00:00").unwrap(),
+                Transaction::from_local(-30.0, "Market", "2025-03-03 12:00:00").unwrap(),
+                Transaction::from_local(-1.0, "Shower", "2025-04-01 12:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        let totals = manager
+            .fetch_merchant_totals(&FilterOptions::default())
+            .unwrap();
+        assert_eq!(
+            totals,
+            vec![
+                MerchantTotal {
+                    merchant: "Market".to_string(),
+                    count: 1,
+                    total: -30.0
+                },
+                MerchantTotal {
+                    merchant: "Canteen".to_string(),
+                    count: 2,
+                    total: -25.5
+                },
+                MerchantTotal {
+                    merchant: "Shower".to_string(),
+                    count: 1,
+                    total: -1.0
+                },
+            ]
+        );
+
+        let filter = FilterOptions::default().end(
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 3, 2, 23, 59, 59)
+                .unwrap(),
+        );
+        let totals = manager.fetch_merchant_totals(&filter).unwrap();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].count, 2);
+    }
+
+    #[test]
+    fn test_diff() {
+        let manager = TransactionManager::new(None).unwrap();
+        let existing = Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap();
+        manager.insert(&vec![existing.clone()]).unwrap();
+
+        let staging = TransactionManager::new(None).unwrap();
+        let new = Transaction::from_local(-15.5, "Canteen", "2025-03-02 12:00:00").unwrap();
+        staging.insert(&vec![existing, new.clone()]).unwrap();
+
+        let (diff, duplicates) = manager.diff(&staging).unwrap();
+        assert_eq!(diff, vec![new]);
+        assert_eq!(duplicates, 1);
+    }
+
+    #[test]
+    fn test_notes() {
+        let manager = TransactionManager::new(None).unwrap();
+        let t = Transaction::from_local(-12.5, "Canteen", "2025-03-01 12:00:00").unwrap();
+        manager.insert(&vec![t.clone()]).unwrap();
+        assert_eq!(manager.get_note(t.id).unwrap(), None);
+
+        manager.set_note(t.id, "  treated a friend ").unwrap();
+        assert_eq!(
+            manager.get_note(t.id).unwrap().as_deref(),
+            Some("treated a friend")
+        );
+        manager.set_note(t.id, "refund pending").unwrap();
+        assert_eq!(
+            manager.fetch_notes().unwrap(),
+            HashMap::from([(t.id, "refund pending".to_string())])
+        );
+
+        manager.set_note(t.id, " ").unwrap();
+        assert_eq!(manager.get_note(t.id).unwrap(), None);
+        assert!(manager.fetch_notes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_removes_notes() {
+        let manager = TransactionManager::new(None).unwrap();
+        let canteen = Transaction::from_local(-12.5, "Canteen", "2025-03-01 12:00:00").unwrap();
+        let shop = Transaction::from_local(-3.0, "Shop", "2025-03-02 12:00:00").unwrap();
+        let bath = Transaction::from_local(-4.0, "Bathhouse", "2025-03-03 12:00:00").unwrap();
+        let all = vec![canteen.clone(), shop.clone(), bath.clone()];
+        let add_notes = || {
+            manager.insert(&all).unwrap();
+            for t in &all {
+                manager.set_note(t.id, "note").unwrap();
+            }
+        };
+
+        add_notes();
+        manager
+            .delete_filtered(&FilterOptions::default().merchant("Canteen"))
+            .unwrap();
+        assert_eq!(
+            manager.fetch_notes().unwrap(),
+            HashMap::from([(shop.id, "note".to_string()), (bath.id, "note".to_string())])
+        );
+        // a re-fetched transaction starts without a note
+        manager.insert(&vec![canteen.clone()]).unwrap();
+        assert_eq!(manager.get_note(canteen.id).unwrap(), None);
+
+        manager.apply_batch(&[BatchStep::Delete(shop.id)]).unwrap();
+        assert_eq!(manager.get_note(shop.id).unwrap(), None);
+        assert!(manager.get_note(bath.id).unwrap().is_some());
+
+        add_notes();
+        manager.clear_db().unwrap();
+        assert!(manager.fetch_notes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_merchants() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-1.0, "梧桐苑餐厅", "2025-03-01 08:00:00").unwrap(),
+                Transaction::from_local(-2.0, "梧桐苑超市", "2025-03-01 09:00:00").unwrap(),
+                Transaction::from_local(-3.0, "梧桐苑超市", "2025-03-02 09:00:00").unwrap(),
+                Transaction::from_local(-4.0, "康桥苑餐厅", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-5.0, "100%_Shop", "2025-03-01 13:00:00").unwrap(),
+                Transaction::from_local(-6.0, "100 Shop", "2025-03-01 14:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        // most transactions first
+        assert_eq!(
+            manager.search_merchants("梧桐苑", 10).unwrap(),
+            vec!["梧桐苑超市", "梧桐苑餐厅"]
+        );
+        assert_eq!(
+            manager.search_merchants("梧桐苑", 1).unwrap(),
+            vec!["梧桐苑超市"]
+        );
+        // prefix only, not anywhere in the name
+        assert!(manager.search_merchants("餐厅", 10).unwrap().is_empty());
+        // wildcards are matched literally
+        assert_eq!(
+            manager.search_merchants("100%_", 10).unwrap(),
+            vec!["100%_Shop"]
+        );
+        assert_eq!(manager.search_merchants("", 10).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_amount_rounding() {
+        let time = "2025-03-01 12:00:00";
+        let exact = Transaction::from_local(-15.5, "Canteen", time).unwrap();
+        let noisy = Transaction::from_local(-15.500000001, "Canteen", time).unwrap();
+        assert_eq!(noisy, exact);
+        assert_eq!(Transaction::dedup(vec![exact.clone(), noisy]).len(), 1);
+
+        // records built by hand are rounded when stored
+        let manager = TransactionManager::new(None).unwrap();
+        let corrected = Transaction {
+            amount: -0.1 - 0.2,
+            ..exact.clone()
+        };
+        manager.upsert(&[corrected]).unwrap();
+        assert_eq!(manager.fetch_by_id(exact.id).unwrap().unwrap().amount, -0.3);
+        let other = Transaction::from_local(-1.0, "Market", time).unwrap();
+        manager
+            .insert(&vec![Transaction {
+                amount: 2.675,
+                ..other.clone()
+            }])
+            .unwrap();
+        assert_eq!(manager.fetch_by_id(other.id).unwrap().unwrap().amount, 2.68);
+    }
+
+    #[test]
+    fn test_amount_boundaries() {
+        let manager = TransactionManager::new(None).unwrap();
+        let amounts = [
+            -20.0,
+            -10.01,
+            -10.004,
+            -10.000000001,
+            -9.999999999,
+            -5.000000001,
+            -4.99,
+        ];
+        let transactions: Vec<_> = amounts
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| Transaction {
+                amount: *amount,
+                ..Transaction::new(
+                    *amount,
+                    "Canteen".to_string(),
+                    OFFSET_UTC_PLUS8
+                        .with_ymd_and_hms(2025, 3, 1, 12, i as u32, 0)
+                        .unwrap(),
+                )
+            })
+            .collect();
+        // written around the rounding on insert, like rows stored by older versions
+        {
+            let conn = manager.conn.lock().unwrap();
+            for t in &transactions {
+                conn.execute(
+                    INSERT_TRANSACTION,
+                    params![
+                        t.id,
+                        t.time,
+                        t.amount,
+                        t.merchant,
+                        t.time.timestamp(),
+                        classify_merchant(&t.merchant).to_string()
+                    ],
+                )
+                .unwrap();
+            }
+        }
+
+        let check = |filter: FilterOptions, expected: &[f64]| {
+            let mut fetched: Vec<_> = manager
+                .fetch_filtered(&filter)
+                .unwrap()
+                .iter()
+                .map(|t| t.amount)
+                .collect();
+            fetched.sort_by(f64::total_cmp);
+            assert_eq!(fetched, expected, "{}", filter);
+            let matched: Vec<_> = transactions
+                .iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| t.amount)
+                .collect();
+            assert_eq!(matched, expected, "matches() differs for {}", filter);
+        };
+
+        // the left bound is inclusive to the cent, the right one exclusive
+        check(
+            FilterOptions::default().min(-10.0).max(-5.0),
+            &[-10.004, -10.000000001, -9.999999999],
+        );
+        // a single bound leaves the other side open
+        check(FilterOptions::default().max(-10.0), &[-20.0, -10.01]);
+        check(FilterOptions::default().min(-5.0), &[-5.000000001, -4.99]);
+        check(
+            FilterOptions::default().min_abs_amount(10.0),
+            &[-20.0, -10.01, -10.004, -10.000000001, -9.999999999],
+        );
+    }
+
+    #[test]
+    fn test_fetch_min_abs_amount() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(0.0, "Canteen", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-0.01, "Canteen", "2025-03-01 12:01:00").unwrap(),
+                Transaction::from_local(-12.5, "Canteen", "2025-03-01 12:02:00").unwrap(),
+                Transaction::from_local(50.0, "Top up", "2025-03-01 12:03:00").unwrap(),
+            ])
+            .unwrap();
+
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().min_abs_amount(0.0))
+            .unwrap();
+        assert_eq!(results.len(), 4);
+
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().min_abs_amount(0.01))
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|t| t.amount != 0.0));
+
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().min_abs_amount(1.0))
+            .unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_fetch_merchant_contains() {
+        let manager = TransactionManager::new(None).unwrap();
+        let data = crate::libs::fetcher::test_utils::get_mock_data(200);
+        manager.insert(&data).unwrap();
+        let count = |f: fn(&str) -> bool| data.iter().filter(|t| f(&t.merchant)).count();
+
+        // exact match stays exact
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().merchant("浴室"))
+            .unwrap();
+        assert!(results.is_empty());
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().merchant("西14西15东12浴室"))
+            .unwrap();
+        assert_eq!(results.len(), count(|m| m == "西14西15东12浴室"));
+
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().merchant_contains("浴室"))
+            .unwrap();
+        assert_eq!(results.len(), count(|m| m.contains("浴室")));
+        assert!(results.len() > count(|m| m == "西14西15东12浴室"));
+        assert!(results.iter().any(|t| t.merchant == "西14西15东12浴室"));
+
+        // wildcards are matched literally
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().merchant_contains("%"))
+            .unwrap();
+        assert!(results.is_empty());
+        manager
+            .insert(&vec![
+                Transaction::from_local(-1.0, "100%_果汁", "2025-03-01 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let results = manager
+            .fetch_filtered(&FilterOptions::default().merchant_contains("%_"))
+            .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_filtered() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "Canteen", "2025-01-31 23:59:59").unwrap(),
+                Transaction::from_local(-15.5, "Canteen", "2025-02-01 00:00:00").unwrap(),
+                Transaction::from_local(-30.0, "Market", "2025-03-03 12:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        let cutoff = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 2, 1, 0, 0, 0)
+            .unwrap();
+        let older = FilterOptions::default().end(cutoff);
+        assert_eq!(manager.delete_filtered(&older).unwrap(), 1);
+        assert_eq!(manager.delete_filtered(&older).unwrap(), 0);
+        assert_eq!(manager.fetch_count().unwrap(), 2);
+
+        let market = FilterOptions::default().merchant("Market");
+        assert_eq!(manager.delete_filtered(&market).unwrap(), 1);
+        assert_eq!(
+            manager.fetch_all().unwrap()[0].time,
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 2, 1, 0, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_apply_batch() {
+        let manager = TransactionManager::new(None).unwrap();
+        let a = Transaction::from_local(-10.0, "Canteen", "2025-02-01 12:00:00").unwrap();
+        let b = Transaction::from_local(-15.5, "Canteen", "2025-02-02 12:00:00").unwrap();
+        manager.insert(&vec![a.clone(), b.clone()]).unwrap();
+
+        // delete, then fetch the deleted one again along with one already stored
+        let affected = manager
+            .apply_batch(&[
+                BatchStep::Delete(a.id),
+                BatchStep::Delete(a.id),
+                BatchStep::Insert(vec![a.clone(), b.clone()]),
+            ])
+            .unwrap();
+        assert_eq!(affected, vec![1, 0, 1]);
+        assert_eq!(manager.fetch_count().unwrap(), 2);
+
+        // a conflicting record rolls back the delete before it
+        let corrected = Transaction {
+            amount: b.amount - 1.0,
+            ..b.clone()
+        };
+        let (step, _) = manager
+            .apply_batch(&[BatchStep::Delete(a.id), BatchStep::Insert(vec![corrected])])
+            .unwrap_err();
+        assert_eq!(step, 1);
+        assert_eq!(manager.fetch_all().unwrap(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "梧桐苑餐厅", "2025-01-31 23:59:59").unwrap(),
+                Transaction::from_local(-15.5, "Campus Market", "2025-02-01 00:00:00").unwrap(),
+                Transaction::from_local(-3.0, "炸吧", "2025-03-03 12:00:00").unwrap(),
+                Transaction::from_local(50.0, "100%_Top-up", "2025-03-04 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let all = manager.fetch_all().unwrap();
+
+        let cutoff = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 2, 1, 0, 0, 0)
+            .unwrap();
+        // the in-memory check agrees with the database query
+        for filter in [
+            FilterOptions::default(),
+            FilterOptions::default().start(cutoff),
+            FilterOptions::default().end(cutoff),
+            FilterOptions::default().merchant("炸吧"),
+            FilterOptions::default().merchant_contains("market"),
+            FilterOptions::default().merchant_contains("%_"),
+            FilterOptions::default().min(-12.0).max(0.0),
+            FilterOptions::default().min_abs_amount(10.0),
+            FilterOptions::default().category(MerchantType::CanteenFood),
+        ] {
+            let mut expected = manager.fetch_filtered(&filter).unwrap();
+            let mut matched: Vec<_> = all.iter().filter(|t| filter.matches(t)).cloned().collect();
+            expected.sort_by_key(|t| t.id);
+            matched.sort_by_key(|t| t.id);
+            assert_eq!(matched, expected, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn test_vacuum() {
+        // no-op in memory
+        TransactionManager::new(None).unwrap().vacuum().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("transactions.db");
+        let manager = TransactionManager::new(Some(db_path.clone())).unwrap();
+        manager
+            .insert(&crate::libs::fetcher::test_utils::get_mock_data(2000))
+            .unwrap();
+        manager.clear_db().unwrap();
+        let wal_path = dir.path().join("transactions.db-wal");
+        let file_size = || {
+            std::fs::metadata(&db_path).unwrap().len()
+                + std::fs::metadata(&wal_path).map_or(0, |m| m.len())
+        };
+        let before = file_size();
+
+        manager.vacuum().unwrap();
+        let after = file_size();
+        assert!(after < before, "{} should be less than {}", after, before);
+        assert_eq!(manager.fetch_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_category() {
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "炸吧", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-15.5, "炸吧", "2025-03-02 12:00:00").unwrap(),
+                Transaction::from_local(-3.0, "东区浴室-和风", "2025-03-03 12:00:00").unwrap(),
+                Transaction::from_local(-1.0, "Somewhere", "2025-04-01 12:00:00").unwrap(),
+            ])
+            .unwrap();
+
+        let counts = manager
+            .fetch_category_counts(&FilterOptions::default())
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                (MerchantType::CanteenFood, 2),
+                (MerchantType::Unknown, 1),
+                (MerchantType::Bathhouse, 1),
+            ]
+        );
+
+        let filter = FilterOptions::default().category(MerchantType::CanteenFood);
+        let results = manager.fetch_filtered(&filter).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|t| t.merchant == "炸吧"));
+        assert_eq!(
+            manager
+                .fetch_category_counts(&FilterOptions::default().min_abs_amount(5.0))
+                .unwrap(),
+            vec![(MerchantType::CanteenFood, 2)]
+        );
+
+        // nothing changes when the stored categories are up to date
+        assert_eq!(manager.recategorize_all().unwrap(), 0);
+        manager
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE transactions SET category = ? WHERE merchant = 'Somewhere'",
+                params![MerchantType::Other.to_string()],
+            )
+            .unwrap();
+        assert_eq!(manager.recategorize_all().unwrap(), 1);
+        assert_eq!(
+            manager
+                .fetch_filtered(&FilterOptions::default().category(MerchantType::Unknown))
+                .unwrap()
+                .len(),
+            1
+        );
     }
 }
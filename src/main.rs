@@ -28,6 +28,11 @@
 //! cargo run -- export-csv --output transactions.csv
 //! ```
 //!
+//! ### 环境自检
+//! ```bash
+//! cargo run -- doctor --online
+//! ```
+//!
 //! ### 清理数据库
 //! ```bash
 //! cargo run -- clear-db
@@ -40,6 +45,8 @@
 //! - `--data-dir`: 数据目录路径
 //! - `--db-in-mem`: 使用内存数据库（数据不会持久化）
 //! - `--use-mock-data`: 使用模拟数据进行测试
+//! - `-v`/`--verbose`: 提高日志详细程度（`-vv` 为 trace）
+//! - `-q`/`--quiet`: 只记录警告和错误
 
 /// 应用程序的核心动作定义和状态管理
 mod actions;
@@ -72,7 +79,11 @@ mod tui;
 /// 实用工具模块，包含错误处理、日志记录等
 mod utils;
 
-use actix_web::{HttpServer, middleware::Logger, web};
+use actix_web::{
+    HttpServer,
+    middleware::{self, Logger},
+    web,
+};
 use app::{App, RootState};
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -85,7 +96,9 @@ use libs::transactions::TransactionManager;
 /// 根据命令行参数决定运行模式：
 /// - 无子命令：启动TUI模式
 /// - `clear-db`：清理本地数据库
+/// - `purge`：删除旧的交易记录
 /// - `web`：启动Web服务器
+/// - `doctor`：检查运行环境
 /// - `export-csv`：导出数据为CSV格式
 ///
 /// # 返回值
@@ -99,17 +112,26 @@ use libs::transactions::TransactionManager;
 /// - 网络服务启动失败
 /// - CSV导出失败
 #[cfg(not(tarpaulin_include))]
-async fn run() -> Result<()> {
+async fn run(args: cli::Cli) -> Result<()> {
     use cli::{ClapSource, Commands};
     use color_eyre::eyre::Context;
     use libs::transactions::TransactionManager;
-    let args = cli::Cli::parse();
+
+    if args.version_json {
+        println!("{}", cli::version_json(!args.compact_json));
+        return Ok(());
+    }
 
     // application state
     let config = crate::config::Config::new(Some(ClapSource::new(&args)))
         .context("Error when loading config")
         .unwrap();
 
+    if args.print_config {
+        println!("{}", config.to_json(!args.compact_json)?);
+        return Ok(());
+    }
+
     match &args.command {
         Some(Commands::ClearDb) => {
             let manager = TransactionManager::new(config.config.db_path())
@@ -118,6 +140,57 @@ async fn run() -> Result<()> {
             println!("Database cleared");
             Ok(())
         }
+        Some(Commands::VacuumDb) => {
+            let Some(db_path) = config.config.db_path() else {
+                println!("Using an in-memory database, nothing to vacuum");
+                return Ok(());
+            };
+            let manager = TransactionManager::new(Some(db_path.clone()))
+                .context("Error when connecting to Database")?;
+            // the database runs in WAL mode, recent writes may still be in the -wal file
+            let mut wal_path = db_path.clone().into_os_string();
+            wal_path.push("-wal");
+            let file_size = || -> color_eyre::Result<u64> {
+                let db = std::fs::metadata(&db_path)
+                    .with_context(|| format!("Failed to read size of {}", db_path.display()))?;
+                let wal = std::fs::metadata(&wal_path).map_or(0, |m| m.len());
+                Ok(db.len() + wal)
+            };
+            let before = file_size()?;
+            manager.vacuum().context("Error when vacuuming database")?;
+            let after = file_size()?;
+            println!(
+                "Database vacuumed: {} -> {} bytes ({} bytes freed)",
+                before,
+                after,
+                before.saturating_sub(after)
+            );
+            Ok(())
+        }
+        Some(Commands::Purge { older_than }) => {
+            let cutoff =
+                utils::date::parse_cutoff(older_than, chrono::Local::now().fixed_offset())?;
+            let manager = TransactionManager::new(config.config.db_path())
+                .context("Error when connecting to Database")?;
+            let purged = manager
+                .delete_filtered(&libs::transactions::FilterOptions::default().end(cutoff))
+                .context("Error when purging transactions")?;
+            println!(
+                "Purged {} transactions older than {}",
+                purged,
+                cutoff.format(libs::transactions::LOCAL_TIME_FORMAT)
+            );
+            Ok(())
+        }
+        Some(Commands::Recategorize) => {
+            let manager = TransactionManager::new(config.config.db_path())
+                .context("Error when connecting to Database")?;
+            let changed = manager
+                .recategorize_all()
+                .context("Error when recategorizing transactions")?;
+            println!("Recategorized {} transactions", changed);
+            Ok(())
+        }
         Some(Commands::Web) => {
             println!("Visit http://localhost:8080 to view the web interface");
             let manager = TransactionManager::new(config.config.db_path())
@@ -125,6 +198,30 @@ async fn run() -> Result<()> {
             web_main(manager).await?;
             Ok(())
         }
+        Some(Commands::Doctor {
+            online,
+            print_curl,
+            include_cookie,
+        }) => {
+            let fetcher = libs::fetcher::RealMealFetcher::default()
+                .origin(crate::config::get_api_origin())
+                .proxy(config.fetch.proxy.clone());
+            if *print_curl {
+                println!(
+                    "{}",
+                    libs::doctor::curl_command(&config, fetcher, *include_cookie)?
+                );
+                return Ok(());
+            }
+            let results = libs::doctor::run_checks(&config, online.then_some(fetcher));
+            libs::doctor::print_report(&results)
+        }
+        Some(Commands::DebugInfo) => {
+            let manager = TransactionManager::new(config.config.db_path())
+                .context("Error when connecting to Database")?;
+            println!("{}", libs::debug_info::build_report(&config, &manager)?);
+            Ok(())
+        }
         Some(Commands::ExportCsv {
             output,
             merchant,
@@ -132,6 +229,13 @@ async fn run() -> Result<()> {
             max_amount,
             time_start,
             time_end,
+            report,
+            limit,
+            summary,
+            crlf,
+            timestamp,
+            split_by,
+            notes,
         }) => {
             let manager = TransactionManager::new(config.config.db_path())
                 .context("Error when connecting to Database")?;
@@ -143,6 +247,17 @@ async fn run() -> Result<()> {
                 max_amount: *max_amount,
                 time_start: time_start.clone(),
                 time_end: time_end.clone(),
+                report: *report,
+                limit: *limit,
+                summary_row: *summary,
+                line_ending: if *crlf {
+                    libs::export_csv::LineEnding::Crlf
+                } else {
+                    libs::export_csv::LineEnding::Lf
+                },
+                timestamp: *timestamp,
+                split_by: *split_by,
+                notes: *notes,
             };
 
             CsvExporter::execute_export(&manager, &export_options)
@@ -151,14 +266,18 @@ async fn run() -> Result<()> {
         }
 
         None => {
-            let state = RootState::new(config);
+            let state = RootState::new(config)?;
             let mut app = App::new(
                 state,
                 tui::Tui::new()?
                     .tick_rate(args.tick_rate)
                     .frame_rate(args.frame_rate)
+                    .alt_screen(!args.no_alt_screen)
                     .into(),
             );
+            if let Some(spec) = &args.open {
+                app.open(spec);
+            }
 
             app.run().await?;
             Ok(())
@@ -187,11 +306,14 @@ async fn run() -> Result<()> {
 /// - 自动请求日志记录
 async fn web_main(manager: TransactionManager) -> std::io::Result<()> {
     let transaction_manager = web::Data::new(manager);
+    let metrics = web::Data::new(server::metrics::Metrics::default());
 
     HttpServer::new(move || {
         actix_web::App::new()
             .wrap(Logger::default()) // Add Logger middleware
+            .wrap(middleware::from_fn(server::metrics::track_requests))
             .app_data(transaction_manager.clone()) // Add TransactionManager to app data
+            .app_data(metrics.clone())
             .configure(server::api::config_routes) // Configure routes from server.rs
             .default_service(web::route().to(server::serve_frontend)) // Serve frontend
     })
@@ -205,8 +327,9 @@ async fn web_main(manager: TransactionManager) -> std::io::Result<()> {
 /// 执行以下初始化步骤：
 /// 1. 加载.env文件中的环境变量
 /// 2. 初始化错误处理系统
-/// 3. 初始化日志记录系统
-/// 4. 调用主运行函数
+/// 3. 解析命令行参数
+/// 4. 按 `--verbose` / `--quiet` 初始化日志记录系统
+/// 5. 调用主运行函数
 ///
 /// # 返回值
 ///
@@ -216,10 +339,15 @@ async fn web_main(manager: TransactionManager) -> std::io::Result<()> {
 async fn main() -> Result<()> {
     dotenv().ok();
     utils::errors::init()?;
-    utils::logging::init()?;
+    let args = cli::Cli::parse();
+    utils::logging::init(args.log_level())?;
 
-    let result = run().await;
+    let result = run(args).await;
 
+    if result.is_err() {
+        // the error may come from inside the TUI, make sure it is readable
+        utils::errors::restore_terminal();
+    }
     result?;
 
     Ok(())
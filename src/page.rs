@@ -35,6 +35,7 @@
 //! - fetch: 数据获取页面，从服务器获取交易记录
 //! - help_popup: 帮助弹窗，显示快捷键和操作指南
 //! - home: 主页面，应用程序的起始页面
+//! - settings: 设置页面，切换常用配置项并保存到配置文件
 //! - transactions: 交易记录页面，显示和管理交易数据
 //!
 //! ## 事件处理流程
@@ -46,8 +47,10 @@
 //! 5. Action被发送到应用程序进行状态更新
 //! 6. 状态更新触发重新渲染
 
+use crate::actions::Action;
 use crate::app::layer_manager::EventHandlingStatus;
 use crate::tui::Event;
+use crate::utils::help_msg::HelpMsg;
 use downcast_rs::{DowncastSync, impl_downcast};
 use ratatui::Frame;
 use ratatui::layout::Rect;
@@ -67,6 +70,16 @@ pub(crate) mod cookie_input;
 /// 负责从XJTU服务器获取交易记录，支持进度显示和错误处理。
 pub(crate) mod fetch;
 
+/// 确认对话框模块
+///
+/// 通用的是/否确认弹窗，如合并预览结果、开始大范围获取、继续暂停的获取前的确认。
+pub(crate) mod confirm_popup;
+
+/// 错误对话框模块
+///
+/// 显示页面无法自行处理的错误，如读取数据库失败，按 `Enter` 或 `Esc` 关闭。
+pub(crate) mod error_popup;
+
 /// 帮助弹窗模块
 ///
 /// 显示应用程序的快捷键说明和操作指南。
@@ -77,11 +90,22 @@ pub(crate) mod help_popup;
 /// 应用程序的起始页面，提供主要功能的导航入口。
 pub(crate) mod home;
 
+/// 设置页面模块
+///
+/// 列出常用配置项，修改后写入配置文件。
+pub(crate) mod settings;
+
 /// 交易记录页面模块
 ///
 /// 显示交易记录列表，支持筛选、排序和详细查看。
 pub(crate) mod transactions;
 
+/// 页面测试辅助工具模块
+///
+/// 按顺序向页面输入事件并收集发出的动作。
+#[cfg(test)]
+pub(crate) mod test_utils;
+
 /// 页面层级抽象
 ///
 /// 代表应用程序中的一个UI层级或页面。每个Layer都是一个独立的功能单元，
@@ -141,6 +165,25 @@ pub trait Layer: WidgetExt + EventLoopParticipant + DowncastSync {
     /// - 建立必要的连接
     /// - 注册事件监听器
     fn init(&mut self) {}
+
+    /// 页面重新回到栈顶时调用
+    ///
+    /// 上层页面弹出后调用，可用于刷新在其他页面中可能已变化的数据。默认实现为空。
+    fn resume(&mut self) {}
+
+    /// 页面当前可用的快捷键
+    ///
+    /// 供全局快捷键一览（`F1`）汇总使用，通常与页面底部显示的帮助信息相同。
+    /// 默认返回空列表，表示没有需要列出的快捷键。
+    fn help(&self) -> HelpMsg {
+        HelpMsg::default()
+    }
+
+    /// 处理发给页面的动作
+    ///
+    /// App 把 [`Action::Fetch`] 等页面动作交给栈顶页面，例如确认对话框弹出自身后
+    /// 发送的 `on_confirm`。默认忽略。
+    fn handle_action(&mut self, _action: &Action) {}
 }
 impl_downcast!(sync Layer);
 
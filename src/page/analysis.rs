@@ -1,36 +1,147 @@
+use chrono::NaiveDate;
+use color_eyre::eyre::{Context, Result};
 use crossterm::event::KeyCode;
+use heatmap::HeatmapData;
 use merchant::MerchantData;
 use merchant_type::MerchantCategoryData;
 use ratatui::{
-    layout::{Constraint, Layout},
+    layout::{Constraint, Flex, Layout},
     style::{Stylize, palette::tailwind},
-    widgets::Tabs,
+    text::Text,
+    widgets::{Paragraph, Tabs},
 };
 use strum::{Display, EnumIter, IntoEnumIterator};
 use time_period::TimePeriodData;
 use time_series::TimeSeriesData;
 
 use crate::{
-    actions::{ActionSender, LayerManageAction},
+    actions::{ActionSender, LayerManageAction, Layers},
     app::layer_manager::EventHandlingStatus,
-    libs::transactions::{Transaction, TransactionManager},
+    libs::transactions::{FilterOptions, Transaction, TransactionManager},
     tui::Event,
-    utils::help_msg::{HelpEntry, HelpMsg},
+    utils::{
+        amount::format_amount_plain,
+        clipboard,
+        help_msg::{HelpEntry, HelpMsg},
+        merchant_class::MerchantType,
+        merchant_normalize::MerchantAliases,
+    },
 };
 
 use super::{EventLoopParticipant, Layer, WidgetExt};
 
+mod heatmap;
 mod merchant;
 mod merchant_type;
 mod time_period;
-mod time_series;
+pub(crate) mod time_series;
 
 pub(crate) struct Analysis {
     manager: crate::libs::transactions::TransactionManager,
     tx: ActionSender,
 
     analysis_type: AnalysisType,
-    data: Vec<Transaction>,
+    data: AnalysisData,
+    load_state: LoadState,
+    /// Only analyse transactions matching these filters, shown above the help
+    filter_option: Option<FilterOptions>,
+    min_abs_amount: f64,
+    merchant_aliases: MerchantAliases,
+    /// Switching past the last tab goes back to the first one, and vice versa
+    wrap_navigation: bool,
+    /// One-line feedback shown above the help, cleared on the next key press
+    notice: Option<String>,
+    /// How positive amounts count in the time series and merchant totals
+    income_mode: IncomeMode,
+    /// What the time series and merchant charts measure
+    metric: Metric,
+    /// Merchants listed before the rest are folded into "Others"
+    merchant_top_n: usize,
+}
+
+/// How positive amounts, such as recharges and refunds, count in the spending totals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display)]
+pub(crate) enum IncomeMode {
+    /// Leave positive amounts out
+    #[default]
+    #[strum(to_string = "Spending only")]
+    SpendOnly,
+    /// Subtract positive amounts from the spending
+    #[strum(to_string = "Net income")]
+    Net,
+    /// Sum positive amounts into a separate income series
+    #[strum(to_string = "Separate income")]
+    Separate,
+}
+
+/// What the time series and merchant charts measure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display)]
+enum Metric {
+    /// Sum of the amounts
+    #[default]
+    Amount,
+    /// Number of transactions
+    Count,
+}
+
+impl Metric {
+    fn toggled(self) -> Self {
+        match self {
+            Metric::Amount => Metric::Count,
+            Metric::Count => Metric::Amount,
+        }
+    }
+}
+
+impl IncomeMode {
+    fn next(self) -> Self {
+        match self {
+            IncomeMode::SpendOnly => IncomeMode::Net,
+            IncomeMode::Net => IncomeMode::Separate,
+            IncomeMode::Separate => IncomeMode::SpendOnly,
+        }
+    }
+
+    /// Spending and income of `amounts` in this mode
+    ///
+    /// Spending is positive when money goes out, and can be negative in [`IncomeMode::Net`]
+    /// when more came in. Income is only counted in [`IncomeMode::Separate`].
+    pub(crate) fn totals(self, amounts: impl IntoIterator<Item = f64>) -> (f64, f64) {
+        amounts
+            .into_iter()
+            .fold((0.0, 0.0), |(spend, income), amount| match self {
+                IncomeMode::SpendOnly if amount > 0.0 => (spend, income),
+                IncomeMode::Separate if amount > 0.0 => (spend, income + amount),
+                _ => (spend - amount, income),
+            })
+    }
+}
+
+/// Everything the tabs are built from, loaded together by [`Analysis::reload`]
+#[derive(Debug, Clone, Default)]
+struct AnalysisData {
+    /// Transactions with merchant aliases applied
+    transactions: Vec<Transaction>,
+    /// Number of transactions in each category, counted from the stored `category` column
+    category_counts: Vec<(MerchantType, u32)>,
+    /// Transaction count and total of each day with transactions, counted in the database
+    daily_counts: Vec<(NaiveDate, u64, f64)>,
+    /// How positive amounts count in the time series and merchant tabs
+    income_mode: IncomeMode,
+    /// What the time series and merchant tabs measure
+    metric: Metric,
+    /// Merchants listed on the merchant tab before the rest are folded into "Others"
+    merchant_top_n: usize,
+}
+
+/// Result of loading transactions for analysis
+#[derive(Debug, Clone, PartialEq)]
+enum LoadState {
+    Loaded,
+    /// No transactions in the database
+    Empty,
+    /// Failed to query the database, the error is shown in an error layer
+    Failed,
 }
 
 #[derive(Display, EnumIter)]
@@ -43,23 +154,41 @@ enum AnalysisType {
     Merchant(MerchantData),
     #[strum(to_string = "MerchantCategory")]
     MerchantCategory(MerchantCategoryData),
+    #[strum(to_string = "Heatmap")]
+    Heatmap(HeatmapData),
 }
 
 impl AnalysisType {
-    fn next(&self, data: &[Transaction]) -> Self {
-        match self {
-            Self::TimePeriod(_) => Self::TimeSeries(TimeSeriesData::new(data)),
-            Self::TimeSeries(_) => Self::Merchant(MerchantData::new(data)),
-            Self::Merchant(_) => Self::MerchantCategory(MerchantCategoryData::new(data)),
-            Self::MerchantCategory(_) => Self::TimePeriod(TimePeriodData::new(data)),
-        }
+    fn next(&self, data: &AnalysisData) -> Self {
+        Self::from_index((self.to_index() + 1) % Self::iter().count(), data)
+            .expect("index of the next tab should be valid")
     }
-    fn previous(&self, data: &[Transaction]) -> Self {
-        match self {
-            Self::TimePeriod(_) => Self::MerchantCategory(MerchantCategoryData::new(data)),
-            Self::MerchantCategory(_) => Self::Merchant(MerchantData::new(data)),
-            Self::TimeSeries(_) => Self::TimePeriod(TimePeriodData::new(data)),
-            Self::Merchant(_) => Self::TimeSeries(TimeSeriesData::new(data)),
+    fn previous(&self, data: &AnalysisData) -> Self {
+        let count = Self::iter().count();
+        Self::from_index((self.to_index() + count - 1) % count, data)
+            .expect("index of the previous tab should be valid")
+    }
+    /// Build the analysis type at the given tab index, `None` if out of range
+    fn from_index(index: usize, data: &AnalysisData) -> Option<Self> {
+        let transactions = &data.transactions;
+        match index {
+            0 => Some(Self::TimePeriod(TimePeriodData::new(transactions))),
+            1 => {
+                let mut series = TimeSeriesData::new(transactions, data.income_mode);
+                series.set_metric(data.metric);
+                Some(Self::TimeSeries(series))
+            }
+            2 => {
+                let mut merchants =
+                    MerchantData::new(transactions, data.income_mode).top_n(data.merchant_top_n);
+                merchants.set_metric(data.metric);
+                Some(Self::Merchant(merchants))
+            }
+            3 => Some(Self::MerchantCategory(MerchantCategoryData::from_counts(
+                &data.category_counts,
+            ))),
+            4 => Some(Self::Heatmap(HeatmapData::new(&data.daily_counts))),
+            _ => None,
         }
     }
     fn to_index(&self) -> usize {
@@ -68,6 +197,7 @@ impl AnalysisType {
             AnalysisType::TimeSeries(_) => 1,
             AnalysisType::Merchant(_) => 2,
             AnalysisType::MerchantCategory(_) => 3,
+            AnalysisType::Heatmap(_) => 4,
         }
     }
     fn get_palette(&self) -> tailwind::Palette {
@@ -76,30 +206,214 @@ impl AnalysisType {
             AnalysisType::TimeSeries(_) => tailwind::GREEN,
             AnalysisType::Merchant(_) => tailwind::INDIGO,
             AnalysisType::MerchantCategory(_) => tailwind::YELLOW,
+            AnalysisType::Heatmap(_) => tailwind::TEAL,
         }
     }
+    fn to_pairs(&self) -> Vec<(String, f64)> {
+        match self {
+            AnalysisType::TimePeriod(data) => data.to_pairs(),
+            AnalysisType::TimeSeries(data) => data.to_pairs(),
+            AnalysisType::Merchant(data) => data.to_pairs(),
+            AnalysisType::MerchantCategory(data) => data.to_pairs(),
+            AnalysisType::Heatmap(data) => data.to_pairs(),
+        }
+    }
+    /// Chart data as TSV with a header row, `None` if there is nothing to copy
+    fn to_tsv(&self) -> Option<String> {
+        let pairs = self.to_pairs();
+        if pairs.is_empty() {
+            return None;
+        }
+        let value_header = match self {
+            AnalysisType::TimePeriod(_)
+            | AnalysisType::MerchantCategory(_)
+            | AnalysisType::Heatmap(_) => Metric::Count,
+            AnalysisType::TimeSeries(data) => data.metric(),
+            AnalysisType::Merchant(data) => data.metric(),
+        };
+        let mut tsv = format!("{}\t{}\n", self, value_header);
+        for (label, value) in pairs {
+            let label = label.replace(['\t', '\n'], " ");
+            let value = match value_header {
+                Metric::Amount => format_amount_plain(value),
+                Metric::Count => value.to_string(),
+            };
+            tsv.push_str(&format!("{}\t{}\n", label, value));
+        }
+        Some(tsv)
+    }
 }
 
 impl Analysis {
-    pub fn new(tx: ActionSender, manager: TransactionManager) -> Self {
-        let mut new = Self {
+    pub fn new(
+        filter_option: Option<FilterOptions>,
+        tx: ActionSender,
+        manager: TransactionManager,
+    ) -> Self {
+        Self {
             manager,
             tx,
             analysis_type: AnalysisType::TimePeriod(Default::default()),
-            data: vec![],
+            data: AnalysisData::default(),
+            load_state: LoadState::Empty,
+            filter_option,
+            min_abs_amount: 0.0,
+            merchant_aliases: MerchantAliases::default(),
+            wrap_navigation: true,
+            notice: None,
+            income_mode: IncomeMode::default(),
+            metric: Metric::default(),
+            merchant_top_n: merchant::DEFAULT_TOP_N,
+        }
+    }
+
+    /// Leave out transactions whose absolute amount is below `threshold`
+    pub fn min_abs_amount(mut self, threshold: f64) -> Self {
+        self.min_abs_amount = threshold;
+        self
+    }
+
+    /// Merge merchant names through `aliases` before analysing
+    pub fn merchant_aliases(mut self, aliases: MerchantAliases) -> Self {
+        self.merchant_aliases = aliases;
+        self
+    }
+
+    /// List the first `top_n` merchants on the merchant tab and fold the rest into "Others",
+    /// 0 to list all of them
+    pub fn merchant_top_n(mut self, top_n: usize) -> Self {
+        self.merchant_top_n = if top_n == 0 { usize::MAX } else { top_n };
+        self
+    }
+
+    /// Wrap around when switching tabs past either end, `false` to stop there
+    pub fn wrap_navigation(mut self, wrap: bool) -> Self {
+        self.wrap_navigation = wrap;
+        self
+    }
+
+    /// Reload transactions from the database and rebuild the current tab
+    ///
+    /// The selected tab is kept, as is the scroll position of the merchant tab.
+    fn reload(&mut self) {
+        match self.load_data() {
+            Ok(data) => {
+                self.load_state = if data.transactions.is_empty() {
+                    LoadState::Empty
+                } else {
+                    LoadState::Loaded
+                };
+                self.data = data;
+            }
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                self.load_state = LoadState::Failed;
+                self.tx.send(LayerManageAction::Push(
+                    Layers::Error(format!("{:#}", e)).into_push_config(true),
+                ));
+                self.data = AnalysisData {
+                    income_mode: self.income_mode,
+                    metric: self.metric,
+                    merchant_top_n: self.merchant_top_n,
+                    ..Default::default()
+                };
+            }
+        }
+
+        let mut analysis_type = AnalysisType::from_index(self.analysis_type.to_index(), &self.data)
+            .expect("index of current tab should be valid");
+        if let (AnalysisType::Merchant(old), AnalysisType::Merchant(new)) =
+            (&self.analysis_type, &mut analysis_type)
+        {
+            new.keep_view_of(old);
+        }
+        if let (AnalysisType::TimePeriod(old), AnalysisType::TimePeriod(new)) =
+            (&self.analysis_type, &mut analysis_type)
+        {
+            new.by_hour = old.by_hour;
+        }
+        if let (AnalysisType::MerchantCategory(old), AnalysisType::MerchantCategory(new)) =
+            (&self.analysis_type, &mut analysis_type)
+        {
+            new.set_min_share(old.min_share());
+        }
+        self.analysis_type = analysis_type;
+    }
+
+    /// Copy the data of the current tab to the clipboard and report the outcome
+    fn copy_tsv(&mut self) {
+        let Some(tsv) = self.analysis_type.to_tsv() else {
+            self.notice = Some("Nothing to copy".to_string());
+            return;
+        };
+        let rows = tsv.lines().count() - 1;
+        self.notice = Some(match clipboard::copy(&tsv) {
+            Ok(()) => format!("Copied {} rows as TSV", rows),
+            Err(e) => {
+                tracing::warn!("{:?}", e);
+                format!("{:#}", e)
+            }
+        });
+    }
+
+    /// Load the transactions matching the page filters with normalized merchant names, skipping
+    /// the full load if there are none
+    fn load_data(&self) -> Result<AnalysisData> {
+        let count = self
+            .manager
+            .fetch_count()
+            .context("Failed to count transactions")?;
+        if count == 0 {
+            return Ok(AnalysisData {
+                income_mode: self.income_mode,
+                metric: self.metric,
+                merchant_top_n: self.merchant_top_n,
+                ..Default::default()
+            });
+        }
+        let filter = self.filter_option.clone().unwrap_or_default();
+        let filter = if self.min_abs_amount > 0.0 {
+            filter.min_abs_amount(self.min_abs_amount)
+        } else {
+            filter
         };
-        new.data = new
+        let transactions = if filter == FilterOptions::default() {
+            self.manager.fetch_all()
+        } else {
+            self.manager.fetch_filtered(&filter)
+        }
+        .context("Failed to load transactions")?;
+        let category_counts = self
             .manager
-            .fetch_all()
-            .expect("Failed to load transactions");
-        new.analysis_type = AnalysisType::TimePeriod(TimePeriodData::new(&new.data));
-        new
+            .fetch_category_counts(&filter)
+            .context("Failed to count transactions by category")?;
+        let daily_counts = self
+            .manager
+            .daily_counts(&filter)
+            .context("Failed to count transactions by day")?;
+        Ok(AnalysisData {
+            transactions: self.merchant_aliases.apply(transactions),
+            category_counts,
+            daily_counts,
+            income_mode: self.income_mode,
+            metric: self.metric,
+            merchant_top_n: self.merchant_top_n,
+        })
     }
 }
 
 impl EventLoopParticipant for Analysis {
     fn handle_events(&mut self, event: &crate::tui::Event) -> EventHandlingStatus {
         let mut status = EventHandlingStatus::default();
+        if let Event::Key(_) = event {
+            self.notice = None;
+        }
+        if let AnalysisType::Merchant(ref mut data) = self.analysis_type {
+            let status = data.scroll.handle_events(event);
+            if matches!(status, EventHandlingStatus::Consumed) {
+                return status;
+            }
+        }
         #[allow(clippy::single_match)]
         match event {
             Event::Key(key) => match key.code {
@@ -108,22 +422,88 @@ impl EventLoopParticipant for Analysis {
                     status.consumed();
                 }
                 KeyCode::Char('h') | KeyCode::Left => {
-                    self.analysis_type = self.analysis_type.previous(&self.data);
+                    if self.wrap_navigation || self.analysis_type.to_index() > 0 {
+                        self.analysis_type = self.analysis_type.previous(&self.data);
+                    }
                     status.consumed();
                 }
                 KeyCode::Char('l') | KeyCode::Right => {
-                    self.analysis_type = self.analysis_type.next(&self.data);
+                    let last = AnalysisType::iter().count() - 1;
+                    if self.wrap_navigation || self.analysis_type.to_index() < last {
+                        self.analysis_type = self.analysis_type.next(&self.data);
+                    }
+                    status.consumed();
+                }
+                KeyCode::Char('r') => {
+                    self.reload();
+                    status.consumed();
+                }
+                KeyCode::Char('y') => {
+                    self.copy_tsv();
                     status.consumed();
                 }
-                KeyCode::Char('j') | KeyCode::Down => {
+                KeyCode::Char(c @ '1'..='9') => {
+                    let index = c as usize - '1' as usize;
+                    if let Some(analysis_type) = AnalysisType::from_index(index, &self.data) {
+                        self.analysis_type = analysis_type;
+                        status.consumed();
+                    }
+                }
+                KeyCode::Char('a') => {
                     if let AnalysisType::Merchant(ref mut data) = self.analysis_type {
-                        data.scroll_state.scroll_down();
+                        data.toggle_show_all();
                         status.consumed();
                     }
                 }
-                KeyCode::Char('k') | KeyCode::Up => {
+                KeyCode::Char('v') => {
                     if let AnalysisType::Merchant(ref mut data) = self.analysis_type {
-                        data.scroll_state.scroll_up();
+                        data.set_merge_variants(!data.merge_variants());
+                        status.consumed();
+                    }
+                }
+                KeyCode::Char('i') => {
+                    if matches!(
+                        self.analysis_type,
+                        AnalysisType::TimeSeries(_) | AnalysisType::Merchant(_)
+                    ) {
+                        self.income_mode = self.income_mode.next();
+                        self.reload();
+                        self.notice = Some(format!("Totals: {}", self.income_mode));
+                        status.consumed();
+                    }
+                }
+                KeyCode::Char('c') => {
+                    let metric = self.metric.toggled();
+                    let handled = match self.analysis_type {
+                        AnalysisType::TimeSeries(ref mut data) => {
+                            data.set_metric(metric);
+                            true
+                        }
+                        AnalysisType::Merchant(ref mut data) => {
+                            data.set_metric(metric);
+                            true
+                        }
+                        _ => false,
+                    };
+                    if handled {
+                        self.metric = metric;
+                        self.data.metric = metric;
+                        status.consumed();
+                    }
+                }
+                KeyCode::Char('t') => {
+                    if let AnalysisType::TimePeriod(ref mut data) = self.analysis_type {
+                        data.toggle_by_hour();
+                        status.consumed();
+                    }
+                }
+                KeyCode::Char('g') => {
+                    if let AnalysisType::MerchantCategory(ref mut data) = self.analysis_type {
+                        data.set_min_share(data.next_min_share());
+                        self.notice = Some(match data.min_share() {
+                            0 => "Showing all categories".to_string(),
+                            share => format!("Grouping categories under {}% into 其他", share),
+                        });
                         status.consumed();
                     }
                 }
@@ -135,17 +515,45 @@ impl EventLoopParticipant for Analysis {
     }
 }
 
-impl Layer for Analysis {}
+impl Layer for Analysis {
+    fn init(&mut self) {
+        self.reload();
+    }
+
+    fn help(&self) -> HelpMsg {
+        self.get_help_message()
+    }
+}
 
 impl WidgetExt for Analysis {
     fn render(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) {
-        let [header_area, main_area, help_area] = Layout::vertical([
+        let [header_area, main_area, filter_area, notice_area, help_area] = Layout::vertical([
             Constraint::Length(1),
             Constraint::Fill(1),
+            Constraint::Length(self.filter_option.is_some() as u16),
+            Constraint::Length(self.notice.is_some() as u16),
             Constraint::Length(3),
         ])
         .areas(area);
 
+        if let Some(filter) = &self.filter_option {
+            frame.render_widget(
+                Paragraph::new(format!("Filters: {}", filter))
+                    .centered()
+                    .fg(tailwind::GRAY.c500),
+                filter_area,
+            );
+        }
+
+        if let Some(notice) = &self.notice {
+            frame.render_widget(
+                Paragraph::new(notice.as_str())
+                    .centered()
+                    .fg(tailwind::YELLOW.c500),
+                notice_area,
+            );
+        }
+
         let tabs = Tabs::new(AnalysisType::iter().map(|e| {
             format!(" {} ", e)
                 .fg(tailwind::GRAY.c500)
@@ -160,11 +568,28 @@ impl WidgetExt for Analysis {
 
         let palette = self.analysis_type.get_palette();
 
+        let message = match &self.load_state {
+            LoadState::Loaded => None,
+            LoadState::Empty => Some(Text::from("No data to analyze").fg(tailwind::GRAY.c500)),
+            LoadState::Failed => {
+                Some(Text::from("Failed to load data, press r to retry").fg(tailwind::RED.c500))
+            }
+        };
+        if let Some(message) = message {
+            let [message_area] = Layout::vertical([Constraint::Length(message.height() as u16)])
+                .flex(Flex::Center)
+                .areas(main_area);
+            frame.render_widget(Paragraph::new(message).centered(), message_area);
+            self.get_help_message().render(frame, help_area);
+            return;
+        }
+
         match &mut self.analysis_type {
             AnalysisType::TimePeriod(data) => data.render(main_area, frame, palette),
             AnalysisType::TimeSeries(data) => data.render(main_area, frame, palette),
             AnalysisType::Merchant(data) => data.render(main_area, frame, palette),
             AnalysisType::MerchantCategory(data) => data.render(main_area, frame, palette),
+            AnalysisType::Heatmap(data) => data.render(main_area, frame, palette),
         };
 
         self.get_help_message().render(frame, help_area);
@@ -176,6 +601,58 @@ impl Analysis {
         let mut help = HelpMsg::default();
         help.push(HelpEntry::new('h', "Last tab"));
         help.push(HelpEntry::new('l', "Next Tab"));
+        help.push(HelpEntry::new_plain(
+            format!("1-{}", AnalysisType::iter().count()),
+            "Jump to tab".to_string(),
+        ));
+        if let AnalysisType::TimePeriod(data) = &self.analysis_type {
+            help.push(HelpEntry::new(
+                't',
+                if data.by_hour { "By meal" } else { "By hour" },
+            ));
+        }
+        if let AnalysisType::Merchant(data) = &self.analysis_type {
+            help.push(HelpEntry::new(
+                'a',
+                if data.show_all {
+                    "Top merchants"
+                } else {
+                    "All merchants"
+                },
+            ));
+            help.push(HelpEntry::new(
+                'v',
+                if data.merge_variants() {
+                    "Exact names"
+                } else {
+                    "Merge name variants"
+                },
+            ));
+        }
+        if let AnalysisType::MerchantCategory(data) = &self.analysis_type {
+            help.push(HelpEntry::new(
+                'g',
+                match data.next_min_share() {
+                    0 => "Show all categories".to_string(),
+                    share => format!("Group under {}%", share),
+                },
+            ));
+        }
+        if matches!(
+            self.analysis_type,
+            AnalysisType::TimeSeries(_) | AnalysisType::Merchant(_)
+        ) {
+            help.push(HelpEntry::new('i', self.income_mode.next().to_string()));
+            help.push(HelpEntry::new(
+                'c',
+                match self.metric {
+                    Metric::Amount => "Show counts",
+                    Metric::Count => "Show amounts",
+                },
+            ));
+        }
+        help.push(HelpEntry::new('y', "Copy as TSV"));
+        help.push(HelpEntry::new('r', "Refresh"));
         help.push(HelpEntry::new(KeyCode::Esc, "Go back"));
         help
     }
@@ -194,7 +671,8 @@ mod test {
         let manager = TransactionManager::new(None).unwrap();
         let data = fetcher::test_utils::get_mock_data(50);
         manager.insert(&data).unwrap();
-        let page = Analysis::new(tx.clone().into(), manager);
+        let mut page = Analysis::new(None, tx.clone().into(), manager);
+        page.init();
         (_rx, page)
     }
 
@@ -202,7 +680,68 @@ mod test {
     fn test_initial_state() {
         let (_, page) = get_test_objs();
         assert!(matches!(page.analysis_type, AnalysisType::TimePeriod(_)));
-        assert!(!page.data.is_empty());
+        assert_eq!(page.load_state, LoadState::Loaded);
+        assert!(!page.data.transactions.is_empty());
+    }
+
+    #[test]
+    fn test_filtered() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&fetcher::test_utils::get_mock_data(50))
+            .unwrap();
+        let merchant = manager.fetch_all().unwrap()[0].merchant.clone();
+        let filter = FilterOptions::default().merchant(merchant.clone());
+        let expected = manager.fetch_filtered(&filter).unwrap().len();
+
+        let mut page = Analysis::new(Some(filter), tx.into(), manager);
+
+        page.init();
+        assert_eq!(page.load_state, LoadState::Loaded);
+        assert_eq!(page.data.transactions.len(), expected);
+        assert!(
+            page.data
+                .transactions
+                .iter()
+                .all(|t| t.merchant == merchant)
+        );
+        assert_eq!(
+            page.data
+                .category_counts
+                .iter()
+                .map(|(_, count)| *count as usize)
+                .sum::<usize>(),
+            expected
+        );
+
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| {
+                page.render(f, f.area());
+            })
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_empty_state() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut page = Analysis::new(None, tx.into(), TransactionManager::new(None).unwrap());
+        page.init();
+        assert_eq!(page.load_state, LoadState::Empty);
+        assert!(page.data.transactions.is_empty());
+
+        // navigating tabs with no data should not panic
+        page.handle_event_with_status_check(&'l'.into());
+
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| {
+                page.render(f, f.area());
+            })
+            .unwrap();
+        assert_snapshot!(terminal.backend());
     }
 
     #[test]
@@ -221,15 +760,362 @@ mod test {
 
         // Test wrapping around
         page.handle_event_with_status_check(&'h'.into());
+        assert!(matches!(page.analysis_type, AnalysisType::Heatmap(_)));
+        page.handle_event_with_status_check(&'h'.into());
         assert!(matches!(
             page.analysis_type,
             AnalysisType::MerchantCategory(_)
         ));
-        // Test wrapping around
         page.handle_event_with_status_check(&'h'.into());
         assert!(matches!(page.analysis_type, AnalysisType::Merchant(_)));
     }
 
+    #[test]
+    fn test_tab_navigation_no_wrap() {
+        let (_, page) = get_test_objs();
+        let mut page = page.wrap_navigation(false);
+
+        page.handle_event_with_status_check(&'h'.into());
+        assert!(matches!(page.analysis_type, AnalysisType::TimePeriod(_)));
+
+        page.handle_event_with_status_check(&'5'.into());
+        page.handle_event_with_status_check(&'l'.into());
+        assert!(matches!(page.analysis_type, AnalysisType::Heatmap(_)));
+        page.handle_event_with_status_check(&'h'.into());
+        assert!(matches!(
+            page.analysis_type,
+            AnalysisType::MerchantCategory(_)
+        ));
+    }
+
+    #[test]
+    fn test_tab_jump() {
+        let (_, mut page) = get_test_objs();
+
+        page.handle_event_with_status_check(&'3'.into());
+        assert!(matches!(page.analysis_type, AnalysisType::Merchant(_)));
+        page.handle_event_with_status_check(&'4'.into());
+        assert!(matches!(
+            page.analysis_type,
+            AnalysisType::MerchantCategory(_)
+        ));
+        page.handle_event_with_status_check(&'1'.into());
+        assert!(matches!(page.analysis_type, AnalysisType::TimePeriod(_)));
+        page.handle_event_with_status_check(&'2'.into());
+        assert!(matches!(page.analysis_type, AnalysisType::TimeSeries(_)));
+
+        // keys beyond the last tab are not handled
+        let status = page.handle_events(&'9'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+        assert!(matches!(page.analysis_type, AnalysisType::TimeSeries(_)));
+    }
+
+    #[test]
+    fn test_refresh() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let manager = TransactionManager::new(None).unwrap();
+        let mut page = Analysis::new(None, tx.into(), manager.clone());
+        page.init();
+        assert_eq!(page.load_state, LoadState::Empty);
+
+        // switch to merchant tab and keep it across refreshes
+        page.handle_event_with_status_check(&'3'.into());
+        manager
+            .insert(&fetcher::test_utils::get_mock_data(50))
+            .unwrap();
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(page.load_state, LoadState::Loaded);
+        assert_eq!(
+            page.data.transactions.len(),
+            manager.fetch_count().unwrap() as usize
+        );
+        assert!(matches!(page.analysis_type, AnalysisType::Merchant(_)));
+
+        page.handle_event_with_status_check(&'j'.into());
+        let offset = get_merchant_data(&page.analysis_type).scroll.offset();
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(
+            get_merchant_data(&page.analysis_type).scroll.offset(),
+            offset
+        );
+    }
+
+    #[test]
+    fn test_toggle_all_merchants() {
+        let (_, mut page) = get_test_objs();
+
+        // only handled on the merchant tab
+        let status = page.handle_events(&'a'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+
+        page.handle_event_with_status_check(&'3'.into());
+        assert!(!get_merchant_data(&page.analysis_type).show_all);
+        page.handle_event_with_status_check(&'a'.into());
+        assert!(get_merchant_data(&page.analysis_type).show_all);
+
+        // kept across refreshes
+        page.handle_event_with_status_check(&'r'.into());
+        assert!(get_merchant_data(&page.analysis_type).show_all);
+    }
+
+    #[test]
+    fn test_group_small_categories() {
+        let (_, mut page) = get_test_objs();
+
+        // only handled on the category tab
+        let status = page.handle_events(&'g'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+
+        page.handle_event_with_status_check(&'4'.into());
+        let total: f64 = page.analysis_type.to_pairs().iter().map(|(_, v)| v).sum();
+        page.handle_event_with_status_check(&'g'.into());
+        assert_eq!(
+            page.notice.as_deref(),
+            Some("Grouping categories under 5% into 其他")
+        );
+        let AnalysisType::MerchantCategory(data) = &page.analysis_type else {
+            panic!("expected the category tab");
+        };
+        assert_eq!(data.min_share(), 5);
+        let grouped: f64 = page.analysis_type.to_pairs().iter().map(|(_, v)| v).sum();
+        assert_eq!(grouped, total);
+
+        // kept across refreshes
+        page.handle_event_with_status_check(&'r'.into());
+        let AnalysisType::MerchantCategory(data) = &page.analysis_type else {
+            panic!("expected the category tab");
+        };
+        assert_eq!(data.min_share(), 5);
+    }
+
+    #[test]
+    fn test_toggle_merge_variants() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "梧桐苑（二楼）", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-12.0, "梧桐苑(二楼)", "2025-03-02 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let mut page = Analysis::new(None, tx.into(), manager);
+        page.init();
+
+        // only handled on the merchant tab
+        let status = page.handle_events(&'v'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+
+        page.handle_event_with_status_check(&'3'.into());
+        assert_eq!(page.analysis_type.to_pairs().len(), 1);
+        page.handle_event_with_status_check(&'v'.into());
+        assert_eq!(page.analysis_type.to_pairs().len(), 2);
+
+        // kept across refreshes
+        page.handle_event_with_status_check(&'r'.into());
+        assert!(!get_merchant_data(&page.analysis_type).merge_variants());
+    }
+
+    #[test]
+    fn test_merchant_top_n() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "A", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-8.0, "B", "2025-03-02 12:00:00").unwrap(),
+                Transaction::from_local(-6.0, "C", "2025-03-03 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let mut page = Analysis::new(None, tx.into(), manager).merchant_top_n(1);
+        page.init();
+        page.handle_event_with_status_check(&'3'.into());
+        assert_eq!(
+            page.analysis_type.to_pairs(),
+            [("A".to_string(), 10.0), ("Others".to_string(), 14.0)]
+        );
+
+        // kept across refreshes
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(page.analysis_type.to_pairs().len(), 2);
+
+        let mut page = page.merchant_top_n(0);
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(page.analysis_type.to_pairs().len(), 3);
+    }
+
+    #[test]
+    fn test_toggle_income_mode() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&vec![
+                Transaction::from_local(-10.0, "梧桐苑", "2025-03-01 12:00:00").unwrap(),
+                Transaction::from_local(-12.0, "康桥苑", "2025-04-02 12:00:00").unwrap(),
+                Transaction::from_local(30.0, "充值", "2025-04-03 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let mut page = Analysis::new(None, tx.into(), manager);
+        page.init();
+
+        // only handled on the time series and merchant tabs
+        let status = page.handle_events(&'i'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+
+        page.handle_event_with_status_check(&'2'.into());
+        let pairs = |page: &Analysis| page.analysis_type.to_pairs();
+        assert_eq!(
+            pairs(&page),
+            [("2025-03".to_string(), 10.0), ("2025-04".to_string(), 12.0)]
+        );
+
+        page.handle_event_with_status_check(&'i'.into());
+        assert_eq!(page.income_mode, IncomeMode::Net);
+        assert_eq!(page.notice.as_deref(), Some("Totals: Net income"));
+        assert_eq!(
+            pairs(&page),
+            [
+                ("2025-03".to_string(), 10.0),
+                ("2025-04".to_string(), -18.0)
+            ]
+        );
+
+        // the mode is shared with the merchant tab and kept across refreshes
+        page.handle_event_with_status_check(&'i'.into());
+        page.handle_event_with_status_check(&'3'.into());
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(page.income_mode, IncomeMode::Separate);
+        assert_eq!(
+            pairs(&page).last(),
+            Some(&("充值 (income)".to_string(), 30.0))
+        );
+
+        page.handle_event_with_status_check(&'2'.into());
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| {
+                page.render(f, f.area());
+            })
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        page.handle_event_with_status_check(&'i'.into());
+        assert_eq!(page.income_mode, IncomeMode::SpendOnly);
+    }
+
+    #[test]
+    fn test_toggle_metric() {
+        let (_, mut page) = get_test_objs();
+
+        // only handled on the time series and merchant tabs
+        let status = page.handle_events(&'c'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+
+        page.handle_event_with_status_check(&'2'.into());
+        page.handle_event_with_status_check(&'c'.into());
+        let tsv = page.analysis_type.to_tsv().unwrap();
+        assert!(tsv.starts_with("Time Series\tCount\n"));
+        let total: f64 = page.analysis_type.to_pairs().iter().map(|(_, v)| v).sum();
+        assert_eq!(total, page.data.transactions.len() as f64);
+
+        // kept when switching tabs and refreshing
+        page.handle_event_with_status_check(&'3'.into());
+        page.handle_event_with_status_check(&'a'.into());
+        page.handle_event_with_status_check(&'r'.into());
+        let tsv = page.analysis_type.to_tsv().unwrap();
+        assert!(tsv.starts_with("Merchant\tCount\n"));
+        let total: f64 = page.analysis_type.to_pairs().iter().map(|(_, v)| v).sum();
+        assert_eq!(total, page.data.transactions.len() as f64);
+
+        page.handle_event_with_status_check(&'c'.into());
+        assert!(
+            page.analysis_type
+                .to_tsv()
+                .unwrap()
+                .starts_with("Merchant\tAmount\n")
+        );
+    }
+
+    #[test]
+    fn test_toggle_by_hour() {
+        let (_, mut page) = get_test_objs();
+
+        page.handle_event_with_status_check(&'t'.into());
+        let AnalysisType::TimePeriod(data) = &page.analysis_type else {
+            panic!("Should be time period");
+        };
+        assert!(data.by_hour);
+
+        // kept across refreshes
+        page.handle_event_with_status_check(&'r'.into());
+        let AnalysisType::TimePeriod(data) = &page.analysis_type else {
+            panic!("Should be time period");
+        };
+        assert!(data.by_hour);
+
+        // only handled on the time period tab
+        page.handle_event_with_status_check(&'2'.into());
+        let status = page.handle_events(&'t'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+    }
+
+    #[test]
+    fn test_copy_tsv() {
+        let (_, mut page) = get_test_objs();
+
+        let tsv = page.analysis_type.to_tsv().unwrap();
+        let mut lines = tsv.lines();
+        assert_eq!(lines.next(), Some("Time Period\tCount"));
+        let total: f64 = lines
+            .map(|l| l.split_once('\t').unwrap().1.parse::<f64>().unwrap())
+            .sum();
+        assert_eq!(total, page.data.transactions.len() as f64);
+
+        page.handle_event_with_status_check(&'t'.into());
+        assert_eq!(page.analysis_type.to_tsv().unwrap().lines().count(), 25);
+
+        page.handle_event_with_status_check(&'3'.into());
+        let tsv = page.analysis_type.to_tsv().unwrap();
+        assert!(tsv.starts_with("Merchant\tAmount\n"));
+        assert!(tsv.lines().skip(1).all(|l| !l.contains('-')));
+
+        // the outcome depends on whether the tests run in a terminal, either way it is reported
+        page.handle_event_with_status_check(&'y'.into());
+        assert!(page.notice.is_some());
+        page.handle_event_with_status_check(&'4'.into());
+        assert!(page.notice.is_none());
+    }
+
+    #[test]
+    fn test_load_failed() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let manager = TransactionManager::new(None).unwrap();
+        manager.drop_table("transactions");
+        let mut page = Analysis::new(None, tx.into(), manager);
+        page.init();
+        assert_eq!(page.load_state, LoadState::Failed);
+        let Action::Layer(LayerManageAction::Push(config)) = rx.try_recv().unwrap() else {
+            panic!("Should push an error layer");
+        };
+        let Layers::Error(message) = config.layer else {
+            panic!("Should push an error layer");
+        };
+        assert!(message.contains("no such table"), "{message}");
+
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| page.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_copy_tsv_empty() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut page = Analysis::new(None, tx.into(), TransactionManager::new(None).unwrap());
+        page.init();
+        page.handle_event_with_status_check(&'2'.into());
+        page.handle_event_with_status_check(&'y'.into());
+        assert_eq!(page.notice.as_deref(), Some("Nothing to copy"));
+    }
+
     fn get_merchant_data(analysis_type: &AnalysisType) -> MerchantData {
         if let AnalysisType::Merchant(data) = analysis_type {
             return data.clone();
@@ -243,31 +1129,21 @@ mod test {
         let (_, mut page) = get_test_objs();
 
         // First switch to Merchant tab
-        page.handle_event_with_status_check(&'h'.into());
-        page.handle_event_with_status_check(&'h'.into());
+        page.handle_event_with_status_check(&'3'.into());
 
-        let initial_offset = get_merchant_data(&page.analysis_type)
-            .scroll_state
-            .offset()
-            .y;
+        let initial_offset = get_merchant_data(&page.analysis_type).scroll.offset();
 
         // Test scrolling down
         page.handle_event_with_status_check(&'j'.into());
         assert_eq!(
-            get_merchant_data(&page.analysis_type)
-                .scroll_state
-                .offset()
-                .y,
+            get_merchant_data(&page.analysis_type).scroll.offset(),
             initial_offset + 1
         );
 
         // Test scrolling up
         page.handle_event_with_status_check(&'k'.into());
         assert_eq!(
-            get_merchant_data(&page.analysis_type)
-                .scroll_state
-                .offset()
-                .y,
+            get_merchant_data(&page.analysis_type).scroll.offset(),
             initial_offset
         );
     }
@@ -289,7 +1165,7 @@ mod test {
     fn test_render_merchant_category() {
         let (_, mut page) = get_test_objs();
         let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
-        page.handle_event_with_status_check(&'h'.into());
+        page.handle_event_with_status_check(&'4'.into());
         terminal
             .draw(|f| {
                 page.render(f, f.area());
@@ -303,8 +1179,7 @@ mod test {
         let (_, mut page) = get_test_objs();
         let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
         // Switch to Merchant tab and render again
-        page.handle_event_with_status_check(&'h'.into());
-        page.handle_event_with_status_check(&'h'.into());
+        page.handle_event_with_status_check(&'3'.into());
 
         terminal
             .draw(|f| {
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use ratatui::{
+    Frame,
+    style::{Style, palette::tailwind},
+    symbols,
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph},
+};
+
+/// Width of the weekday labels left of the cells
+const LABEL_WIDTH: u16 = 4;
+
+/// Width of the cell of one day, including the gap after it
+const CELL_WIDTH: u16 = 2;
+
+/// Label of each weekday row, Monday first, every other one left blank
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", "Sun"];
+
+/// How often the user spent on each day, drawn as a calendar with one column per week
+#[derive(Debug, Default, Clone)]
+pub(super) struct HeatmapData {
+    /// Date, transaction count and total amount of each day with transactions, oldest first
+    days: Vec<(NaiveDate, u64, f64)>,
+}
+
+impl HeatmapData {
+    /// Build from the output of [`TransactionManager::daily_counts`]
+    ///
+    /// [`TransactionManager::daily_counts`]: crate::libs::transactions::TransactionManager::daily_counts
+    pub(super) fn new(days: &[(NaiveDate, u64, f64)]) -> Self {
+        Self {
+            days: days.to_vec(),
+        }
+    }
+
+    /// Date and transaction count of each day with transactions
+    pub(super) fn to_pairs(&self) -> Vec<(String, f64)> {
+        self.days
+            .iter()
+            .map(|(date, count, _)| (date.to_string(), *count as f64))
+            .collect()
+    }
+
+    /// Shade of `count`, from 0 for no transactions up to 4 for the busiest day
+    fn level(count: u64, max: u64) -> usize {
+        if count == 0 || max == 0 {
+            return 0;
+        }
+        (count * 4).div_ceil(max).clamp(1, 4) as usize
+    }
+
+    /// Month names above the first column of each month, skipped where they would overlap
+    fn month_header(start: NaiveDate, weeks: i64) -> String {
+        let mut header = " ".repeat(LABEL_WIDTH as usize);
+        for week in 0..weeks {
+            let monday = start + Duration::weeks(week);
+            let column = LABEL_WIDTH as usize + week as usize * CELL_WIDTH as usize;
+            let new_month = week == 0 || (monday - Duration::weeks(1)).month() != monday.month();
+            if new_month && header.len() <= column {
+                header.push_str(&" ".repeat(column - header.len()));
+                header.push_str(&monday.format("%b").to_string());
+            }
+        }
+        header
+    }
+
+    pub(super) fn render(
+        &self,
+        area: ratatui::prelude::Rect,
+        frame: &mut Frame,
+        color: tailwind::Palette,
+    ) {
+        let block = Block::bordered()
+            .border_set(symbols::border::PROPORTIONAL_TALL)
+            .border_style(color.c600)
+            .padding(Padding::horizontal(1));
+
+        let (Some(&(first, ..)), Some(&(last, ..))) = (self.days.first(), self.days.last()) else {
+            frame.render_widget(
+                Paragraph::new("No data available yet").block(block.clone()),
+                area,
+            );
+            return;
+        };
+
+        let monday_of =
+            |date: NaiveDate| date - Duration::days(date.weekday().num_days_from_monday().into());
+        let span_weeks = (monday_of(last) - monday_of(first)).num_weeks() + 1;
+        let fit_weeks = i64::from(block.inner(area).width.saturating_sub(LABEL_WIDTH) / CELL_WIDTH);
+        let weeks = span_weeks.min(fit_weeks).max(1);
+        let start = monday_of(last) - Duration::weeks(weeks - 1);
+
+        let counts: HashMap<NaiveDate, u64> = self
+            .days
+            .iter()
+            .map(|&(date, count, _)| (date, count))
+            .collect();
+        let max = self
+            .days
+            .iter()
+            .map(|&(_, count, _)| count)
+            .max()
+            .unwrap_or(0);
+        let shades = [
+            tailwind::GRAY.c800,
+            color.c900,
+            color.c700,
+            color.c500,
+            color.c300,
+        ];
+        let cell = |level: usize| Span::styled("■ ", Style::default().fg(shades[level]));
+
+        let mut lines = vec![Line::styled(
+            Self::month_header(start, weeks),
+            Style::default().fg(tailwind::GRAY.c500),
+        )];
+        for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+            let mut spans = vec![Span::styled(
+                format!("{:<width$}", label, width = LABEL_WIDTH as usize),
+                Style::default().fg(tailwind::GRAY.c500),
+            )];
+            for week in 0..weeks {
+                let date = start + Duration::weeks(week) + Duration::days(weekday as i64);
+                spans.push(if date < first || date > last {
+                    Span::raw("  ")
+                } else {
+                    cell(Self::level(counts.get(&date).copied().unwrap_or(0), max))
+                });
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let total: u64 = self.days.iter().map(|&(_, count, _)| count).sum();
+        let (busiest, busiest_count, _) = self
+            .days
+            .iter()
+            .max_by_key(|&&(date, count, _)| (count, std::cmp::Reverse(date)))
+            .copied()
+            .unwrap_or((last, 0, 0.0));
+        lines.push(Line::default());
+        lines.push(Line::from(
+            [Span::raw("Less ")]
+                .into_iter()
+                .chain((0..shades.len()).map(cell))
+                .chain([Span::raw("More")])
+                .collect::<Vec<_>>(),
+        ));
+        lines.push(Line::raw(format!(
+            "{} transactions, {} days, busiest {} ({})",
+            total,
+            self.days.len(),
+            busiest,
+            busiest_count
+        )));
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use insta::assert_snapshot;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(HeatmapData::level(0, 8), 0);
+        assert_eq!(HeatmapData::level(1, 8), 1);
+        assert_eq!(HeatmapData::level(2, 8), 1);
+        assert_eq!(HeatmapData::level(3, 8), 2);
+        assert_eq!(HeatmapData::level(8, 8), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        let data = HeatmapData::new(&[
+            (date("2025-02-20"), 1, -5.0),
+            (date("2025-02-21"), 3, -30.0),
+            (date("2025-03-02"), 8, -60.0),
+            (date("2025-03-10"), 2, -12.0),
+            (date("2025-03-12"), 8, -48.0),
+        ]);
+        assert_eq!(data.to_pairs()[1], ("2025-02-21".to_string(), 3.0));
+
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(52, 13)).unwrap();
+        terminal
+            .draw(|f| data.render(f.area(), f, tailwind::TEAL))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        // only the latest weeks that fit are shown
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(14, 13)).unwrap();
+        terminal
+            .draw(|f| data.render(f.area(), f, tailwind::TEAL))
+            .unwrap();
+        assert_snapshot!("render_narrow", terminal.backend());
+    }
+
+    #[test]
+    fn test_empty_render() {
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(40, 5)).unwrap();
+        terminal
+            .draw(|f| HeatmapData::default().render(f.area(), f, tailwind::TEAL))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
@@ -2,36 +2,219 @@ use std::collections::HashMap;
 
 use ratatui::{
     Frame,
-    layout::{Rect, Size},
+    layout::Rect,
     style::{Style, Stylize as _, palette::tailwind},
     symbols,
     text::Line,
     widgets::{Bar, BarChart, BarGroup, Block, Clear, Padding, Paragraph},
 };
-use tui_scrollview::{ScrollView, ScrollViewState, ScrollbarVisibility};
+use unicode_width::UnicodeWidthStr;
 
-use crate::libs::transactions::Transaction;
+use super::{IncomeMode, Metric};
+use crate::{
+    component::scroll_list::ScrollList,
+    libs::transactions::Transaction,
+    utils::{
+        amount::{AmountFormat, amount_style},
+        merchant_class::normalize_merchant_name,
+    },
+};
+
+/// Number of merchants shown before the rest are folded into "Others"
+pub(super) const DEFAULT_TOP_N: usize = 20;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub(super) struct MerchantData {
+    /// Spending and number of transactions of each exact merchant name, kept to regroup when
+    /// toggling `merge_variants` or `metric`
+    exact: Vec<(String, f64, usize)>,
+    /// All merchants in the current metric, negative for spending and positive for income,
+    /// sorted with the largest spending first
     data: Vec<(String, f64)>,
-    pub scroll_state: ScrollViewState,
+    top_n: usize,
+    pub show_all: bool,
+    /// Group names differing only in spacing or character width together
+    merge_variants: bool,
+    /// How positive amounts count, income rows are kept after all spending rows
+    income_mode: IncomeMode,
+    metric: Metric,
+    pub scroll: ScrollList,
+}
+
+impl Default for MerchantData {
+    fn default() -> Self {
+        Self {
+            exact: Vec::new(),
+            data: Vec::new(),
+            top_n: DEFAULT_TOP_N,
+            show_all: false,
+            merge_variants: true,
+            income_mode: IncomeMode::default(),
+            metric: Metric::default(),
+            scroll: ScrollList::default().scroll_only(),
+        }
+    }
 }
+
 impl MerchantData {
-    pub fn new(data: &[Transaction]) -> Self {
-        let hash_map = data.iter().fold(HashMap::new(), |mut acc, entry| {
-            match acc.get(&entry.merchant) {
-                Some(v) => acc.insert(&entry.merchant, *v + entry.amount),
-                None => acc.insert(&entry.merchant, entry.amount),
-            };
+    pub fn new(data: &[Transaction], income_mode: IncomeMode) -> Self {
+        let by_name = data.iter().fold(HashMap::new(), |mut acc, t| {
+            acc.entry(t.merchant.as_str())
+                .or_insert_with(Vec::new)
+                .push(t.amount);
             acc
         });
-        let mut entries: Vec<_> = hash_map.iter().collect();
-        entries.sort_by(|a, b| a.1.total_cmp(b.1));
-        MerchantData {
-            data: entries.iter().map(|e| ((*e.0).to_string(), *e.1)).collect(),
-            scroll_state: ScrollViewState::default(),
+        // spending is kept negative like the amounts, income positive
+        let mut exact = Vec::new();
+        for (name, amounts) in by_name {
+            let (spend, income) = income_mode.totals(amounts.iter().copied());
+            let income_count = match income_mode {
+                IncomeMode::Separate => amounts.iter().filter(|amount| **amount > 0.0).count(),
+                _ => 0,
+            };
+            let spend_count = match income_mode {
+                IncomeMode::Net => amounts.len(),
+                _ => amounts.iter().filter(|amount| **amount <= 0.0).count(),
+            };
+            if income_mode == IncomeMode::Net || spend_count > 0 {
+                exact.push((name.to_string(), -spend, spend_count));
+            }
+            if income > 0.0 {
+                exact.push((format!("{} (income)", name), income, income_count));
+            }
         }
+        let mut new = MerchantData {
+            exact,
+            income_mode,
+            ..Default::default()
+        };
+        new.regroup();
+        new
+    }
+
+    /// Sum amounts and counts by the key `key_of` gives each name
+    fn group<'a>(
+        entries: impl Iterator<Item = &'a (String, f64, usize)>,
+        key_of: impl Fn(&str) -> String,
+    ) -> HashMap<String, (f64, usize)> {
+        entries.fold(HashMap::new(), |mut acc, (name, amount, count)| {
+            let (total, total_count) = acc.entry(key_of(name)).or_insert((0.0, 0));
+            *total += amount;
+            *total_count += count;
+            acc
+        })
+    }
+
+    /// Rebuild `data` from the exact names according to `merge_variants` and `metric`
+    fn regroup(&mut self) {
+        let grouped = if self.merge_variants {
+            Self::group(self.exact.iter(), normalize_merchant_name)
+        } else {
+            Self::group(self.exact.iter(), |name| name.to_string())
+        };
+        let metric = self.metric;
+        let mut data: Vec<_> = grouped
+            .into_iter()
+            .map(|(name, (amount, count))| {
+                let value = match metric {
+                    Metric::Amount => amount,
+                    // keep the sign so income still sorts after spending
+                    Metric::Count if amount > 0.0 => count as f64,
+                    Metric::Count => -(count as f64),
+                };
+                (name, value)
+            })
+            .collect();
+        data.sort_by(|a, b| a.1.total_cmp(&b.1));
+        self.data = data;
+        self.scroll.set_len(self.visible_data().len());
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Measure merchants by total amount or by number of transactions
+    pub fn set_metric(&mut self, metric: Metric) {
+        if self.metric != metric {
+            self.metric = metric;
+            self.regroup();
+            self.scroll.first();
+        }
+    }
+
+    pub fn merge_variants(&self) -> bool {
+        self.merge_variants
+    }
+
+    /// Group names differing only in spacing or character width together, or keep them apart
+    pub fn set_merge_variants(&mut self, merge: bool) {
+        if self.merge_variants != merge {
+            self.merge_variants = merge;
+            self.regroup();
+            self.scroll.first();
+        }
+    }
+
+    /// Set how many merchants are shown before the rest are folded into "Others"
+    pub fn top_n(mut self, top_n: usize) -> Self {
+        self.top_n = top_n;
+        self.scroll.set_len(self.visible_data().len());
+        self
+    }
+
+    /// Switch between showing all merchants and only the top N
+    pub fn toggle_show_all(&mut self) {
+        self.show_all = !self.show_all;
+        self.scroll.set_len(self.visible_data().len());
+        self.scroll.first();
+    }
+
+    /// Show the merchants the way `old` did, e.g. after reloading the data
+    pub fn keep_view_of(&mut self, old: &MerchantData) {
+        self.show_all = old.show_all;
+        self.set_merge_variants(old.merge_variants());
+        self.scroll = old.scroll;
+        self.scroll.set_len(self.visible_data().len());
+    }
+
+    /// Merchants to display, with those beyond the top N summed into an "Others" row
+    ///
+    /// A separate income series is never folded and follows the spending rows.
+    fn visible_data(&self) -> Vec<(String, f64)> {
+        let (spending, income) = if self.income_mode == IncomeMode::Separate {
+            self.data
+                .split_at(self.data.partition_point(|(_, v)| *v <= 0.0))
+        } else {
+            (self.data.as_slice(), [].as_slice())
+        };
+        if self.show_all || spending.len() <= self.top_n {
+            return self.data.clone();
+        }
+        let others: f64 = spending[self.top_n..].iter().map(|(_, v)| v).sum();
+        let mut visible = spending[..self.top_n].to_vec();
+        visible.push(("Others".to_string(), others));
+        visible.extend_from_slice(income);
+        visible
+    }
+
+    /// Value shown for a row, spending and income both positive
+    ///
+    /// Net totals keep their sign, so a merchant that took in more than it spent is negative.
+    fn shown_value(&self, value: f64) -> f64 {
+        if self.income_mode == IncomeMode::Net && self.metric == Metric::Amount {
+            -value
+        } else {
+            value.abs()
+        }
+    }
+
+    /// Merchants currently shown, with the spending as a positive amount or count
+    pub(super) fn to_pairs(&self) -> Vec<(String, f64)> {
+        self.visible_data()
+            .into_iter()
+            .map(|(name, value)| (name, self.shown_value(value)))
+            .collect()
     }
 }
 
@@ -56,15 +239,50 @@ impl MerchantData {
         }
 
         let style = Style::default().fg(tailwind::BLUE.c300);
-        let bars: Vec<Bar> = self
-            .data
-            .clone()
-            .into_iter()
+        // Horizontal bars split the value text by bytes when it is longer than the bar,
+        // so keep it ASCII by leaving out the currency symbol.
+        let amount_format = AmountFormat::default().symbol("");
+        let data = self.visible_data();
+        // inset 1
+        let chart_area = Rect {
+            x: main_area.x + 1,
+            y: main_area.y + 1,
+            width: main_area.width - 2,
+            height: main_area.height - 2,
+        };
+        // every bar but the last is followed by a blank line
+        let visible = self
+            .scroll
+            .visible((chart_area.height as usize).div_ceil(2));
+        // scale by all merchants so bars keep their length while scrolling
+        let max = data
+            .iter()
+            .map(|(_, value)| ((self.shown_value(*value).max(0.0) * 100.0).round() as u64) / 100)
+            .max()
+            .unwrap_or_default();
+        // and pad the labels to the widest name so the bars start in the same column
+        let label_width = data
+            .iter()
+            .map(|(name, _)| name.width())
+            .max()
+            .unwrap_or_default();
+        let bars: Vec<Bar> = data[visible]
+            .iter()
             .map(|(name, value)| {
+                let shown = self.shown_value(*value);
+                let style = amount_style(style, *value);
+                let text = match self.metric {
+                    Metric::Amount => amount_format.format(shown),
+                    Metric::Count => shown.to_string(),
+                };
                 Bar::default()
-                    .value(((value.abs() * 100.0).round() as u64) / 100)
-                    .text_value(format!("{:.2}", value.abs()))
-                    .label(Line::from(name))
+                    .value(((shown.max(0.0) * 100.0).round() as u64) / 100)
+                    .text_value(text)
+                    .label(Line::from(format!(
+                        "{}{}",
+                        name,
+                        " ".repeat(label_width - name.width())
+                    )))
                     .style(style)
                     .value_style(style.reversed())
             })
@@ -74,29 +292,19 @@ impl MerchantData {
             .data(BarGroup::default().bars(&bars))
             .bar_width(1)
             .bar_gap(1)
+            .max(max)
             .direction(ratatui::layout::Direction::Horizontal);
 
-        // inset 1
-        let chart_area = Rect {
-            x: main_area.x + 1,
-            y: main_area.y + 1,
-            width: main_area.width - 2,
-            height: main_area.height - 2,
-        };
         frame.render_widget(block.clone(), main_area);
         frame.render_widget(Clear, chart_area);
-
-        let chart_height = ((self.data.len()) * 2 - 1) as u16;
-
-        let mut scroll_view = ScrollView::new(Size::new(chart_area.width, chart_height))
-            .horizontal_scrollbar_visibility(ScrollbarVisibility::Never);
-
-        scroll_view.render_widget(
+        frame.render_widget(
             bar_chart,
-            Rect::new(0, 0, chart_area.width - 1, chart_height),
+            Rect {
+                width: chart_area.width - 1,
+                ..chart_area
+            },
         );
-
-        frame.render_stateful_widget(scroll_view, chart_area, &mut self.scroll_state);
+        self.scroll.render_scrollbar(frame, chart_area);
     }
 }
 
@@ -107,6 +315,128 @@ mod test {
 
     use super::*;
 
+    fn get_test_data() -> Vec<Transaction> {
+        crate::libs::fetcher::test_utils::get_mock_data(50)
+    }
+
+    #[test]
+    fn test_top_n() {
+        let transactions = get_test_data();
+        let mut data = MerchantData::new(&transactions, IncomeMode::SpendOnly).top_n(3);
+        let total: f64 = data.data.iter().map(|(_, v)| v).sum();
+        assert!(data.data.len() > 4);
+
+        let visible = data.visible_data();
+        assert_eq!(visible.len(), 4);
+        assert_eq!(visible[..3], data.data[..3]);
+        assert_eq!(visible[3].0, "Others");
+        let visible_total: f64 = visible.iter().map(|(_, v)| v).sum();
+        assert!((visible_total - total).abs() < 1e-6);
+
+        data.toggle_show_all();
+        assert_eq!(data.visible_data(), data.data);
+    }
+
+    #[test]
+    fn test_merge_variants() {
+        let transactions = vec![
+            Transaction::from_local(-10.0, "梧桐苑（二楼）", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-12.0, " 梧桐苑(二楼)", "2025-03-02 12:00:00").unwrap(),
+            Transaction::from_local(-5.0, "东区  浴室", "2025-03-03 12:00:00").unwrap(),
+        ];
+        let mut data = MerchantData::new(&transactions, IncomeMode::SpendOnly);
+        assert!(data.merge_variants());
+        assert_eq!(
+            data.data,
+            vec![
+                ("梧桐苑(二楼)".to_string(), -22.0),
+                ("东区 浴室".to_string(), -5.0)
+            ]
+        );
+
+        data.set_merge_variants(false);
+        assert_eq!(data.data.len(), 3);
+        assert_eq!(data.data[2], ("东区  浴室".to_string(), -5.0));
+    }
+
+    #[test]
+    fn test_income_modes() {
+        let transactions = vec![
+            Transaction::from_local(-10.0, "A", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-4.0, "B", "2025-03-01 13:00:00").unwrap(),
+            Transaction::from_local(3.0, "B", "2025-03-02 12:00:00").unwrap(),
+            Transaction::from_local(-2.0, "C", "2025-03-03 12:00:00").unwrap(),
+            Transaction::from_local(50.0, "充值", "2025-03-04 12:00:00").unwrap(),
+        ];
+
+        let data = MerchantData::new(&transactions, IncomeMode::SpendOnly);
+        assert_eq!(
+            data.to_pairs(),
+            [
+                ("A".to_string(), 10.0),
+                ("B".to_string(), 4.0),
+                ("C".to_string(), 2.0)
+            ]
+        );
+
+        let data = MerchantData::new(&transactions, IncomeMode::Net);
+        assert_eq!(
+            data.to_pairs(),
+            [
+                ("A".to_string(), 10.0),
+                ("C".to_string(), 2.0),
+                ("B".to_string(), 1.0),
+                ("充值".to_string(), -50.0)
+            ]
+        );
+
+        // income rows stay after the folded spending
+        let data = MerchantData::new(&transactions, IncomeMode::Separate).top_n(1);
+        assert_eq!(
+            data.to_pairs(),
+            [
+                ("A".to_string(), 10.0),
+                ("Others".to_string(), 6.0),
+                ("B (income)".to_string(), 3.0),
+                ("充值 (income)".to_string(), 50.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_metric() {
+        let transactions = vec![
+            Transaction::from_local(-30.0, "A", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-4.0, "B", "2025-03-01 13:00:00").unwrap(),
+            Transaction::from_local(-3.0, "B", "2025-03-02 12:00:00").unwrap(),
+            Transaction::from_local(-2.0, "B", "2025-03-03 12:00:00").unwrap(),
+            Transaction::from_local(-1.0, "C", "2025-03-03 12:00:00").unwrap(),
+            Transaction::from_local(50.0, "充值", "2025-03-04 12:00:00").unwrap(),
+        ];
+        let mut data = MerchantData::new(&transactions, IncomeMode::Separate).top_n(1);
+        data.set_metric(Metric::Count);
+        assert_eq!(data.metric(), Metric::Count);
+        // sorted by frequency, income still last
+        assert_eq!(
+            data.to_pairs(),
+            [
+                ("B".to_string(), 3.0),
+                ("Others".to_string(), 2.0),
+                ("充值 (income)".to_string(), 1.0)
+            ]
+        );
+
+        data.set_metric(Metric::Amount);
+        assert_eq!(data.to_pairs()[0], ("A".to_string(), 30.0));
+    }
+
+    #[test]
+    fn test_top_n_not_reached() {
+        let transactions = get_test_data();
+        let data = MerchantData::new(&transactions, IncomeMode::SpendOnly).top_n(usize::MAX);
+        assert_eq!(data.visible_data(), data.data);
+    }
+
     #[test]
     fn test_empty_render() {
         let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
@@ -6,9 +6,14 @@ use ratatui::{
     widgets::{Bar, BarChart, BarGroup, Block, Padding, Paragraph},
 };
 
-use crate::libs::transactions::Transaction;
 use crate::utils::merchant_class::MerchantType; // 引入商家分类
 
+/// Shares (in percent) cycled through when grouping small categories, 0 to show all
+const MIN_SHARE_STEPS: [u32; 3] = [0, 5, 10];
+
+/// Label of the category small ones are grouped into
+const OTHER_LABEL: &str = "其他";
+
 #[derive(Debug, Default, Clone)]
 pub(super) struct MerchantCategoryData {
     canteen_food: u32,
@@ -17,22 +22,26 @@ pub(super) struct MerchantCategoryData {
     bathhouse: u32,
     other: u32,
     unknown: u32,
+    /// Categories below this percentage of all transactions are added to "其他", 0 to keep all
+    min_share: u32,
 }
 
 impl MerchantCategoryData {
-    pub(super) fn new(data: &[Transaction]) -> Self {
-        data.iter().fold(Self::default(), |mut acc, entry| {
-            let merchant_type = MerchantType::from_str(&entry.merchant);
-            match merchant_type {
-                MerchantType::CanteenFood => acc.canteen_food += 1,
-                MerchantType::CanteenDrink => acc.canteen_drink += 1,
-                MerchantType::Supermarket => acc.supermarket += 1,
-                MerchantType::Bathhouse => acc.bathhouse += 1,
-                MerchantType::Other => acc.other += 1,
-                MerchantType::Unknown => acc.unknown += 1,
-            }
-            acc
-        })
+    /// Build from the transaction count of each category
+    pub(super) fn from_counts(counts: &[(MerchantType, u32)]) -> Self {
+        counts
+            .iter()
+            .fold(Self::default(), |mut acc, (merchant_type, count)| {
+                match merchant_type {
+                    MerchantType::CanteenFood => acc.canteen_food += count,
+                    MerchantType::CanteenDrink => acc.canteen_drink += count,
+                    MerchantType::Supermarket => acc.supermarket += count,
+                    MerchantType::Bathhouse => acc.bathhouse += count,
+                    MerchantType::Other => acc.other += count,
+                    MerchantType::Unknown => acc.unknown += count,
+                }
+                acc
+            })
     }
 
     fn all_zero(&self) -> bool {
@@ -43,6 +52,58 @@ impl MerchantCategoryData {
             && self.other == 0
             && self.unknown == 0
     }
+
+    pub(super) fn min_share(&self) -> u32 {
+        self.min_share
+    }
+
+    pub(super) fn set_min_share(&mut self, min_share: u32) {
+        self.min_share = min_share;
+    }
+
+    /// The threshold after the current one in [`MIN_SHARE_STEPS`]
+    pub(super) fn next_min_share(&self) -> u32 {
+        let idx = MIN_SHARE_STEPS
+            .iter()
+            .position(|&s| s == self.min_share)
+            .map_or(0, |i| (i + 1) % MIN_SHARE_STEPS.len());
+        MIN_SHARE_STEPS[idx]
+    }
+
+    /// Categories to display, with those below `min_share` added to "其他"
+    ///
+    /// Grouped categories are dropped from the list, so the counts still add up to the total.
+    fn visible_data(&self) -> Vec<(&'static str, u32)> {
+        if self.min_share == 0 {
+            return self.into_iter().collect();
+        }
+        let total: u32 = self.into_iter().map(|(_, value)| value).sum();
+        let is_small = |name: &str, value: u32| {
+            name != OTHER_LABEL
+                && u64::from(value) * 100 < u64::from(total) * u64::from(self.min_share)
+        };
+        let grouped: u32 = self
+            .into_iter()
+            .filter(|&(name, value)| is_small(name, value))
+            .map(|(_, value)| value)
+            .sum();
+        self.into_iter()
+            .filter(|&(name, value)| !is_small(name, value))
+            .map(|(name, value)| match name {
+                OTHER_LABEL => (name, value + grouped),
+                _ => (name, value),
+            })
+            .filter(|&(_, value)| value > 0)
+            .collect()
+    }
+
+    /// Label and count of each category
+    pub(super) fn to_pairs(&self) -> Vec<(String, f64)> {
+        self.visible_data()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), f64::from(value)))
+            .collect()
+    }
 }
 
 impl IntoIterator for &MerchantCategoryData {
@@ -55,7 +116,7 @@ impl IntoIterator for &MerchantCategoryData {
             ("食堂饮品", self.canteen_drink),
             ("超市", self.supermarket),
             ("浴室", self.bathhouse),
-            ("其他", self.other),
+            (OTHER_LABEL, self.other),
             ("未知", self.unknown),
         ]
         .into_iter()
@@ -81,6 +142,7 @@ impl MerchantCategoryData {
 
         let style = Style::default().fg(color.c300);
         let bars: Vec<Bar> = self
+            .visible_data()
             .into_iter()
             .map(|(name, value)| {
                 Bar::default()
@@ -107,6 +169,33 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn test_group_small() {
+        let mut data = MerchantCategoryData::from_counts(&[
+            (MerchantType::CanteenFood, 90),
+            (MerchantType::Supermarket, 6),
+            (MerchantType::Bathhouse, 3),
+            (MerchantType::Other, 1),
+        ]);
+        assert_eq!(data.visible_data().len(), 6);
+
+        data.set_min_share(data.next_min_share());
+        assert_eq!(data.min_share(), 5);
+        assert_eq!(
+            data.visible_data(),
+            vec![("食堂食物", 90), ("超市", 6), ("其他", 4)]
+        );
+
+        data.set_min_share(data.next_min_share());
+        assert_eq!(data.visible_data(), vec![("食堂食物", 90), ("其他", 10)]);
+        assert_eq!(data.to_pairs().iter().map(|(_, v)| v).sum::<f64>(), 100.0);
+
+        // wraps back to showing every category
+        data.set_min_share(data.next_min_share());
+        assert_eq!(data.min_share(), 0);
+        assert_eq!(data.visible_data().len(), 6);
+    }
+
     #[test]
     fn test_empty_render() {
         let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
@@ -1,4 +1,4 @@
-use chrono::NaiveTime;
+use chrono::{NaiveTime, Timelike};
 use ratatui::{
     Frame,
     style::{Style, Stylize as _, palette::tailwind},
@@ -19,11 +19,23 @@ pub(super) struct TimePeriodData {
     dinner: u32,
     /// other
     unknown: u32,
+    /// Transaction count for each hour of the day
+    hourly: [u32; 24],
+    /// Show the 24-hour breakdown instead of meal periods
+    pub by_hour: bool,
+}
+
+/// Count transactions by hour of the day (0-23)
+pub(super) fn bucket_by_hour(data: &[Transaction]) -> [u32; 24] {
+    data.iter().fold([0; 24], |mut acc, entry| {
+        acc[entry.time.hour() as usize] += 1;
+        acc
+    })
 }
 
 impl TimePeriodData {
     pub(super) fn new(data: &[Transaction]) -> Self {
-        data.iter().fold(Self::default(), |mut acc, entry| {
+        let periods = data.iter().fold(Self::default(), |mut acc, entry| {
             let time = entry.time.time();
             if Self::check_time_in(time, (5, 0), (10, 30)) {
                 acc.breakfast += 1;
@@ -35,7 +47,16 @@ impl TimePeriodData {
                 acc.unknown += 1;
             }
             acc
-        })
+        });
+        Self {
+            hourly: bucket_by_hour(data),
+            ..periods
+        }
+    }
+
+    /// Switch between meal periods and the hour-of-day breakdown
+    pub fn toggle_by_hour(&mut self) {
+        self.by_hour = !self.by_hour;
     }
     fn check_time_in(time: NaiveTime, start: (u32, u32), end: (u32, u32)) -> bool {
         if time >= NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap()
@@ -48,6 +69,20 @@ impl TimePeriodData {
     fn all_zero(&self) -> bool {
         self.breakfast == 0 && self.lunch == 0 && self.dinner == 0 && self.unknown == 0
     }
+    /// Label and count of each bar currently shown
+    pub(super) fn to_pairs(&self) -> Vec<(String, f64)> {
+        if self.by_hour {
+            self.hourly
+                .iter()
+                .enumerate()
+                .map(|(hour, value)| (format!("{:02}", hour), f64::from(*value)))
+                .collect()
+        } else {
+            self.into_iter()
+                .map(|(name, value)| (name.to_string(), f64::from(value)))
+                .collect()
+        }
+    }
 }
 impl IntoIterator for &TimePeriodData {
     type Item = (&'static str, u32);
@@ -84,6 +119,12 @@ impl TimePeriodData {
         }
 
         let style = Style::default().fg(color.c300);
+
+        if self.by_hour {
+            self.render_by_hour(area, frame, block, style);
+            return;
+        }
+
         let bars: Vec<Bar> = self
             .into_iter()
             .map(|(name, value)| {
@@ -102,6 +143,38 @@ impl TimePeriodData {
             .direction(ratatui::layout::Direction::Horizontal);
         frame.render_widget(bar_chart, area);
     }
+
+    fn render_by_hour(
+        &self,
+        area: ratatui::prelude::Rect,
+        frame: &mut Frame,
+        block: Block,
+        style: Style,
+    ) {
+        // fit all 24 bars (with a gap of 1) into the inner width
+        let bar_width = ((block.inner(area).width + 1) / 24)
+            .saturating_sub(1)
+            .max(1);
+
+        let bars: Vec<Bar> = self
+            .hourly
+            .iter()
+            .enumerate()
+            .map(|(hour, value)| {
+                Bar::default()
+                    .value(u64::from(*value))
+                    .label(Line::from(format!("{:02}", hour)))
+                    .style(style)
+                    .value_style(style.reversed())
+            })
+            .collect();
+        let bar_chart = BarChart::default()
+            .block(block)
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1);
+        frame.render_widget(bar_chart, area);
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +184,42 @@ mod test {
 
     use super::*;
 
+    fn get_test_data() -> Vec<Transaction> {
+        [
+            "2025-03-01 07:30:00",
+            "2025-03-01 12:00:00",
+            "2025-03-01 12:59:59",
+            "2025-03-02 18:15:00",
+            "2025-03-02 23:59:00",
+            "2025-03-03 00:00:00",
+        ]
+        .into_iter()
+        .map(|time| Transaction::from_local(-10.0, "Canteen", time).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_bucket_by_hour() {
+        let hourly = bucket_by_hour(&get_test_data());
+        assert_eq!(hourly[0], 1);
+        assert_eq!(hourly[7], 1);
+        assert_eq!(hourly[12], 2);
+        assert_eq!(hourly[18], 1);
+        assert_eq!(hourly[23], 1);
+        assert_eq!(hourly.iter().sum::<u32>(), 6);
+    }
+
+    #[test]
+    fn test_render_by_hour() {
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let mut data = TimePeriodData::new(&get_test_data());
+        data.toggle_by_hour();
+        terminal
+            .draw(|f| data.render(f.area(), f, tailwind::BLUE))
+            .unwrap();
+        assert_snapshot!(terminal.backend())
+    }
+
     #[test]
     fn test_empty_render() {
         let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
@@ -10,10 +10,14 @@ use ratatui::{
 };
 use tracing::info;
 
-use crate::libs::transactions::Transaction;
+use super::{IncomeMode, Metric};
+use crate::{
+    libs::transactions::Transaction,
+    utils::amount::{AmountFormat, INCOME_FG, amount_style},
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct YearMonth {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct YearMonth {
     year: u16,
     month: u16,
 }
@@ -44,49 +48,130 @@ impl std::fmt::Display for YearMonth {
     }
 }
 
-#[derive(Debug, Default, Clone)]
-pub(super) struct TimeSeriesData {
-    data: Vec<(YearMonth, f64)>,
+/// 单个月份的消费汇总
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MonthlyBucket {
+    pub period: YearMonth,
+    /// 消费金额之和，按 [`IncomeMode`] 处理正数金额
+    pub total: f64,
+    /// 收入金额之和，仅在 [`IncomeMode::Separate`] 下统计
+    pub income: f64,
+    /// 计入 `total` 的交易笔数
+    pub count: usize,
+    /// 计入 `income` 的交易笔数
+    pub income_count: usize,
 }
 
-impl TimeSeriesData {
-    pub(super) fn new(data: &[Transaction]) -> Self {
-        let mut processed_data: Vec<(YearMonth, f64)> = data
-            .iter()
-            .fold(HashMap::new(), |mut acc, entry| {
-                let year = entry.time.year() as u16;
-                let month = entry.time.month() as u16;
-                match acc.get(&(year, month)) {
-                    Some(v) => acc.insert((year, month), *v + entry.amount.abs()),
-                    None => acc.insert((year, month), entry.amount.abs()),
-                };
-                acc
-            })
-            .iter()
-            .map(|((year, month), amount)| (YearMonth::new(*year, *month), *amount))
-            .collect();
-
-        processed_data.sort_by(|a, b| a.0.cmp(&b.0));
-
-        let processed_data = processed_data
-            .into_iter()
-            .fold(Vec::new(), |mut acc, entry| {
-                let mut last_ym = acc.last().unwrap_or(&entry).0;
-                while last_ym.next() < entry.0 {
-                    info!("Missing data for {:?}, {:?}", last_ym, entry.0);
-                    acc.push((last_ym, 0.0));
-                    last_ym = last_ym.next();
+/// 将交易记录按月汇总
+///
+/// 结果按月份升序排列，首尾之间没有交易的月份会以零值补齐。
+///
+/// # 参数
+///
+/// * `data` - 交易记录，无需排序
+/// * `mode` - 正数金额（充值、退款）的计入方式
+///
+/// # 返回值
+///
+/// 每个月份的消费总额、收入与笔数，没有交易时返回空列表
+pub(crate) fn bucket_by_month(data: &[Transaction], mode: IncomeMode) -> Vec<MonthlyBucket> {
+    let mut buckets: Vec<MonthlyBucket> = data
+        .iter()
+        .filter(|entry| mode != IncomeMode::SpendOnly || entry.amount <= 0.0)
+        .fold(HashMap::new(), |mut acc, entry| {
+            let period = YearMonth::new(entry.time.year() as u16, entry.time.month() as u16);
+            acc.entry(period)
+                .or_insert_with(Vec::new)
+                .push(entry.amount);
+            acc
+        })
+        .into_iter()
+        .map(|(period, amounts)| {
+            let (total, income) = mode.totals(amounts.iter().copied());
+            let income_count = match mode {
+                IncomeMode::Separate => amounts.iter().filter(|amount| **amount > 0.0).count(),
+                _ => 0,
+            };
+            MonthlyBucket {
+                period,
+                total,
+                income,
+                count: amounts.len() - income_count,
+                income_count,
+            }
+        })
+        .collect();
+
+    buckets.sort_by_key(|b| b.period);
+
+    let buckets = buckets
+        .into_iter()
+        .fold(Vec::<MonthlyBucket>::new(), |mut acc, entry| {
+            if let Some(last) = acc.last() {
+                let mut missing_ym = last.period.next();
+                while missing_ym < entry.period {
+                    info!("Missing data for {:?}", missing_ym);
+                    acc.push(MonthlyBucket {
+                        period: missing_ym,
+                        total: 0.0,
+                        income: 0.0,
+                        count: 0,
+                        income_count: 0,
+                    });
+                    missing_ym = missing_ym.next();
                 }
-                acc.push(entry);
-                acc
-            });
+            }
+            acc.push(entry);
+            acc
+        });
 
-        info!("{:?}", processed_data);
+    info!("{:?}", buckets);
 
+    buckets
+}
+
+#[derive(Debug, Default, Clone)]
+pub(super) struct TimeSeriesData {
+    data: Vec<MonthlyBucket>,
+    income_mode: IncomeMode,
+    metric: Metric,
+}
+
+impl TimeSeriesData {
+    pub(super) fn new(data: &[Transaction], income_mode: IncomeMode) -> Self {
         Self {
-            data: processed_data,
+            data: bucket_by_month(data, income_mode),
+            income_mode,
+            metric: Metric::default(),
         }
     }
+    pub(super) fn metric(&self) -> Metric {
+        self.metric
+    }
+    pub(super) fn set_metric(&mut self, metric: Metric) {
+        self.metric = metric;
+    }
+    /// Spending and income of `bucket` in the current metric
+    fn values(&self, bucket: &MonthlyBucket) -> (f64, f64) {
+        match self.metric {
+            Metric::Amount => (bucket.total, bucket.income),
+            Metric::Count => (bucket.count as f64, bucket.income_count as f64),
+        }
+    }
+    /// Label and total (or count) of every month, including those scrolled out of the chart
+    ///
+    /// With a separate income series, each month is followed by an `income` row.
+    pub(super) fn to_pairs(&self) -> Vec<(String, f64)> {
+        self.data
+            .iter()
+            .flat_map(|bucket| {
+                let (total, income) = self.values(bucket);
+                let income = (self.income_mode == IncomeMode::Separate)
+                    .then(|| (format!("{} income", bucket.period), income));
+                std::iter::once((bucket.period.to_string(), total)).chain(income)
+            })
+            .collect()
+    }
     pub(super) fn render(
         &self,
         area: ratatui::prelude::Rect,
@@ -106,26 +191,52 @@ impl TimeSeriesData {
             return;
         }
 
-        let bars = (area.width as u64 - 6) / 8;
+        let separate = self.income_mode == IncomeMode::Separate;
+        let months = (area.width as u64 - 6) / if separate { 16 } else { 8 };
 
         let style = Style::default().fg(color.c300);
-        let bars: Vec<Bar> = self.data[self.data.len().saturating_sub(bars as usize)..]
-            .iter()
-            .map(|(ym, value)| {
-                Bar::default()
-                    .value(value.round() as u64)
-                    .label(Line::from(ym.to_string()))
-                    .style(style)
-                    .value_style(style.reversed())
-            })
-            .collect();
+        let income_style = Style::default().fg(INCOME_FG);
+        let bar = |value: f64, style: Style| {
+            let text = match self.metric {
+                Metric::Amount => AmountFormat::default().decimals(0).format(value),
+                Metric::Count => value.to_string(),
+            };
+            Bar::default()
+                .value(value.max(0.0).round() as u64)
+                .text_value(text)
+                .style(style)
+                .value_style(style.reversed())
+        };
+        let shown = &self.data[self.data.len().saturating_sub(months as usize)..];
 
-        let bar_chart = BarChart::default()
+        let mut bar_chart = BarChart::default()
             .block(block)
-            .data(BarGroup::default().bars(&bars))
             .bar_width(7)
             .bar_gap(1)
             .bar_style(style);
+        if separate {
+            // one group per month, spending next to income
+            for bucket in shown {
+                let (total, income) = self.values(bucket);
+                bar_chart = bar_chart.data(
+                    BarGroup::default()
+                        .label(Line::from(bucket.period.to_string()).centered())
+                        .bars(&[bar(total, style), bar(income, income_style)]),
+                );
+            }
+            bar_chart = bar_chart.group_gap(1);
+        } else {
+            let bars: Vec<Bar> = shown
+                .iter()
+                .map(|bucket| {
+                    let total = self.values(bucket).0;
+                    // a negative net spending means more came in than went out
+                    bar(total, amount_style(style, -total))
+                        .label(Line::from(bucket.period.to_string()))
+                })
+                .collect();
+            bar_chart = bar_chart.data(BarGroup::default().bars(&bars));
+        }
 
         frame.render_widget(bar_chart, area);
     }
@@ -138,6 +249,97 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn test_bucket_by_month() {
+        let data = vec![
+            Transaction::from_local(-10.0, "A", "2024-01-15 12:00:00").unwrap(),
+            Transaction::from_local(-5.5, "A", "2024-04-01 08:00:00").unwrap(),
+            Transaction::from_local(-2.5, "B", "2024-01-31 18:00:00").unwrap(),
+        ];
+        let buckets = bucket_by_month(&data, IncomeMode::SpendOnly);
+        let periods: Vec<String> = buckets.iter().map(|b| b.period.to_string()).collect();
+        assert_eq!(periods, ["2024-01", "2024-02", "2024-03", "2024-04"]);
+        assert_eq!(buckets[0].total, 12.5);
+        assert_eq!(buckets[0].count, 2);
+        assert_eq!(buckets[1].count, 0);
+        assert_eq!(buckets[3].total, 5.5);
+
+        assert!(bucket_by_month(&[], IncomeMode::SpendOnly).is_empty());
+    }
+
+    #[test]
+    fn test_bucket_income() {
+        let data = vec![
+            Transaction::from_local(-10.0, "A", "2024-01-15 12:00:00").unwrap(),
+            Transaction::from_local(50.0, "充值", "2024-01-16 12:00:00").unwrap(),
+            Transaction::from_local(-2.5, "B", "2024-01-31 18:00:00").unwrap(),
+            Transaction::from_local(20.0, "充值", "2024-02-01 08:00:00").unwrap(),
+        ];
+
+        let buckets = bucket_by_month(&data, IncomeMode::SpendOnly);
+        // the recharge-only month is left out
+        assert_eq!(buckets.len(), 1);
+        assert_eq!((buckets[0].total, buckets[0].income), (12.5, 0.0));
+        assert_eq!(buckets[0].count, 2);
+
+        let buckets = bucket_by_month(&data, IncomeMode::Net);
+        assert_eq!((buckets[0].total, buckets[0].income), (-37.5, 0.0));
+        assert_eq!((buckets[1].total, buckets[1].income), (-20.0, 0.0));
+        assert_eq!(buckets[0].count, 3);
+
+        let buckets = bucket_by_month(&data, IncomeMode::Separate);
+        assert_eq!((buckets[0].total, buckets[0].income), (12.5, 50.0));
+        assert_eq!((buckets[1].total, buckets[1].income), (0.0, 20.0));
+        assert_eq!((buckets[0].count, buckets[0].income_count), (2, 1));
+
+        let pairs = TimeSeriesData::new(&data, IncomeMode::Separate).to_pairs();
+        assert_eq!(
+            pairs,
+            [
+                ("2024-01".to_string(), 12.5),
+                ("2024-01 income".to_string(), 50.0),
+                ("2024-02".to_string(), 0.0),
+                ("2024-02 income".to_string(), 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_metric() {
+        let data = vec![
+            Transaction::from_local(-10.0, "A", "2024-01-15 12:00:00").unwrap(),
+            Transaction::from_local(-2.5, "B", "2024-01-31 18:00:00").unwrap(),
+            Transaction::from_local(50.0, "充值", "2024-01-16 12:00:00").unwrap(),
+            Transaction::from_local(-5.5, "A", "2024-03-01 08:00:00").unwrap(),
+        ];
+        let mut series = TimeSeriesData::new(&data, IncomeMode::SpendOnly);
+        series.set_metric(Metric::Count);
+        assert_eq!(
+            series.to_pairs(),
+            [
+                ("2024-01".to_string(), 2.0),
+                ("2024-02".to_string(), 0.0),
+                ("2024-03".to_string(), 1.0),
+            ]
+        );
+
+        let mut series = TimeSeriesData::new(&data, IncomeMode::Separate);
+        series.set_metric(Metric::Count);
+        assert_eq!(
+            series.to_pairs()[..2],
+            [
+                ("2024-01".to_string(), 2.0),
+                ("2024-01 income".to_string(), 1.0),
+            ]
+        );
+
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(60, 10)).unwrap();
+        terminal
+            .draw(|f| series.render(f.area(), f, tailwind::BLUE))
+            .unwrap();
+        assert_snapshot!(terminal.backend())
+    }
+
     #[test]
     fn test_empty_render() {
         let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
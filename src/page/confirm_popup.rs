@@ -0,0 +1,191 @@
+use std::cmp::{max, min};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::Rect,
+    style::{Style, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    actions::{Action, ActionSender, LayerManageAction},
+    app::layer_manager::EventHandlingStatus,
+    tui::Event,
+    utils::help_msg::{HelpEntry, HelpMsg},
+};
+
+use super::{EventLoopParticipant, Layer, WidgetExt};
+
+/// A yes/no confirmation dialog
+///
+/// Pops itself on `y`, `n` or `Esc`, then sends `on_confirm` or `on_cancel`
+/// respectively, so the action is handled by the layer below.
+pub(crate) struct ConfirmPopup {
+    prompt: String,
+    on_confirm: Action,
+    on_cancel: Action,
+
+    tx: ActionSender,
+}
+
+impl ConfirmPopup {
+    pub fn new(tx: ActionSender, prompt: String, on_confirm: Action, on_cancel: Action) -> Self {
+        Self {
+            prompt,
+            on_confirm,
+            on_cancel,
+            tx,
+        }
+    }
+
+    fn get_help_msg() -> HelpMsg {
+        vec![
+            HelpEntry::new('y', "Confirm"),
+            HelpEntry::new('n', "Cancel"),
+            HelpEntry::new(KeyCode::Esc, "Cancel"),
+        ]
+        .into()
+    }
+}
+
+impl EventLoopParticipant for ConfirmPopup {
+    fn handle_events(&mut self, event: &Event) -> EventHandlingStatus {
+        let mut status = EventHandlingStatus::default();
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.tx.send(LayerManageAction::Pop);
+                    self.tx.send(self.on_confirm.clone());
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.tx.send(LayerManageAction::Pop);
+                    self.tx.send(self.on_cancel.clone());
+                }
+                _ => {}
+            }
+            // the dialog is modal, never let key events reach the layer below
+            status.consumed();
+        }
+        status
+    }
+}
+
+impl Layer for ConfirmPopup {
+    fn help(&self) -> HelpMsg {
+        Self::get_help_msg()
+    }
+}
+
+impl WidgetExt for ConfirmPopup {
+    fn render(&mut self, frame: &mut ratatui::Frame, area: Rect) {
+        let width = min(
+            max(UnicodeWidthStr::width(self.prompt.as_str()) as u16 + 6, 40),
+            area.width.saturating_sub(4),
+        );
+        let height = min(7, area.height);
+        let show_area = Rect {
+            x: area.x + (area.width.saturating_sub(width)) / 2,
+            y: area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        };
+        let bottom_help_area = Rect {
+            x: area.x,
+            y: area.y + area.height.saturating_sub(3),
+            width: area.width,
+            height: min(3, area.height),
+        };
+
+        frame.render_widget(Clear, bottom_help_area);
+        ConfirmPopup::get_help_msg().render(frame, bottom_help_area);
+
+        let block = Block::new()
+            .title(Line::raw("Confirm").centered())
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .padding(Padding::uniform(1));
+
+        let key_style = Style::default().fg(tailwind::BLUE.c400);
+        let text = Text::from(vec![
+            Line::raw(self.prompt.as_str()),
+            Line::default(),
+            Line::from(vec![
+                Span::styled("[y]", key_style),
+                Span::raw(" Yes    "),
+                Span::styled("[n]", key_style),
+                Span::raw(" No"),
+            ]),
+        ]);
+
+        frame.render_widget(Clear, show_area);
+        frame.render_widget(
+            Paragraph::new(text)
+                .centered()
+                .wrap(Wrap { trim: true })
+                .block(block),
+            show_area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    use super::*;
+
+    fn get_test_objs() -> (UnboundedReceiver<Action>, ConfirmPopup) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let popup = ConfirmPopup::new(
+            tx.into(),
+            "Clear the local database?".to_string(),
+            Action::Quit,
+            Action::Render,
+        );
+        (rx, popup)
+    }
+
+    #[test]
+    fn test_confirm() {
+        let (mut rx, mut popup) = get_test_objs();
+        popup.handle_event_with_status_check(&'y'.into());
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::Layer(LayerManageAction::Pop)
+        ));
+        assert!(matches!(rx.try_recv().unwrap(), Action::Quit));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_cancel() {
+        for key in [Event::from('n'), KeyCode::Esc.into()] {
+            let (mut rx, mut popup) = get_test_objs();
+            popup.handle_event_with_status_check(&key);
+            assert!(matches!(
+                rx.try_recv().unwrap(),
+                Action::Layer(LayerManageAction::Pop)
+            ));
+            assert!(matches!(rx.try_recv().unwrap(), Action::Render));
+        }
+    }
+
+    #[test]
+    fn test_other_keys_consumed() {
+        let (mut rx, mut popup) = get_test_objs();
+        popup.handle_event_with_status_check(&'q'.into());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let (_, mut popup) = get_test_objs();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| popup.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
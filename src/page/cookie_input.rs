@@ -10,6 +10,7 @@
 //! - **数据持久化**: 自动保存输入信息到本地数据库
 //! - **格式化处理**: 自动处理 Cookie 格式，支持有无 `hallticket=` 前缀
 //! - **数据回显**: 从数据库加载已保存的认证信息
+//! - **提示信息**: 会话过期等原因跳转至本页时，在顶部显示原因
 //!
 //! ## 页面布局
 //!
@@ -70,7 +71,7 @@
 //! | 按键 | 功能 |
 //! |------|------|
 //! | `j`/`k` | 在输入框间切换焦点 |
-//! | `Esc` | 返回数据获取页面 |
+//! | `Esc` | 返回打开本页的数据获取页面 |
 //! | `?` | 显示帮助信息 |
 //!
 //! ## 数据存储
@@ -90,7 +91,7 @@
 //! 2. 系统自动加载已保存的认证信息（如果存在）
 //! 3. 用户可以修改学号和 Cookie 信息
 //! 4. 输入完成后自动保存到数据库
-//! 5. 返回数据获取页面继续操作
+//! 5. 返回数据获取页面继续操作，因 Cookie 失效而暂停的获取可在那里继续
 //!
 //! ## 错误处理
 //!
@@ -108,6 +109,8 @@ use crossterm::event::KeyCode;
 use ratatui::Frame;
 use ratatui::layout::Rect;
 use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::Text;
 
 use crate::actions::ActionSender;
 use crate::actions::LayerManageAction;
@@ -119,6 +122,9 @@ use crate::utils::help_msg::{HelpEntry, HelpMsg};
 
 use super::{EventLoopParticipant, Layer, WidgetExt};
 
+/// Upper bound for the pasted hallticket, well above any real cookie value
+const COOKIE_MAX_LEN: usize = 4096;
+
 #[derive(Clone, Debug)]
 pub struct CookieInput {
     state: Focus,
@@ -128,6 +134,9 @@ pub struct CookieInput {
 
     cookie_input: InputComp,
     account_input: InputComp,
+
+    /// Shown above the inputs, e.g. why the user was sent here
+    notice: Option<String>,
 }
 
 impl CookieInput {
@@ -139,12 +148,20 @@ impl CookieInput {
         Self {
             state: Default::default(),
             manager,
-            cookie_input: InputComp::new().init_text(cookie).title("Hallticket"),
+            cookie_input: InputComp::new()
+                .init_text(cookie)
+                .title("Hallticket")
+                .max_len(COOKIE_MAX_LEN),
             account_input: InputComp::new().init_text(account).title("Account"),
             tx: action_tx,
+            notice: None,
         }
     }
 
+    pub fn notice(self, notice: Option<String>) -> Self {
+        Self { notice, ..self }
+    }
+
     pub fn get_help_msg(&self) -> crate::utils::help_msg::HelpMsg {
         let help_msg: HelpMsg = vec![
             HelpEntry::new_plain("hjkl", "Move focus"),
@@ -184,18 +201,31 @@ impl Focus {
 impl WidgetExt for CookieInput {
     fn render(&mut self, frame: &mut Frame, area: Rect) {
         let chunks = &Layout::default()
-            .constraints([Constraint::Fill(1), Constraint::Length(3)])
+            .constraints([
+                Constraint::Length(if self.notice.is_some() { 1 } else { 0 }),
+                Constraint::Fill(1),
+                Constraint::Length(3),
+            ])
             .split(area);
 
+        if let Some(notice) = &self.notice {
+            frame.render_widget(
+                Text::raw(notice.as_str())
+                    .centered()
+                    .style(Style::default().fg(Color::Yellow)),
+                chunks[0],
+            );
+        }
+
         let sub_chunks = &Layout::default()
             .margin(1)
             .constraints([Constraint::Length(5), Constraint::Length(5)])
-            .split(chunks[0]);
+            .split(chunks[1]);
 
         self.account_input.render(frame, sub_chunks[0]);
         self.cookie_input.render(frame, sub_chunks[1]);
 
-        self.get_help_msg().render(frame, chunks[1]);
+        self.get_help_msg().render(frame, chunks[2]);
     }
 }
 
@@ -229,15 +259,9 @@ impl EventLoopParticipant for CookieInput {
 
         if let crate::tui::Event::Key(key) = &event {
             match (key.modifiers, key.code) {
-                (_, KeyCode::Char('k')) => {
-                    self.change_focus(self.state.prev());
-                    status.consumed();
-                }
-                (_, KeyCode::Char('j')) => {
-                    self.change_focus(self.state.next());
-                    status.consumed();
-                }
-                (_, KeyCode::Esc) => self.tx.send(LayerManageAction::Swap(Layers::Fetch)),
+                (_, KeyCode::Char('k')) => self.change_focus(self.state.prev()),
+                (_, KeyCode::Char('j')) => self.change_focus(self.state.next()),
+                (_, KeyCode::Esc) => self.tx.send(LayerManageAction::Pop),
                 (_, KeyCode::Char('?')) => {
                     self.tx.send(LayerManageAction::Push(
                         Layers::Help(self.get_help_msg()).into_push_config(true),
@@ -245,6 +269,8 @@ impl EventLoopParticipant for CookieInput {
                 }
                 _ => (),
             }
+            // pushed over the fetch page, keep keys from starting a fetch below
+            status.consumed();
         };
         status
     }
@@ -254,6 +280,10 @@ impl Layer for CookieInput {
     fn init(&mut self) {
         self.account_input.set_mode(InputMode::Focused);
     }
+
+    fn help(&self) -> HelpMsg {
+        self.get_help_msg()
+    }
 }
 
 impl CookieInput {
@@ -284,7 +314,11 @@ mod test {
     use tokio::sync::mpsc::{self, UnboundedReceiver};
 
     use super::*;
-    use crate::{actions::Action, tui::Event};
+    use crate::{
+        actions::Action,
+        page::test_utils::{run_keys, type_str},
+        tui::Event,
+    };
 
     fn get_test_objs() -> (UnboundedReceiver<Action>, CookieInput) {
         let (tx, rx) = mpsc::unbounded_channel();
@@ -312,21 +346,42 @@ mod test {
         assert!(matches!(page.state, Focus::Account));
     }
 
+    #[test]
+    fn test_back() {
+        let (mut rx, mut page) = get_test_objs();
+        // pushed over the fetch page, which must not see these
+        page.handle_event_with_status_check(&' '.into());
+        page.handle_event_with_status_check(&KeyCode::Esc.into());
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::Layer(LayerManageAction::Pop)
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
     #[test]
     fn test_account_input() {
-        let (_, mut page) = get_test_objs();
+        let (mut rx, mut page) = get_test_objs();
 
         page.handle_event_with_status_check(&KeyCode::Enter.into());
         assert!(page.account_input.is_inputting());
-        page.handle_event_with_status_check(&'a'.into());
-        page.handle_event_with_status_check(&'j'.into());
-        page.handle_event_with_status_check(&KeyCode::Enter.into());
+        run_keys(
+            &mut page,
+            &mut rx,
+            &[type_str("aj"), vec![KeyCode::Enter.into()]].concat(),
+        );
         assert_eq!(page.manager.get_account_cookie_may_empty().unwrap().0, "aj");
 
-        page.handle_event_with_status_check(&KeyCode::Enter.into());
-        page.handle_event_with_status_check(&KeyCode::Left.into());
-        page.handle_event_with_status_check(&Event::Paste("kl".into()));
-        page.handle_event_with_status_check(&KeyCode::Enter.into());
+        run_keys(
+            &mut page,
+            &mut rx,
+            &[
+                KeyCode::Enter.into(),
+                KeyCode::Left.into(),
+                Event::Paste("kl".into()),
+                KeyCode::Enter.into(),
+            ],
+        );
         assert_eq!(
             page.manager.get_account_cookie_may_empty().unwrap().0,
             // cSpell:ignore aklj
@@ -336,14 +391,15 @@ mod test {
 
     #[test]
     fn test_cookie_input() {
-        let (_, mut page) = get_test_objs();
+        let (mut rx, mut page) = get_test_objs();
 
-        page.handle_event_with_status_check(&'j'.into());
-        page.handle_event_with_status_check(&KeyCode::Enter.into());
+        run_keys(&mut page, &mut rx, &['j'.into(), KeyCode::Enter.into()]);
         assert!(page.cookie_input.is_inputting());
-        page.handle_event_with_status_check(&'a'.into());
-        page.handle_event_with_status_check(&'j'.into());
-        page.handle_event_with_status_check(&KeyCode::Enter.into());
+        run_keys(
+            &mut page,
+            &mut rx,
+            &[type_str("aj"), vec![KeyCode::Enter.into()]].concat(),
+        );
         assert_eq!(
             page.manager.get_account_cookie_may_empty().unwrap().1,
             "hallticket=aj"
@@ -373,4 +429,21 @@ mod test {
 
         assert_snapshot!(terminal.backend())
     }
+
+    #[test]
+    fn test_cookie_input_render_notice() {
+        let (_, page) = get_test_objs();
+        let mut page = page.notice(Some(
+            "Your session expired, please re-enter your cookie".into(),
+        ));
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(80, 20)).unwrap();
+
+        terminal
+            .draw(|f| {
+                page.render(f, f.area());
+            })
+            .unwrap();
+
+        assert_snapshot!(terminal.backend())
+    }
 }
@@ -0,0 +1,154 @@
+use std::cmp::{max, min};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize, palette::tailwind},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    actions::{ActionSender, LayerManageAction},
+    app::layer_manager::EventHandlingStatus,
+    tui::Event,
+    utils::help_msg::{HelpEntry, HelpMsg},
+};
+
+use super::{EventLoopParticipant, Layer, WidgetExt};
+
+/// A dialog showing an error, popped on `Enter` or `Esc`
+pub(crate) struct ErrorPopup {
+    message: String,
+
+    tx: ActionSender,
+}
+
+impl ErrorPopup {
+    pub fn new(tx: ActionSender, message: String) -> Self {
+        Self { message, tx }
+    }
+
+    fn get_help_msg() -> HelpMsg {
+        vec![
+            HelpEntry::new(KeyCode::Enter, "Close"),
+            HelpEntry::new(KeyCode::Esc, "Close"),
+        ]
+        .into()
+    }
+}
+
+impl EventLoopParticipant for ErrorPopup {
+    fn handle_events(&mut self, event: &Event) -> EventHandlingStatus {
+        let mut status = EventHandlingStatus::default();
+        if let Event::Key(key) = event {
+            if let KeyCode::Enter | KeyCode::Esc = key.code {
+                self.tx.send(LayerManageAction::Pop);
+            }
+            // the dialog is modal, never let key events reach the layer below
+            status.consumed();
+        }
+        status
+    }
+}
+
+impl Layer for ErrorPopup {
+    fn help(&self) -> HelpMsg {
+        Self::get_help_msg()
+    }
+}
+
+impl WidgetExt for ErrorPopup {
+    fn render(&mut self, frame: &mut ratatui::Frame, area: Rect) {
+        let message_width = UnicodeWidthStr::width(self.message.as_str()) as u16;
+        let width = min(max(message_width + 6, 40), area.width.saturating_sub(4));
+        // borders and padding take 6 columns, long messages wrap
+        let message_lines = message_width.div_ceil(width.saturating_sub(6).max(1));
+        let height = min(message_lines + 6, area.height);
+        let show_area = Rect {
+            x: area.x + (area.width.saturating_sub(width)) / 2,
+            y: area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        };
+        let bottom_help_area = Rect {
+            x: area.x,
+            y: area.y + area.height.saturating_sub(3),
+            width: area.width,
+            height: min(3, area.height),
+        };
+
+        frame.render_widget(Clear, bottom_help_area);
+        ErrorPopup::get_help_msg().render(frame, bottom_help_area);
+
+        let block = Block::new()
+            .title(Line::raw("Error").centered())
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(tailwind::RED.c500))
+            .padding(Padding::uniform(1));
+
+        let key_style = Style::default().fg(tailwind::BLUE.c400);
+        let text = Text::from(vec![
+            Line::raw(self.message.as_str()),
+            Line::default(),
+            Line::from(vec![Span::styled("[Esc]", key_style), Span::raw(" Close")]),
+        ]);
+
+        frame.render_widget(Clear, show_area);
+        frame.render_widget(
+            Paragraph::new(text)
+                .centered()
+                .wrap(Wrap { trim: true })
+                .block(block)
+                .fg(tailwind::RED.c300),
+            show_area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    use super::*;
+    use crate::actions::Action;
+
+    fn get_test_objs() -> (UnboundedReceiver<Action>, ErrorPopup) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let popup = ErrorPopup::new(
+            tx.into(),
+            "Failed to load transactions: database is locked".to_string(),
+        );
+        (rx, popup)
+    }
+
+    #[test]
+    fn test_close() {
+        for key in [Event::from(KeyCode::Enter), KeyCode::Esc.into()] {
+            let (mut rx, mut popup) = get_test_objs();
+            popup.handle_event_with_status_check(&key);
+            assert!(matches!(
+                rx.try_recv().unwrap(),
+                Action::Layer(LayerManageAction::Pop)
+            ));
+            assert!(rx.try_recv().is_err());
+        }
+
+        // other keys are consumed without closing
+        let (mut rx, mut popup) = get_test_objs();
+        popup.handle_event_with_status_check(&'q'.into());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let (_, mut popup) = get_test_objs();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| popup.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
@@ -8,7 +8,11 @@
 //! - **时间范围选择**: 预设的时间段选项（1年、3个月、1个月）
 //! - **自定义日期**: 用户可输入特定的开始日期
 //! - **进度显示**: 实时显示数据获取进度和状态
-//! - **本地缓存**: 显示本地数据库中的交易记录数量
+//! - **本地缓存**: 显示本地数据库中的交易记录数量和上次获取的时间
+//! - **校园卡余额**: 启用 `fetch.fetch_balance` 时获取后一并查询余额，显示上次查询的结果
+//! - **预览获取**: 先获取到临时的内存数据库，在确认对话框中确认新增条数后再合并
+//! - **大范围确认**: 预计请求页数过多时先确认再开始获取
+//! - **断点续取**: Cookie 中途失效时暂停获取，更新 Cookie 后从出错的页继续
 //! - **异步获取**: 后台异步获取数据，不阻塞 UI 操作
 //!
 //! ## 页面布局
@@ -21,6 +25,8 @@
 //! ├─────────────────────────────────────────────────────────┤
 //! │                                                         │
 //! │ Local DB Count: 1234 transactions                      │
+//! │ Last synced: 2025-03-01 12:00:00                       │
+//! │ Card balance: ¥86.50 (as of 2025-03-01 12:00:00)       │
 //! │ Status: [Idle | Fetching | Complete]                   │
 //! │ Progress: Page 5/10, 500 transactions fetched          │
 //! │                                                         │
@@ -37,6 +43,9 @@
 //! - `P1Month`: 过去1个月按钮
 //! - `UserInput`: 自定义日期输入框
 //!
+//! 配置了 `fetch.default_fetch_days` 时，若天数与某个预设按钮一致则初始聚焦该按钮，
+//! 否则聚焦自定义输入框并预填对应的开始日期。
+//!
 //! ## 状态管理
 //!
 //! ### 获取状态 (FetchingState)
@@ -55,6 +64,7 @@
 //!     current_page: u32,             // 当前页数
 //!     total_entries_fetched: u32,    // 已获取条目数
 //!     oldest_date: Option<DateTime>, // 最早交易日期
+//!     elapsed: Duration,             // 已用时间，用于计算每秒记录数
 //! }
 //! ```
 //!
@@ -69,25 +79,79 @@
 //!     │                             ├─ 执行网络请求
 //!     │                             ├─ 更新进度状态
 //!     │ ←────────── 进度更新消息 ─── │
-//!     │ ←────────── 数据插入消息 ─── │
+//!     │                             ├─ 每 5 页写入数据库
+//!     │ ←────────── 获取完成消息 ─── │
 //!     │                             │
 //! ```
 //!
+//! ## 会话过期
+//!
+//! 服务器返回 HTML（通常是登录页）而非 JSON 时，视为 Cookie 已失效。获取会暂停在出错的页，
+//! 并打开 Cookie 输入页提示用户重新输入。已获取的记录不会丢失：普通获取已写入数据库，
+//! 预览获取暂存在内存中。
+//!
+//! Cookie 输入页下方是继续获取的确认对话框：更新 Cookie 后按 `Esc` 回到对话框，
+//! 按 `y` 从出错的页继续获取，`n` 放弃。
+//! 其他错误（如网络错误）结束获取，提示出错的页码和已保存的记录数。
+//!
+//! ## 大范围获取确认
+//!
+//! 开始获取前按回溯天数粗略估计需要请求的页数（天数 × 每天约 5 条 ÷ 每页条数）。
+//! 超过 `fetch.confirm_fetch_pages`（默认 50 页）时先弹出确认对话框，按 `y` 开始、`n` 取消，
+//! 避免误选很早的日期后长时间请求服务器。设为 0 则不确认。
+//!
+//! ## 增量写入
+//!
+//! 普通获取时，后台任务每获取 5 页就把这些记录写入数据库，出错时也会先写入已获取的部分，
+//! 因此长时间获取中途失败、程序崩溃或被终止时，已获取的页不会丢失。重新获取时，
+//! 已保存的记录会被冲突触发器静默跳过。预览获取仍在结束后一次性比较，确认后才写入。
+//!
+//! 获取到与已有记录 `id` 相同但数据不同的交易时，按配置项 `fetch.conflict_policy`
+//! 报错停止、跳过新记录或以新记录覆盖，参见 [`transactions::ConflictPolicy`]。
+//!
+//! ## 最近使用的日期
+//!
+//! 从自定义输入框开始获取时，开始日期会按账户记录到数据库，每个账户保留最近 10 个。
+//! 焦点在输入框上时按 `d` 打开列表，`jk` 选择、`Enter` 填入输入框，`Esc` 关闭。
+//!
+//! ## 只保存指定商家
+//!
+//! 配置了 `fetch.merchant` 时，只保存商家名称包含该文本的交易（不区分 ASCII 大小写），
+//! 预览获取也只比较这些记录。服务器不支持按商家筛选，因此仍会下载所有页，
+//! 获取耗时不会减少，只是其他商家的记录不会写入数据库。
+//!
+//! ## 本地记录数
+//!
+//! 页面显示的本地数据库记录数会在每次 `Tick` 时自动刷新（至多每秒一次），
+//! 因此其他进程（如 `web` 模式或另一个 TUI 实例）写入的数据也能及时反映。
+//! 按 `r` 可以立即刷新。
+//!
+//! ## 补全空缺
+//!
+//! 按 `g` 查找已保存的交易之间连续 3 天以上没有交易的日期，重新获取以补全这些空缺。
+//! 服务器只能从最新的交易往前翻页，因此仍会一直获取到最早的空缺，
+//! 但只保存落在第一个空缺开始到最后一个空缺结束之间的交易，已有的记录会被跳过。
+//! 假期等本来就没有消费的日期也会被当作空缺，重新获取后仍然为空。
+//!
 //! ## 键盘快捷键
 //!
 //! | 按键 | 功能 |
 //! |------|------|
 //! | `hjkl` | 移动焦点 |
 //! | `Space` | 开始获取数据 |
+//! | `p` | 预览获取，在确认对话框中显示新增与重复条数，按 `y` 合并、`n` 放弃 |
 //! | `e` | 编辑账户和Cookie |
-//! | `r` | 刷新本地数据库计数 |
-//! | `Esc` | 返回上一页 |
+//! | `r` | 立即刷新本地数据库计数 |
+//! | `g` | 补全已保存交易之间的日期空缺 |
+//! | `d` | 焦点在输入框时，选择最近使用的自定义日期 |
+//! | `Esc` | 返回上一页，获取进行中时无效，避免中途丢弃获取 |
 //! | `?` | 显示帮助 |
 //!
 //! ## 日期格式
 //!
 //! 自定义日期输入支持以下格式：
 //! - `YYYY-MM-DD` (如: 2024-01-15)
+//! - 只输入数字时自动插入 `-`，如 `20240115` 变为 `2024-01-15`
 //! - 自动转换为 UTC+8 时区
 //!
 //! ## 使用示例
@@ -103,25 +167,36 @@
 //! // 页面会处理所有用户交互和数据获取逻辑
 //! ```
 
-use chrono::{DateTime, FixedOffset, Local};
-use color_eyre::eyre::Context;
+use std::{
+    cell::Cell,
+    num::NonZeroU32,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use color_eyre::eyre::{Context, bail};
 use crossterm::event::KeyCode;
 use ratatui::{
     layout::{Constraint, Flex, Layout, Rect},
-    style::{Color, Style},
-    text::Text,
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
     widgets::{Block, BorderType, Borders, Paragraph},
 };
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
-use tracing::{info, instrument, warn};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{info, warn};
 
 use crate::{
-    actions::{ActionSender, LayerManageAction, Layers},
+    actions::{Action, ActionSender, LayerManageAction, Layers},
     app::layer_manager::EventHandlingStatus,
     component::input::InputComp,
-    libs::{fetcher::MealFetcher, transactions::OFFSET_UTC_PLUS8},
+    libs::{fetcher::MealFetcher, transactions::FilterOptions},
     tui::Event,
-    utils::help_msg::{HelpEntry, HelpMsg},
+    utils::{
+        amount::format_amount,
+        date::{format_date_input, parse_day_start, start_of},
+        help_msg::{HelpEntry, HelpMsg},
+    },
 };
 use crate::{
     component::input::InputMode,
@@ -137,25 +212,138 @@ pub enum FetchingState {
     Fetching(FetchProgress),
 }
 
+/// What a fetch task fetches, and where to pick up again after the cookie expired
+#[derive(Clone, Debug)]
+pub struct FetchJob {
+    pub date: DateTime<FixedOffset>,
+    pub preview: bool,
+    /// Only store or preview fetched transactions matching this
+    pub keep: Option<FilterOptions>,
+    /// First page to request, after the last page fetched before pausing
+    pub first_page: u32,
+    /// Transactions fetched in preview mode before pausing
+    pub collected: Vec<transactions::Transaction>,
+    /// Number of transactions written to the database before pausing
+    pub stored: usize,
+}
+
+impl FetchJob {
+    fn new(date: DateTime<FixedOffset>, preview: bool, keep: Option<FilterOptions>) -> Self {
+        Self {
+            date,
+            preview,
+            keep,
+            first_page: 1,
+            collected: Vec::new(),
+            stored: 0,
+        }
+    }
+}
+
+/// Result of a preview fetch, shown in the merge confirmation
+#[derive(Clone, Default, Debug)]
+pub struct FetchPreview {
+    /// Fetched transactions not yet in the local database
+    pub new: Vec<transactions::Transaction>,
+    /// Number of fetched transactions already in the local database
+    pub duplicates: usize,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct FetchProgress {
     pub current_page: u32,
     pub total_entries_fetched: u32,
     pub oldest_date: Option<DateTime<FixedOffset>>,
+    /// Time elapsed since the fetch started
+    pub elapsed: Duration,
+}
+
+impl FetchProgress {
+    /// Records fetched per second since the fetch started
+    ///
+    /// Returns `None` if no time has elapsed yet.
+    pub fn records_per_sec(&self) -> Option<f64> {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            Some(self.total_entries_fetched as f64 / secs)
+        } else {
+            None
+        }
+    }
+}
+
+/// Follow-ups of the confirmation dialogs opened by the fetch page, see [`Action::Fetch`]
+#[derive(Clone, Debug)]
+pub enum FetchCommand {
+    /// Merge previewed transactions into the local database
+    Merge(Vec<transactions::Transaction>),
+    /// Start a fetch estimated to take many pages
+    Start {
+        date: DateTime<FixedOffset>,
+        preview: bool,
+        /// Only store transactions in this half-open range, set when filling gaps
+        only_range: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+    },
+    /// Pick up a fetch paused by an expired cookie
+    Resume(FetchJob),
 }
 
 #[derive(Clone, Debug)]
 pub enum FetchingAction {
     UpdateFetchStatus(FetchingState),
     InsertTransaction(Vec<transactions::Transaction>),
+    /// Fetched in preview mode, diff against the local database instead of inserting
+    PreviewTransaction(Vec<transactions::Transaction>),
+    /// The server answered with a login page, the fetch pauses until the cookie is re-entered
+    SessionExpired(FetchJob),
+    /// The fetch task stopped on an error other than an expired cookie
+    Failed {
+        page: u32,
+        /// Transactions written to the database before the error
+        stored: usize,
+        error: String,
+    },
+    /// The fetch task finished after writing this many transactions to the database itself
+    Stored(usize),
 }
 
+/// Minimum interval between automatic refreshes of the local db count
+const COUNT_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Rough number of card transactions per day, used to estimate the pages of a fetch
+const EXPECTED_RECORDS_PER_DAY: u32 = 5;
+/// Write fetched transactions to the database after this many pages
+const FLUSH_EVERY_PAGES: NonZeroU32 = NonZeroU32::new(5).unwrap();
+/// Days without transactions in a row counted as a gap by the `g` key
+const MIN_GAP_DAYS: i64 = 3;
+
 #[derive(Debug)]
 pub struct Fetch {
     fetching_state: FetchingState,
     local_db_cnt: u64,
+    /// When fetched transactions were last stored, refreshed along with `local_db_cnt`
+    last_fetched_at: Option<DateTime<FixedOffset>>,
+    /// Last known card balance and when it was fetched, refreshed along with `local_db_cnt`
+    balance: Option<(f64, DateTime<FixedOffset>)>,
+    /// When `local_db_cnt` was last read from the database
+    last_count_refresh: Instant,
+    /// Ask before fetches estimated to take more pages than this, 0 to never ask
+    confirm_fetch_pages: u32,
+    /// Only store fetched transactions matching this, e.g. of a single merchant
+    store_filter: Option<FilterOptions>,
+    /// How to store fetched transactions that conflict with stored ones
+    conflict_policy: transactions::ConflictPolicy,
+    /// Also query the card balance after each successful fetch
+    fetch_balance: bool,
     fetch_start_date: Option<DateTime<FixedOffset>>,
     current_focus: Focus,
+    /// Recently used custom start dates, shown while picking one
+    date_picker: Option<DatePicker>,
+    /// One-off message shown while idle, cleared on the next key press
+    notice: Option<String>,
+    /// Handed to background fetches, which are blocking tasks that outlive the runtime otherwise
+    cancel: CancellationToken,
+    /// Cancels `cancel` once the page is dropped, e.g. on Ctrl+C
+    _cancel_on_drop: DropGuard,
 
     self_rx: UnboundedReceiver<FetchingAction>,
     self_tx: UnboundedSender<FetchingAction>,
@@ -170,18 +358,32 @@ pub struct Fetch {
 impl Fetch {
     pub fn new(tx: ActionSender, manager: transactions::TransactionManager) -> Self {
         let (self_tx, self_rx) = mpsc::unbounded_channel::<FetchingAction>();
+        let cancel = CancellationToken::new();
         Self {
             fetching_state: Default::default(),
             local_db_cnt: Default::default(),
+            last_fetched_at: None,
+            balance: None,
+            last_count_refresh: Instant::now(),
+            confirm_fetch_pages: 0,
+            store_filter: None,
+            conflict_policy: Default::default(),
+            fetch_balance: false,
             fetch_start_date: Default::default(),
             current_focus: Default::default(),
+            date_picker: None,
+            notice: None,
+            cancel: cancel.clone(),
+            _cancel_on_drop: cancel.drop_guard(),
 
             self_rx,
             self_tx,
 
             input: InputComp::new()
                 .title("Custom Start Date (2025-03-02 style input)")
-                .auto_submit(true),
+                .auto_submit(true)
+                .with_history(true)
+                .formatter(format_date_input),
             tx,
             manager,
 
@@ -195,6 +397,14 @@ impl Fetch {
         if self.input.is_inputting() {
             return self.input.get_help_msg();
         }
+        if self.date_picker.is_some() {
+            return vec![
+                HelpEntry::new_plain("jk", "Move"),
+                HelpEntry::new(KeyCode::Enter, "Use date"),
+                HelpEntry::new(KeyCode::Esc, "Close"),
+            ]
+            .into();
+        }
 
         let mut help: HelpMsg = vec![
             HelpEntry::new_plain("hjkl", "Move focus"),
@@ -203,14 +413,73 @@ impl Fetch {
             HelpEntry::new('r', "Refresh local db count"),
             HelpEntry::new(KeyCode::Esc, "Back"),
             HelpEntry::new(' ', "Start fetch"),
+            HelpEntry::new('p', "Preview fetch"),
+            HelpEntry::new('g', "Fill date gaps"),
         ]
         .into();
         if let Focus::UserInput = self.current_focus {
-            help.extend(&self.input.get_help_msg())
+            help.extend(&self.input.get_help_msg());
+            help.push(HelpEntry::new('d', "Recent dates"));
         }
         help
     }
 
+    /// Ask before starting a fetch estimated to take more than `pages` pages, 0 to never ask
+    pub fn confirm_fetch_pages(self, pages: u32) -> Self {
+        Self {
+            confirm_fetch_pages: pages,
+            ..self
+        }
+    }
+
+    /// Only store fetched transactions whose merchant contains `merchant`
+    pub fn merchant(self, merchant: Option<String>) -> Self {
+        Self {
+            store_filter: merchant.map(|m| FilterOptions::default().merchant_contains(m)),
+            ..self
+        }
+    }
+
+    /// How to store fetched transactions with the id of a stored one but different data
+    pub fn conflict_policy(self, policy: transactions::ConflictPolicy) -> Self {
+        Self {
+            conflict_policy: policy,
+            ..self
+        }
+    }
+
+    /// Query the card balance after each successful fetch
+    pub fn fetch_balance(self, fetch_balance: bool) -> Self {
+        Self {
+            fetch_balance,
+            ..self
+        }
+    }
+
+    /// Start on the preset matching `days`, or prefill the custom date input with it
+    pub fn default_fetch_days(self, days: Option<NonZeroU32>) -> Self {
+        let Some(days) = days.map(|d| i64::from(d.get())) else {
+            return self;
+        };
+        let preset = [Focus::P1Year, Focus::P3Months, Focus::P1Month]
+            .into_iter()
+            .find(|f| f.days() == Some(days));
+        match preset {
+            Some(focus) => Self {
+                current_focus: focus,
+                ..self
+            },
+            None => {
+                let date = Local::now().date_naive() - chrono::Duration::days(days);
+                Self {
+                    current_focus: Focus::UserInput,
+                    input: self.input.init_text(date.format("%Y-%m-%d").to_string()),
+                    ..self
+                }
+            }
+        }
+    }
+
     pub fn client<T: Into<MealFetcher>>(mut self, client: T) -> Self {
         self.client = client.into();
         self
@@ -222,6 +491,13 @@ impl Fetch {
     }
 }
 
+/// List of recently used custom start dates, newest first
+#[derive(Clone, Debug)]
+struct DatePicker {
+    dates: Vec<NaiveDate>,
+    selected: usize,
+}
+
 #[derive(Clone, Default, Debug)]
 pub enum Focus {
     #[default]
@@ -249,6 +525,16 @@ impl Focus {
             Focus::UserInput => Focus::P1Month,
         }
     }
+
+    /// Look-back window of a preset button, `None` for the custom input
+    fn days(&self) -> Option<i64> {
+        match self {
+            Focus::P1Year => Some(365),
+            Focus::P3Months => Some(90),
+            Focus::P1Month => Some(30),
+            Focus::UserInput => None,
+        }
+    }
 }
 
 impl WidgetExt for Fetch {
@@ -307,28 +593,84 @@ impl WidgetExt for Fetch {
 
         self.input.render(frame, area[1]);
 
+        if let Some(picker) = &self.date_picker {
+            let mut lines = vec![Line::from("Recently used start dates").bold()];
+            if picker.dates.is_empty() {
+                lines.push(Line::from("No custom start date used yet"));
+            }
+            lines.extend(picker.dates.iter().enumerate().map(|(i, date)| {
+                let text = date.format("%Y-%m-%d").to_string();
+                if i == picker.selected {
+                    Line::from(format!("> {} <", text)).fg(Color::Cyan)
+                } else {
+                    Line::from(text)
+                }
+            }));
+            frame.render_widget(
+                Text::from(lines)
+                    .style(Style::default().fg(Color::Gray))
+                    .centered(),
+                area[2],
+            );
+            self.get_help_msg().render(frame, area[3]);
+            return;
+        }
+
         // 修改这里：显示获取结果
         match &self.fetching_state {
             FetchingState::Idle => {
+                let mut text = format!(
+                    "Currently {} records locally stored.\n Press \"Space\" to fetch transactions since {}",
+                    self.local_db_cnt,
+                    self.fetch_start_date.map_or("N/A".to_string(), |date| date
+                        .format("%Y-%m-%d")
+                        .to_string()),
+                );
+                text.push_str(&format!(
+                    "\nLast synced: {}",
+                    self.last_fetched_at.map_or("never".to_string(), |time| time
+                        .format(transactions::LOCAL_TIME_FORMAT)
+                        .to_string())
+                ));
+                if let Some((balance, time)) = self.balance {
+                    text.push_str(&format!(
+                        "\nCard balance: {} (as of {})",
+                        format_amount(balance),
+                        time.format(transactions::LOCAL_TIME_FORMAT)
+                    ));
+                }
+                if let Some(notice) = &self.notice {
+                    text.push('\n');
+                    text.push_str(notice);
+                }
+                if let Some(merchant) = self
+                    .store_filter
+                    .as_ref()
+                    .and_then(|f| f.merchant_contains.as_ref())
+                {
+                    text.push_str(&format!(
+                        "\nOnly storing merchants containing \"{}\", all pages are still downloaded",
+                        merchant
+                    ));
+                }
                 frame.render_widget(
-                    Text::raw(format!(
-                        "Currently {} records locally stored.\n Press \"Space\" to fetch transactions since {}",
-                        self.local_db_cnt,
-                        self.fetch_start_date.map_or("N/A".to_string(), |date| date.format("%Y-%m-%d").to_string()),
-                    ))
-                    .style(Style::default().fg(Color::Gray))
-                    .centered(),
+                    Text::raw(text)
+                        .style(Style::default().fg(Color::Gray))
+                        .centered(),
                     area[2],
                 );
             }
             FetchingState::Fetching(progress) => {
                 let progress_text = format!(
-                    "Fetching...\nCurrent Page: {}\nTotal Entries Fetched: {}\nOldest Date: {}",
+                    "Fetching...\nCurrent Page: {}\nTotal Entries Fetched: {}\nOldest Date: {}\nRate: {}\nWait for the fetch to finish before going back",
                     progress.current_page,
                     progress.total_entries_fetched,
                     progress
                         .oldest_date
-                        .map_or("N/A".to_string(), |date| date.to_string())
+                        .map_or("N/A".to_string(), |date| date.to_string()),
+                    progress
+                        .records_per_sec()
+                        .map_or("N/A".to_string(), |rate| format!("{:.1} records/s", rate))
                 );
                 frame.render_widget(
                     Text::raw(progress_text)
@@ -346,10 +688,39 @@ impl WidgetExt for Fetch {
 impl EventLoopParticipant for Fetch {
     fn handle_events(&mut self, event: &Event) -> EventHandlingStatus {
         let mut status = EventHandlingStatus::default();
+        if let Event::Key(_) = event {
+            self.notice = None;
+        }
+
+        if let (Some(picker), Event::Key(key)) = (&mut self.date_picker, event) {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    picker.selected =
+                        (picker.selected + 1).min(picker.dates.len().saturating_sub(1));
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    picker.selected = picker.selected.saturating_sub(1);
+                }
+                KeyCode::Enter => {
+                    if let Some(date) = picker.dates.get(picker.selected) {
+                        let text = date.format("%Y-%m-%d").to_string();
+                        self.fetch_start_date = parse_day_start(&text).ok();
+                        self.input.set_text(text);
+                    }
+                    self.date_picker = None;
+                }
+                KeyCode::Char('d') | KeyCode::Esc => {
+                    self.date_picker = None;
+                }
+                _ => {}
+            }
+            status.consumed();
+            return status;
+        }
 
         let (input_status, input_result) = self.input.handle_events(event);
         if let Some(result) = input_result {
-            self.fetch_start_date = Fetch::parse_user_input(&result)
+            self.fetch_start_date = parse_day_start(&result).ok()
         }
         if matches!(input_status, EventHandlingStatus::Consumed) {
             return input_status;
@@ -360,11 +731,20 @@ impl EventLoopParticipant for Fetch {
                 while let Ok(action) = self.self_rx.try_recv() {
                     self.update(action);
                 }
+                if self.last_count_refresh.elapsed() >= COUNT_REFRESH_INTERVAL {
+                    self.refresh_db_cnt();
+                }
             }
             Event::Key(key) => match (key.modifiers, key.code) {
                 (_, KeyCode::Char(' ')) => {
                     if let Some(date) = self.fetch_start_date {
-                        self.start_fetch(date);
+                        self.request_fetch(date, false, None);
+                        status.consumed();
+                    }
+                }
+                (_, KeyCode::Char('p')) => {
+                    if let Some(date) = self.fetch_start_date {
+                        self.request_fetch(date, true, None);
                         status.consumed();
                     }
                 }
@@ -377,17 +757,31 @@ impl EventLoopParticipant for Fetch {
                     status.consumed();
                 }
                 (_, KeyCode::Char('r')) => {
-                    self.local_db_cnt = self.manager.fetch_count().unwrap();
+                    self.refresh_db_cnt();
                     status.consumed()
                 }
+                (_, KeyCode::Char('g')) if matches!(self.fetching_state, FetchingState::Idle) => {
+                    self.fill_gaps();
+                    status.consumed();
+                }
+                (_, KeyCode::Char('d'))
+                    if matches!(self.current_focus, Focus::UserInput)
+                        && matches!(self.fetching_state, FetchingState::Idle) =>
+                {
+                    self.open_date_picker();
+                    status.consumed();
+                }
                 (_, KeyCode::Char('e')) => {
-                    self.tx.send(LayerManageAction::Swap(Layers::CookieInput));
+                    self.edit_cookie(None);
                     status.consumed();
                 }
 
                 (_, KeyCode::Esc) => {
-                    self.tx
-                        .send(LayerManageAction::Swap(Layers::Transaction(None)));
+                    // leaving would drop the page and cancel the fetch halfway
+                    if matches!(self.fetching_state, FetchingState::Idle) {
+                        self.tx
+                            .send(LayerManageAction::Swap(Layers::Transaction(None)));
+                    }
                     status.consumed();
                 }
                 (_, KeyCode::Char('?')) => {
@@ -406,77 +800,291 @@ impl EventLoopParticipant for Fetch {
 
 impl Layer for Fetch {
     fn init(&mut self) {
-        self.local_db_cnt = self.manager.fetch_count().unwrap();
+        self.refresh_db_cnt();
 
         // make sure to load start_fetch_date
         self.move_focus(self.current_focus.clone());
     }
+
+    fn help(&self) -> HelpMsg {
+        self.get_help_msg()
+    }
+
+    fn handle_action(&mut self, action: &Action) {
+        if let Action::Fetch(command) = action {
+            match command.clone() {
+                FetchCommand::Merge(new) => self.update(FetchingAction::InsertTransaction(new)),
+                FetchCommand::Start {
+                    date,
+                    preview,
+                    only_range,
+                } => self.start_fetch(date, preview, only_range),
+                FetchCommand::Resume(job) => self.run_fetch(job),
+            }
+        }
+    }
 }
 
 impl Fetch {
-    #[instrument]
-    fn parse_user_input(input: &str) -> Option<DateTime<FixedOffset>> {
-        chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d")
-            .ok()
-            .map(|d| {
-                d.and_hms_opt(0, 0, 0)
-                    .unwrap()
-                    .and_local_timezone(OFFSET_UTC_PLUS8)
-                    .single()
-                    .expect("Failed to convert to local timezone")
-            })
+    /// Re-read the local db count, keeping the old value if the database is unavailable
+    fn refresh_db_cnt(&mut self) {
+        match self.manager.fetch_count() {
+            Ok(cnt) => self.local_db_cnt = cnt,
+            Err(e) => warn!("Failed to count local transactions: {:?}", e),
+        }
+        match self.manager.get_last_fetched_at() {
+            Ok(time) => self.last_fetched_at = time,
+            Err(e) => warn!("Failed to read the last fetch time: {:?}", e),
+        }
+        match self.manager.get_balance() {
+            Ok(balance) => self.balance = balance,
+            Err(e) => warn!("Failed to read the card balance: {:?}", e),
+        }
+        self.last_count_refresh = Instant::now();
+    }
+
+    /// Remember now as the last sync time, a failure only costs the "Last synced" hint
+    fn record_fetched_at(manager: &transactions::TransactionManager) {
+        if let Err(e) = manager.set_last_fetched_at(Local::now().fixed_offset()) {
+            warn!("{:?}", e);
+        }
+    }
+
+    /// Query and store the card balance, a failure only costs the balance hint
+    fn record_balance(client: &MealFetcher, manager: &transactions::TransactionManager) {
+        let result = client
+            .fetch_balance()
+            .and_then(|balance| manager.set_balance(balance, Local::now().fixed_offset()));
+        if let Err(e) = result {
+            warn!("Failed to update the card balance: {:?}", e);
+        }
     }
 
+    /// Run `job` in a background task
+    ///
+    /// Unless previewing, pages are written to `manager` every [`FLUSH_EVERY_PAGES`] pages
+    /// while fetching. Otherwise all records are collected and sent for preview.
+    /// With `balance_in` set, the card balance is queried and stored there after a successful fetch.
+    /// If the cookie expires, the job is sent back to pick up again from the failed page.
+    /// Once `cancel` is cancelled, the fetch stops before the next page, keeping the pages
+    /// already stored.
     fn fetch<T: Into<MealFetcher>>(
         tx: UnboundedSender<FetchingAction>,
         client: T,
-        date: DateTime<FixedOffset>,
+        job: FetchJob,
+        manager: transactions::TransactionManager,
+        policy: transactions::ConflictPolicy,
+        balance_in: Option<transactions::TransactionManager>,
+        cancel: CancellationToken,
     ) {
         let client = client.into();
-
-        let tx2 = tx.clone();
-        let update_progress = move |progress: FetchProgress| {
-            tx.send(FetchingAction::UpdateFetchStatus(FetchingState::Fetching(
-                progress,
-            )))
-            .context("Updating progress failed because layer was dropped while fetching")
-        };
+        let balance_client = client.clone();
 
         tokio::task::spawn_blocking(move || {
-            let records = match fetcher::fetch(date, client, update_progress)
-                .context("Error fetching in Fetch page")
-            {
-                Ok(data) => data,
+            let FetchJob {
+                date,
+                preview,
+                keep,
+                first_page,
+                mut collected,
+                mut stored,
+            } = job;
+
+            let last_page = Cell::new(0);
+            let update_progress = |progress: FetchProgress| {
+                if cancel.is_cancelled() {
+                    bail!("Fetch cancelled");
+                }
+                last_page.set(progress.current_page);
+                tx.send(FetchingAction::UpdateFetchStatus(FetchingState::Fetching(
+                    progress,
+                )))
+                .context("Updating progress failed because layer was dropped while fetching")
+            };
+
+            let result = fetcher::fetch_flushing(
+                date,
+                client,
+                first_page,
+                update_progress,
+                // a preview only keeps what it got so far, so nothing to flush early
+                if preview {
+                    NonZeroU32::MAX
+                } else {
+                    FLUSH_EVERY_PAGES
+                },
+                |mut batch| {
+                    if let Some(filter) = &keep {
+                        batch.retain(|t| filter.matches(t));
+                    }
+                    if preview {
+                        collected.extend(batch);
+                        return Ok(());
+                    }
+                    stored += manager
+                        .insert_with_policy(&batch, policy)
+                        .context("Error when inserting fetched transactions into database")?;
+                    Ok(())
+                },
+            );
+            let action = match result.context("Error fetching in Fetch page") {
+                Ok(_) if preview => {
+                    FetchingAction::PreviewTransaction(transactions::Transaction::dedup(collected))
+                }
+                Ok(_) => {
+                    Fetch::record_fetched_at(&manager);
+                    FetchingAction::Stored(stored)
+                }
                 Err(e) => {
-                    warn!("Error fetching data: {}", e);
+                    warn!("Error fetching data: {:?}", e);
+                    if e.chain().any(|e| e.is::<fetcher::SessionExpired>()) {
+                        let _ = tx.send(FetchingAction::SessionExpired(FetchJob {
+                            date,
+                            preview,
+                            keep,
+                            first_page: last_page.get() + 1,
+                            collected,
+                            stored,
+                        }));
+                    } else {
+                        let _ = tx.send(FetchingAction::UpdateFetchStatus(FetchingState::Idle));
+                        let _ = tx.send(FetchingAction::Failed {
+                            page: last_page.get() + 1,
+                            stored,
+                            error: e.root_cause().to_string(),
+                        });
+                    }
                     return;
                 }
             };
+            if let Some(manager) = &balance_in {
+                Fetch::record_balance(&balance_client, manager);
+            }
 
-            info!("Fetch stopped with {} records", records.len());
+            match &action {
+                FetchingAction::Stored(count) => info!("Fetch stopped with {} records", count),
+                FetchingAction::PreviewTransaction(records) => {
+                    info!("Fetch stopped with {} records", records.len())
+                }
+                _ => {}
+            }
 
             // This may fail if the layer is dropped while fetching
             // but we don't care about the error here
-            let _ = tx2.send(FetchingAction::UpdateFetchStatus(FetchingState::Idle));
-            let _ = tx2.send(FetchingAction::InsertTransaction(records));
+            let _ = tx.send(FetchingAction::UpdateFetchStatus(FetchingState::Idle));
+            let _ = tx.send(action);
         });
     }
 
     fn update(&mut self, action: FetchingAction) {
         match action {
             FetchingAction::InsertTransaction(transactions) => {
-                self.manager
-                    .insert(&transactions)
-                    .context("Error when inserting fetched transactions into database")
-                    .unwrap();
-                self.local_db_cnt = self.manager.fetch_count().unwrap();
+                match self
+                    .manager
+                    .insert_with_policy(&transactions, self.conflict_policy)
+                {
+                    Ok(_) => Fetch::record_fetched_at(&self.manager),
+                    Err(e) => {
+                        warn!(
+                            "Error when inserting fetched transactions into database: {:?}",
+                            e
+                        );
+                        self.notice = Some(format!("Failed to merge fetched transactions: {}", e));
+                    }
+                }
+                self.refresh_db_cnt();
+            }
+            FetchingAction::PreviewTransaction(transactions) => {
+                match Fetch::preview(&self.manager, transactions) {
+                    Ok(preview) => self.confirm_merge(preview),
+                    Err(e) => {
+                        warn!(
+                            "Error when comparing fetched transactions with database: {:?}",
+                            e
+                        );
+                        self.notice = Some(format!(
+                            "Failed to compare fetched transactions with the local database: {}",
+                            e
+                        ));
+                    }
+                }
             }
 
             FetchingAction::UpdateFetchStatus(state) => {
                 self.fetching_state = state.clone();
             }
+            FetchingAction::Stored(_) => {
+                self.refresh_db_cnt();
+            }
+            FetchingAction::Failed {
+                page,
+                stored,
+                error,
+            } => {
+                self.notice = Some(format!(
+                    "Fetch failed on page {}, {} records kept: {}",
+                    page, stored, error
+                ));
+                self.refresh_db_cnt();
+            }
+            FetchingAction::SessionExpired(job) => {
+                info!(
+                    "Cookie expired, fetch paused before page {}",
+                    job.first_page
+                );
+                self.fetching_state = FetchingState::Idle;
+                self.refresh_db_cnt();
+                // the cookie page goes on top, closing it leads back to the dialog
+                self.tx.send(LayerManageAction::Push(
+                    Layers::Confirm {
+                        prompt: format!(
+                            "The cookie expired while fetching page {}, {} records kept so far.\nResume with the updated cookie?",
+                            job.first_page,
+                            job.stored + job.collected.len()
+                        ),
+                        on_confirm: Box::new(Action::Fetch(FetchCommand::Resume(job))),
+                        on_cancel: Box::new(Action::Render),
+                    }
+                    .into_push_config(true),
+                ));
+                self.edit_cookie(Some(fetcher::SessionExpired.to_string()));
+            }
         }
     }
+    /// Load `transactions` into a throwaway in-memory database and diff it against `manager`
+    fn preview(
+        manager: &transactions::TransactionManager,
+        transactions: Vec<transactions::Transaction>,
+    ) -> color_eyre::Result<FetchPreview> {
+        let staging = transactions::TransactionManager::new(None)?;
+        staging.insert(&transactions)?;
+        let (new, duplicates) = manager.diff(&staging)?;
+        Ok(FetchPreview { new, duplicates })
+    }
+
+    /// Ask before merging the new transactions of a preview
+    fn confirm_merge(&mut self, preview: FetchPreview) {
+        if preview.new.is_empty() {
+            self.notice = Some(format!(
+                "Fetched {} duplicate records, nothing new to merge",
+                preview.duplicates
+            ));
+            return;
+        }
+        self.tx.send(LayerManageAction::Push(
+            Layers::Confirm {
+                prompt: format!(
+                    "Fetched {} new and {} duplicate records.\nMerge into the local database?",
+                    preview.new.len(),
+                    preview.duplicates
+                ),
+                on_confirm: Box::new(Action::Fetch(FetchCommand::Merge(preview.new))),
+                on_cancel: Box::new(Action::Render),
+            }
+            .into_push_config(true),
+        ));
+    }
+
     fn move_focus(&mut self, focus: Focus) {
         self.current_focus = focus.clone();
 
@@ -487,11 +1095,10 @@ impl Fetch {
                 .unwrap()
         };
 
-        self.fetch_start_date = match &self.current_focus {
-            Focus::P1Year => Some(get_date_from_now(365)),
-            Focus::P1Month => Some(get_date_from_now(30)),
-            Focus::P3Months => Some(get_date_from_now(90)),
-            Focus::UserInput => None,
+        self.fetch_start_date = match self.current_focus.days() {
+            Some(days) => Some(get_date_from_now(days)),
+            // keep whatever date is already typed in
+            None => parse_day_start(&self.input.get_text()).ok(),
         };
 
         if let Focus::UserInput = &self.current_focus {
@@ -501,22 +1108,178 @@ impl Fetch {
         }
     }
 
-    fn start_fetch(&mut self, date: DateTime<FixedOffset>) {
+    /// Rough number of pages needed to fetch everything since `date`
+    fn estimate_pages(&self, date: DateTime<FixedOffset>) -> u32 {
+        let days = (Local::now().fixed_offset() - date).num_days().max(1);
+        let records = u32::try_from(days)
+            .unwrap_or(u32::MAX)
+            .saturating_mul(EXPECTED_RECORDS_PER_DAY);
+        records.div_ceil(self.client.page_size().max(1))
+    }
+
+    /// Start the fetch, or ask first if it is estimated to take too many pages
+    fn request_fetch(
+        &mut self,
+        date: DateTime<FixedOffset>,
+        preview: bool,
+        only_range: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+    ) {
+        let estimated_pages = self.estimate_pages(date);
+        if self.confirm_fetch_pages > 0 && estimated_pages > self.confirm_fetch_pages {
+            self.tx.send(LayerManageAction::Push(
+                Layers::Confirm {
+                    prompt: format!(
+                        "Fetching since {} takes about {} pages of requests.\nStart anyway?",
+                        date.format("%Y-%m-%d"),
+                        estimated_pages
+                    ),
+                    on_confirm: Box::new(Action::Fetch(FetchCommand::Start {
+                        date,
+                        preview,
+                        only_range,
+                    })),
+                    on_cancel: Box::new(Action::Render),
+                }
+                .into_push_config(true),
+            ));
+        } else {
+            self.start_fetch(date, preview, only_range);
+        }
+    }
+
+    /// Re-fetch the days missing between stored transactions
+    ///
+    /// The server pages back from the newest transaction, so this still fetches back to the
+    /// earliest gap, but only stores what falls between the first and the last gap.
+    fn fill_gaps(&mut self) {
+        let gaps = match self.manager.find_date_gaps(MIN_GAP_DAYS) {
+            Ok(gaps) => gaps,
+            Err(e) => {
+                warn!("Failed to look for date gaps: {:?}", e);
+                self.notice = Some("Failed to look for date gaps".to_string());
+                return;
+            }
+        };
+        let (Some(first), Some(last)) = (gaps.first(), gaps.last()) else {
+            self.notice = Some(format!(
+                "No gaps of {}+ days between stored transactions",
+                MIN_GAP_DAYS
+            ));
+            return;
+        };
+        self.notice = Some(format!(
+            "Filling {} gap(s) between {} and {}",
+            gaps.len(),
+            first.first,
+            last.last
+        ));
+        let start = start_of(first.first);
+        // last.last is in the past, so there is always a next day
+        let end = start_of(last.last.succ_opt().unwrap());
+        self.request_fetch(start, false, Some((start, end)));
+    }
+
+    /// Account the recent custom dates are stored under, empty if none is set
+    fn account(&self) -> String {
+        self.manager
+            .get_account_cookie_may_empty()
+            .map(|(account, _)| account)
+            .unwrap_or_default()
+    }
+
+    fn open_date_picker(&mut self) {
+        let dates = self
+            .manager
+            .recent_fetch_dates(&self.account())
+            .unwrap_or_else(|e| {
+                warn!("Failed to load recent fetch dates: {:?}", e);
+                vec![]
+            });
+        self.date_picker = Some(DatePicker { dates, selected: 0 });
+    }
+
+    fn start_fetch(
+        &mut self,
+        date: DateTime<FixedOffset>,
+        preview: bool,
+        only_range: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+    ) {
+        if only_range.is_none()
+            && let Focus::UserInput = self.current_focus
+            && let Err(e) = self
+                .manager
+                .record_fetch_date(&self.account(), date.date_naive())
+        {
+            warn!("Failed to remember the fetch date: {:?}", e);
+        }
+        let keep = match only_range {
+            Some((start, end)) => Some(
+                self.store_filter
+                    .clone()
+                    .unwrap_or_default()
+                    .start(start)
+                    .end(end),
+            ),
+            None => self.store_filter.clone(),
+        };
+        self.run_fetch(FetchJob::new(date, preview, keep));
+    }
+
+    /// Start `job`, or pick a paused one up again with the current account and cookie
+    fn run_fetch(&mut self, job: FetchJob) {
         let tx = self.self_tx.clone();
+        let manager = self.manager.clone();
+        let policy = self.conflict_policy;
+        let balance_in = self.fetch_balance.then(|| self.manager.clone());
 
         match &self.client {
             MealFetcher::Real(c) => {
                 if let Ok((account, cookie)) = self.manager.get_account_cookie() {
-                    Fetch::fetch(tx, c.clone().account(account).cookie(cookie), date);
+                    Fetch::fetch(
+                        tx,
+                        c.clone().account(account).cookie(cookie),
+                        job,
+                        manager,
+                        policy,
+                        balance_in,
+                        self.cancel.clone(),
+                    );
                 } else {
-                    self.tx.send(LayerManageAction::Swap(Layers::CookieInput));
+                    self.edit_cookie(None);
                 }
             }
             MealFetcher::Mock(c) => {
-                Fetch::fetch(tx, c.clone(), date);
+                Fetch::fetch(
+                    tx,
+                    c.clone(),
+                    job,
+                    manager,
+                    policy,
+                    balance_in,
+                    self.cancel.clone(),
+                );
+            }
+            #[cfg(test)]
+            MealFetcher::Scripted(c) => {
+                Fetch::fetch(
+                    tx,
+                    c.clone(),
+                    job,
+                    manager,
+                    policy,
+                    balance_in,
+                    self.cancel.clone(),
+                );
             }
         }
     }
+
+    /// Open the account & cookie page on top, coming back here with `Esc`
+    fn edit_cookie(&self, notice: Option<String>) {
+        self.tx.send(LayerManageAction::Push(
+            Layers::CookieInput(notice).into_push_config(false),
+        ));
+    }
 }
 
 #[cfg(test)]
@@ -530,6 +1293,7 @@ mod test {
     use crate::{
         actions::Action,
         libs::transactions::{OFFSET_UTC_PLUS8, TransactionManager},
+        page::test_utils::{run_keys, type_str},
         tui::Event,
     };
 
@@ -541,6 +1305,20 @@ mod test {
         (rx, page)
     }
 
+    /// Prompt and `on_confirm` of the confirm dialog the page asked to open
+    fn pushed_confirm(rx: &mut UnboundedReceiver<Action>) -> (String, Action) {
+        let Ok(Action::Layer(LayerManageAction::Push(config))) = rx.try_recv() else {
+            panic!("Should open a dialog");
+        };
+        let Layers::Confirm {
+            prompt, on_confirm, ..
+        } = config.layer
+        else {
+            panic!("Should open a confirm dialog");
+        };
+        (prompt, *on_confirm)
+    }
+
     #[test]
     fn test_navigation() {
         let (_, mut page) = get_test_objs();
@@ -566,14 +1344,14 @@ mod test {
 
     #[test]
     fn test_user_input() {
-        let (_, mut page) = get_test_objs();
+        let (mut rx, mut page) = get_test_objs();
         page.handle_event_with_status_check(&'k'.into());
         assert!(matches!(page.current_focus, Focus::UserInput));
-        page.handle_event_with_status_check(&KeyCode::Enter.into());
-        let seq = "2025-03-02";
-        seq.chars().for_each(|c| {
-            page.handle_event_with_status_check(&c.into());
-        });
+        run_keys(
+            &mut page,
+            &mut rx,
+            &[vec![KeyCode::Enter.into()], type_str("2025-03-02")].concat(),
+        );
         assert_eq!(
             page.fetch_start_date.unwrap(),
             OFFSET_UTC_PLUS8
@@ -582,12 +1360,157 @@ mod test {
         );
         page.handle_event_with_status_check(&KeyCode::Enter.into());
     }
+
+    #[test]
+    fn test_recent_dates() {
+        let (mut rx, mut page) = get_test_objs();
+        page.manager.update_account("account").unwrap();
+        let date = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        page.manager.record_fetch_date("account", date(1)).unwrap();
+        page.manager.record_fetch_date("account", date(2)).unwrap();
+
+        // only available on the custom input
+        let _ = page.handle_events(&'d'.into());
+        assert!(page.date_picker.is_none());
+        run_keys(&mut page, &mut rx, &['k'.into(), 'd'.into(), 'j'.into()]);
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|f| page.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+
+        page.handle_event_with_status_check(&KeyCode::Enter.into());
+        assert!(page.date_picker.is_none());
+        assert_eq!(page.input.get_text(), "2025-03-01");
+        assert_eq!(
+            page.fetch_start_date.unwrap(),
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+                .unwrap()
+        );
+    }
+
     #[test]
     fn test_consume_navigation_events() {
-        let (_rx, mut page) = get_test_objs();
-        page.handle_event_with_status_check(&'?'.into());
-        page.handle_event_with_status_check(&'e'.into());
-        page.handle_event_with_status_check(&KeyCode::Esc.into());
+        let (mut rx, mut page) = get_test_objs();
+        let actions = run_keys(
+            &mut page,
+            &mut rx,
+            &['?'.into(), 'e'.into(), KeyCode::Esc.into()],
+        );
+        assert!(!actions.is_empty());
+    }
+
+    #[test]
+    fn test_esc_while_fetching() {
+        let (mut rx, mut page) = get_test_objs();
+        page.fetching_state = FetchingState::Fetching(Default::default());
+        let actions = run_keys(&mut page, &mut rx, &[KeyCode::Esc.into()]);
+        assert!(actions.is_empty(), "Should not leave during a fetch");
+        assert!(!page.cancel.is_cancelled());
+
+        page.fetching_state = FetchingState::Idle;
+        let actions = run_keys(&mut page, &mut rx, &[KeyCode::Esc.into()]);
+        assert!(matches!(
+            actions.as_slice(),
+            [Action::Layer(LayerManageAction::Swap(Layers::Transaction(
+                None
+            )))]
+        ));
+    }
+
+    #[test]
+    fn test_default_fetch_days() {
+        let get_page = |days| {
+            let (tx, _) = mpsc::unbounded_channel();
+            let mut page = Fetch::new(tx.into(), TransactionManager::new(None).unwrap())
+                .default_fetch_days(NonZeroU32::new(days));
+            page.init();
+            page
+        };
+
+        let page = get_page(90);
+        assert!(matches!(page.current_focus, Focus::P3Months));
+        assert!(page.input.get_text().is_empty());
+
+        let page = get_page(14);
+        assert!(matches!(page.current_focus, Focus::UserInput));
+        let expected = Local::now().date_naive() - chrono::Duration::days(14);
+        assert_eq!(
+            page.input.get_text(),
+            expected.format("%Y-%m-%d").to_string()
+        );
+        assert_eq!(page.fetch_start_date.unwrap().date_naive(), expected);
+
+        // the typed date survives moving focus away and back
+        let mut page = page;
+        page.handle_event_with_status_check(&'j'.into());
+        assert!(matches!(page.current_focus, Focus::P1Year));
+        page.handle_event_with_status_check(&'k'.into());
+        assert_eq!(page.fetch_start_date.unwrap().date_naive(), expected);
+    }
+
+    #[test]
+    fn test_preview() {
+        let (mut rx, mut page) = get_test_objs();
+        let existing =
+            transactions::Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap();
+        let new =
+            transactions::Transaction::from_local(-15.5, "Canteen", "2025-03-02 12:00:00").unwrap();
+        page.manager.insert(&vec![existing.clone()]).unwrap();
+
+        page.update(FetchingAction::PreviewTransaction(vec![
+            existing.clone(),
+            new.clone(),
+        ]));
+        let (prompt, on_confirm) = pushed_confirm(&mut rx);
+        assert_eq!(
+            prompt,
+            "Fetched 1 new and 1 duplicate records.\nMerge into the local database?"
+        );
+        let Action::Fetch(FetchCommand::Merge(merged)) = &on_confirm else {
+            panic!("Should merge on confirm");
+        };
+        assert_eq!(merged, &vec![new.clone()]);
+        // declining sends nothing back, so nothing is written until confirmed
+        assert_eq!(page.manager.fetch_count().unwrap(), 1);
+
+        page.handle_action(&on_confirm);
+        assert_eq!(page.manager.fetch_count().unwrap(), 2);
+        assert_eq!(page.local_db_cnt, 2);
+        assert!(page.last_fetched_at.is_some());
+
+        // nothing new, no need to ask
+        page.update(FetchingAction::PreviewTransaction(vec![existing, new]));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(
+            page.notice.as_deref(),
+            Some("Fetched 2 duplicate records, nothing new to merge")
+        );
+    }
+
+    #[test]
+    fn test_auto_refresh_count() {
+        let (_, mut page) = get_test_objs();
+        assert_eq!(page.local_db_cnt, 0);
+
+        // written by someone else, e.g. the web server
+        let t =
+            transactions::Transaction::from_local(-10.0, "Canteen", "2025-03-01 12:00:00").unwrap();
+        page.manager.insert(&vec![t]).unwrap();
+
+        // throttled right after the last refresh
+        let _ = page.handle_events(&Event::Tick);
+        assert_eq!(page.local_db_cnt, 0);
+
+        page.last_count_refresh -= COUNT_REFRESH_INTERVAL;
+        let _ = page.handle_events(&Event::Tick);
+        assert_eq!(page.local_db_cnt, 1);
+
+        // 'r' still refreshes immediately
+        let t =
+            transactions::Transaction::from_local(-5.0, "Canteen", "2025-03-02 12:00:00").unwrap();
+        page.manager.insert(&vec![t]).unwrap();
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(page.local_db_cnt, 2);
     }
 
     #[test]
@@ -604,6 +1527,118 @@ mod test {
         assert_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn test_render_balance() {
+        let (_, mut page) = get_test_objs();
+        let time = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 12, 0, 0)
+            .unwrap();
+        page.manager.set_balance(86.5, time).unwrap();
+        page.handle_event_with_status_check(&'r'.into());
+        assert_eq!(page.balance, Some((86.5, time)));
+
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(120, 20)).unwrap();
+        page.handle_event_with_status_check(&'h'.into());
+        terminal.draw(|f| page.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_fill_gaps() {
+        let (mut rx, page) = get_test_objs();
+        let mut page = page.confirm_fetch_pages(1);
+        page.handle_event_with_status_check(&'g'.into());
+        assert!(matches!(page.fetching_state, FetchingState::Idle));
+        assert_eq!(
+            page.notice.as_deref(),
+            Some("No gaps of 3+ days between stored transactions")
+        );
+
+        page.manager
+            .insert(&vec![
+                transactions::Transaction::from_local(-1.0, "A", "2025-01-01 12:00:00").unwrap(),
+                transactions::Transaction::from_local(-1.0, "A", "2025-01-03 12:00:00").unwrap(),
+                transactions::Transaction::from_local(-1.0, "A", "2025-01-10 12:00:00").unwrap(),
+                transactions::Transaction::from_local(-1.0, "A", "2025-02-01 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        page.handle_event_with_status_check(&'g'.into());
+        assert_eq!(
+            page.notice.as_deref(),
+            Some("Filling 2 gap(s) between 2025-01-04 and 2025-01-31")
+        );
+        let (_, on_confirm) = pushed_confirm(&mut rx);
+        let Action::Fetch(FetchCommand::Start {
+            date,
+            preview,
+            only_range,
+        }) = on_confirm
+        else {
+            panic!("Should start the fetch on confirm");
+        };
+        let day = |d: &str| parse_day_start(d).unwrap();
+        assert_eq!(date, day("2025-01-04"));
+        assert!(!preview);
+        assert_eq!(only_range, Some((day("2025-01-04"), day("2025-02-01"))));
+
+        // the notice goes away on the next key
+        page.handle_event_with_status_check(&'r'.into());
+        assert!(page.notice.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_confirm_large_fetch() {
+        let get_page = || {
+            let (rx, page) = get_test_objs();
+            let page = page
+                .client(MealFetcher::Mock(
+                    fetcher::MockMealFetcher::default().per_page(50),
+                ))
+                .confirm_fetch_pages(10);
+            (rx, page)
+        };
+        let now = Local::now().fixed_offset();
+
+        // 60 days * 5 / 50 = 6 pages, below the threshold
+        let (mut rx, mut page) = get_page();
+        assert_eq!(page.estimate_pages(now - chrono::Duration::days(60)), 6);
+        page.fetch_start_date = Some(now - chrono::Duration::days(60));
+        page.handle_event_with_status_check(&' '.into());
+        assert!(rx.try_recv().is_err(), "Should not ask");
+        page.self_rx
+            .recv()
+            .await
+            .expect("fetch should start right away");
+
+        // 730 days * 5 / 50 = 73 pages
+        let (mut rx, mut page) = get_page();
+        let date = now - chrono::Duration::days(730);
+        page.fetch_start_date = Some(date);
+        page.handle_event_with_status_check(&'p'.into());
+        let (prompt, on_confirm) = pushed_confirm(&mut rx);
+        assert_eq!(
+            prompt,
+            format!(
+                "Fetching since {} takes about 73 pages of requests.\nStart anyway?",
+                date.format("%Y-%m-%d")
+            )
+        );
+        assert!(matches!(
+            on_confirm,
+            Action::Fetch(FetchCommand::Start { preview: true, .. })
+        ));
+        assert!(
+            page.self_rx.try_recv().is_err(),
+            "Should not start fetching before confirming"
+        );
+
+        page.handle_action(&on_confirm);
+        let action = tokio::time::timeout(Duration::from_secs(10), page.self_rx.recv())
+            .await
+            .expect("fetch should start after confirming");
+        assert!(action.is_some());
+    }
+
     #[tokio::test]
     async fn test_fetch() {
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FetchingAction>();
@@ -613,7 +1648,16 @@ mod test {
             .with_ymd_and_hms(2025, 03, 1, 0, 0, 0)
             .unwrap();
 
-        Fetch::fetch(tx.into(), client, date);
+        let manager = TransactionManager::new(None).unwrap();
+        Fetch::fetch(
+            tx.into(),
+            client,
+            FetchJob::new(date, false, None),
+            manager.clone(),
+            Default::default(),
+            Some(manager.clone()),
+            CancellationToken::new(),
+        );
 
         let timeout = tokio::time::sleep(std::time::Duration::from_secs(10));
         tokio::pin!(timeout);
@@ -640,10 +1684,18 @@ mod test {
                                 }
                             }
                         }
-                        FetchingAction::InsertTransaction(transactions) => {
-                            assert!(!transactions.is_empty(), "Should receive some transactions");
+                        FetchingAction::Stored(count) => {
+                            assert!(count > 0, "Should store some transactions");
+                            assert_eq!(manager.fetch_count().unwrap(), count as u64);
+                            assert!(manager.get_last_fetched_at().unwrap().is_some());
+                            let (balance, _) = manager.get_balance().unwrap().expect("Should store the balance");
+                            assert_eq!(balance, fetcher::MockMealFetcher::BALANCE);
                             received_insert = true;
                         }
+                        FetchingAction::InsertTransaction(_) => panic!("Should be stored by the fetch task"),
+                        FetchingAction::PreviewTransaction(_) => panic!("Should not preview"),
+                        FetchingAction::SessionExpired(_) => panic!("Session should not expire"),
+                        FetchingAction::Failed { error, .. } => panic!("Fetch failed: {error}"),
                     }
 
                     // Exit loop when we've received all expected actions
@@ -671,6 +1723,267 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn test_fetch_merchant_only() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FetchingAction>();
+        let date = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+            .unwrap();
+        let manager = TransactionManager::new(None).unwrap();
+        let mut fetch = async || {
+            Fetch::fetch(
+                tx.clone(),
+                MealFetcher::Mock(fetcher::MockMealFetcher::default()),
+                FetchJob::new(
+                    date,
+                    false,
+                    Some(FilterOptions::default().merchant_contains("浴室")),
+                ),
+                manager.clone(),
+                Default::default(),
+                None,
+                CancellationToken::new(),
+            );
+            tokio::time::timeout(Duration::from_secs(10), async {
+                loop {
+                    if let Some(FetchingAction::Stored(count)) = rx.recv().await {
+                        return count;
+                    }
+                }
+            })
+            .await
+            .expect("Should finish fetching")
+        };
+
+        let stored = fetch().await;
+        assert!(stored > 0, "Should store some transactions");
+        let transactions = manager.fetch_all().unwrap();
+        assert_eq!(transactions.len(), stored);
+        assert!(transactions.iter().all(|t| t.merchant.contains("浴室")));
+
+        // fetching the same records again writes nothing
+        assert_eq!(fetch().await, 0);
+        assert_eq!(manager.fetch_count().unwrap() as usize, stored);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cancelled() {
+        let (_, page) = get_test_objs();
+        let cancel = page.cancel.clone();
+        drop(page);
+        assert!(
+            cancel.is_cancelled(),
+            "Dropping the page should cancel its fetches"
+        );
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<FetchingAction>();
+        let date = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+            .unwrap();
+        let manager = TransactionManager::new(None).unwrap();
+        Fetch::fetch(
+            tx,
+            MealFetcher::Mock(fetcher::MockMealFetcher::default()),
+            FetchJob::new(date, false, None),
+            manager.clone(),
+            Default::default(),
+            None,
+            cancel,
+        );
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while let Some(action) = rx.recv().await {
+                assert!(
+                    !matches!(action, FetchingAction::Stored(_)),
+                    "A cancelled fetch should not finish"
+                );
+            }
+        })
+        .await
+        .expect("Should stop fetching");
+        assert_eq!(manager.fetch_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_insert_conflict_policy() {
+        let stored = fetcher::test_utils::get_mock_data(1).remove(0);
+        // the card system corrected the amount of a record it already returned
+        let corrected = transactions::Transaction {
+            amount: stored.amount - 1.0,
+            ..stored.clone()
+        };
+
+        for (policy, expected) in [
+            // fails without panicking, leaving a notice
+            (transactions::ConflictPolicy::Abort, &stored),
+            (transactions::ConflictPolicy::Skip, &stored),
+            (transactions::ConflictPolicy::Overwrite, &corrected),
+        ] {
+            let (_, page) = get_test_objs();
+            let mut page = page.conflict_policy(policy);
+            page.manager.insert(&vec![stored.clone()]).unwrap();
+            page.update(FetchingAction::InsertTransaction(vec![corrected.clone()]));
+            assert_eq!(
+                page.manager.fetch_by_id(stored.id).unwrap().as_ref(),
+                Some(expected),
+                "{policy}"
+            );
+            assert_eq!(page.local_db_cnt, 1);
+            assert_eq!(
+                page.notice.is_some(),
+                policy == transactions::ConflictPolicy::Abort,
+                "{policy}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_session_expired() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/Report/GetPersonTrjn")
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_body("<!DOCTYPE html><html><body>Login</body></html>")
+            .create_async()
+            .await;
+
+        let (mut rx, page) = get_test_objs();
+        page.manager.update_account("account").unwrap();
+        page.manager.update_cookie("hallticket=expired").unwrap();
+        let mut page = page.client(fetcher::RealMealFetcher::default().origin(server.url()));
+
+        page.handle_event_with_status_check(&' '.into());
+
+        let timeout = tokio::time::sleep(std::time::Duration::from_secs(10));
+        tokio::pin!(timeout);
+        loop {
+            tokio::select! {
+                Some(action) = page.self_rx.recv() => {
+                    let expired = matches!(action, FetchingAction::SessionExpired(_));
+                    page.update(action);
+                    if expired {
+                        break;
+                    }
+                }
+                _ = &mut timeout => panic!("Should have received session expired action"),
+            }
+        }
+        mock.assert_async().await;
+
+        assert!(matches!(page.fetching_state, FetchingState::Idle));
+        let (_, on_confirm) = pushed_confirm(&mut rx);
+        let Action::Fetch(FetchCommand::Resume(job)) = on_confirm else {
+            panic!("Should resume the fetch on confirm");
+        };
+        assert_eq!(job.first_page, 1);
+        let Action::Layer(LayerManageAction::Push(config)) = rx.try_recv().unwrap() else {
+            panic!("Should open cookie input on top");
+        };
+        let Layers::CookieInput(Some(notice)) = config.layer else {
+            panic!("Should open cookie input with a notice");
+        };
+        assert_eq!(notice, "Your session expired, please re-enter your cookie");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_resume_after_session_expired() {
+        let data = fetcher::test_utils::get_mock_data(6);
+        let client = fetcher::ScriptedMealFetcher::new(vec![
+            Ok(fetcher::ScriptedMealFetcher::page_of(&data[..3])),
+            Ok("<html>Login</html>".to_string()),
+            Ok(fetcher::ScriptedMealFetcher::page_of(&data[3..])),
+        ]);
+        let (mut rx, page) = get_test_objs();
+        let mut page = page.client(client);
+        page.fetch_start_date = Some(
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2000, 1, 1, 0, 0, 0)
+                .unwrap(),
+        );
+
+        async fn recv_until(page: &mut Fetch, done: fn(&FetchingAction) -> bool) {
+            tokio::time::timeout(Duration::from_secs(10), async {
+                loop {
+                    let action = page.self_rx.recv().await.unwrap();
+                    let stop = done(&action);
+                    page.update(action);
+                    if stop {
+                        break;
+                    }
+                }
+            })
+            .await
+            .expect("Should receive the action");
+        }
+
+        page.handle_event_with_status_check(&' '.into());
+        recv_until(&mut page, |a| {
+            matches!(a, FetchingAction::SessionExpired(_))
+        })
+        .await;
+        let (prompt, on_confirm) = pushed_confirm(&mut rx);
+        assert_eq!(
+            prompt,
+            "The cookie expired while fetching page 2, 3 records kept so far.\nResume with the updated cookie?"
+        );
+        let Action::Fetch(FetchCommand::Resume(job)) = &on_confirm else {
+            panic!("Should resume the fetch on confirm");
+        };
+        assert_eq!(job.first_page, 2);
+        assert_eq!(job.stored, 3);
+        assert_eq!(page.local_db_cnt, 3);
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Action::Layer(LayerManageAction::Push(_))
+        ));
+
+        page.handle_action(&on_confirm);
+        recv_until(&mut page, |a| matches!(a, FetchingAction::Stored(_))).await;
+        assert!(matches!(page.fetching_state, FetchingState::Idle));
+        assert_eq!(page.local_db_cnt, 6);
+        assert_eq!(page.manager.fetch_all().unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_failed() {
+        let data = fetcher::test_utils::get_mock_data(6);
+        let client = fetcher::ScriptedMealFetcher::new(
+            data.chunks(3)
+                .map(|page| Ok(fetcher::ScriptedMealFetcher::page_of(page)))
+                .chain([Err(color_eyre::eyre::eyre!("connection reset"))])
+                .collect(),
+        );
+        let (_, page) = get_test_objs();
+        let mut page = page.client(client);
+        page.fetch_start_date = Some(
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2000, 1, 1, 0, 0, 0)
+                .unwrap(),
+        );
+
+        page.handle_event_with_status_check(&' '.into());
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let action = page.self_rx.recv().await.unwrap();
+                let failed = matches!(action, FetchingAction::Failed { .. });
+                page.update(action);
+                if failed {
+                    break;
+                }
+            }
+        })
+        .await
+        .expect("Should receive the failed action");
+
+        assert!(matches!(page.fetching_state, FetchingState::Idle));
+        assert_eq!(
+            page.notice.as_deref(),
+            Some("Fetch failed on page 3, 6 records kept: connection reset")
+        );
+        assert_eq!(page.local_db_cnt, 6);
+    }
+
     #[tokio::test]
     async fn test_fetch_progress() {
         let (_, page) = get_test_objs();
@@ -690,6 +2003,10 @@ mod test {
 
         // start fetching
         page.handle_event_with_status_check(&' '.into());
+        assert_eq!(
+            page.manager.recent_fetch_dates("account").unwrap(),
+            vec![NaiveDate::from_ymd_opt(2024, 9, 1).unwrap()]
+        );
 
         let timeout = tokio::time::sleep(std::time::Duration::from_secs(10));
         tokio::pin!(timeout);
@@ -702,8 +2019,8 @@ mod test {
                 Some(action) = page.self_rx.recv() => {
 
                     match &action {
-                        FetchingAction::InsertTransaction(transactions) => {
-                            assert!(!transactions.is_empty(), "Should receive some transactions");
+                        FetchingAction::Stored(count) => {
+                            assert!(*count > 0, "Should store some transactions");
                             received_insert = true;
                         }
                         _ => {}
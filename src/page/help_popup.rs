@@ -1,18 +1,20 @@
 use std::cmp::{max, min};
 
-use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
     layout::{Constraint, Rect},
     style::{Modifier, Style, palette::tailwind},
     text::{Line, Text},
-    widgets::{Block, BorderType, Borders, Cell, Clear, HighlightSpacing, Padding, Row, Table},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, HighlightSpacing, Padding, Row, Table, TableState,
+    },
 };
 use unicode_width::UnicodeWidthStr;
 
 use crate::{
     actions::{ActionSender, LayerManageAction},
     app::layer_manager::EventHandlingStatus,
+    component::scroll_list::ScrollList,
     tui::Event,
     utils::help_msg::{HelpEntry, HelpMsg},
 };
@@ -25,7 +27,7 @@ pub(crate) struct HelpPopup {
     longest_key: u16,
     longest_desc: u16,
 
-    table_state: ratatui::widgets::TableState,
+    list: ScrollList,
 
     tx: ActionSender,
 }
@@ -49,52 +51,25 @@ impl HelpPopup {
             .unwrap();
 
         Some(Self {
+            list: ScrollList::new(msg.len()),
             help_msg: msg,
             longest_key: longest_key as u16,
             longest_desc: longest_desc as u16,
-            table_state: ratatui::widgets::TableState::default().with_selected(0),
             tx,
         })
     }
 }
 
-#[derive(Clone, Debug)]
-pub enum HelpPopupAction {
-    Up,
-    Down,
-    Start,
-    End,
-}
-
 impl EventLoopParticipant for HelpPopup {
     fn handle_events(&mut self, event: &crate::tui::Event) -> EventHandlingStatus {
-        let mut status = EventHandlingStatus::default();
-        #[allow(clippy::single_match)]
-        match event {
-            Event::Key(key) => match key.code {
-                KeyCode::Esc => {
-                    self.tx.send(LayerManageAction::Pop);
-                    status.consumed();
-                }
-                KeyCode::Char('j') => {
-                    self.update(HelpPopupAction::Down);
-                    status.consumed();
-                }
-                KeyCode::Char('k') => {
-                    self.update(HelpPopupAction::Up);
-                    status.consumed();
-                }
-                KeyCode::Char('g') => {
-                    self.update(HelpPopupAction::Start);
-                    status.consumed();
-                }
-                KeyCode::Char('G') => {
-                    self.update(HelpPopupAction::End);
-                    status.consumed();
-                }
-                _ => {}
-            },
-            _ => {}
+        let mut status = self.list.handle_events(event);
+        if matches!(status, EventHandlingStatus::Consumed) {
+            return status;
+        }
+        // any other key closes the popup, as users tend to expect
+        if let Event::Key(_) = event {
+            self.tx.send(LayerManageAction::Pop);
+            status.consumed();
         }
         status
     }
@@ -130,13 +105,18 @@ impl WidgetExt for HelpPopup {
 }
 
 impl HelpPopup {
+    #[cfg(test)]
+    pub(crate) fn help_msg(&self) -> &HelpMsg {
+        &self.help_msg
+    }
+
     pub fn get_self_help_msg() -> HelpMsg {
         let help_msg = vec![
             HelpEntry::new('j', "Go Down"),
             HelpEntry::new('k', "Go Up"),
             HelpEntry::new('g', "Go to Top"),
             HelpEntry::new('G', "Go to Bottom"),
-            HelpEntry::new(KeyCode::Esc, "Close help"),
+            HelpEntry::new_plain("any key", "Close help"),
         ];
         help_msg.into()
     }
@@ -153,6 +133,7 @@ impl HelpPopup {
             .padding(Padding::horizontal(1))
             .padding(Padding::vertical(1));
 
+        let visible = self.list.visible(block.inner(area).height as usize);
         let items = self.help_msg.iter().map(|entry| {
             Row::new([
                 Cell::new(Text::raw(format!("  {}", entry.key())).right_aligned())
@@ -173,29 +154,16 @@ impl HelpPopup {
         .highlight_spacing(HighlightSpacing::Always)
         .column_spacing(2);
 
-        frame.render_stateful_widget(list, area, &mut self.table_state);
-    }
-
-    fn update(&mut self, action: HelpPopupAction) {
-        match action {
-            HelpPopupAction::Up => {
-                self.table_state.select_previous();
-            }
-            HelpPopupAction::Down => {
-                self.table_state.select_next();
-            }
-            HelpPopupAction::Start => {
-                self.table_state.select_first();
-            }
-            HelpPopupAction::End => {
-                self.table_state.select_last();
-            }
-        }
+        let mut table_state = TableState::default()
+            .with_offset(visible.start)
+            .with_selected(self.list.selected());
+        frame.render_stateful_widget(list, area, &mut table_state);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crossterm::event::KeyCode;
     use insta::assert_snapshot;
     use ratatui::Terminal;
 
@@ -209,7 +177,7 @@ mod tests {
         assert_eq!(help_popup.help_msg.len(), 1);
         assert_eq!(help_popup.longest_desc, 4);
         assert_eq!(help_popup.longest_key, 1);
-        assert_eq!(help_popup.table_state.selected(), Some(0));
+        assert_eq!(help_popup.list.selected(), Some(0));
     }
 
     #[test]
@@ -235,7 +203,7 @@ mod tests {
                     help_popup.render(f, f.area());
                 })
                 .unwrap();
-            assert_eq!(help_popup.table_state.selected(), expected);
+            assert_eq!(help_popup.list.selected(), expected);
         };
 
         test_loop('j', Some(1));
@@ -246,6 +214,34 @@ mod tests {
         test_loop('g', Some(0));
     }
 
+    #[test]
+    fn test_close_on_any_key() {
+        for key in [
+            Event::from(KeyCode::Esc),
+            KeyCode::Enter.into(),
+            'q'.into(),
+            'x'.into(),
+        ] {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let mut help_popup =
+                HelpPopup::new(tx.into(), vec![HelpEntry::new('a', "test")].into()).unwrap();
+            help_popup.handle_event_with_status_check(&key);
+            assert!(matches!(
+                rx.try_recv().unwrap(),
+                crate::actions::Action::Layer(LayerManageAction::Pop)
+            ));
+        }
+
+        // navigation keys keep the popup open
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut help_popup =
+            HelpPopup::new(tx.into(), vec![HelpEntry::new('a', "test")].into()).unwrap();
+        for key in ['j', 'k', 'g', 'G'] {
+            help_popup.handle_event_with_status_check(&key.into());
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
     #[test]
     fn test_help_popup_render() {
         let (tx, _) = tokio::sync::mpsc::unbounded_channel();
@@ -7,21 +7,28 @@
 //! - **响应式布局**: 根据终端宽度自动调整 ASCII 艺术字显示
 //! - **键盘导航**: 支持单键快捷键进行页面导航
 //! - **帮助系统**: 集成帮助提示和快捷键说明
+//! - **数据目录**: 用系统默认的文件管理器打开数据目录，打开失败（如无图形界面）时显示其路径
+//! - **近期概况**: 在艺术字下方显示交易总数、本月支出、上次同步时间以及查询过的校园卡余额，数据库为空时不显示
 //!
 //! ## ASCII 艺术字显示规则
 //!
 //! ```text
 //! 终端宽度 >= 100 字符  →  显示完整 XJTU MealFlow 艺术字
-//! 终端宽度 >= 60 字符   →  显示简化 MealFlow 艺术字  
-//! 终端宽度 < 60 字符    →  显示纯文本 "XJTU MealFlow"
+//! 终端宽度 >= 60 字符   →  显示简化 MealFlow 艺术字
+//! 终端宽度 >= 40 字符   →  显示带边框的 XJTU MealFlow 横幅
+//! 终端宽度 < 40 字符    →  显示纯文本 "XJTU MealFlow"
 //! ```
 //!
+//! 各级宽度阈值和对应的艺术字文件定义在 [`ASCII_ARTS`] 中，按顺序选取第一个放得下的。
+//!
 //! ## 键盘快捷键
 //!
 //! | 按键 | 功能 | 目标页面 |
 //! |------|------|----------|
 //! | `T` | 交易记录 | Transactions |
 //! | `a` | 数据分析 | Analysis |
+//! | `s` | 设置 | Settings |
+//! | `o` | 在文件管理器中打开数据目录 | - |
 //! | `?` | 帮助信息 | Help Popup |
 //! | `q` | 退出应用 | - |
 //!
@@ -34,6 +41,10 @@
 //! │     (垂直居中显示)                  │
 //! │                                     │
 //! ├─────────────────────────────────────┤
+//! │ 近期概况 (宽度不足时每项一行)       │
+//! ├─────────────────────────────────────┤
+//! │ 提示信息 (仅在有提示时显示 1 行)   │
+//! ├─────────────────────────────────────┤
 //! │ 快捷键帮助信息 (固定高度 3 行)     │
 //! └─────────────────────────────────────┘
 //! ```
@@ -45,9 +56,9 @@
 //! use crate::actions::ActionSender;
 //!
 //! // 创建主页面实例
-//! let home = Home {
-//!     tx: action_sender,
-//! };
+//! let home = Home::new(action_sender)
+//!     .data_dir(config.config.data_dir.clone())
+//!     .manager(manager.clone());
 //!
 //! // 页面会自动处理渲染和事件
 //! ```
@@ -59,24 +70,100 @@
 //! - 键盘事件处理测试
 //! - 页面导航功能测试
 
-use std::vec;
+use std::{
+    path::{Path, PathBuf},
+    vec,
+};
 
 use crate::{
     actions::{Action, ActionSender, LayerManageAction, Layers},
     app::layer_manager::EventHandlingStatus,
+    config::get_data_dir,
+    libs::transactions::{FilterOptions, LOCAL_TIME_FORMAT, OFFSET_UTC_PLUS8, TransactionManager},
     tui::Event,
-    utils::help_msg::{HelpEntry, HelpMsg},
+    utils::{
+        amount::format_amount,
+        help_msg::{HelpEntry, HelpMsg},
+    },
 };
 
 use super::{EventLoopParticipant, Layer, WidgetExt};
+use chrono::{DateTime, Datelike, FixedOffset, Local, TimeZone};
+use color_eyre::Result;
 use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Flex, Layout},
     style::{Color, Style},
+    text::Line,
     widgets::Paragraph,
 };
 
+/// 主页面艺术字，按所需的最小终端宽度从大到小排列
+///
+/// 每项为 (最小宽度, 艺术字)，最后一项的最小宽度应为 0 作为兜底
+const ASCII_ARTS: [(u16, &str); 4] = [
+    (100, include_str!("../../data/xjtu-mealflow.txt")),
+    (60, include_str!("../../data/mealflow.txt")),
+    (40, include_str!("../../data/xjtu-mealflow-banner.txt")),
+    (0, "XJTU MealFlow"),
+];
+
+/// Recent activity shown below the art
+#[derive(Clone, Debug, PartialEq)]
+struct Summary {
+    count: u64,
+    /// Total spending of the current month, as a positive amount
+    month_spent: f64,
+    last_fetched_at: Option<DateTime<FixedOffset>>,
+    /// Last fetched card balance, `None` if never fetched
+    balance: Option<f64>,
+}
+
+impl Summary {
+    /// Read the summary as of `now`, `None` while the database is empty
+    fn load(manager: &TransactionManager, now: DateTime<FixedOffset>) -> Result<Option<Self>> {
+        let count = manager.fetch_count()?;
+        if count == 0 {
+            return Ok(None);
+        }
+        let now = now.with_timezone(&OFFSET_UTC_PLUS8);
+        let month_start = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+            .unwrap();
+        let month_spent = manager.fetch_spent(&FilterOptions::default().start(month_start))?;
+        Ok(Some(Self {
+            count,
+            month_spent,
+            last_fetched_at: manager.get_last_fetched_at()?,
+            balance: manager.get_balance()?.map(|(balance, _)| balance),
+        }))
+    }
+
+    /// One line if it fits in `width`, otherwise one line per item
+    fn lines(&self, width: u16) -> Vec<String> {
+        let mut items = vec![
+            format!("{} transactions", self.count),
+            format!("{} spent this month", format_amount(self.month_spent)),
+            format!(
+                "Last synced: {}",
+                self.last_fetched_at.map_or("never".to_string(), |time| time
+                    .format(LOCAL_TIME_FORMAT)
+                    .to_string())
+            ),
+        ];
+        if let Some(balance) = self.balance {
+            items.push(format!("{} left on card", format_amount(balance)));
+        }
+        let joined = items.join(" · ");
+        if joined.chars().count() <= width as usize {
+            vec![joined]
+        } else {
+            items
+        }
+    }
+}
+
 /// 主页面结构体
 ///
 /// 负责渲染应用程序的主页面，包括 ASCII 艺术字显示和导航功能。
@@ -85,15 +172,74 @@ use ratatui::{
 /// ## 字段说明
 ///
 /// - `tx`: Action 发送器，用于处理用户交互和页面导航
+/// - `data_dir`: 按 `o` 时打开的数据目录
+/// - `manager`: 读取近期概况的数据库，未设置时不显示概况
 #[derive(Clone, Debug)]
 pub struct Home {
     /// Action 消息发送器
     ///
     /// 用于发送用户操作产生的 Action 到应用程序的消息处理系统
     pub tx: ActionSender,
+
+    data_dir: PathBuf,
+    /// Shown above the help, e.g. the outcome of opening the data directory
+    notice: Option<String>,
+    /// Opens a path with the platform opener, replaced in tests
+    opener: fn(&Path) -> std::io::Result<()>,
+    manager: Option<TransactionManager>,
+    /// Current time deciding the month of the summary, replaced in tests
+    clock: fn() -> DateTime<FixedOffset>,
+    summary: Option<Summary>,
 }
 
 impl Home {
+    pub fn new(tx: ActionSender) -> Self {
+        Self {
+            tx,
+            data_dir: get_data_dir(),
+            notice: None,
+            opener: |path| open::that_detached(path),
+            manager: None,
+            clock: || Local::now().fixed_offset(),
+            summary: None,
+        }
+    }
+
+    /// Show a summary of the transactions in `manager` below the art
+    pub fn manager(self, manager: TransactionManager) -> Self {
+        Self {
+            manager: Some(manager),
+            ..self
+        }
+    }
+
+    /// Re-read the summary, keeping the old one if the database is unavailable
+    fn refresh_summary(&mut self) {
+        if let Some(manager) = &self.manager {
+            match Summary::load(manager, (self.clock)()) {
+                Ok(summary) => self.summary = summary,
+                Err(e) => tracing::warn!("Failed to load the home summary: {:?}", e),
+            }
+        }
+    }
+
+    /// Set the data directory opened with `o`, defaults to [`get_data_dir`]
+    pub fn data_dir(self, data_dir: PathBuf) -> Self {
+        Self { data_dir, ..self }
+    }
+
+    /// Open the data directory in the file manager, falling back to showing its path
+    fn open_data_dir(&mut self) {
+        let path = self.data_dir.display();
+        self.notice = Some(match (self.opener)(&self.data_dir) {
+            Ok(()) => format!("Opened {}", path),
+            Err(e) => {
+                tracing::warn!("Failed to open {}: {}", path, e);
+                format!("Could not open the file manager, data directory: {}", path)
+            }
+        });
+    }
+
     /// 获取主页面的帮助信息
     ///
     /// 返回当前页面可用的快捷键列表和功能说明。
@@ -105,6 +251,8 @@ impl Home {
     fn get_help_msg(&self) -> HelpMsg {
         let help_msg: HelpMsg = vec![
             HelpEntry::new('T', "Go to transactions page"),
+            HelpEntry::new('s', "Settings"),
+            HelpEntry::new('o', "Open data directory"),
             HelpEntry::new('q', "Quit"),
             HelpEntry::new('?', "Show help"),
         ]
@@ -117,24 +265,30 @@ impl Home {
 impl Default for Home {
     fn default() -> Self {
         let (tx, _) = tokio::sync::mpsc::unbounded_channel();
-        Self {
-            tx: ActionSender(tx),
-        }
+        Self::new(ActionSender(tx))
     }
 }
 
 impl WidgetExt for Home {
     fn render(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
-        let ascii_art = if area.width >= 100 {
-            include_str!("../../data/xjtu-mealflow.txt")
-        } else if area.width >= 60 {
-            include_str!("../../data/mealflow.txt")
-        } else {
-            "XJTU MealFlow"
-        };
+        let &(_, ascii_art) = ASCII_ARTS
+            .iter()
+            .find(|(min_width, _)| area.width >= *min_width)
+            .unwrap_or(&ASCII_ARTS[ASCII_ARTS.len() - 1]);
+
+        let summary = self
+            .summary
+            .as_ref()
+            .map(|summary| summary.lines(area.width))
+            .unwrap_or_default();
 
         let area = &Layout::default()
-            .constraints([Constraint::Fill(1), Constraint::Length(3)])
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(summary.len() as u16),
+                Constraint::Length(self.notice.is_some() as u16),
+                Constraint::Length(3),
+            ])
             .split(area);
 
         let height = ascii_art.lines().count() as u16;
@@ -149,7 +303,23 @@ impl WidgetExt for Home {
             *v_align_area,
         );
 
-        self.get_help_msg().render(frame, area[1]);
+        frame.render_widget(
+            Paragraph::new(summary.into_iter().map(Line::from).collect::<Vec<_>>())
+                .style(Style::default().fg(Color::Gray))
+                .alignment(Alignment::Center),
+            area[1],
+        );
+
+        if let Some(notice) = &self.notice {
+            frame.render_widget(
+                Paragraph::new(notice.as_str())
+                    .style(Style::default().fg(Color::Yellow))
+                    .alignment(Alignment::Center),
+                area[2],
+            );
+        }
+
+        self.get_help_msg().render(frame, area[3]);
     }
 }
 
@@ -157,6 +327,7 @@ impl EventLoopParticipant for Home {
     fn handle_events(&mut self, _event: &crate::tui::Event) -> EventHandlingStatus {
         let mut status = EventHandlingStatus::default();
         if let Event::Key(key) = _event {
+            self.notice = None;
             match key.code {
                 KeyCode::Char('?') => {
                     self.tx.send(LayerManageAction::Push(
@@ -167,7 +338,7 @@ impl EventLoopParticipant for Home {
                 KeyCode::Char('a') => {
                     // TODO add help msg for this
                     self.tx.send(LayerManageAction::Push(
-                        Layers::Analysis.into_push_config(false),
+                        Layers::Analysis(None).into_push_config(false),
                     ));
                     status.consumed();
                 }
@@ -177,6 +348,16 @@ impl EventLoopParticipant for Home {
                     ));
                     status.consumed();
                 }
+                KeyCode::Char('s') => {
+                    self.tx.send(LayerManageAction::Push(
+                        Layers::Settings.into_push_config(false),
+                    ));
+                    status.consumed();
+                }
+                KeyCode::Char('o') => {
+                    self.open_data_dir();
+                    status.consumed();
+                }
                 KeyCode::Char('q') => {
                     self.tx.send(Action::Quit);
                     status.consumed();
@@ -188,7 +369,19 @@ impl EventLoopParticipant for Home {
     }
 }
 
-impl Layer for Home {}
+impl Layer for Home {
+    fn init(&mut self) {
+        self.refresh_summary();
+    }
+
+    fn resume(&mut self) {
+        self.refresh_summary();
+    }
+
+    fn help(&self) -> HelpMsg {
+        self.get_help_msg()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -196,14 +389,12 @@ mod tests {
     use ratatui::{Terminal, backend::TestBackend};
     use tokio::sync::mpsc;
 
-    use crate::actions::Action;
+    use crate::{actions::Action, libs::transactions::Transaction, page::test_utils::run_keys};
 
     use super::*;
 
     fn get_test_page() -> Home {
-        let mut home = Home {
-            tx: ActionSender(tokio::sync::mpsc::unbounded_channel().0),
-        };
+        let mut home = Home::new(ActionSender(tokio::sync::mpsc::unbounded_channel().0));
         home.init();
         home
     }
@@ -239,45 +430,146 @@ mod tests {
     }
 
     #[test]
-    fn test_events() {
-        let (tx, mut _rx) = mpsc::unbounded_channel::<Action>();
-        let mut home = Home { tx: tx.into() };
-        home.handle_event_with_status_check(&'?'.into());
-        let mut should_receive_layer_opt = false;
-        while let Ok(action) = _rx.try_recv() {
-            if let Action::Layer(LayerManageAction::Push(act)) = action {
-                assert!(matches!(act.layer, Layers::Help(_)));
-                should_receive_layer_opt = true;
+    fn test_render_medium() {
+        let mut page = get_test_page();
+        let mut terminal = Terminal::new(TestBackend::new(50, 25)).unwrap();
+        terminal
+            .draw(|frame| page.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_render_tiny() {
+        let mut page = get_test_page();
+        let mut terminal = Terminal::new(TestBackend::new(30, 25)).unwrap();
+        terminal
+            .draw(|frame| page.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn test_summary() {
+        let manager = TransactionManager::new(None).unwrap();
+        let now = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 15, 12, 0, 0)
+            .unwrap();
+        assert_eq!(Summary::load(&manager, now).unwrap(), None);
+
+        manager
+            .insert(&vec![
+                Transaction::from_local(-12.5, "Canteen", "2025-02-28 23:30:00").unwrap(),
+                Transaction::from_local(-8.0, "Canteen", "2025-03-01 00:10:00").unwrap(),
+                Transaction::from_local(-20.0, "Bathhouse", "2025-03-10 20:00:00").unwrap(),
+                Transaction::from_local(100.0, "Top up", "2025-03-11 09:00:00").unwrap(),
+            ])
+            .unwrap();
+        manager.set_last_fetched_at(now).unwrap();
+        let summary = Summary::load(&manager, now).unwrap().unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                count: 4,
+                month_spent: 28.0,
+                last_fetched_at: Some(now),
+                balance: None,
             }
-        }
-        assert!(should_receive_layer_opt);
+        );
+
+        manager.set_balance(86.5, now).unwrap();
+        let summary = Summary::load(&manager, now).unwrap().unwrap();
+        assert_eq!(summary.balance, Some(86.5));
+
+        let mut page = get_test_page().manager(manager);
+        page.clock = || {
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 3, 15, 12, 0, 0)
+                .unwrap()
+        };
+        page.init();
+        assert_eq!(page.summary, Some(summary));
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+        terminal
+            .draw(|frame| page.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        // one item per line when the line does not fit
+        let mut terminal = Terminal::new(TestBackend::new(40, 25)).unwrap();
+        terminal
+            .draw(|frame| page.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!("summary_narrow", terminal.backend());
+
+        // refreshed when the page is back on top, e.g. after a fetch
+        page.manager
+            .as_ref()
+            .unwrap()
+            .insert(&vec![
+                Transaction::from_local(-2.0, "Canteen", "2025-03-14 12:00:00").unwrap(),
+            ])
+            .unwrap();
+        let _ = page.handle_events(&Event::Tick);
+        assert_eq!(page.summary.as_ref().unwrap().count, 4);
+        page.resume();
+        assert_eq!(page.summary.as_ref().unwrap().count, 5);
+        assert_eq!(page.summary.as_ref().unwrap().month_spent, 30.0);
+    }
+
+    /// Press `key` on the home page and return the layers it pushed
+    fn pushed_layers(key: char) -> Vec<Layers> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut home = Home::new(tx.into());
+        run_keys(&mut home, &mut rx, &[key.into()])
+            .into_iter()
+            .filter_map(|action| match action {
+                Action::Layer(LayerManageAction::Push(act)) => Some(act.layer),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_events() {
+        let layers = pushed_layers('?');
+        assert!(matches!(layers.as_slice(), [Layers::Help(_)]));
     }
     #[test]
     fn test_event_nav_to_analysis() {
-        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
-        let mut home = Home { tx: tx.into() };
-        home.handle_event_with_status_check(&'a'.into());
-        let mut should_receive_layer_opt = false;
-        while let Ok(action) = rx.try_recv() {
-            if let Action::Layer(LayerManageAction::Push(act)) = action {
-                assert!(matches!(act.layer, Layers::Analysis));
-                should_receive_layer_opt = true;
-            }
-        }
-        assert!(should_receive_layer_opt);
+        let layers = pushed_layers('a');
+        assert!(matches!(layers.as_slice(), [Layers::Analysis(None)]));
     }
     #[test]
     fn test_event_nav_to_transactions() {
-        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
-        let mut home = Home { tx: tx.into() };
-        home.handle_event_with_status_check(&'T'.into());
-        let mut should_receive_layer_opt = false;
-        while let Ok(action) = rx.try_recv() {
-            if let Action::Layer(LayerManageAction::Push(act)) = action {
-                assert!(matches!(act.layer, Layers::Transaction(_)));
-                should_receive_layer_opt = true;
-            }
-        }
-        assert!(should_receive_layer_opt);
+        let layers = pushed_layers('T');
+        assert!(matches!(layers.as_slice(), [Layers::Transaction(_)]));
+    }
+
+    #[test]
+    fn test_event_nav_to_settings() {
+        let layers = pushed_layers('s');
+        assert!(matches!(layers.as_slice(), [Layers::Settings]));
+    }
+
+    #[test]
+    fn test_open_data_dir() {
+        let mut page = get_test_page().data_dir(PathBuf::from("/data/mealflow"));
+        page.opener = |_| Ok(());
+        page.handle_event_with_status_check(&'o'.into());
+        assert_eq!(page.notice.as_deref(), Some("Opened /data/mealflow"));
+
+        // falls back to showing the path, e.g. without a desktop
+        page.opener = |_| Err(std::io::Error::other("no opener"));
+        page.handle_event_with_status_check(&'o'.into());
+        let mut terminal = Terminal::new(TestBackend::new(80, 8)).unwrap();
+        terminal
+            .draw(|frame| page.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        // cleared by the next key
+        let _ = page.handle_events(&'x'.into());
+        assert!(page.notice.is_none());
     }
 }
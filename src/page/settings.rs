@@ -0,0 +1,360 @@
+//! # 设置页面模块
+//!
+//! 列出常用的配置项，在界面中直接切换，修改立即写入配置文件
+//! （见 [`AppConfig::config_path`](crate::config::AppConfig::config_path)），下次启动时仍然生效。
+//!
+//! ## 功能特性
+//!
+//! - **集中管理**: 原本只能通过命令行参数或配置文件设置的选项集中在一个页面
+//! - **即时生效**: 修改后通过 [`Action::UpdateConfig`] 更新应用程序配置，之后打开的页面使用新值
+//! - **数据持久化**: 只写入被修改的配置项，配置文件中的其他内容保持不变
+//!
+//! ## 页面布局
+//!
+//! ```text
+//! ┌─ Settings ──────────────────────────────────────────────┐
+//! │ Compact table            off                            │
+//! │ Wrap navigation          on                             │
+//! │ Fetch conflict policy    abort                          │
+//! └─────────────────────────────────────────────────────────┘
+//!  提示信息（保存结果，仅在有提示时显示 1 行）
+//!  快捷键帮助信息
+//! ```
+//!
+//! ## 键盘快捷键
+//!
+//! | 按键 | 功能 |
+//! |------|------|
+//! | `j`/`k` | 上下移动选中项 |
+//! | `Enter`/`Space` | 切换选中项的值 |
+//! | `Esc` | 返回上一页面 |
+//! | `?` | 显示帮助信息 |
+//!
+//! ## 优先级
+//!
+//! 命令行参数的优先级高于配置文件，启动时通过参数指定的选项在下次启动时仍以参数为准。
+
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use crossterm::event::KeyCode;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{
+        Block, BorderType, Borders, HighlightSpacing, Padding, Paragraph, Row, Table, TableState,
+    },
+};
+use tracing::warn;
+
+use crate::{
+    actions::{Action, ActionSender, LayerManageAction, Layers},
+    app::layer_manager::EventHandlingStatus,
+    config::{self, Config},
+    libs::transactions::ConflictPolicy,
+    tui::Event,
+    utils::help_msg::{HelpEntry, HelpMsg},
+};
+
+use super::{EventLoopParticipant, Layer, WidgetExt};
+
+/// A config option that can be changed on the settings page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    CompactTable,
+    WrapNavigation,
+    ConflictPolicy,
+}
+
+/// Settings in the order they are listed
+const SETTINGS: [Setting; 3] = [
+    Setting::CompactTable,
+    Setting::WrapNavigation,
+    Setting::ConflictPolicy,
+];
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Setting::CompactTable => "Compact table",
+            Setting::WrapNavigation => "Wrap navigation",
+            Setting::ConflictPolicy => "Fetch conflict policy",
+        }
+    }
+
+    /// Path of the setting in the config file
+    fn key(self) -> &'static str {
+        match self {
+            Setting::CompactTable => "ui.compact_table",
+            Setting::WrapNavigation => "ui.wrap_navigation",
+            Setting::ConflictPolicy => "fetch.conflict_policy",
+        }
+    }
+
+    fn value(self, config: &Config) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        match self {
+            Setting::CompactTable => on_off(config.ui.compact_table),
+            Setting::WrapNavigation => on_off(config.ui.wrap_navigation),
+            Setting::ConflictPolicy => config.fetch.conflict_policy.to_string(),
+        }
+    }
+
+    /// Move the setting in `config` to its next value, returning it as stored in the config file
+    fn toggle(self, config: &mut Config) -> serde_json::Value {
+        match self {
+            Setting::CompactTable => {
+                config.ui.compact_table = !config.ui.compact_table;
+                config.ui.compact_table.into()
+            }
+            Setting::WrapNavigation => {
+                config.ui.wrap_navigation = !config.ui.wrap_navigation;
+                config.ui.wrap_navigation.into()
+            }
+            Setting::ConflictPolicy => {
+                let policies = ConflictPolicy::value_variants();
+                let current = policies
+                    .iter()
+                    .position(|p| *p == config.fetch.conflict_policy)
+                    .unwrap_or_default();
+                config.fetch.conflict_policy = policies[(current + 1) % policies.len()];
+                config.fetch.conflict_policy.to_string().into()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Settings {
+    tx: ActionSender,
+    config: Config,
+    /// Where changed settings are saved
+    config_path: PathBuf,
+    table_state: TableState,
+    /// Outcome of the last change, cleared on the next key press
+    notice: Option<String>,
+}
+
+impl Settings {
+    pub fn new(tx: ActionSender, config: Config) -> Self {
+        Self {
+            tx,
+            config_path: config.config.config_path(),
+            config,
+            table_state: TableState::default().with_selected(0),
+            notice: None,
+        }
+    }
+
+    fn get_help_msg(&self) -> HelpMsg {
+        vec![
+            HelpEntry::new_plain("jk", "Move"),
+            HelpEntry::new(KeyCode::Enter, "Change"),
+            HelpEntry::new(KeyCode::Esc, "Back"),
+            HelpEntry::new('?', "Help"),
+        ]
+        .into()
+    }
+
+    fn selected(&self) -> Setting {
+        SETTINGS[self.table_state.selected().unwrap_or_default()]
+    }
+
+    /// Change the selected setting, save it and hand the new config to the app
+    fn toggle_selected(&mut self) {
+        let setting = self.selected();
+        let value = setting.toggle(&mut self.config);
+        self.notice = Some(
+            match config::save_config_value(&self.config_path, setting.key(), value) {
+                Ok(()) => format!("Saved to {}", self.config_path.display()),
+                Err(e) => {
+                    warn!("Failed to save {}: {:?}", setting.key(), e);
+                    format!("Changed for this session only, saving failed: {}", e)
+                }
+            },
+        );
+        self.tx
+            .send(Action::UpdateConfig(Box::new(self.config.clone())));
+    }
+}
+
+impl WidgetExt for Settings {
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(self.notice.is_some() as u16),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+        let block = Block::new()
+            .title(Line::raw("Settings").centered())
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL)
+            .padding(Padding::horizontal(1));
+        let rows = SETTINGS
+            .iter()
+            .map(|setting| Row::new([setting.label().to_string(), setting.value(&self.config)]));
+        let table = Table::new(rows, [Constraint::Length(24), Constraint::Fill(1)])
+            .block(block)
+            .row_highlight_style(Style::from(&self.config.ui.selection))
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(table, chunks[0], &mut self.table_state);
+
+        if let Some(notice) = &self.notice {
+            frame.render_widget(
+                Paragraph::new(notice.as_str())
+                    .style(Style::default().fg(Color::Yellow))
+                    .centered(),
+                chunks[1],
+            );
+        }
+
+        self.get_help_msg().render(frame, chunks[2]);
+    }
+}
+
+impl EventLoopParticipant for Settings {
+    fn handle_events(&mut self, event: &Event) -> EventHandlingStatus {
+        let mut status = EventHandlingStatus::default();
+        let Event::Key(key) = event else {
+            return status;
+        };
+        self.notice = None;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                let next = self.table_state.selected().map_or(0, |i| i + 1);
+                self.table_state.select(Some(next.min(SETTINGS.len() - 1)));
+                status.consumed();
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.table_state.select_previous();
+                status.consumed();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                self.toggle_selected();
+                status.consumed();
+            }
+            KeyCode::Esc => {
+                self.tx.send(LayerManageAction::Pop);
+                status.consumed();
+            }
+            KeyCode::Char('?') => {
+                self.tx.send(LayerManageAction::Push(
+                    Layers::Help(self.get_help_msg()).into_push_config(true),
+                ));
+                status.consumed();
+            }
+            _ => {}
+        }
+        status
+    }
+}
+
+impl Layer for Settings {
+    fn help(&self) -> HelpMsg {
+        self.get_help_msg()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use clap::Parser;
+    use insta::assert_snapshot;
+    use ratatui::backend::TestBackend;
+    use tempfile::TempDir;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    use super::*;
+    use crate::{
+        cli::{ClapSource, Cli},
+        page::test_utils::run_keys,
+    };
+
+    /// Settings page saving to `config.json` in `data_dir`
+    fn page_in(data_dir: &Path) -> (UnboundedReceiver<Action>, Settings) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let args = Cli::parse_from(["test-config", "--data-dir", data_dir.to_str().unwrap()]);
+        let config = Config::from_sources(Some(ClapSource::new(&args)), None).unwrap();
+        let mut page = Settings::new(tx.into(), config);
+        page.init();
+        (rx, page)
+    }
+
+    fn get_test_objs() -> (UnboundedReceiver<Action>, Settings, TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let (rx, page) = page_in(dir.path());
+        (rx, page, dir)
+    }
+
+    #[test]
+    fn test_navigation() {
+        let (mut rx, mut page, _dir) = get_test_objs();
+        assert_eq!(page.selected(), Setting::CompactTable);
+        run_keys(&mut page, &mut rx, &['j'.into(), 'j'.into(), 'j'.into()]);
+        assert_eq!(page.selected(), Setting::ConflictPolicy);
+        run_keys(&mut page, &mut rx, &['k'.into()]);
+        assert_eq!(page.selected(), Setting::WrapNavigation);
+    }
+
+    #[test]
+    fn test_toggle_saves() {
+        let (mut rx, mut page, dir) = get_test_objs();
+        let actions = run_keys(&mut page, &mut rx, &[KeyCode::Enter.into()]);
+        assert!(matches!(
+            actions.as_slice(),
+            [Action::UpdateConfig(config)] if config.ui.compact_table
+        ));
+
+        // abort -> skip -> overwrite
+        run_keys(
+            &mut page,
+            &mut rx,
+            &['j'.into(), 'j'.into(), ' '.into(), ' '.into()],
+        );
+        assert_eq!(page.config.fetch.conflict_policy, ConflictPolicy::Overwrite);
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("config.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            saved,
+            serde_json::json!({
+                "ui": { "compact_table": true },
+                "fetch": { "conflict_policy": "overwrite" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_save_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        // the config file can not be created under a regular file
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let (mut rx, mut page) = page_in(&file);
+        let actions = run_keys(&mut page, &mut rx, &[KeyCode::Enter.into()]);
+        assert!(
+            page.config.ui.compact_table,
+            "Should still apply for this session"
+        );
+        assert!(matches!(actions.as_slice(), [Action::UpdateConfig(_)]));
+        assert!(
+            page.notice
+                .as_deref()
+                .is_some_and(|n| n.starts_with("Changed for this session only"))
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let (_, mut page, _dir) = get_test_objs();
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|f| page.render(f, f.area())).unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
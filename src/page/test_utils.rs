@@ -0,0 +1,46 @@
+//! 页面测试辅助工具
+//!
+//! 向页面依次输入一串事件并收集页面发出的 [`Action`]，
+//! 省去测试中逐个调用 `handle_event_with_status_check` 再手动清空通道的样板代码。
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use super::EventLoopParticipant;
+use crate::{actions::Action, tui::Event};
+
+/// 向页面依次输入事件，返回页面发出的所有动作
+///
+/// 每个事件都必须被页面消费，行为与 `handle_event_with_status_check` 相同。
+///
+/// # 参数
+///
+/// * `page` - 接收事件的页面
+/// * `rx` - 页面发送动作所用通道的接收端
+/// * `events` - 按顺序输入的事件
+///
+/// # 返回值
+///
+/// 通道中的全部动作，按发送顺序排列，包括调用前就已在通道中的动作
+///
+/// # Panics
+///
+/// 如果某个事件没有被消费，将会panic
+pub(crate) fn run_keys<P: EventLoopParticipant + ?Sized>(
+    page: &mut P,
+    rx: &mut UnboundedReceiver<Action>,
+    events: &[Event],
+) -> Vec<Action> {
+    for event in events {
+        page.handle_event_with_status_check(event);
+    }
+    let mut actions = Vec::new();
+    while let Ok(action) = rx.try_recv() {
+        actions.push(action);
+    }
+    actions
+}
+
+/// 把文本拆成逐个字符的按键事件，用于模拟键盘输入
+pub(crate) fn type_str(text: &str) -> Vec<Event> {
+    text.chars().map(Event::from).collect()
+}
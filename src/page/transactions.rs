@@ -1,11 +1,22 @@
-use std::cmp::max;
+use std::{ops::Deref, path::PathBuf};
 
 use crate::{
     actions::{ActionSender, LayerManageAction, Layers},
     app::layer_manager::EventHandlingStatus,
-    libs::transactions::{FilterOptions, Transaction, TransactionManager},
+    component::{
+        input::{InputComp, InputMode},
+        table::{Column, TableComp},
+    },
+    libs::{
+        filter_presets::{self, FilterPreset},
+        transactions::{FilterOptions, Transaction, TransactionManager},
+    },
     tui::Event,
-    utils::help_msg::{HelpEntry, HelpMsg},
+    utils::{
+        amount::{amount_style, format_amount},
+        fuzzy,
+        help_msg::{HelpEntry, HelpMsg},
+    },
 };
 
 use super::{EventLoopParticipant, Layer, WidgetExt};
@@ -13,43 +24,75 @@ use color_eyre::eyre::Context;
 use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Layout, Margin, Rect},
-    style::{Color, Modifier, Style, Stylize, palette::tailwind},
-    text::Text,
-    widgets::{
-        Cell, Clear, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Table, TableState,
-    },
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize, palette::tailwind},
+    widgets::{Clear, Paragraph},
 };
-use unicode_width::UnicodeWidthStr;
-
-struct TableColors {
-    buffer_bg: Color,
-    header_bg: Color,
-    header_fg: Color,
-    row_fg: Color,
-    selected_row_style_fg: Color,
-    // selected_column_style_fg: Color,
-    // selected_cell_style_fg: Color,
-    normal_row_color: Color,
-    alt_row_color: Color,
-    // footer_border_color: Color,
+
+/// Foreground of rows above the spend alert threshold
+const ALERT_FG: Color = tailwind::RED.c400;
+/// Appended to the merchant of rows with a note
+const NOTE_MARKER: &str = "✎";
+
+/// A row of the table, a transaction with its note
+#[derive(Clone, Debug)]
+struct NotedTransaction {
+    transaction: Transaction,
+    note: Option<String>,
 }
 
-const TABLE_COLORS: TableColors = TableColors {
-    buffer_bg: Color::Reset,
-    header_bg: tailwind::INDIGO.c950,
-    header_fg: tailwind::GRAY.c100,
-    row_fg: tailwind::GRAY.c200,
-    selected_row_style_fg: tailwind::INDIGO.c300,
-    // selected_column_style_fg: tailwind::INDIGO.c400,
-    // selected_cell_style_fg: tailwind::INDIGO.c600,
-    normal_row_color: Color::Reset,
-    alt_row_color: tailwind::GRAY.c950,
-    // footer_border_color: tailwind::INDIGO.c400,
-};
+impl From<Transaction> for NotedTransaction {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            transaction,
+            note: None,
+        }
+    }
+}
+
+impl Deref for NotedTransaction {
+    type Target = Transaction;
 
-const ITEM_HEIGHT: usize = 3;
+    fn deref(&self) -> &Transaction {
+        &self.transaction
+    }
+}
+
+/// How precisely the time column is shown, cycled with `t`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum TimePrecision {
+    Date,
+    #[default]
+    Minute,
+    Second,
+}
+
+impl TimePrecision {
+    fn next(self) -> Self {
+        match self {
+            TimePrecision::Date => TimePrecision::Minute,
+            TimePrecision::Minute => TimePrecision::Second,
+            TimePrecision::Second => TimePrecision::Date,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TimePrecision::Date => "Dates only",
+            TimePrecision::Minute => "Minutes",
+            TimePrecision::Second => "Seconds",
+        }
+    }
+
+    /// Row formatter of the table showing times at this precision
+    fn format_row(self) -> fn(&NotedTransaction) -> Vec<String> {
+        match self {
+            TimePrecision::Date => |t| format_row(t, "%Y-%m-%d"),
+            TimePrecision::Minute => |t| format_row(t, "%Y-%m-%d %H:%M"),
+            TimePrecision::Second => |t| format_row(t, "%Y-%m-%d %H:%M:%S"),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Transactions {
@@ -57,11 +100,28 @@ pub struct Transactions {
     tx: crate::actions::ActionSender,
     manager: TransactionManager,
 
-    transactions: Vec<crate::libs::transactions::Transaction>,
+    table: TableComp<NotedTransaction>,
+    min_abs_amount: f64,
+    spend_alert: Option<f64>,
+    /// Show the `Filters: ...` panel above the help, only used with a filter
+    show_filters: bool,
+    time_precision: TimePrecision,
 
-    table_state: TableState,
-    scroll_state: ScrollbarState,
-    longest_item_lens: (usize, usize, usize),
+    /// Filter the page was opened with, restored after cycling past the last preset
+    base_filter: Option<FilterOptions>,
+    /// File holding the saved presets, `None` to disable presets
+    presets_path: Option<PathBuf>,
+    presets: Vec<FilterPreset>,
+    /// Index of the preset currently applied
+    preset: Option<usize>,
+    /// Asks for the name when saving the current filter as a preset
+    preset_name: InputComp,
+    /// Edits the note of the selected row
+    note_input: InputComp,
+    /// Fuzzy searches the merchants on the list, jumping to the best match
+    search_input: InputComp,
+    /// One-off message shown above the help, cleared on the next key press
+    notice: Option<String>,
 }
 
 impl Transactions {
@@ -70,33 +130,115 @@ impl Transactions {
         tx: ActionSender,
         manager: TransactionManager,
     ) -> Self {
-        let mut t = Self {
+        Self {
+            base_filter: filter_option.clone(),
             filter_option,
             tx,
             manager,
 
-            transactions: Default::default(),
+            table: new_table(),
+            min_abs_amount: 0.0,
+            spend_alert: None,
+            show_filters: true,
+            time_precision: TimePrecision::default(),
 
-            table_state: TableState::default(),
-            scroll_state: ScrollbarState::default(),
-            longest_item_lens: (0, 0, 0),
-        };
-        t.load_from_db();
-        t
+            presets_path: None,
+            presets: vec![],
+            preset: None,
+            preset_name: InputComp::new().title("Preset name").max_len(50),
+            note_input: InputComp::new().title("Note").max_len(200),
+            search_input: InputComp::new().title("Search merchant").max_len(50),
+            notice: None,
+        }
+    }
+
+    /// Set the style of the selected row in the table
+    pub fn selected_row_style<T: Into<Style>>(mut self, style: T) -> Self {
+        self.table = self.table.selected_row_style(style);
+        self
+    }
+
+    /// Highlight rows whose absolute amount exceeds `threshold`, `None` to disable
+    pub fn spend_alert(mut self, threshold: Option<f64>) -> Self {
+        self.spend_alert = threshold;
+        self
+    }
+
+    /// Use 1-line rows in the table
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.table = self.table.compact(compact);
+        self
+    }
+
+    /// Wrap around at the first and last row, `false` to stop there
+    pub fn wrap_navigation(mut self, wrap: bool) -> Self {
+        self.table = self.table.wrap(wrap);
+        self
+    }
+
+    /// Hide transactions whose absolute amount is below `threshold`
+    pub fn min_abs_amount(mut self, threshold: f64) -> Self {
+        self.min_abs_amount = threshold;
+        self
+    }
+
+    /// Save and cycle through filter presets in `path`, see [`filter_presets`]
+    pub fn presets_path(mut self, path: PathBuf) -> Self {
+        self.presets = filter_presets::load_presets(&path).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load filter presets: {:?}", e);
+            vec![]
+        });
+        self.presets_path = Some(path);
+        self
     }
 
     fn get_help_msg(&self) -> HelpMsg {
+        if let Some(input) = self.active_input() {
+            return input.get_help_msg();
+        }
         let mut help_msg = HelpMsg::default();
 
         help_msg.push(HelpEntry::new('?', "Show help"));
         if self.filter_option.is_some() {
             help_msg.push(HelpEntry::new(KeyCode::Esc, "Back"));
+            help_msg.push(HelpEntry::new(
+                'F',
+                if self.show_filters {
+                    "Hide filters"
+                } else {
+                    "Show filters"
+                },
+            ));
         } else {
             help_msg.push(HelpEntry::new('f', "Fetch"));
         }
 
         help_msg.push(HelpEntry::new(' ', "Filter this merchant"));
+        help_msg.push(HelpEntry::new('A', "Analyze this merchant"));
+        help_msg.push(HelpEntry::new('n', "Edit note"));
+        help_msg.push(HelpEntry::new('/', "Search merchant"));
         help_msg.push(HelpEntry::new('l', "Load from local cache"));
+        help_msg.push(HelpEntry::new(
+            'c',
+            if self.table.is_compact() {
+                "Spacious rows"
+            } else {
+                "Compact rows"
+            },
+        ));
+        help_msg.push(HelpEntry::new(
+            't',
+            format!("Time: {}", self.time_precision.next().label()),
+        ));
+        if self.presets_path.is_some() {
+            help_msg.push(HelpEntry::new('p', "Next preset"));
+            if self.filter_option.is_some() {
+                help_msg.push(HelpEntry::new('P', "Save filter as preset"));
+            }
+            if self.preset.is_some() {
+                help_msg.push(HelpEntry::new('D', "Delete preset"));
+            }
+        }
 
         help_msg
     }
@@ -117,28 +259,62 @@ impl Transactions {
 impl WidgetExt for Transactions {
     fn render(&mut self, frame: &mut Frame, area: Rect) {
         frame.render_widget(Clear, area);
-        let (main_area, help_area) = match &self.filter_option {
+        let notice = self.notice.clone().or_else(|| {
+            self.table
+                .selected_item()
+                .and_then(|t| t.note.as_ref())
+                .map(|note| format!("Note: {}", note))
+        });
+        let [area, notice_area, input_area, help_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(notice.is_some() as u16),
+            Constraint::Length(if self.active_input().is_some() { 3 } else { 0 }),
+            Constraint::Length(3),
+        ])
+        .areas(area);
+        if let Some(notice) = notice {
+            frame.render_widget(Paragraph::new(notice).fg(tailwind::GRAY.c500), notice_area);
+        }
+        if let Some(input) = self.active_input_mut() {
+            input.render(frame, input_area);
+        }
+
+        let main_area = match &self.filter_option {
             Some(opt) => {
-                let areas = &Layout::vertical([
+                let [main_area, filters_area] = Layout::vertical([
                     Constraint::Fill(1),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
+                    Constraint::Length(if self.show_filters { 3 } else { 0 }),
                 ])
-                .split(area);
-
-                frame.render_widget(Paragraph::new(format!("\nFilters: {}\n", opt)), areas[1]);
+                .areas(area);
 
-                (areas[0], areas[2])
-            }
-            None => {
-                let areas =
-                    &Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).split(area);
+                let label = match self.current_preset() {
+                    Some(preset) => format!("Filters ({})", preset.name),
+                    None => "Filters".to_string(),
+                };
+                frame.render_widget(
+                    Paragraph::new(format!("\n{}: {}\n", label, opt)),
+                    filters_area,
+                );
 
-                (areas[0], areas[1])
+                main_area
             }
+            None => area,
         };
-        self.render_table(frame, main_area);
-        self.render_scrollbar(frame, main_area);
+        if self.filter_option.is_some() && self.table.is_empty() {
+            let [message_area] = Layout::vertical([Constraint::Length(1)])
+                .flex(Flex::Center)
+                .areas(main_area);
+            frame.render_widget(
+                Paragraph::new("No transactions match this filter")
+                    .centered()
+                    .fg(tailwind::GRAY.c500),
+                message_area,
+            );
+        } else {
+            let spend_alert = self.spend_alert;
+            self.table
+                .render(frame, main_area, |i, t| row_style(spend_alert, i, t));
+        }
 
         self.get_help_msg().render(frame, help_area);
     }
@@ -146,8 +322,40 @@ impl WidgetExt for Transactions {
 
 impl EventLoopParticipant for Transactions {
     fn handle_events(&mut self, event: &Event) -> EventHandlingStatus {
+        if self.preset_name.is_inputting() {
+            let (status, name) = self.preset_name.handle_events(event);
+            if let Some(name) = name {
+                self.save_preset(name.trim());
+            }
+            if !self.preset_name.is_inputting() {
+                self.preset_name.set_mode(InputMode::Idle);
+            }
+            return status;
+        }
+        if self.note_input.is_inputting() {
+            let (status, note) = self.note_input.handle_events(event);
+            if let Some(note) = note {
+                self.save_note(&note);
+            }
+            if !self.note_input.is_inputting() {
+                self.note_input.set_mode(InputMode::Idle);
+            }
+            return status;
+        }
+        if self.search_input.is_inputting() {
+            let (status, query) = self.search_input.handle_events(event);
+            if let Some(query) = query {
+                self.search_merchant(query.trim());
+            }
+            if !self.search_input.is_inputting() {
+                self.search_input.set_mode(InputMode::Idle);
+            }
+            return status;
+        }
+
         let mut status = EventHandlingStatus::default();
         if let Event::Key(key) = event {
+            self.notice = None;
             match (key.modifiers, key.code) {
                 // navigate to fetch page
                 (_, KeyCode::Char('f')) => {
@@ -156,16 +364,59 @@ impl EventLoopParticipant for Transactions {
                         status.consumed();
                     }
                 }
+                (_, KeyCode::Char('F')) if self.filter_option.is_some() => {
+                    self.show_filters = !self.show_filters;
+                    status.consumed();
+                }
                 (_, KeyCode::Char('l')) => {
                     self.load_from_db();
                     status.consumed();
                 }
+                (_, KeyCode::Char('c')) => {
+                    self.table.toggle_compact();
+                    status.consumed();
+                }
+                (_, KeyCode::Char('p')) if self.presets_path.is_some() => {
+                    self.next_preset();
+                    status.consumed();
+                }
+                (_, KeyCode::Char('P')) if self.presets_path.is_some() => {
+                    if self.filter_option.is_some() {
+                        let name = self.current_preset().map(|p| p.name.clone());
+                        self.preset_name.set_text(name.unwrap_or_default());
+                        self.preset_name.set_mode(InputMode::Inputting);
+                    } else {
+                        self.notice = Some("No filter to save, cycle or apply one first".into());
+                    }
+                    status.consumed();
+                }
+                (_, KeyCode::Char('n')) => {
+                    if let Some(t) = self.table.selected_item() {
+                        self.note_input.set_text(t.note.clone().unwrap_or_default());
+                        self.note_input.set_mode(InputMode::Inputting);
+                    }
+                    status.consumed();
+                }
+                (_, KeyCode::Char('/')) => {
+                    self.search_input.set_text(String::new());
+                    self.search_input.set_mode(InputMode::Inputting);
+                    status.consumed();
+                }
+                (_, KeyCode::Char('D')) if self.presets_path.is_some() => {
+                    self.delete_preset();
+                    status.consumed();
+                }
+                (_, KeyCode::Char('t')) => {
+                    self.time_precision = self.time_precision.next();
+                    self.table.set_format_row(self.time_precision.format_row());
+                    status.consumed();
+                }
                 (_, KeyCode::Char('j')) | (_, KeyCode::Down) => {
-                    self.change_focus(1);
+                    self.table.change_focus(1);
                     status.consumed();
                 }
                 (_, KeyCode::Char('k')) | (_, KeyCode::Up) => {
-                    self.change_focus(-1);
+                    self.table.change_focus(-1);
                     status.consumed();
                 }
                 (_, KeyCode::Char('?')) => {
@@ -175,17 +426,18 @@ impl EventLoopParticipant for Transactions {
                     status.consumed();
                 }
                 (_, KeyCode::Char(' ')) => {
-                    if let Some(index) = self.table_state.selected() {
-                        if let Some(transaction) = self.transactions.get(index) {
-                            let layer = Layers::Transaction(Some(
-                                self.filter_option
-                                    .clone()
-                                    .unwrap_or_default()
-                                    .merchant(transaction.merchant.clone()),
-                            ));
-                            self.tx
-                                .send(LayerManageAction::Push(layer.into_push_config(false)));
-                        }
+                    if let Some(layer) =
+                        self.merchant_filter().map(|f| Layers::Transaction(Some(f)))
+                    {
+                        self.tx
+                            .send(LayerManageAction::Push(layer.into_push_config(false)));
+                    }
+                    status.consumed();
+                }
+                (_, KeyCode::Char('A')) => {
+                    if let Some(layer) = self.merchant_filter().map(|f| Layers::Analysis(Some(f))) {
+                        self.tx
+                            .send(LayerManageAction::Push(layer.into_push_config(false)));
                     }
                     status.consumed();
                 }
@@ -225,159 +477,240 @@ impl EventLoopParticipant for Transactions {
     // }
 }
 
-impl Layer for Transactions {}
+impl Layer for Transactions {
+    fn init(&mut self) {
+        self.load_from_db();
+    }
+
+    fn help(&self) -> HelpMsg {
+        self.get_help_msg()
+    }
+}
 
 impl Transactions {
-    fn render_table(&mut self, frame: &mut Frame, area: Rect) {
-        let header_style = Style::default()
-            .fg(TABLE_COLORS.header_fg)
-            .bg(TABLE_COLORS.header_bg);
-        let selected_row_style = Style::default()
-            .add_modifier(Modifier::REVERSED)
-            .fg(TABLE_COLORS.selected_row_style_fg);
-        // let selected_col_style = Style::default().fg(TABLE_COLORS.selected_column_style_fg);
-        // let selected_cell_style = Style::default()
-        //     .add_modifier(Modifier::REVERSED)
-        //     .fg(TABLE_COLORS.selected_cell_style_fg);
-
-        let header = HEADER_STR
-            .iter()
-            .map(|r| Cell::from(format!("\n{}\n", r)))
-            .collect::<Row>()
-            .style(header_style)
-            .height(3);
-
-        let rows = self.transactions.iter().enumerate().map(|(i, t)| {
-            let color = match i % 2 {
-                0 => TABLE_COLORS.normal_row_color,
-                _ => TABLE_COLORS.alt_row_color,
-            };
-            Row::new(vec![
-                Text::from(format!("\n{}\n", t.amount)).alignment(Alignment::Right),
-                Text::from(format!("\n{}\n", t.time.format("%Y-%m-%d %H:%M"))),
-                Text::from(format!("\n{}\n", t.merchant)),
-            ])
-            .style(Style::new().fg(TABLE_COLORS.row_fg).bg(color))
-            .height(ITEM_HEIGHT as u16)
-        });
-        let bar = " █ ";
-
-        let t = Table::new(
-            rows,
-            [
-                Constraint::Length((self.longest_item_lens.0 + 2).try_into().unwrap()),
-                Constraint::Min((self.longest_item_lens.1 + 2).try_into().unwrap()),
-                Constraint::Min((self.longest_item_lens.2 + 2).try_into().unwrap()),
-            ],
-        )
-        .header(header)
-        .row_highlight_style(selected_row_style)
-        // .column_highlight_style(selected_col_style)
-        // .cell_highlight_style(selected_cell_style)
-        .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
-        .bg(TABLE_COLORS.buffer_bg)
-        .highlight_spacing(HighlightSpacing::Always)
-        .column_spacing(4);
-
-        frame.render_stateful_widget(t, area, &mut self.table_state);
-    }
-
-    fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect) {
-        frame.render_stateful_widget(
-            Scrollbar::default()
-                .orientation(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(None)
-                .end_symbol(None),
-            area.inner(Margin {
-                vertical: 1,
-                horizontal: 1,
-            }),
-            &mut self.scroll_state,
-        );
+    /// The input box currently taking keys, if any
+    fn active_input(&self) -> Option<&InputComp> {
+        [&self.preset_name, &self.note_input, &self.search_input]
+            .into_iter()
+            .find(|input| input.is_inputting())
     }
 
-    fn load_from_db(&mut self) {
-        match &self.filter_option {
-            Some(option) => {
-                self.transactions = self
-                    .manager
-                    .fetch_filtered(option)
-                    .with_context(|| {
-                        format!(
-                            "Failed to load transactions from database with filter: {:?}",
-                            option
-                        )
-                    })
-                    .unwrap();
+    fn active_input_mut(&mut self) -> Option<&mut InputComp> {
+        [
+            &mut self.preset_name,
+            &mut self.note_input,
+            &mut self.search_input,
+        ]
+        .into_iter()
+        .find(|input| input.is_inputting())
+    }
+
+    /// Set the note of the selected row, an empty note removes it
+    fn save_note(&mut self, note: &str) {
+        let Some(id) = self.table.selected_item().map(|t| t.id) else {
+            return;
+        };
+        match self.manager.set_note(id, note) {
+            Ok(()) => {
+                let note = note.trim();
+                self.table
+                    .update_selected(|t| t.note = (!note.is_empty()).then(|| note.to_string()));
+            }
+            Err(e) => self.notice = Some(format!("Failed to save note: {}", e)),
+        }
+    }
+
+    /// Select the first row of the merchant best matching `query`, see [`fuzzy`]
+    fn search_merchant(&mut self, query: &str) {
+        if query.is_empty() {
+            return;
+        }
+        let items = self.table.items();
+        let best = fuzzy::rank(query, items.iter().map(|t| t.merchant.as_str()))
+            .first()
+            .and_then(|best| items.iter().position(|t| t.merchant == *best));
+        match best {
+            Some(index) => self.table.select(index),
+            None => self.notice = Some(format!("No merchant matches \"{}\"", query)),
+        }
+    }
+
+    fn current_preset(&self) -> Option<&FilterPreset> {
+        self.preset.and_then(|i| self.presets.get(i))
+    }
+
+    /// Apply the next saved preset, going back to the original filter after the last one
+    fn next_preset(&mut self) {
+        let Some(path) = &self.presets_path else {
+            return;
+        };
+        // another page may have changed the presets since
+        match filter_presets::load_presets(path) {
+            Ok(presets) => self.presets = presets,
+            Err(e) => {
+                self.notice = Some(format!("Failed to load presets: {}", e));
+                return;
+            }
+        }
+        if self.presets.is_empty() {
+            self.notice = Some("No saved presets, press P on a filtered list to save one".into());
+            return;
+        }
+
+        let next = self.preset.map_or(0, |i| i + 1);
+        match self.presets.get(next) {
+            Some(preset) => {
+                self.filter_option = Some(preset.filter.clone());
+                self.preset = Some(next);
             }
             None => {
-                self.transactions = self
-                    .manager
-                    .fetch_all()
-                    .context("Failed to load transactions from database")
-                    .unwrap();
+                self.filter_option = self.base_filter.clone();
+                self.preset = None;
             }
         }
-        self.transactions.sort_by(|a, b| b.time.cmp(&a.time));
-        self.scroll_state = self
-            .scroll_state
-            .content_length(self.transactions.len() * ITEM_HEIGHT)
-            .position(0);
-        self.longest_item_lens = constraint_len_calculator(&self.transactions, HEADER_STR);
-        if self.transactions.is_empty() {
-            self.table_state.select(None);
-        } else {
-            self.table_state.select(Some(0));
+        self.load_from_db();
+    }
+
+    /// Save the current filter as the preset `name`, replacing one with the same name
+    fn save_preset(&mut self, name: &str) {
+        let (Some(path), Some(filter)) = (&self.presets_path, &self.filter_option) else {
+            return;
+        };
+        if name.is_empty() {
+            self.notice = Some("Preset name can not be empty".into());
+            return;
+        }
+        let preset = FilterPreset {
+            name: name.to_string(),
+            filter: filter.clone(),
+        };
+        match filter_presets::save_preset(path, preset) {
+            Ok(presets) => {
+                self.preset = presets.iter().position(|p| p.name == name);
+                self.presets = presets;
+                self.notice = Some(format!("Saved preset \"{}\"", name));
+            }
+            Err(e) => self.notice = Some(format!("Failed to save preset: {}", e)),
         }
     }
 
-    fn change_focus(&mut self, index: isize) {
-        let cur_index = self.table_state.selected().unwrap_or(0);
-        let max = self.transactions.len();
-        if max == 0 {
+    /// Delete the applied preset, keeping its filter on the list
+    fn delete_preset(&mut self) {
+        let (Some(path), Some(name)) = (
+            &self.presets_path,
+            self.current_preset().map(|p| p.name.clone()),
+        ) else {
+            self.notice = Some("No preset applied, press p to cycle to one".into());
             return;
+        };
+        match filter_presets::delete_preset(path, &name) {
+            Ok(presets) => {
+                self.presets = presets;
+                self.preset = None;
+                self.notice = Some(format!("Deleted preset \"{}\"", name));
+            }
+            Err(e) => self.notice = Some(format!("Failed to delete preset: {}", e)),
         }
-        // add index to current index, wrap around from 0  to max
-        let new_index = if index < 0 {
-            (cur_index as isize + index + max as isize) as usize % max
+    }
+
+    /// The current filters narrowed down to the merchant of the selected row
+    fn merchant_filter(&self) -> Option<FilterOptions> {
+        self.table.selected_item().map(|transaction| {
+            self.filter_option
+                .clone()
+                .unwrap_or_default()
+                .merchant(transaction.merchant.clone())
+        })
+    }
+
+    fn load_from_db(&mut self) {
+        let filter_option = if self.min_abs_amount > 0.0 {
+            Some(
+                self.filter_option
+                    .clone()
+                    .unwrap_or_default()
+                    .min_abs_amount(self.min_abs_amount),
+            )
         } else {
-            (cur_index as isize + index) as usize % max
+            self.filter_option.clone()
+        };
+        let mut transactions = match &filter_option {
+            Some(option) => self
+                .manager
+                .fetch_filtered(option)
+                .with_context(|| {
+                    format!(
+                        "Failed to load transactions from database with filter: {:?}",
+                        option
+                    )
+                })
+                .unwrap(),
+            None => self
+                .manager
+                .fetch_all()
+                .context("Failed to load transactions from database")
+                .unwrap(),
         };
-        self.table_state.select(Some(new_index));
-        self.scroll_state = self.scroll_state.position(new_index * ITEM_HEIGHT);
+        transactions.sort_by(|a, b| b.time.cmp(&a.time));
+        let notes = self.manager.fetch_notes().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load transaction notes: {:?}", e);
+            Default::default()
+        });
+        self.table.set_items(
+            transactions
+                .into_iter()
+                .map(|transaction| NotedTransaction {
+                    note: notes.get(&transaction.id).cloned(),
+                    transaction,
+                })
+                .collect(),
+        );
     }
 }
 
-const HEADER_STR: &[&str] = &["金额", "时间", "商家"];
-
-fn constraint_len_calculator(items: &[Transaction], header: &[&str]) -> (usize, usize, usize) {
-    let data_len = items.iter().fold((0, 0, 0), |acc, item| {
-        let amount_len = max(
-            acc.0,
-            UnicodeWidthStr::width(item.amount.to_string().as_str()),
-        );
-        let time_len = max(
-            acc.1,
-            UnicodeWidthStr::width(format!("{}", item.time.format("%Y-%m-%d %H:%M")).as_str()),
-        );
-        let merchant_len = max(
-            acc.2,
-            UnicodeWidthStr::width_cjk(item.merchant.to_string().as_str()),
-        );
-        (amount_len, time_len, merchant_len)
-    });
-    (
-        max(data_len.0, UnicodeWidthStr::width_cjk(header[0])),
-        max(data_len.1, UnicodeWidthStr::width_cjk(header[1])),
-        max(data_len.2, UnicodeWidthStr::width_cjk(header[2])),
+fn new_table() -> TableComp<NotedTransaction> {
+    TableComp::new(
+        vec![
+            Column::new("金额").fixed().right(),
+            Column::new("时间"),
+            Column::new("商家"),
+        ],
+        TimePrecision::default().format_row(),
     )
 }
 
+/// Cells of a table row, with the time formatted by `time_format`
+/// and the merchant marked when the row has a note
+fn format_row(t: &NotedTransaction, time_format: &str) -> Vec<String> {
+    vec![
+        format_amount(t.amount),
+        t.time.format(time_format).to_string(),
+        match t.note {
+            Some(_) => format!("{} {}", t.merchant, NOTE_MARKER),
+            None => t.merchant.clone(),
+        },
+    ]
+}
+
+/// Striped rows with income in green, and those above the `spend_alert` threshold in bold red
+fn row_style(spend_alert: Option<f64>, index: usize, transaction: &Transaction) -> Style {
+    let style = amount_style(
+        TableComp::<Transaction>::stripe_style(index),
+        transaction.amount,
+    );
+    match spend_alert {
+        Some(threshold) if transaction.amount.abs() > threshold => style.fg(ALERT_FG).bold(),
+        _ => style,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         actions::{Action, PushPageConfig},
+        component::table::TABLE_COLORS,
         libs::fetcher,
+        page::test_utils::{run_keys, type_str},
     };
 
     use super::*;
@@ -398,64 +731,74 @@ mod test {
         let mut transaction = Transactions::new(filter_opt, tx.into(), manager);
         transaction.init();
 
-        assert!(!transaction.transactions.is_empty());
+        assert!(!transaction.table.items().is_empty());
 
         (rx, transaction)
     }
 
     #[test]
     fn table_length() {
-        let data = fetcher::test_utils::get_mock_data(5);
-        let result = constraint_len_calculator(&data, HEADER_STR);
-        println!("data: {:?}", data);
-        println!("result: {:?}", result);
-        assert_eq!(result.0, 6);
-        assert_eq!(result.1, 16);
-        assert_eq!(result.2, 16);
+        let mut table = new_table();
+        table.set_items(
+            fetcher::test_utils::get_mock_data(5)
+                .into_iter()
+                .map(NotedTransaction::from)
+                .collect(),
+        );
+        // "-¥18.72"
+        assert_eq!(table.widths(), [7, 16, 16]);
     }
 
     #[test]
     fn table_length_only_header() {
-        let data = vec![];
-        let result = constraint_len_calculator(&data, HEADER_STR);
-        assert_eq!(result.0, 4);
-        assert_eq!(result.1, 4);
-        assert_eq!(result.2, 4);
+        assert_eq!(new_table().widths(), [4, 4, 4]);
     }
 
     #[test]
     fn order() {
         let (_, transaction) = get_test_objs(None, 50);
-        transaction
-            .transactions
-            .iter()
-            .enumerate()
-            .for_each(|(i, t)| {
-                if i == 0 {
-                    return;
-                }
-                assert!(t.time <= transaction.transactions[i - 1].time);
-            });
+        let items = transaction.table.items();
+        items.iter().enumerate().for_each(|(i, t)| {
+            if i == 0 {
+                return;
+            }
+            assert!(t.time <= items[i - 1].time);
+        });
     }
 
     #[test]
     fn navigation() {
         let (_, mut transaction) = get_test_objs(None, 50);
-        assert_eq!(transaction.table_state.selected(), Some(0));
+        assert_eq!(transaction.table.selected(), Some(0));
 
         transaction.handle_event_with_status_check(&'j'.into());
-        assert_eq!(transaction.table_state.selected(), Some(1));
+        assert_eq!(transaction.table.selected(), Some(1));
 
         transaction.handle_event_with_status_check(&'k'.into());
-        assert_eq!(transaction.table_state.selected(), Some(0));
+        assert_eq!(transaction.table.selected(), Some(0));
 
         transaction.handle_event_with_status_check(&'k'.into());
         assert_eq!(
-            transaction.table_state.selected(),
-            Some(transaction.transactions.len() - 1)
+            transaction.table.selected(),
+            Some(transaction.table.items().len() - 1)
         );
     }
 
+    #[test]
+    fn navigation_no_wrap() {
+        let (_, transaction) = get_test_objs(None, 50);
+        let mut transaction = transaction.wrap_navigation(false);
+        let last = transaction.table.items().len() - 1;
+
+        transaction.handle_event_with_status_check(&'k'.into());
+        assert_eq!(transaction.table.selected(), Some(0));
+
+        for _ in 0..=last {
+            transaction.handle_event_with_status_check(&'j'.into());
+        }
+        assert_eq!(transaction.table.selected(), Some(last));
+    }
+
     #[test]
     fn render() {
         let (_, mut transaction) = get_test_objs(None, 50);
@@ -473,12 +816,91 @@ mod test {
         assert_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn render_compact() {
+        let (_, mut transaction) = get_test_objs(None, 50);
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+
+        transaction.handle_event_with_status_check(&'j'.into());
+        transaction.handle_event_with_status_check(&'c'.into());
+        assert_eq!(transaction.table.selected(), Some(1));
+        terminal
+            .draw(|frame| transaction.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn spend_alert() {
+        let (_, transaction) = get_test_objs(None, 5);
+        let small = Transaction::from_local(-12.5, "Canteen", "2025-03-01 12:00:00").unwrap();
+        let large = Transaction::from_local(-50.01, "Canteen", "2025-03-01 12:00:00").unwrap();
+        let exact = Transaction::from_local(-50.0, "Canteen", "2025-03-01 12:00:00").unwrap();
+
+        // off by default
+        assert_eq!(
+            row_style(transaction.spend_alert, 0, &large).fg,
+            Some(TABLE_COLORS.row_fg)
+        );
+
+        let transaction = transaction.spend_alert(Some(50.0));
+        let style = row_style(transaction.spend_alert, 1, &large);
+        assert_eq!(style.fg, Some(ALERT_FG));
+        assert_eq!(style.bg, Some(TABLE_COLORS.alt_row_color));
+        assert!(style.add_modifier.contains(ratatui::style::Modifier::BOLD));
+        assert_eq!(
+            row_style(transaction.spend_alert, 0, &small).fg,
+            Some(TABLE_COLORS.row_fg)
+        );
+        assert_eq!(
+            row_style(transaction.spend_alert, 0, &exact).fg,
+            Some(TABLE_COLORS.row_fg)
+        );
+    }
+
+    #[test]
+    fn time_precision() {
+        let (_, mut transaction) = get_test_objs(None, 5);
+        transaction.handle_event_with_status_check(&'j'.into());
+        // "2025-03-01 12:00"
+        assert_eq!(transaction.table.widths()[1], 16);
+
+        transaction.handle_event_with_status_check(&'t'.into());
+        assert_eq!(transaction.time_precision, TimePrecision::Second);
+        assert_eq!(transaction.table.widths()[1], 19);
+        // the selection stays on the same row
+        assert_eq!(transaction.table.selected(), Some(1));
+
+        transaction.handle_event_with_status_check(&'t'.into());
+        assert_eq!(transaction.time_precision, TimePrecision::Date);
+        assert_eq!(transaction.table.widths()[1], 10);
+
+        transaction.handle_event_with_status_check(&'t'.into());
+        assert_eq!(transaction.time_precision, TimePrecision::Minute);
+        assert_eq!(transaction.table.widths()[1], 16);
+    }
+
+    #[test]
+    fn income_color() {
+        let (_, transaction) = get_test_objs(None, 5);
+        let recharge = Transaction::from_local(100.0, "充值", "2025-03-01 12:00:00").unwrap();
+        let spend = Transaction::from_local(-12.5, "Canteen", "2025-03-01 12:00:00").unwrap();
+
+        let style = row_style(transaction.spend_alert, 1, &recharge);
+        assert_eq!(style.fg, Some(crate::utils::amount::INCOME_FG));
+        assert_eq!(style.bg, Some(TABLE_COLORS.alt_row_color));
+        assert_eq!(
+            row_style(transaction.spend_alert, 0, &spend).fg,
+            Some(TABLE_COLORS.row_fg)
+        );
+    }
+
     #[test]
     fn with_filter() {
         let (mut rx, mut transaction) =
             get_test_objs(Some(FilterOptions::default().merchant("寿司")), 200);
         assert!(transaction.filter_option.is_some());
-        transaction.transactions.iter().for_each(|t| {
+        transaction.table.items().iter().for_each(|t| {
             assert!(t.merchant.contains("寿司"));
         });
         let s = transaction.handle_events(&'f'.into());
@@ -505,33 +927,219 @@ mod test {
     }
 
     #[test]
-    fn push_filtered_page() {
+    fn toggle_filters() {
+        let (mut rx, mut transaction) =
+            get_test_objs(Some(FilterOptions::default().merchant("寿司")), 200);
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+
+        run_keys(&mut transaction, &mut rx, &['F'.into()]);
+        assert!(!transaction.show_filters);
+        terminal
+            .draw(|frame| transaction.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        run_keys(&mut transaction, &mut rx, &['F'.into()]);
+        assert!(transaction.show_filters);
+
+        // nothing to hide without a filter
+        let (_, mut transaction) = get_test_objs(None, 50);
+        let status = transaction.handle_events(&'F'.into());
+        assert!(matches!(status, EventHandlingStatus::ShouldPropagate));
+    }
+
+    #[test]
+    fn render_with_filter_no_match() {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let manager = TransactionManager::new(None).unwrap();
+        manager
+            .insert(&fetcher::test_utils::get_mock_data(50))
+            .unwrap();
+        let mut transaction = Transactions::new(
+            Some(FilterOptions::default().merchant("不存在的商家")),
+            tx.into(),
+            manager,
+        );
+        transaction.init();
+        assert!(transaction.table.items().is_empty());
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+        terminal
+            .draw(|frame| transaction.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn presets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(filter_presets::PRESETS_FILE);
+        let sushi = FilterOptions::default().merchant("寿司");
+
+        // save the filter of a filtered page
+        let (mut rx, transaction) = get_test_objs(Some(sushi.clone()), 200);
+        let mut transaction = transaction.presets_path(path.clone());
+        let mut keys = vec!['P'.into()];
+        keys.extend(type_str("Sushi"));
+        keys.push(KeyCode::Enter.into());
+        run_keys(&mut transaction, &mut rx, &keys);
+        assert!(!transaction.preset_name.is_inputting());
+        assert_eq!(
+            transaction.notice.as_deref(),
+            Some("Saved preset \"Sushi\"")
+        );
+        let saved = filter_presets::load_presets(&path).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].filter, sushi);
+
+        // cycle on the unfiltered page
+        let (mut rx, transaction) = get_test_objs(None, 200);
+        let all = transaction.table.items().len();
+        let mut transaction = transaction.presets_path(path.clone());
+        run_keys(&mut transaction, &mut rx, &['P'.into()]);
+        assert!(!transaction.preset_name.is_inputting(), "Nothing to save");
+
+        run_keys(&mut transaction, &mut rx, &['p'.into()]);
+        assert_eq!(transaction.filter_option, Some(sushi.clone()));
+        assert!(
+            transaction
+                .table
+                .items()
+                .iter()
+                .all(|t| t.merchant.contains("寿司"))
+        );
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+        terminal
+            .draw(|frame| transaction.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        run_keys(&mut transaction, &mut rx, &['p'.into()]);
+        assert_eq!(transaction.filter_option, None);
+        assert_eq!(transaction.table.items().len(), all);
+
+        // delete keeps the filter on the list
+        run_keys(&mut transaction, &mut rx, &['p'.into(), 'D'.into()]);
+        assert_eq!(transaction.filter_option, Some(sushi));
+        assert_eq!(transaction.preset, None);
+        assert!(filter_presets::load_presets(&path).unwrap().is_empty());
+
+        run_keys(&mut transaction, &mut rx, &['p'.into()]);
+        assert_eq!(
+            transaction.notice.as_deref(),
+            Some("No saved presets, press P on a filtered list to save one")
+        );
+    }
+
+    #[test]
+    fn notes() {
         let (mut rx, mut transaction) = get_test_objs(None, 50);
         transaction.handle_event_with_status_check(&'j'.into());
-        transaction.handle_event_with_status_check(&' '.into());
-        let mut received_push_page = false;
-        while let Ok(action) = rx.try_recv() {
-            if let Action::Layer(LayerManageAction::Push(PushPageConfig {
-                layer: Layers::Transaction(filter),
-                render_self: false,
-            })) = action
-            {
-                assert!(filter.is_some());
-                Some(assert_eq!(
-                    filter.unwrap(),
-                    FilterOptions::default().merchant(
-                        transaction
-                            .transactions
-                            .get(transaction.table_state.selected().unwrap())
-                            .unwrap()
-                            .merchant
-                            .clone()
-                    )
-                ));
-                received_push_page = true;
-            }
-        }
+        let id = transaction.table.selected_item().unwrap().id;
+
+        let mut keys = vec!['n'.into()];
+        keys.extend(type_str("Dinner with friends"));
+        keys.push(KeyCode::Enter.into());
+        run_keys(&mut transaction, &mut rx, &keys);
+        assert!(!transaction.note_input.is_inputting());
+        assert_eq!(transaction.table.selected(), Some(1), "Selection kept");
+        assert_eq!(
+            transaction.manager.get_note(id).unwrap().as_deref(),
+            Some("Dinner with friends")
+        );
+        let mut terminal = Terminal::new(TestBackend::new(80, 25)).unwrap();
+        terminal
+            .draw(|frame| transaction.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        // kept after reloading
+        run_keys(&mut transaction, &mut rx, &['l'.into()]);
+        assert_eq!(
+            transaction.table.items()[1].note.as_deref(),
+            Some("Dinner with friends")
+        );
+
+        // an empty note removes it
+        transaction.handle_event_with_status_check(&'j'.into());
+        let mut keys = vec!['n'.into()];
+        keys.extend(std::iter::repeat_n(KeyCode::Backspace.into(), 19));
+        keys.push(KeyCode::Enter.into());
+        run_keys(&mut transaction, &mut rx, &keys);
+        assert_eq!(transaction.manager.get_note(id).unwrap(), None);
+        assert_eq!(transaction.table.items()[1].note, None);
+    }
+
+    /// Layers pushed by `actions` without rendering the current page below
+    fn pushed_layers(actions: Vec<Action>) -> Vec<Layers> {
+        actions
+            .into_iter()
+            .filter_map(|action| match action {
+                Action::Layer(LayerManageAction::Push(PushPageConfig {
+                    layer,
+                    render_self: false,
+                })) => Some(layer),
+                _ => None,
+            })
+            .collect()
+    }
 
-        assert!(received_push_page);
+    #[test]
+    fn search_merchant() {
+        let (mut rx, mut transaction) = get_test_objs(None, 200);
+        let mut keys = vec!['/'.into()];
+        keys.extend(type_str("西浴"));
+        keys.push(KeyCode::Enter.into());
+        run_keys(&mut transaction, &mut rx, &keys);
+        assert!(!transaction.search_input.is_inputting());
+        let selected = transaction.table.selected().unwrap();
+        let merchant = &transaction.table.items()[selected].merchant;
+        assert!(fuzzy::score("西浴", merchant).is_some());
+        assert!(
+            transaction.table.items()[..selected]
+                .iter()
+                .all(|t| t.merchant != *merchant),
+            "First row of the merchant"
+        );
+
+        let mut keys = vec!['/'.into()];
+        keys.extend(type_str("不存在"));
+        keys.push(KeyCode::Enter.into());
+        run_keys(&mut transaction, &mut rx, &keys);
+        assert_eq!(
+            transaction.table.selected(),
+            Some(selected),
+            "Selection kept"
+        );
+        assert_eq!(
+            transaction.notice.as_deref(),
+            Some("No merchant matches \"不存在\"")
+        );
+    }
+
+    #[test]
+    fn push_filtered_page() {
+        let (mut rx, mut transaction) = get_test_objs(None, 50);
+        let actions = run_keys(&mut transaction, &mut rx, &['j'.into(), ' '.into()]);
+        let expected = FilterOptions::default()
+            .merchant(transaction.table.selected_item().unwrap().merchant.clone());
+        assert!(matches!(
+            pushed_layers(actions).as_slice(),
+            [Layers::Transaction(Some(filter))] if *filter == expected
+        ));
+    }
+
+    #[test]
+    fn push_analysis_page() {
+        let (mut rx, mut transaction) =
+            get_test_objs(Some(FilterOptions::default().min_abs_amount(1.0)), 50);
+        let actions = run_keys(&mut transaction, &mut rx, &['A'.into()]);
+        let expected = FilterOptions::default()
+            .min_abs_amount(1.0)
+            .merchant(transaction.table.selected_item().unwrap().merchant.clone());
+        assert!(matches!(
+            pushed_layers(actions).as_slice(),
+            [Layers::Analysis(Some(filter))] if *filter == expected
+        ));
     }
 }
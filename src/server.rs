@@ -14,6 +14,7 @@
 //!
 //! - `/`: 默认路由，返回index.html
 //! - `/api/*`: API路由（在api模块中定义）
+//! - `/metrics`: Prometheus 格式的运行指标（在metrics模块中定义）
 //! - `/*`: 其他所有路径，尝试匹配静态文件，否则返回index.html（SPA支持）
 //!
 //! ## 使用示例
@@ -41,6 +42,11 @@ use rust_embed::RustEmbed;
 /// 包含所有REST API的路由定义和请求处理器。
 pub mod api;
 
+/// 监控指标模块
+///
+/// 统计请求数和导出次数，并以 Prometheus 文本格式输出。
+pub mod metrics;
+
 /// 前端嵌入式资源
 ///
 /// 使用 `rust-embed` 将前端构建产物嵌入到二进制文件中，
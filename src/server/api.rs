@@ -31,6 +31,9 @@
 //!
 //! ## API 端点概览
 //!
+//! 返回 JSON 的接口默认输出带缩进的 JSON，加上 `?pretty=false` 输出单行紧凑格式，
+//! 适合数据量较大的请求，见 [`crate::utils::json`]。
+//!
 //! ### 交易数据查询
 //!
 //! | 方法 | 路径 | 功能 | 参数 |
@@ -38,46 +41,109 @@
 //! | GET | `/transactions` | 获取所有交易记录 | - |
 //! | POST | `/transactions/query` | 按条件筛选交易 | FilterOptions |
 //! | GET | `/transactions/count` | 获取交易总数 | - |
-//! | POST | `/transactions/fetch` | 从 XJTU 获取新数据 | start_date |
+//! | GET | `/transactions/{id}` | 获取单条交易记录，不存在时返回 404 | id |
+//! | POST | `/batch` | 按顺序执行多个删除或获取操作，全部成功才写入数据库，见 [`BatchOp`] | 操作数组 |
+//! | POST | `/transactions/fetch` | 从 XJTU 获取新数据，客户端断开连接时停止获取并保存已获取的部分，Cookie 失效时返回 401 | start_date |
+//!
+//! ### 数据分析
+//!
+//! | 方法 | 路径 | 功能 | 参数 |
+//! |------|------|------|------|
+//! | GET | `/analysis/merchant/{merchant}/trend` | 单个商户的消费趋势 | bucket（目前仅支持 `month`） |
+//!
+//! ### 商户搜索
+//!
+//! | 方法 | 路径 | 功能 | 参数 |
+//! |------|------|------|------|
+//! | GET | `/merchants/search` | 按前缀搜索商户名称，交易笔数多的在前，用于自动补全 | q, limit（默认 10，最多 100） |
 //!
 //! ### 配置管理
 //!
 //! | 方法 | 路径 | 功能 | 参数 |
 //! |------|------|------|------|
 //! | GET | `/config/account-cookie` | 获取认证信息 | - |
+//! | GET | `/config/last-fetched` | 上次获取并保存交易的时间，从未获取过时为 `null` | - |
 //! | PUT | `/config/account` | 更新账号 | account |
 //! | PUT | `/config/hallticket` | 更新 Cookie | hallticket |
+//! | PUT | `/config/credentials` | 同时更新账号和 Cookie | account, hallticket |
 //!
 //! ### 数据导出
 //!
 //! | 方法 | 路径 | 功能 | 参数 |
 //! |------|------|------|------|
-//! | GET | `/export/csv` | 导出 CSV 数据 | 筛选参数 |
+//! | GET | `/export/csv` | 导出 CSV 或 Excel 数据 | 筛选参数、format、notes |
+//!
+//! 导出的响应格式按以下顺序确定：
+//! 1. `Accept` 请求头中优先级最高的 `text/csv`、`application/json` 或 [`XLSX_MIME`]
+//! 2. `format` 查询参数（`csv`、`json` 或 `xlsx`）
+//! 3. 默认为 CSV 文件下载
+//!
+//! `Accept: */*` 等通配符不指定格式，此时使用查询参数。JSON 响应将 CSV 内容放在 `content` 字段中，
+//! `xlsx` 为 Excel 工作簿下载，时间和金额列分别为日期时间和数字类型。
+//! `notes=true` 时追加交易备注列。
+//!
+//! ### 监控
+//!
+//! | 方法 | 路径 | 功能 | 参数 |
+//! |------|------|------|------|
+//! | GET | `/metrics` | Prometheus 格式的运行指标，见 [`super::metrics`] | - |
 
 use actix_web::{
+    HttpRequest,
     HttpResponse,
     Responder,
     Result as ActixResult,
-    error::{ErrorInternalServerError, ErrorNotFound},
-    http::header::{ContentDisposition, DispositionParam, DispositionType}, // Added for typed headers
+    body::BoxBody,
+    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
+    http::header::{self, Accept, ContentDisposition, DispositionParam, DispositionType, Header}, // Added for typed headers
     web,
 };
 use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
 // Assuming Transaction and FilterOptions are correctly defined and made public in libs::transactions
 // and derive Serialize and Deserialize.
 // Also, Transaction should be public for tests.
-use crate::libs::{
-    export_csv::{CsvExporter, ExportOptions},
-    fetcher::{RealMealFetcher, fetch},
-    transactions::{FilterOptions, TransactionManager}, // Assuming Transaction is also in here or imported separately for tests
+use crate::{
+    libs::{
+        export_csv::{CsvExporter, ExportOptions, LineEnding},
+        fetcher::{MealFetcher, RealMealFetcher, SessionExpired, fetch_async},
+        transactions::{BatchStep, FilterOptions, TransactionManager}, // Assuming Transaction is also in here or imported separately for tests
+    },
+    page::analysis::{IncomeMode, time_series::bucket_by_month},
+    server::metrics::{self, Metrics},
+    utils::json,
 };
 
+/// JSON 响应，格式由请求的 `pretty` 查询参数决定，见 [`crate::utils::json`]
+struct JsonBody<T>(T);
+
+impl<T: Serialize> Responder for JsonBody<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let pretty =
+            web::Query::<std::collections::HashMap<String, String>>::from_query(req.query_string())
+                .map_or(true, |query| {
+                    json::parse_pretty(query.get("pretty").map(String::as_str))
+                });
+        match json::to_json(&self.0, pretty) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(header::ContentType::json())
+                .body(body),
+            Err(e) => {
+                tracing::error!("Failed to serialize response: {:?}", e);
+                HttpResponse::InternalServerError().finish()
+            }
+        }
+    }
+}
+
 // --- Helper for converting Result to ActixResult ---
 fn to_actix_response<T: Serialize>(result: color_eyre::Result<T>) -> ActixResult<impl Responder> {
     match result {
-        Ok(data) => Ok(web::Json(data)),
+        Ok(data) => Ok(JsonBody(data)),
         Err(e) => {
             // Log the full error for debugging
             tracing::error!("Handler error: {:?}", e);
@@ -127,6 +193,25 @@ async fn handle_fetch_transaction_count(
     to_actix_response(manager.fetch_count())
 }
 
+// GET /transactions/{id}
+async fn handle_fetch_transaction_by_id(
+    manager: web::Data<TransactionManager>,
+    id: web::Path<i64>,
+) -> ActixResult<impl Responder> {
+    let id = id.into_inner();
+    match manager.fetch_by_id(id) {
+        Ok(Some(transaction)) => Ok(JsonBody(transaction)),
+        Ok(None) => Err(ErrorNotFound(format!("Transaction {} not found", id))),
+        Err(e) => {
+            tracing::error!("Handler error: {:?}", e);
+            Err(ErrorInternalServerError(format!(
+                "An internal error occurred: {}",
+                e
+            )))
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 struct FetchTransactionsRequest {
     start_date: DateTime<FixedOffset>, // Ensure chrono's "serde" feature is enabled
@@ -137,24 +222,43 @@ async fn handle_fetch_transactions(
     manager: web::Data<TransactionManager>,
     req: web::Json<FetchTransactionsRequest>,
 ) -> ActixResult<impl Responder> {
-    let (account, cookie) = manager.get_account_cookie().map_err(|e| {
-        tracing::error!("Failed to get account/cookie: {:?}", e);
-        ErrorInternalServerError(format!("Failed to get account/cookie: {}", e))
-    })?;
-    let client = RealMealFetcher::default().account(account).cookie(cookie);
-    let results = tokio::task::spawn_blocking(move || {
-        fetch(
-            req.start_date,
-            crate::libs::fetcher::MealFetcher::Real(client),
-            |_t| Ok(()), // Assuming _t is a transaction type, progress callback
-        )
-    })
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to spawn blocking task: {:?}", e);
-        ErrorInternalServerError(format!("Failed to spawn blocking task: {}", e))
+    let client = real_client(&manager).map_err(|e| {
+        tracing::error!("{:?}", e);
+        ErrorInternalServerError(e.to_string())
     })?;
 
+    // Actix drops this handler when the client disconnects, which cancels the fetch
+    // through the guard. The task itself keeps running to store what it already got.
+    let cancel = CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+    let task = actix_web::rt::spawn(fetch_and_store(
+        manager.clone(),
+        client,
+        req.start_date,
+        cancel,
+    ));
+    task.await.map_err(|e| {
+        tracing::error!("Fetch task failed: {:?}", e);
+        ErrorInternalServerError("Fetch task failed")
+    })??;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Fetch since `start_date` and insert the results, including those gathered before `cancel`
+async fn fetch_and_store(
+    manager: web::Data<TransactionManager>,
+    client: MealFetcher,
+    start_date: DateTime<FixedOffset>,
+    cancel: CancellationToken,
+) -> ActixResult<()> {
+    let results = fetch_async(
+        start_date,
+        client,
+        |_| Ok(()), // no progress reporting over HTTP
+        &cancel,
+    )
+    .await;
+
     tracing::debug!("Fetched transactions: {:?}", results);
     match results {
         Ok(r) => {
@@ -163,14 +267,171 @@ async fn handle_fetch_transactions(
                 tracing::error!("Failed to insert transactions: {:?}", e);
                 ErrorInternalServerError(format!("Failed to insert transactions: {}", e))
             })?;
-            Ok(HttpResponse::Ok().finish())
+            if cancel.is_cancelled() {
+                // the fetch did not reach `start_date`, so this is no full sync
+                tracing::info!(
+                    "Client disconnected, stored {} fetched transactions",
+                    r.len()
+                );
+            } else if let Err(e) = manager.set_last_fetched_at(chrono::Local::now().fixed_offset())
+            {
+                tracing::warn!("{:?}", e);
+            }
+            Ok(())
         }
-        Err(e) => {
-            tracing::error!("Failed to fetch transactions: {:?}", e);
-            Err(ErrorInternalServerError(format!(
-                "Failed to fetch transactions: {}",
-                e
-            )))
+        Err(e) => Err(fetch_error(&e)),
+    }
+}
+
+/// Client for the XJTU server with the stored account and cookie
+fn real_client(manager: &TransactionManager) -> color_eyre::Result<MealFetcher> {
+    let (account, cookie) = manager
+        .get_account_cookie()
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to get account/cookie: {}", e))?;
+    Ok(MealFetcher::Real(
+        RealMealFetcher::default()
+            .origin(crate::config::get_api_origin())
+            .account(account)
+            .cookie(cookie),
+    ))
+}
+
+/// Response for a failed fetch, telling an expired cookie apart from other errors
+fn fetch_error(e: &color_eyre::Report) -> actix_web::Error {
+    if e.chain().any(|e| e.is::<SessionExpired>()) {
+        // nothing prompts for a new cookie here, so fail fast instead of retrying
+        tracing::warn!("Cookie expired while fetching: {:?}", e);
+        ErrorUnauthorized(format!(
+            "Cookie expired, update it with PUT /config/hallticket: {}",
+            e
+        ))
+    } else {
+        tracing::error!("Failed to fetch transactions: {:?}", e);
+        ErrorInternalServerError(format!("Failed to fetch transactions: {}", e))
+    }
+}
+
+/// Most operations accepted by one batch request
+const MAX_BATCH_OPS: usize = 100;
+
+/// 批量请求中的一个操作
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum BatchOp {
+    /// 删除单条交易记录
+    Delete { id: i64 },
+    /// 从 XJTU 获取 `start_date` 之后的交易并写入
+    Fetch { start_date: DateTime<FixedOffset> },
+}
+
+/// 批量请求中单个操作的结果
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum BatchOpResult {
+    /// 已执行，`affected` 为删除或新写入的记录数
+    Ok { affected: usize },
+    /// 执行失败，整个批量请求都已回滚
+    Failed { error: String },
+    /// 因其他操作失败而没有生效
+    NotApplied,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct BatchResponse {
+    /// 所有操作是否都已生效，为 `false` 时数据库没有任何变化
+    committed: bool,
+    /// 与请求中的操作一一对应
+    results: Vec<BatchOpResult>,
+}
+
+impl BatchResponse {
+    /// Response for a batch of `len` operations stopped by `error` at `op`
+    fn failed(len: usize, op: usize, error: String) -> Self {
+        let results = (0..len)
+            .map(|i| {
+                if i == op {
+                    BatchOpResult::Failed {
+                        error: error.clone(),
+                    }
+                } else {
+                    BatchOpResult::NotApplied
+                }
+            })
+            .collect();
+        Self {
+            committed: false,
+            results,
+        }
+    }
+}
+
+// POST /batch
+async fn handle_batch(
+    manager: web::Data<TransactionManager>,
+    ops: web::Json<Vec<BatchOp>>,
+) -> ActixResult<impl Responder> {
+    if ops.is_empty() || ops.len() > MAX_BATCH_OPS {
+        return Err(ErrorBadRequest(format!(
+            "Expected 1 to {} operations, got {}",
+            MAX_BATCH_OPS,
+            ops.len()
+        )));
+    }
+    let client = real_client(&manager);
+    // stops the fetches once Actix drops this handler on client disconnect
+    let cancel = CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+    Ok(JsonBody(run_batch(&manager, &ops, &client, &cancel).await))
+}
+
+/// Run `ops` in order, applying all their database changes in one transaction
+///
+/// Fetches go to the network first, so the database is only locked once everything is
+/// fetched and a failed fetch leaves it untouched. `client` is only used by fetch ops,
+/// which stop early once `cancel` is cancelled.
+async fn run_batch(
+    manager: &TransactionManager,
+    ops: &[BatchOp],
+    client: &color_eyre::Result<MealFetcher>,
+    cancel: &CancellationToken,
+) -> BatchResponse {
+    let mut steps = Vec::with_capacity(ops.len());
+    for (i, op) in ops.iter().enumerate() {
+        steps.push(match op {
+            BatchOp::Delete { id } => BatchStep::Delete(*id),
+            BatchOp::Fetch { start_date } => {
+                let client = match client {
+                    Ok(client) => client.clone(),
+                    Err(e) => return BatchResponse::failed(ops.len(), i, e.to_string()),
+                };
+                match fetch_async(*start_date, client, |_| Ok(()), cancel).await {
+                    Ok(transactions) => BatchStep::Insert(transactions),
+                    Err(e) => {
+                        return BatchResponse::failed(ops.len(), i, fetch_error(&e).to_string());
+                    }
+                }
+            }
+        });
+    }
+
+    match manager.apply_batch(&steps) {
+        Ok(affected) => {
+            if ops.iter().any(|op| matches!(op, BatchOp::Fetch { .. }))
+                && let Err(e) = manager.set_last_fetched_at(chrono::Local::now().fixed_offset())
+            {
+                tracing::warn!("{:?}", e);
+            }
+            BatchResponse {
+                committed: true,
+                results: affected
+                    .into_iter()
+                    .map(|affected| BatchOpResult::Ok { affected })
+                    .collect(),
+            }
+        }
+        Err((i, e)) => {
+            tracing::error!("Batch operation {} failed: {:?}", i, e);
+            BatchResponse::failed(ops.len(), i, e.to_string())
         }
     }
 }
@@ -207,6 +468,21 @@ async fn handle_update_hallticket(
     to_actix_empty_response(manager.update_hallticket(&req.hallticket))
 }
 
+#[derive(Deserialize, Serialize)] // Added Serialize for test usage
+struct CredentialsUpdateRequest {
+    account: String,
+    hallticket: String,
+}
+
+// PUT /config/credentials
+async fn handle_update_credentials(
+    manager: web::Data<TransactionManager>,
+    req: web::Json<CredentialsUpdateRequest>,
+) -> ActixResult<impl Responder> {
+    let cookie = format!("hallticket={}", req.hallticket);
+    to_actix_empty_response(manager.set_credentials(&req.account, &cookie))
+}
+
 #[derive(Serialize, Deserialize)] // Added Deserialize for test usage
 struct AccountCookieResponse {
     account: String,
@@ -218,7 +494,7 @@ async fn handle_get_account_cookie(
     manager: web::Data<TransactionManager>,
 ) -> ActixResult<impl Responder> {
     match manager.get_account_cookie() {
-        Ok((account, cookie)) => Ok(web::Json(AccountCookieResponse { account, cookie })),
+        Ok((account, cookie)) => Ok(JsonBody(AccountCookieResponse { account, cookie })),
         Err(e) => {
             tracing::error!("Failed to get account/cookie: {:?}", e);
             let err_str = e.to_string();
@@ -236,6 +512,97 @@ async fn handle_get_account_cookie(
     }
 }
 
+#[derive(Serialize, Deserialize)] // Added Deserialize for test usage
+struct LastFetchedResponse {
+    last_fetched_at: Option<DateTime<FixedOffset>>,
+}
+
+// GET /config/last-fetched
+async fn handle_get_last_fetched(
+    manager: web::Data<TransactionManager>,
+) -> ActixResult<impl Responder> {
+    let last_fetched_at = manager.get_last_fetched_at().map_err(|e| {
+        tracing::error!("{:?}", e);
+        ErrorInternalServerError(format!("{:#}", e))
+    })?;
+    Ok(JsonBody(LastFetchedResponse { last_fetched_at }))
+}
+
+/// 商户消费趋势请求参数
+#[derive(Debug, Deserialize)]
+struct MerchantTrendQuery {
+    /// 汇总粒度，目前仅支持 `month`
+    #[serde(default = "default_bucket")]
+    bucket: String,
+}
+
+fn default_bucket() -> String {
+    "month".to_string()
+}
+
+/// 商户消费趋势中的一个时间段
+#[derive(Debug, Serialize, Deserialize)]
+struct MerchantTrendPoint {
+    /// 时间段，如 `2024-03`
+    period: String,
+    /// 消费金额（正数）
+    total: f64,
+    count: usize,
+}
+
+// GET /analysis/merchant/{merchant}/trend
+async fn handle_merchant_trend(
+    manager: web::Data<TransactionManager>,
+    merchant: web::Path<String>,
+    query: web::Query<MerchantTrendQuery>,
+) -> ActixResult<impl Responder> {
+    if query.bucket != "month" {
+        return Err(ErrorBadRequest(format!(
+            "Unsupported bucket: {}, expected month",
+            query.bucket
+        )));
+    }
+
+    let filter = FilterOptions::default().merchant(merchant.into_inner());
+    to_actix_response(manager.fetch_filtered(&filter).map(|transactions| {
+        bucket_by_month(&transactions, IncomeMode::SpendOnly)
+            .into_iter()
+            .map(|bucket| MerchantTrendPoint {
+                period: bucket.period.to_string(),
+                total: bucket.total,
+                count: bucket.count,
+            })
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// 商户搜索默认返回的条数
+const DEFAULT_MERCHANT_SEARCH_LIMIT: usize = 10;
+/// 商户搜索最多返回的条数
+const MAX_MERCHANT_SEARCH_LIMIT: usize = 100;
+
+/// 商户搜索请求参数
+#[derive(Debug, Deserialize)]
+struct MerchantSearchQuery {
+    /// 商户名称前缀，为空时匹配所有商户
+    #[serde(default)]
+    q: String,
+    /// 最多返回的条数
+    limit: Option<usize>,
+}
+
+// GET /merchants/search
+async fn handle_search_merchants(
+    manager: web::Data<TransactionManager>,
+    query: web::Query<MerchantSearchQuery>,
+) -> ActixResult<impl Responder> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_MERCHANT_SEARCH_LIMIT)
+        .min(MAX_MERCHANT_SEARCH_LIMIT);
+    to_actix_response(manager.search_merchants(query.q.trim(), limit))
+}
+
 /// CSV 导出请求参数
 #[derive(Debug, Deserialize)]
 struct CsvExportQuery {
@@ -247,15 +614,55 @@ struct CsvExportQuery {
     max_amount: Option<f64>,
     /// 开始日期筛选 YYYY-MM-DD
     time_start: Option<String>,
-    /// 结束日期筛选 YYYY-MM-DD
+    /// 结束日期筛选 YYYY-MM-DD，包含当天
     time_end: Option<String>,
+    /// 追加备注列
+    #[serde(default)]
+    notes: bool,
+
+    /// 响应格式，`Accept` 请求头未指定时使用
+    format: Option<String>,
+}
+
+/// Excel 工作簿的 MIME 类型
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
 
-    #[serde(default = "default_format")]
-    format: String,
+/// 导出接口的响应格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Csv,
+    Json,
+    Xlsx,
 }
 
-fn default_format() -> String {
-    "csv".to_string()
+/// 根据 `Accept` 请求头和 `format` 查询参数确定响应格式
+///
+/// # 参数
+///
+/// * `req` - HTTP 请求
+/// * `format` - `format` 查询参数
+///
+/// # 返回值
+///
+/// `Accept` 中优先级最高的已知格式；没有时使用查询参数，再默认为 CSV
+fn negotiate_format(req: &HttpRequest, format: Option<&str>) -> ExportFormat {
+    if let Ok(accept) = Accept::parse(req) {
+        for mime in accept.ranked() {
+            match mime.essence_str() {
+                "text/csv" => return ExportFormat::Csv,
+                "application/json" => return ExportFormat::Json,
+                XLSX_MIME => return ExportFormat::Xlsx,
+                // a wildcard accepts anything, let the query parameter decide
+                "*/*" | "text/*" | "application/*" => break,
+                _ => {}
+            }
+        }
+    }
+    match format {
+        Some("json") => ExportFormat::Json,
+        Some("xlsx") => ExportFormat::Xlsx,
+        _ => ExportFormat::Csv,
+    }
 }
 
 /// CSV 导出响应（JSON 格式）
@@ -269,10 +676,13 @@ struct CsvExportResponse {
 
 // GET /export/csv
 async fn handle_export_csv(
+    req: HttpRequest,
     manager: web::Data<TransactionManager>,
+    metrics: web::Data<Metrics>,
     query: web::Query<CsvExportQuery>,
 ) -> ActixResult<HttpResponse> {
     let params = query.into_inner();
+    let format = negotiate_format(&req, params.format.as_deref());
 
     // 构建导出选项
     let options = ExportOptions {
@@ -282,47 +692,82 @@ async fn handle_export_csv(
         max_amount: params.max_amount,
         time_start: params.time_start,
         time_end: params.time_end,
+        report: None,
+        limit: None,
+        summary_row: false,
+        line_ending: LineEnding::Lf,
+        timestamp: false,
+        split_by: None,
+        notes: params.notes,
     };
 
+    if format == ExportFormat::Xlsx {
+        return Ok(match CsvExporter::export_to_xlsx(&manager, &options) {
+            Ok((xlsx_content, count)) => {
+                metrics.record_export(count);
+                HttpResponse::Ok()
+                    .content_type(XLSX_MIME)
+                    .insert_header((header::VARY, "Accept"))
+                    .insert_header(export_disposition(&options, "xlsx"))
+                    .body(xlsx_content)
+            }
+            Err(e) => export_error(&req, e),
+        });
+    }
+
     // 执行导出
     match CsvExporter::export_to_string(&manager, &options) {
         Ok((csv_content, count)) => {
-            if params.format == "json" {
-                Ok(HttpResponse::Ok().json(CsvExportResponse {
+            metrics.record_export(count);
+            if format == ExportFormat::Json {
+                Ok(JsonBody(CsvExportResponse {
                     success: true,
                     count,
                     content: Some(csv_content),
                     error: None,
-                }))
+                })
+                .customize()
+                .insert_header((header::VARY, "Accept"))
+                .respond_to(&req)
+                .map_into_boxed_body())
             } else {
-                let filename_str = generate_csv_filename(&options);
-                let disposition = ContentDisposition {
-                    disposition: DispositionType::Attachment,
-                    parameters: vec![DispositionParam::Filename(filename_str)],
-                };
-
                 Ok(HttpResponse::Ok()
                     .content_type("text/csv; charset=utf-8")
-                    .insert_header(disposition) // Use typed header
+                    .insert_header((header::VARY, "Accept"))
+                    .insert_header(export_disposition(&options, "csv")) // Use typed header
                     .body(csv_content))
             }
         }
-        Err(e) => {
-            tracing::error!("CSV export failed: {:?}", e);
-            // It's often better to return an HTTP error status for API errors,
-            // but returning 200 with error in JSON is also a valid choice.
-            Ok(HttpResponse::Ok().json(CsvExportResponse {
-                success: false,
-                count: 0,
-                content: None,
-                error: Some(format!("Export failed: {}", e)),
-            }))
-        }
+        Err(e) => Ok(export_error(&req, e)),
+    }
+}
+
+/// Error response of a failed export, the same for every format
+fn export_error(req: &HttpRequest, e: color_eyre::Report) -> HttpResponse {
+    tracing::error!("CSV export failed: {:?}", e);
+    // It's often better to return an HTTP error status for API errors,
+    // but returning 200 with error in JSON is also a valid choice.
+    JsonBody(CsvExportResponse {
+        success: false,
+        count: 0,
+        content: None,
+        error: Some(format!("Export failed: {}", e)),
+    })
+    .respond_to(req)
+}
+
+/// Download the export as a file named after its filters
+fn export_disposition(options: &ExportOptions, extension: &str) -> ContentDisposition {
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(generate_export_filename(
+            options, extension,
+        ))],
     }
 }
 
-/// 根据筛选条件生成文件名
-fn generate_csv_filename(options: &ExportOptions) -> String {
+/// 根据筛选条件生成文件名，`extension` 为不含点的扩展名
+fn generate_export_filename(options: &ExportOptions, extension: &str) -> String {
     let mut parts = vec!["transactions".to_string()];
 
     if let Some(merchant) = &options.merchant {
@@ -343,7 +788,7 @@ fn generate_csv_filename(options: &ExportOptions) -> String {
         parts.push(format!("to_{}", end.replace("-", "")));
     }
 
-    format!("{}.csv", parts.join("_"))
+    format!("{}.{}", parts.join("_"), extension)
 }
 
 // --- Actix App Configuration ---
@@ -354,17 +799,27 @@ pub fn config_routes(cfg: &mut web::ServiceConfig) {
                 .route("", web::get().to(handle_fetch_all_transactions))
                 .route("/query", web::post().to(handle_fetch_filtered_transactions))
                 .route("/count", web::get().to(handle_fetch_transaction_count))
-                .route("/fetch", web::post().to(handle_fetch_transactions)),
+                .route("/fetch", web::post().to(handle_fetch_transactions))
+                .route("/{id}", web::get().to(handle_fetch_transaction_by_id)),
         )
+        .route("/batch", web::post().to(handle_batch))
         .service(
             web::scope("/config")
                 .route("/account", web::put().to(handle_update_account))
                 .route("/hallticket", web::put().to(handle_update_hallticket))
-                .route("/account-cookie", web::get().to(handle_get_account_cookie)),
+                .route("/credentials", web::put().to(handle_update_credentials))
+                .route("/account-cookie", web::get().to(handle_get_account_cookie))
+                .route("/last-fetched", web::get().to(handle_get_last_fetched)),
         )
+        .service(web::scope("/analysis").route(
+            "/merchant/{merchant}/trend",
+            web::get().to(handle_merchant_trend),
+        ))
+        .service(web::scope("/merchants").route("/search", web::get().to(handle_search_merchants)))
         // for csv export:
         .service(web::scope("/export").route("/csv", web::get().to(handle_export_csv)));
-    cfg.service(scope);
+    cfg.service(scope)
+        .route("/metrics", web::get().to(metrics::handle_metrics));
 }
 
 #[cfg(test)]
@@ -382,7 +837,7 @@ mod tests {
     // Helper to initialize TransactionManager for tests (in-memory DB)
     async fn setup_test_app() -> impl actix_web::dev::Service<
         actix_http::Request,
-        Response = actix_web::dev::ServiceResponse,
+        Response = actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
         Error = actix_web::Error,
     > {
         let manager =
@@ -395,6 +850,8 @@ mod tests {
         test::init_service(
             App::new()
                 .app_data(Data::new(manager)) // Use app_data for shared state
+                .app_data(Data::new(Metrics::default()))
+                .wrap(actix_web::middleware::from_fn(metrics::track_requests))
                 .configure(config_routes),
         )
         .await
@@ -446,6 +903,152 @@ mod tests {
         assert_eq!(count, 46); // This count depends on mock data
     }
 
+    #[actix_web::test]
+    async fn test_fetch_transaction_by_id() {
+        let app = setup_test_app().await;
+        let expected = fetcher::test_utils::get_mock_data(50)
+            .into_iter()
+            .next()
+            .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/transactions/{}", expected.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let result: Transaction = test::read_body_json(resp).await;
+        assert_eq!(result, expected);
+
+        let req = test::TestRequest::get()
+            .uri("/api/transactions/-1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // existing static routes still take precedence
+        let req = test::TestRequest::get()
+            .uri("/api/transactions/count")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_metrics() {
+        let app = setup_test_app().await;
+        for uri in [
+            "/api/transactions/count",
+            "/api/transactions/1",
+            "/not-an-api",
+        ] {
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        }
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv?format=json")
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        for line in [
+            "mealflow_transactions 46",
+            r#"mealflow_http_requests_total{method="GET",endpoint="/api/transactions/count"} 1"#,
+            r#"mealflow_http_requests_total{method="GET",endpoint="/api/transactions/{id}"} 1"#,
+            r#"mealflow_http_requests_total{method="GET",endpoint="other"} 1"#,
+            "mealflow_csv_exports_total 1",
+            "mealflow_csv_exported_rows_total 46",
+        ] {
+            assert!(
+                body.lines().any(|l| l == line),
+                "missing {}\n{}",
+                line,
+                body
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_merchant_trend() {
+        let app = setup_test_app().await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/analysis/merchant/%E8%A5%BF14%E8%A5%BF15%E4%B8%9C12%E6%B5%B4%E5%AE%A4/trend?bucket=month")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let result: Vec<MerchantTrendPoint> = test::read_body_json(resp).await;
+        assert!(!result.is_empty());
+        assert_eq!(result.iter().map(|p| p.count).sum::<usize>(), 8);
+        assert!(result.windows(2).all(|w| w[0].period < w[1].period));
+
+        let req = test::TestRequest::get()
+            .uri("/api/analysis/merchant/unknown/trend")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let result: Vec<MerchantTrendPoint> = test::read_body_json(resp).await;
+        assert!(result.is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/api/analysis/merchant/unknown/trend?bucket=week")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_search_merchants() {
+        let app = setup_test_app().await;
+        let search = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+        let resp = test::call_service(&app, search("/api/merchants/search?q=%E8%A5%BF14")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let result: Vec<String> = test::read_body_json(resp).await;
+        assert_eq!(result, vec!["西14西15东12浴室"]);
+
+        let resp = test::call_service(&app, search("/api/merchants/search?limit=3")).await;
+        let result: Vec<String> = test::read_body_json(resp).await;
+        assert_eq!(result.len(), 3);
+
+        let resp = test::call_service(&app, search("/api/merchants/search?q=unknown")).await;
+        let result: Vec<String> = test::read_body_json(resp).await;
+        assert!(result.is_empty());
+
+        let resp = test::call_service(&app, search("/api/merchants/search?limit=abc")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_pretty_json() {
+        let app = setup_test_app().await;
+        let body = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let resp = test::call_service(app, req).await;
+                assert_eq!(resp.status(), StatusCode::OK);
+                assert_eq!(
+                    resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                    "application/json"
+                );
+                String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+            }
+        };
+
+        let pretty = body("/api/merchants/search?limit=2").await;
+        let compact = body("/api/merchants/search?limit=2&pretty=false").await;
+        assert!(pretty.contains("\n  \""));
+        assert!(!compact.contains('\n'));
+        let pretty: Vec<String> = serde_json::from_str(&pretty).unwrap();
+        let compact: Vec<String> = serde_json::from_str(&compact).unwrap();
+        assert_eq!(pretty, compact);
+
+        let compact = body("/api/export/csv?format=json&pretty=0").await;
+        assert!(compact.starts_with("{\"success\":true,"));
+    }
+
     #[actix_web::test]
     async fn test_config_routes() {
         let app = setup_test_app().await;
@@ -483,6 +1086,170 @@ mod tests {
         assert_eq!(ac_response2.cookie, "hallticket=test_hallticket_val"); // Depends on TransactionManager logic
     }
 
+    #[actix_web::test]
+    async fn test_update_credentials() {
+        let app = setup_test_app().await;
+
+        let req = test::TestRequest::put()
+            .uri("/api/config/credentials")
+            .set_json(&CredentialsUpdateRequest {
+                account: "2021123456".to_string(),
+                hallticket: "new_hallticket".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/config/account-cookie")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let response: AccountCookieResponse = test::read_body_json(resp).await;
+        assert_eq!(response.account, "2021123456");
+        assert_eq!(response.cookie, "hallticket=new_hallticket");
+
+        // both fields are required
+        let req = test::TestRequest::put()
+            .uri("/api/config/credentials")
+            .set_json(serde_json::json!({ "account": "2021123456" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_last_fetched() {
+        let manager =
+            TransactionManager::new(None).expect("Failed to create test TransactionManager");
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(manager.clone()))
+                .configure(config_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/config/last-fetched")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body, serde_json::json!({ "last_fetched_at": null }));
+
+        let time = DateTime::parse_from_rfc3339("2025-03-01T12:00:00+08:00").unwrap();
+        manager.set_last_fetched_at(time).unwrap();
+        let req = test::TestRequest::get()
+            .uri("/api/config/last-fetched")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let body: LastFetchedResponse = test::read_body_json(resp).await;
+        assert_eq!(body.last_fetched_at, Some(time));
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_cookie_expired() {
+        use crate::libs::fetcher::{ScriptedMealFetcher, test_utils::get_mock_data};
+
+        let manager =
+            TransactionManager::new(None).expect("Failed to create test TransactionManager");
+        let client = ScriptedMealFetcher::new(vec![
+            Ok(ScriptedMealFetcher::page_of(&get_mock_data(3))),
+            Ok("<html>Login</html>".to_string()),
+        ]);
+        let start = DateTime::parse_from_rfc3339("2000-01-01T00:00:00+08:00").unwrap();
+
+        let err = fetch_and_store(
+            Data::new(manager.clone()),
+            client.into(),
+            start,
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert!(err.to_string().starts_with("Cookie expired"));
+        assert_eq!(manager.fetch_count().unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_batch() {
+        use crate::libs::fetcher::{ScriptedMealFetcher, test_utils::get_mock_data};
+
+        let manager =
+            TransactionManager::new(None).expect("Failed to create test TransactionManager");
+        let data = get_mock_data(3);
+        manager.insert(&data).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(manager.clone()))
+                .configure(config_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/batch")
+            .set_json(vec![
+                BatchOp::Delete { id: data[0].id },
+                BatchOp::Delete { id: data[0].id },
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: BatchResponse = test::read_body_json(resp).await;
+        assert!(body.committed);
+        assert_eq!(
+            body.results,
+            vec![
+                BatchOpResult::Ok { affected: 1 },
+                BatchOpResult::Ok { affected: 0 }
+            ]
+        );
+        assert_eq!(manager.fetch_count().unwrap(), 2);
+
+        let req = test::TestRequest::post()
+            .uri("/api/batch")
+            .set_json(Vec::<BatchOp>::new())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // delete and fetch again, the fetched copy of the deleted one is stored anew
+        let start_date = DateTime::parse_from_rfc3339("2000-01-01T00:00:00+08:00").unwrap();
+        let ops = [
+            BatchOp::Delete { id: data[1].id },
+            BatchOp::Fetch { start_date },
+        ];
+        let client =
+            Ok(ScriptedMealFetcher::new(vec![Ok(ScriptedMealFetcher::page_of(&data))]).into());
+        let body = run_batch(&manager, &ops, &client, &CancellationToken::new()).await;
+        assert!(body.committed);
+        assert_eq!(
+            body.results,
+            vec![
+                BatchOpResult::Ok { affected: 1 },
+                BatchOpResult::Ok { affected: 2 }
+            ]
+        );
+        assert_eq!(manager.fetch_count().unwrap(), 3);
+        assert!(manager.get_last_fetched_at().unwrap().is_some());
+
+        // an expired cookie leaves the database untouched
+        let client =
+            Ok(ScriptedMealFetcher::new(vec![Ok("<html>Login</html>".to_string())]).into());
+        let body = run_batch(&manager, &ops, &client, &CancellationToken::new()).await;
+        assert!(!body.committed);
+        assert_eq!(body.results[0], BatchOpResult::NotApplied);
+        let BatchOpResult::Failed { error } = &body.results[1] else {
+            panic!("Fetch should fail");
+        };
+        assert!(error.starts_with("Cookie expired"), "{}", error);
+        assert_eq!(manager.fetch_count().unwrap(), 3);
+    }
+
     #[actix_web::test]
     async fn test_get_account_cookie_not_found() {
         // Setup a new app with a fresh TransactionManager to ensure no pre-existing cookie data
@@ -536,7 +1303,7 @@ mod tests {
         let disposition = resp.headers().get("content-disposition").unwrap();
         let disposition_str = disposition.to_str().unwrap();
         assert!(disposition_str.contains("attachment"));
-        // Expected filename based on generate_csv_filename and params: transactions_amount_10.00_50.00.csv
+        // Expected filename based on generate_export_filename and params: transactions_amount_10.00_50.00.csv
         assert!(disposition_str.contains("filename=\"transactions_amount_10.00_50.00.csv\""));
 
         let body = test::read_body(resp).await;
@@ -544,6 +1311,35 @@ mod tests {
         assert!(csv_content.contains("ID,Time,Amount,Merchant")); // Basic check for CSV header
     }
 
+    #[actix_web::test]
+    async fn test_xlsx_export() {
+        let app = setup_test_app().await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv?format=xlsx&time_start=2022-01-01")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("content-type").unwrap(), XLSX_MIME);
+        let disposition = resp.headers().get("content-disposition").unwrap();
+        assert!(
+            disposition
+                .to_str()
+                .unwrap()
+                .contains("filename=\"transactions_from_20220101.xlsx\"")
+        );
+        let body = test::read_body(resp).await;
+        assert!(body.starts_with(b"PK\x03\x04"));
+
+        // also chosen by the Accept header
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv")
+            .insert_header(("Accept", XLSX_MIME))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("content-type").unwrap(), XLSX_MIME);
+    }
+
     #[actix_web::test]
     async fn test_csv_export_with_date_filter() {
         let app = setup_test_app().await;
@@ -562,4 +1358,51 @@ mod tests {
         // For the default mock data, this should yield all 46 items.
         assert_eq!(result.count, 46, "Expected count for wide date range");
     }
+
+    #[actix_web::test]
+    async fn test_csv_export_accept_header() {
+        let app = setup_test_app().await;
+        let content_type = |resp: &actix_web::dev::ServiceResponse<_>| {
+            resp.headers()
+                .get("content-type")
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // the header wins over the query parameter
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv?format=csv")
+            .insert_header(("Accept", "application/json"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(content_type(&resp).contains("application/json"));
+        assert_eq!(resp.headers().get("vary").unwrap(), "Accept");
+        let result: CsvExportResponse = test::read_body_json(resp).await;
+        assert_eq!(result.count, 46);
+
+        // highest quality first
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv?format=json")
+            .insert_header(("Accept", "application/json;q=0.5, text/csv"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(content_type(&resp).contains("text/csv"));
+
+        // wildcards fall back to the query parameter, then CSV
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv?format=json")
+            .insert_header(("Accept", "text/html, */*;q=0.8"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(content_type(&resp).contains("application/json"));
+
+        let req = test::TestRequest::get()
+            .uri("/api/export/csv")
+            .insert_header(("Accept", "*/*"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(content_type(&resp).contains("text/csv"));
+    }
 }
@@ -0,0 +1,202 @@
+//! # 监控指标模块
+//!
+//! 以 Prometheus 文本格式在 `GET /metrics` 暴露 Web 服务的运行指标，
+//! 便于将 `web` 模式作为长期服务运行时接入监控。
+//!
+//! ## 指标
+//!
+//! | 名称 | 类型 | 含义 |
+//! |------|------|------|
+//! | `mealflow_transactions` | gauge | 数据库中的交易记录条数 |
+//! | `mealflow_http_requests_total` | counter | 按方法和路由统计的请求数 |
+//! | `mealflow_csv_exports_total` | counter | CSV 导出次数 |
+//! | `mealflow_csv_exported_rows_total` | counter | CSV 导出的记录总数 |
+//!
+//! 请求按路由模板（如 `/api/transactions/{id}`）而不是实际路径统计，
+//! 未匹配任何 API 路由的请求（前端静态资源等）统一记为 `other`，避免标签数量无限增长。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! let metrics = web::Data::new(Metrics::default());
+//! App::new()
+//!     .app_data(metrics.clone())
+//!     .wrap(actix_web::middleware::from_fn(metrics::track_requests))
+//!     .configure(api::config_routes)
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use actix_web::{
+    Error, HttpResponse,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    middleware::Next,
+    web,
+};
+
+use crate::libs::transactions::TransactionManager;
+
+/// Endpoint label of requests that did not match an API route
+const OTHER_ENDPOINT: &str = "other";
+
+/// 进程内共享的指标注册表
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Request count by (method, route pattern)
+    requests: Mutex<BTreeMap<(String, String), u64>>,
+    csv_exports: AtomicU64,
+    csv_exported_rows: AtomicU64,
+}
+
+impl Metrics {
+    /// 记录一次请求
+    pub fn record_request(&self, method: &str, endpoint: &str) {
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), endpoint.to_string()))
+            .or_default() += 1;
+    }
+
+    /// 记录一次 CSV 导出及其导出的记录数
+    pub fn record_export(&self, rows: usize) {
+        self.csv_exports.fetch_add(1, Ordering::Relaxed);
+        self.csv_exported_rows
+            .fetch_add(rows as u64, Ordering::Relaxed);
+    }
+
+    /// 以 Prometheus 文本格式输出所有指标
+    ///
+    /// # 参数
+    ///
+    /// * `transactions` - 当前数据库中的交易记录条数
+    pub fn render(&self, transactions: u64) -> String {
+        let mut out = String::new();
+        write_header(
+            &mut out,
+            "mealflow_transactions",
+            "gauge",
+            "Number of transactions in the local database",
+        );
+        writeln!(out, "mealflow_transactions {}", transactions).unwrap();
+
+        write_header(
+            &mut out,
+            "mealflow_http_requests_total",
+            "counter",
+            "Number of HTTP requests handled, by method and route",
+        );
+        for ((method, endpoint), count) in self.requests.lock().unwrap().iter() {
+            writeln!(
+                out,
+                "mealflow_http_requests_total{{method=\"{}\",endpoint=\"{}\"}} {}",
+                escape_label(method),
+                escape_label(endpoint),
+                count
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut out,
+            "mealflow_csv_exports_total",
+            "counter",
+            "Number of CSV exports",
+        );
+        writeln!(
+            out,
+            "mealflow_csv_exports_total {}",
+            self.csv_exports.load(Ordering::Relaxed)
+        )
+        .unwrap();
+
+        write_header(
+            &mut out,
+            "mealflow_csv_exported_rows_total",
+            "counter",
+            "Number of transactions exported to CSV",
+        );
+        writeln!(
+            out,
+            "mealflow_csv_exported_rows_total {}",
+            self.csv_exported_rows.load(Ordering::Relaxed)
+        )
+        .unwrap();
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// 统计请求数的中间件，配合 `actix_web::middleware::from_fn` 使用
+///
+/// 应用中未注册 [`Metrics`] 时不做任何统计
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let res = next.call(req).await?;
+    // the route is only matched once the request has been handled
+    let request = res.request();
+    if let Some(metrics) = request.app_data::<web::Data<Metrics>>() {
+        let endpoint = request.match_pattern();
+        metrics.record_request(
+            request.method().as_str(),
+            endpoint.as_deref().unwrap_or(OTHER_ENDPOINT),
+        );
+    }
+    Ok(res)
+}
+
+// GET /metrics
+pub async fn handle_metrics(
+    manager: web::Data<TransactionManager>,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse, Error> {
+    let transactions = manager.fetch_count().map_err(|e| {
+        tracing::error!("Failed to count transactions: {:?}", e);
+        ErrorInternalServerError(format!("Failed to count transactions: {}", e))
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics.render(transactions)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let metrics = Metrics::default();
+        metrics.record_request("GET", "/api/transactions");
+        metrics.record_request("GET", "/api/transactions");
+        metrics.record_request("GET", "/api/transactions/{id}");
+        metrics.record_request("POST", "say \"hi\"");
+        metrics.record_export(12);
+        metrics.record_export(3);
+
+        insta::assert_snapshot!(metrics.render(50));
+    }
+}
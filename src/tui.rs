@@ -29,7 +29,7 @@
 //!
 //! - **键盘事件**: 按键按下、释放
 //! - **鼠标事件**: 点击、移动、滚轮
-//! - **系统事件**: 窗口大小变化、焦点变化
+//! - **系统事件**: 窗口大小变化、焦点变化、SIGINT 中断
 //! - **应用事件**: 渲染、退出等内部事件
 //!
 //! ## 使用示例
@@ -59,6 +59,7 @@
 use std::{
     io::{Stderr, stderr},
     ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
     time::Duration,
 };
 
@@ -83,6 +84,16 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+/// Whether the last [`Tui::enter`] used the alternate screen, see [`entered_alt_screen`]
+static ENTERED_ALT_SCREEN: AtomicBool = AtomicBool::new(true);
+
+/// 最近一次进入 TUI 模式时是否使用了备用屏幕缓冲区
+///
+/// panic 和错误处理中恢复终端时据此以相同的方式退出，见 [`Tui::alt_screen`]
+pub fn entered_alt_screen() -> bool {
+    ENTERED_ALT_SCREEN.load(Ordering::Relaxed)
+}
+
 /// TUI事件枚举
 ///
 /// 定义了应用程序中所有可能的事件类型，包括用户输入事件和系统事件。
@@ -99,6 +110,11 @@ pub enum Event {
     /// 当系统发生错误时发送，通常导致应用程序退出
     Error,
 
+    /// 中断事件
+    ///
+    /// 进程收到 SIGINT（如 `kill -INT`）时发送，应用程序据此正常退出并恢复终端
+    Interrupt,
+
     /// Tick事件
     ///
     /// 定时发送的事件，用于驱动动画和定期更新
@@ -244,6 +260,8 @@ pub struct Tui {
     pub mouse: bool,
     /// 是否启用粘贴支持
     pub paste: bool,
+    /// 是否使用备用屏幕缓冲区
+    pub alt_screen: bool,
 }
 
 impl Tui {
@@ -263,6 +281,7 @@ impl Tui {
         let task = tokio::spawn(async {});
         let mouse = false;
         let paste = false;
+        let alt_screen = true;
         Ok(Self {
             terminal,
             task,
@@ -273,6 +292,7 @@ impl Tui {
             tick_rate,
             mouse,
             paste,
+            alt_screen,
         })
     }
 
@@ -318,9 +338,19 @@ impl Tui {
         self
     }
 
+    /// 启用或禁用备用屏幕缓冲区
+    ///
+    /// # 参数
+    ///
+    /// * `alt_screen` - 为假时直接在主屏幕缓冲区绘制，退出后界面保留在终端中，便于调试渲染
+    pub fn alt_screen(mut self, alt_screen: bool) -> Self {
+        self.alt_screen = alt_screen;
+        self
+    }
+
     /// 启动异步事件循环
     ///
-    /// 创建后台任务来处理终端事件，包括键盘输入、鼠标操作和定时事件。
+    /// 创建后台任务来处理终端事件，包括键盘输入、鼠标操作、定时事件和 SIGINT 信号。
     /// 事件通过内部通道传递给主应用程序。
     pub fn start(&mut self) {
         let tick_delay = std::time::Duration::from_secs_f64(1.0 / self.tick_rate);
@@ -333,6 +363,9 @@ impl Tui {
             let mut reader = crossterm::event::EventStream::new();
             let mut tick_interval = tokio::time::interval(tick_delay);
             let mut render_interval = tokio::time::interval(render_delay);
+            // kept across iterations, a signal arriving while no listener exists would be lost
+            let mut interrupt = Box::pin(tokio::signal::ctrl_c());
+            let mut listening = true;
             _event_tx.send(Event::Init).unwrap();
             loop {
                 let tick_delay = tick_interval.tick();
@@ -342,6 +375,19 @@ impl Tui {
                   _ = _cancellation_token.cancelled() => {
                     break;
                   }
+                  // raw mode turns Ctrl+C into a key press, so this only sees signals from elsewhere
+                  result = &mut interrupt, if listening => {
+                    match result {
+                      Ok(()) => {
+                        _event_tx.send(Event::Interrupt).unwrap();
+                        interrupt = Box::pin(tokio::signal::ctrl_c());
+                      }
+                      Err(e) => {
+                        tracing::warn!("Failed to listen for SIGINT: {}", e);
+                        listening = false;
+                      }
+                    }
+                  }
                   maybe_event = crossterm_event => {
                     match maybe_event {
                       Some(Ok(evt)) => {
@@ -407,10 +453,14 @@ impl Tui {
 
     /// 进入TUI模式
     ///
-    /// 设置终端为原始模式，启用备用屏幕缓冲区，并启动事件循环。
+    /// 设置终端为原始模式，启用备用屏幕缓冲区（除非已禁用），并启动事件循环。
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(std::io::stderr(), EnterAlternateScreen, cursor::Hide)?;
+        ENTERED_ALT_SCREEN.store(self.alt_screen, Ordering::Relaxed);
+        if self.alt_screen {
+            crossterm::execute!(std::io::stderr(), EnterAlternateScreen)?;
+        }
+        crossterm::execute!(std::io::stderr(), cursor::Hide)?;
         if self.mouse {
             crossterm::execute!(std::io::stderr(), EnableMouseCapture)?;
         }
@@ -424,6 +474,7 @@ impl Tui {
     /// 退出TUI模式
     ///
     /// 停止事件循环，恢复终端设置，禁用备用屏幕缓冲区。
+    /// 未使用备用屏幕时，光标移到界面下方，使之后的输出不会覆盖界面。
     pub fn exit(&mut self) -> Result<()> {
         self.stop()?;
         if crossterm::terminal::is_raw_mode_enabled()? {
@@ -434,8 +485,17 @@ impl Tui {
             if self.mouse {
                 crossterm::execute!(std::io::stderr(), DisableMouseCapture)?;
             }
-            crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
+            if self.alt_screen {
+                crossterm::execute!(std::io::stderr(), LeaveAlternateScreen)?;
+            } else {
+                let height = self.terminal.size()?.height;
+                crossterm::execute!(std::io::stderr(), cursor::MoveTo(0, height))?;
+            }
+            crossterm::execute!(std::io::stderr(), cursor::Show)?;
             crossterm::terminal::disable_raw_mode()?;
+            if !self.alt_screen {
+                eprintln!();
+            }
         }
         Ok(())
     }
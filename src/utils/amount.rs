@@ -0,0 +1,235 @@
+//! # 金额格式化模块
+//!
+//! 将金额格式化为便于阅读的形式，如 `¥12,345.60`：
+//! - 整数部分每三位插入千位分隔符
+//! - 可配置货币符号与小数位数
+//! - 负数的负号位于货币符号之前，如 `-¥12.50`
+//!
+//! CSV 导出等机器可读的输出使用 [`format_amount_plain`]，同样保留两位小数，
+//! 但不带货币符号和千位分隔符。
+//!
+//! ## 精度
+//!
+//! 校园卡金额以元为单位、精确到分。`f64` 运算可能产生 `-15.500000001` 这样的误差，
+//! 因此交易在创建和写入数据库时都会经 [`round_amount`] 舍入到 [`AMOUNT_DECIMALS`] 位小数，
+//! 使相同的交易总是得到相同的金额和 `id`。
+//!
+//! ## 颜色
+//!
+//! 交易表格和分析图表按金额正负着色：充值等收入（正数）使用 [`INCOME_FG`]，
+//! 消费（负数）保持各处原有的颜色。统一通过 [`amount_style`] 设置。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::utils::amount::{AmountFormat, format_amount};
+//!
+//! assert_eq!(format_amount(-12345.6), "-¥12,345.60");
+//! assert_eq!(AmountFormat::default().decimals(0).format(1234.6), "¥1,235");
+//! assert_eq!(AmountFormat::default().symbol("$").format(1.0), "$1.00");
+//! assert_eq!(round_amount(-15.500000001), -15.5);
+//! assert_eq!(format_amount_plain(-15.5), "-15.50");
+//! ```
+
+use ratatui::style::{Color, Style, palette::tailwind};
+
+/// 收入（正数金额）的前景色
+pub(crate) const INCOME_FG: Color = tailwind::EMERALD.c300;
+
+/// 金额保存和展示的小数位数，即精确到分
+pub(crate) const AMOUNT_DECIMALS: usize = 2;
+
+/// 金额格式配置
+#[derive(Clone, Debug)]
+pub(crate) struct AmountFormat {
+    symbol: String,
+    decimals: usize,
+}
+
+impl Default for AmountFormat {
+    fn default() -> Self {
+        Self {
+            symbol: "¥".to_string(),
+            decimals: AMOUNT_DECIMALS,
+        }
+    }
+}
+
+impl AmountFormat {
+    /// 设置货币符号
+    pub fn symbol<T: Into<String>>(self, symbol: T) -> Self {
+        Self {
+            symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    /// 设置小数位数
+    pub fn decimals(self, decimals: usize) -> Self {
+        Self { decimals, ..self }
+    }
+
+    /// 按当前配置格式化金额
+    ///
+    /// # 参数
+    ///
+    /// * `amount` - 要格式化的金额
+    ///
+    /// # 返回值
+    ///
+    /// 带货币符号和千位分隔符的字符串。四舍五入后为零的值不带负号。
+    pub fn format(&self, amount: f64) -> String {
+        let rounded = format!("{:.*}", self.decimals, amount.abs());
+        let (int_part, frac_part) = match rounded.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (rounded.as_str(), None),
+        };
+
+        let mut grouped = String::with_capacity(int_part.len() + int_part.len() / 3);
+        for (i, c) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+
+        let is_zero = rounded.chars().all(|c| c == '0' || c == '.');
+        let sign = if amount < 0.0 && !is_zero { "-" } else { "" };
+
+        match frac_part {
+            Some(frac_part) => format!("{}{}{}.{}", sign, self.symbol, grouped, frac_part),
+            None => format!("{}{}{}", sign, self.symbol, grouped),
+        }
+    }
+}
+
+/// 使用默认配置（`¥`，两位小数）格式化金额
+pub(crate) fn format_amount(amount: f64) -> String {
+    AmountFormat::default().format(amount)
+}
+
+/// 将金额舍入到 [`AMOUNT_DECIMALS`] 位小数
+///
+/// 按十进制写法四舍五入（远离零），因此 `1.005` 舍入为 `1.01`，
+/// 尽管它的二进制近似值略小于 `1.005`。
+///
+/// # 参数
+///
+/// * `amount` - 要舍入的金额
+///
+/// # 返回值
+///
+/// 舍入后的金额。结果为零时总是返回 `0.0` 而不是 `-0.0`
+pub(crate) fn round_amount(amount: f64) -> f64 {
+    let scale = 10f64.powi(AMOUNT_DECIMALS as i32);
+    // Snap away the representation error first, e.g. 1.005 * 100 = 100.49999999999999,
+    // so that halves round the way they are written
+    let scaled = (amount * scale * 1e6).round() / 1e6;
+    // adding 0.0 turns -0.0 into 0.0
+    scaled.round() / scale + 0.0
+}
+
+/// 格式化为保留 [`AMOUNT_DECIMALS`] 位小数的纯数字，如 `-15.50`，用于 CSV 等机器可读的输出
+pub(crate) fn format_amount_plain(amount: f64) -> String {
+    format!("{:.*}", AMOUNT_DECIMALS, round_amount(amount))
+}
+
+/// 按金额正负调整样式
+///
+/// # 参数
+///
+/// * `style` - 消费时使用的样式
+/// * `amount` - 正数为收入，负数为消费
+///
+/// # 返回值
+///
+/// 收入时前景色改为 [`INCOME_FG`]，消费和零保持 `style` 不变
+pub(crate) fn amount_style(style: Style, amount: f64) -> Style {
+    if amount > 0.0 {
+        style.fg(INCOME_FG)
+    } else {
+        style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_default() {
+        assert_eq!(format_amount(12345.6), "¥12,345.60");
+        assert_eq!(format_amount(1234567.891), "¥1,234,567.89");
+        assert_eq!(format_amount(123.0), "¥123.00");
+        assert_eq!(format_amount(1000.0), "¥1,000.00");
+    }
+
+    #[test]
+    fn format_negative() {
+        assert_eq!(format_amount(-12345.6), "-¥12,345.60");
+        assert_eq!(format_amount(-0.5), "-¥0.50");
+        assert_eq!(format_amount(-999.999), "-¥1,000.00");
+    }
+
+    #[test]
+    fn format_zero() {
+        assert_eq!(format_amount(0.0), "¥0.00");
+        assert_eq!(format_amount(-0.0), "¥0.00");
+        assert_eq!(format_amount(-0.001), "¥0.00");
+    }
+
+    #[test]
+    fn round() {
+        assert_eq!(round_amount(-15.5), -15.5);
+        assert_eq!(round_amount(-15.500000001), -15.5);
+        assert_eq!(round_amount(-15.499999999), -15.5);
+        assert_eq!(round_amount(0.1 + 0.2), 0.3);
+        assert_eq!(round_amount(12.344), 12.34);
+        assert_eq!(round_amount(1e-10), 0.0);
+        assert_eq!(round_amount(123456.789), 123456.79);
+    }
+
+    #[test]
+    fn round_half_away_from_zero() {
+        // the binary value of each of these is slightly below the written half
+        assert_eq!(round_amount(1.005), 1.01);
+        assert_eq!(round_amount(2.675), 2.68);
+        assert_eq!(round_amount(-1.005), -1.01);
+        assert_eq!(round_amount(0.125), 0.13);
+        assert_eq!(round_amount(-0.125), -0.13);
+    }
+
+    #[test]
+    fn round_zero_is_positive() {
+        for amount in [-0.0, -0.001, -0.004999] {
+            let rounded = round_amount(amount);
+            assert_eq!(rounded, 0.0);
+            assert!(rounded.is_sign_positive(), "{}", amount);
+        }
+    }
+
+    #[test]
+    fn format_plain() {
+        assert_eq!(format_amount_plain(-15.5), "-15.50");
+        assert_eq!(format_amount_plain(-15.500000001), "-15.50");
+        assert_eq!(format_amount_plain(1234.0), "1234.00");
+        assert_eq!(format_amount_plain(1.005), "1.01");
+        assert_eq!(format_amount_plain(-0.001), "0.00");
+    }
+
+    #[test]
+    fn style_by_sign() {
+        let style = Style::default().fg(Color::Blue);
+        assert_eq!(amount_style(style, 12.5).fg, Some(INCOME_FG));
+        assert_eq!(amount_style(style, -12.5), style);
+        assert_eq!(amount_style(style, 0.0), style);
+    }
+
+    #[test]
+    fn format_custom() {
+        let format = AmountFormat::default().symbol("$").decimals(0);
+        assert_eq!(format.format(1234.6), "$1,235");
+        assert_eq!(format.format(-1234567.0), "-$1,234,567");
+        assert_eq!(AmountFormat::default().decimals(3).format(1.5), "¥1.500");
+    }
+}
@@ -0,0 +1,58 @@
+//! # 剪贴板模块
+//!
+//! 通过 OSC 52 终端转义序列将文本写入系统剪贴板。
+//! 由终端负责实际的剪贴板操作，因此无需额外的系统依赖，并且在 SSH 会话中同样可用。
+//!
+//! 并非所有终端都支持 OSC 52（部分终端需要在设置中手动开启），
+//! 而且终端不会回报是否成功，因此只能检测标准输出是否为终端。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::utils::clipboard;
+//!
+//! if let Err(e) = clipboard::copy("label\tvalue") {
+//!     // 剪贴板不可用，向用户展示错误即可
+//! }
+//! ```
+
+use std::io::{IsTerminal, Write};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+};
+
+/// 将文本复制到系统剪贴板
+///
+/// # 错误
+///
+/// 标准输出不是终端，或写入失败时返回错误
+pub(crate) fn copy(text: &str) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        bail!("Clipboard unavailable: not running in a terminal");
+    }
+    write_osc52(&mut stdout, text).context("Failed to write to the clipboard")
+}
+
+fn write_osc52<W: Write>(writer: &mut W, text: &str) -> std::io::Result<()> {
+    write!(writer, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_sequence() {
+        let mut out = Vec::new();
+        write_osc52(&mut out, "早餐\t3").unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b]52;c;5pep6aSQCTM=\x07"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! # 日期解析模块
+//!
+//! 统一解析命令行、界面和 Web API 中的日期，所有结果均为 UTC+8 时区。
+//!
+//! ## 日期区间
+//!
+//! 日期格式为 `YYYY-MM-DD`，区间与 [`FilterOptions`](crate::libs::transactions::FilterOptions)
+//! 一致，为左闭右开：
+//! - [`parse_day_start`]：当天 00:00:00，包含在区间内
+//! - [`parse_day_end`]：次日 00:00:00，不包含在区间内，因此结束日期当天的交易全部包含
+//! - [`parse_range`]：同时解析可选的起止日期
+//!
+//! ## 截止时间
+//!
+//! [`parse_cutoff`] 解析命令行中表示截止时间的参数，支持两种写法：
+//! - 绝对日期 `YYYY-MM-DD`：当天 00:00:00（UTC+8）
+//! - 相对天数 `Nd`：当前时间往前 N 天，如 `30d`
+//!
+//! ## 输入格式化
+//!
+//! [`format_date_input`] 供输入框在输入时使用，把连续的数字整理为 `YYYY-MM-DD`，
+//! 如 `20240901` 变为 `2024-09-01`。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::utils::date::{parse_cutoff, parse_range};
+//!
+//! let now = chrono::Local::now().fixed_offset();
+//! let cutoff = parse_cutoff("30d", now)?;
+//! assert_eq!(now - cutoff, chrono::Duration::days(30));
+//!
+//! // 2024-09-01 00:00:00 到 2024-10-01 00:00:00（不包含）
+//! let (start, end) = parse_range(Some("2024-09-01"), Some("2024-09-30"))?;
+//! ```
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use color_eyre::eyre::{Context, ContextCompat, Result, bail};
+
+use crate::libs::transactions::OFFSET_UTC_PLUS8;
+
+/// 左闭右开的时间区间，未指定的一端为 `None`
+pub(crate) type DateRange = (Option<DateTime<FixedOffset>>, Option<DateTime<FixedOffset>>);
+
+/// Parse a `YYYY-MM-DD` date
+fn parse_day(input: &str) -> Result<NaiveDate> {
+    let input = input.trim();
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date: {}, expected YYYY-MM-DD", input))
+}
+
+/// Midnight at the start of `date` in UTC+8
+pub(crate) fn start_of(date: NaiveDate) -> DateTime<FixedOffset> {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(OFFSET_UTC_PLUS8)
+        .unwrap()
+}
+
+/// 解析日期，返回当天开始的时间
+///
+/// # 参数
+///
+/// * `input` - `YYYY-MM-DD`
+///
+/// # 返回值
+///
+/// 当天 00:00:00（UTC+8），作为包含的起始时间
+pub(crate) fn parse_day_start(input: &str) -> Result<DateTime<FixedOffset>> {
+    parse_day(input).map(start_of)
+}
+
+/// 解析日期，返回当天结束的时间
+///
+/// # 参数
+///
+/// * `input` - `YYYY-MM-DD`
+///
+/// # 返回值
+///
+/// 次日 00:00:00（UTC+8），作为不包含的结束时间，使当天的交易全部在区间内
+pub(crate) fn parse_day_end(input: &str) -> Result<DateTime<FixedOffset>> {
+    let date = parse_day(input)?;
+    let next = date
+        .succ_opt()
+        .with_context(|| format!("Date out of range: {}", date))?;
+    Ok(start_of(next))
+}
+
+/// 解析可选的起止日期
+///
+/// # 参数
+///
+/// * `start` - 起始日期 `YYYY-MM-DD`，包含
+/// * `end` - 结束日期 `YYYY-MM-DD`，包含当天
+///
+/// # 返回值
+///
+/// 左闭右开的 `(起始时间, 结束时间)`，未指定的一端为 `None`。
+/// 任一日期格式错误，或起始日期晚于结束日期时返回错误
+pub(crate) fn parse_range(start: Option<&str>, end: Option<&str>) -> Result<DateRange> {
+    let start = start.map(parse_day_start).transpose()?;
+    let end = end.map(parse_day_end).transpose()?;
+    if let (Some(start), Some(end)) = (start, end)
+        && start >= end
+    {
+        bail!(
+            "Start date {} is after end date {}",
+            start.date_naive(),
+            (end - Duration::days(1)).date_naive()
+        );
+    }
+    Ok((start, end))
+}
+
+/// 输入日期时自动插入 `-`
+///
+/// 只有全部由数字组成、且已有的 `-` 都在第 5、8 个字符处时才整理，最多保留 8 位数字；
+/// 否则原样返回，如 `2024-9-1` 这种月、日只写一位的写法。
+///
+/// # 参数
+///
+/// * `input` - 输入框中的文本
+///
+/// # 返回值
+///
+/// 整理后的文本，如 `202409` 变为 `2024-09`。不会在末尾添加 `-`，以免删除时又被补回
+pub(crate) fn format_date_input(input: &str) -> String {
+    let canonical = input
+        .char_indices()
+        .all(|(i, c)| c.is_ascii_digit() || (c == '-' && (i == 4 || i == 7)));
+    if !canonical {
+        return input.to_string();
+    }
+    let mut formatted = String::with_capacity(10);
+    for (i, digit) in input
+        .chars()
+        .filter(char::is_ascii_digit)
+        .take(8)
+        .enumerate()
+    {
+        if i == 4 || i == 6 {
+            formatted.push('-');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// 解析截止时间
+///
+/// # 参数
+///
+/// * `input` - `YYYY-MM-DD` 或 `Nd`
+/// * `now` - 相对天数的参照时间
+///
+/// # 返回值
+///
+/// UTC+8 时区的截止时间
+pub(crate) fn parse_cutoff(
+    input: &str,
+    now: DateTime<FixedOffset>,
+) -> Result<DateTime<FixedOffset>> {
+    let input = input.trim();
+    if let Some(days) = input.strip_suffix('d') {
+        let days: u32 = days
+            .parse()
+            .with_context(|| format!("Invalid number of days: {}", input))?;
+        return Ok((now - Duration::days(days.into())).with_timezone(&OFFSET_UTC_PLUS8));
+    }
+    let Ok(cutoff) = parse_day_start(input) else {
+        bail!(
+            "Invalid date: {}, expected YYYY-MM-DD or a number of days like 30d",
+            input
+        );
+    };
+    Ok(cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn format_input() {
+        assert_eq!(format_date_input("20240901"), "2024-09-01");
+        assert_eq!(format_date_input("2024"), "2024");
+        assert_eq!(format_date_input("20240"), "2024-0");
+        assert_eq!(format_date_input("2024-090"), "2024-09-0");
+        assert_eq!(format_date_input("2024-09-01"), "2024-09-01");
+        // extra digits are dropped
+        assert_eq!(format_date_input("2024-09-011"), "2024-09-01");
+        // anything else is left as typed
+        assert_eq!(format_date_input("2024-9-1"), "2024-9-1");
+        assert_eq!(format_date_input("2024/09/01"), "2024/09/01");
+        assert_eq!(format_date_input(""), "");
+    }
+
+    #[test]
+    fn day_start() {
+        assert_eq!(
+            parse_day_start("2024-02-29").unwrap(),
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2024, 2, 29, 0, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            parse_day_start(" 2025-01-02 ").unwrap().to_rfc3339(),
+            "2025-01-02T00:00:00+08:00"
+        );
+    }
+
+    #[test]
+    fn day_end() {
+        assert_eq!(
+            parse_day_end("2024-02-28").unwrap(),
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2024, 2, 29, 0, 0, 0)
+                .unwrap()
+        );
+        // across month and year ends
+        assert_eq!(
+            parse_day_end("2024-12-31").unwrap().to_rfc3339(),
+            "2025-01-01T00:00:00+08:00"
+        );
+        assert!(parse_day_end("+262142-12-31").is_err());
+    }
+
+    #[test]
+    fn day_invalid() {
+        for input in [
+            "",
+            "2025-02-29",
+            "2025-13-01",
+            "2025/03/01",
+            "30d",
+            "20250301",
+        ] {
+            assert!(parse_day_start(input).is_err(), "{:?} should fail", input);
+            assert!(parse_day_end(input).is_err(), "{:?} should fail", input);
+        }
+    }
+
+    #[test]
+    fn range() {
+        let (start, end) = parse_range(Some("2025-03-01"), Some("2025-03-01")).unwrap();
+        assert_eq!(end.unwrap() - start.unwrap(), Duration::days(1));
+
+        assert_eq!(parse_range(None, None).unwrap(), (None, None));
+        assert_eq!(
+            parse_range(Some("2025-03-01"), None).unwrap(),
+            (Some(parse_day_start("2025-03-01").unwrap()), None)
+        );
+        assert_eq!(
+            parse_range(None, Some("2025-03-01")).unwrap(),
+            (None, Some(parse_day_start("2025-03-02").unwrap()))
+        );
+
+        let err = parse_range(Some("2025-03-02"), Some("2025-03-01")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Start date 2025-03-02 is after end date 2025-03-01"
+        );
+        assert!(parse_range(Some("2025-03-01"), Some("tomorrow")).is_err());
+        assert!(parse_range(Some("yesterday"), None).is_err());
+    }
+
+    #[test]
+    fn parse_date() {
+        let now = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 10, 8, 0, 0)
+            .unwrap();
+        assert_eq!(
+            parse_cutoff("2025-01-02", now).unwrap(),
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 1, 2, 0, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_days() {
+        let now = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 10, 8, 0, 0)
+            .unwrap();
+        assert_eq!(
+            parse_cutoff("30d", now).unwrap(),
+            OFFSET_UTC_PLUS8
+                .with_ymd_and_hms(2025, 2, 8, 8, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(parse_cutoff(" 0d ", now).unwrap(), now);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let now = OFFSET_UTC_PLUS8
+            .with_ymd_and_hms(2025, 3, 10, 8, 0, 0)
+            .unwrap();
+        for input in ["", "d", "-3d", "30", "2025-13-01", "30 days"] {
+            assert!(parse_cutoff(input, now).is_err(), "{:?} should fail", input);
+        }
+    }
+}
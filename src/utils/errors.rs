@@ -129,11 +129,7 @@ pub fn init() -> Result<()> {
         .into_hooks();
     eyre_hook.install()?;
     std::panic::set_hook(Box::new(move |panic_info| {
-        if let Ok(mut t) = crate::tui::Tui::new() {
-            if let Err(r) = t.exit() {
-                error!("Unable to exit Terminal: {:?}", r);
-            }
-        }
+        restore_terminal();
 
         #[cfg(not(debug_assertions))]
         {
@@ -163,6 +159,17 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+/// Leave raw mode and the alternate screen, if the TUI is active
+pub fn restore_terminal() {
+    if let Ok(t) = crate::tui::Tui::new() {
+        // exit the same way the TUI was entered
+        let mut t = t.alt_screen(crate::tui::entered_alt_screen());
+        if let Err(r) = t.exit() {
+            error!("Unable to exit Terminal: {:?}", r);
+        }
+    }
+}
+
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
@@ -0,0 +1,119 @@
+//! # 模糊匹配模块
+//!
+//! 为商家搜索提供模糊匹配与排序，容忍漏字和不连续的输入，
+//! 例如 `浴室` 和 `西浴` 都能匹配 `西14西15东12浴室`。
+//!
+//! ## 匹配规则
+//!
+//! 查询中的字符按顺序出现在候选项中即视为匹配（忽略 ASCII 大小写）。排序时：
+//!
+//! 1. 连续子串匹配优先于不连续匹配
+//! 2. 子串出现位置越靠前越好；不连续匹配中字符之间的间隔越小越好
+//! 3. 得分相同时，较短的候选项优先
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::utils::fuzzy;
+//!
+//! let merchants = ["七彩阁浴室", "西14西15东12浴室", "库迪咖啡"];
+//! assert_eq!(fuzzy::rank("浴室", merchants), vec!["七彩阁浴室", "西14西15东12浴室"]);
+//! ```
+
+/// Score of a contiguous match before subtracting its position
+const SUBSTRING_SCORE: i64 = 1_000_000;
+/// Score of an in-order but scattered match before subtracting the gaps
+const SUBSEQUENCE_SCORE: i64 = 0;
+
+/// 计算 `query` 与 `candidate` 的匹配得分
+///
+/// # 返回值
+///
+/// 得分越高越匹配；不匹配时返回 `None`。空查询匹配所有候选项
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let candidate: Vec<char> = candidate.chars().map(|c| c.to_ascii_lowercase()).collect();
+    if query.is_empty() {
+        return Some(SUBSTRING_SCORE);
+    }
+
+    if let Some(start) = candidate
+        .windows(query.len())
+        .position(|window| window == query.as_slice())
+    {
+        return Some(SUBSTRING_SCORE - start as i64);
+    }
+
+    let mut gaps = 0;
+    let mut last = None;
+    let mut rest = candidate.iter().enumerate();
+    for q in &query {
+        let (index, _) = rest.find(|(_, c)| *c == q)?;
+        if let Some(last) = last {
+            gaps += index - last - 1;
+        }
+        last = Some(index);
+    }
+    Some(SUBSEQUENCE_SCORE - gaps as i64)
+}
+
+/// 按匹配程度排序候选项，并去掉不匹配的项
+///
+/// # 参数
+///
+/// * `query` - 用户输入的查询
+/// * `candidates` - 候选项，如所有商家名称
+///
+/// # 返回值
+///
+/// 匹配的候选项，最匹配的在前
+pub fn rank<'a, I: IntoIterator<Item = &'a str>>(query: &str, candidates: I) -> Vec<&'a str> {
+    let mut matches: Vec<(i64, &str)> = candidates
+        .into_iter()
+        .filter_map(|c| score(query, c).map(|s| (s, c)))
+        .collect();
+    matches.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then(a.chars().count().cmp(&b.chars().count()))
+            .then(a.cmp(b))
+    });
+    matches.into_iter().map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_match() {
+        assert!(score("浴室", "西14西15东12浴室").is_some());
+        assert!(score("西浴", "西14西15东12浴室").is_some());
+        assert!(score("ABC", "abc超市").is_some());
+        assert!(score("", "寿司").is_some());
+        assert!(score("室浴", "西14西15东12浴室").is_none());
+        assert!(score("咖啡厅", "库迪咖啡").is_none());
+
+        // contiguous beats scattered, earlier beats later
+        assert!(score("浴室", "浴室") > score("浴室", "七彩阁浴室"));
+        assert!(score("浴室", "七彩阁浴室") > score("浴室", "浴a室"));
+        assert!(score("浴室", "浴a室") > score("浴室", "浴aa室"));
+    }
+
+    #[test]
+    fn rank_mock_merchants() {
+        let merchants: Vec<String> = crate::libs::fetcher::test_utils::get_mock_data(200)
+            .into_iter()
+            .map(|t| t.merchant)
+            .collect();
+        let ranked = rank("浴室", merchants.iter().map(String::as_str));
+        assert!(ranked.iter().all(|m| m.contains("浴室")));
+        assert!(ranked.contains(&"西14西15东12浴室"));
+
+        assert_eq!(
+            rank("饭", ["快餐盒饭", "牛肉饭", "鸡排饭组", "寿司"]),
+            vec!["牛肉饭", "鸡排饭组", "快餐盒饭"]
+        );
+        assert_eq!(rank("快饭", ["快餐盒饭", "牛肉饭"]), vec!["快餐盒饭"]);
+    }
+}
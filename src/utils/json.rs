@@ -0,0 +1,59 @@
+//! # JSON 输出格式
+//!
+//! Web API 和命令行输出的 JSON 统一经过 [`to_json`] 序列化，默认带缩进便于阅读，
+//! 也可以改为紧凑格式以减少数据量：
+//!
+//! - Web API：在任意返回 JSON 的接口上加 `?pretty=false`
+//! - 命令行：`--print-config` 和 `--version-json` 配合 `--compact-json`
+
+use serde::Serialize;
+
+/// 序列化为 JSON 字符串
+///
+/// # 参数
+///
+/// * `value` - 要序列化的值
+/// * `pretty` - 为真时带缩进和换行，否则输出单行紧凑格式
+///
+/// # 返回值
+///
+/// JSON 字符串，序列化失败时返回错误
+pub(crate) fn to_json<T: Serialize + ?Sized>(
+    value: &T,
+    pretty: bool,
+) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+/// 解析 `pretty` 参数，`false` 和 `0` 表示紧凑格式，其他值（包括未指定）都使用缩进格式
+pub(crate) fn parse_pretty(value: Option<&str>) -> bool {
+    !matches!(value, Some("false" | "0"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let value = serde_json::json!({ "a": [1, 2] });
+        assert_eq!(to_json(&value, false).unwrap(), r#"{"a":[1,2]}"#);
+        assert_eq!(
+            to_json(&value, true).unwrap(),
+            "{\n  \"a\": [\n    1,\n    2\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn pretty_param() {
+        assert!(parse_pretty(None));
+        assert!(parse_pretty(Some("true")));
+        assert!(parse_pretty(Some("yes")));
+        assert!(!parse_pretty(Some("false")));
+        assert!(!parse_pretty(Some("0")));
+    }
+}
@@ -9,7 +9,11 @@ lazy_static::lazy_static! {
     pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
-pub fn init() -> Result<()> {
+/// Initialize file logging
+///
+/// `level` comes from the command line and takes precedence over the environment variables.
+/// Without it, `RUST_LOG` or `LOG_ENV` is used, defaulting to `INFO`.
+pub fn init(level: Option<tracing::Level>) -> Result<()> {
     let directory = config::get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
     let log_path = directory.join(LOG_FILE.clone());
@@ -18,9 +22,12 @@ pub fn init() -> Result<()> {
     // If the `RUST_LOG` environment variable is set, use that as the default, otherwise use the
     // value of the `LOG_ENV` environment variable. If the `LOG_ENV` environment variable contains
     // errors, then this will return an error.
-    let env_filter = env_filter
-        .try_from_env()
-        .or_else(|_| env_filter.with_env_var(LOG_ENV.clone()).from_env())?;
+    let env_filter = match level {
+        Some(level) => EnvFilter::new(level.to_string()),
+        None => env_filter
+            .try_from_env()
+            .or_else(|_| env_filter.with_env_var(LOG_ENV.clone()).from_env())?,
+    };
     let file_subscriber = fmt::layer()
         .with_file(true)
         .with_line_number(true)
@@ -1,9 +1,14 @@
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use strum::Display;
+use unicode_normalization::UnicodeNormalization;
 
-#[derive(Display, Debug, Clone, PartialEq, Eq, Hash)]
-pub(crate) enum MerchantType {
+/// 商家类别
+///
+/// 显示名称同时也是数据库 `category` 列中保存的值
+#[derive(Display, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MerchantType {
     #[strum(to_string = "食堂食物")]
     CanteenFood,
     #[strum(to_string = "食堂饮品")]
@@ -14,12 +19,13 @@ pub(crate) enum MerchantType {
     Bathhouse,
     #[strum(to_string = "其他")]
     Other,
+    #[strum(to_string = "未知")]
     Unknown,
 }
 
 impl MerchantType {
     /// Converts a type string (like "食堂食物") to a MerchantType variant.
-    fn from_type_str(s: &str) -> Self {
+    pub fn from_type_str(s: &str) -> Self {
         match s {
             "食堂食物" => Self::CanteenFood,
             "食堂饮品" => Self::CanteenDrink,
@@ -36,6 +42,28 @@ impl MerchantType {
     }
 }
 
+/// 按内置的分类表判断商家类别，不在表中的商家为 [`MerchantType::Unknown`]
+pub(crate) fn classify_merchant(merchant_name: &str) -> MerchantType {
+    MerchantType::from_str(merchant_name)
+}
+
+/// 统一商家名称的写法，用于合并只有空格或全半角差异的商家
+///
+/// 先做 NFKC 规范化（全角字母、数字、标点和全角空格转为半角），
+/// 再去掉首尾空白，并将连续空白合并为一个空格。
+///
+/// # 参数
+///
+/// * `name` - 原始商家名称
+///
+/// # 返回值
+///
+/// 规范化后的名称，写法相同的名称结果相同
+pub(crate) fn normalize_merchant_name(name: &str) -> String {
+    let folded: String = name.nfkc().collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 #[derive(Debug)]
 struct MerchantTypeData {
     data: HashMap<String, MerchantType>,
@@ -106,6 +134,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize_merchant_name() {
+        assert_eq!(normalize_merchant_name("  梧桐苑餐厅 "), "梧桐苑餐厅");
+        assert_eq!(normalize_merchant_name("东区 \t 浴室"), "东区 浴室");
+        assert_eq!(normalize_merchant_name("梧桐苑（二楼）"), "梧桐苑(二楼)");
+        assert_eq!(normalize_merchant_name("ＡＢＣ\u{3000}超市"), "ABC 超市");
+        assert_eq!(normalize_merchant_name("东区浴室-和风"), "东区浴室-和风");
+        // half-width katakana and circled digits are folded as well
+        assert_eq!(normalize_merchant_name("ｶﾌｪ①"), "カフェ1");
+    }
+
     #[test]
     fn test_type_str_conversion() {
         assert_eq!(
@@ -120,5 +159,12 @@ mod tests {
             MerchantType::from_type_str("InvalidType"),
             MerchantType::Unknown
         );
+        // the display name round-trips, as it is what the database stores
+        for merchant_type in [MerchantType::Bathhouse, MerchantType::Unknown] {
+            assert_eq!(
+                MerchantType::from_type_str(&merchant_type.to_string()),
+                merchant_type
+            );
+        }
     }
 }
@@ -0,0 +1,158 @@
+//! # 商家名称规范化模块
+//!
+//! 同一个商家在不同学期可能以完全不同的名称出现（如“康桥苑一楼”与“康桥苑”），
+//! 导致统计时被拆成多个商家。本模块在分析前按别名表将名称映射为规范名称。
+//!
+//! 别名表通过 `--merchant-alias 别名=规范名称` 配置，可重复指定。
+//! 查找别名前，名称会先经过 [`normalize_merchant_name`] 处理，
+//! 因此无需为空格或全半角差异单独配置。不在别名表中的名称保持原样，
+//! 只有空格或全半角差异的名称由商家分析页按需合并。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use crate::utils::merchant_normalize::{MerchantAlias, MerchantAliases};
+//!
+//! let aliases = MerchantAliases::new(["康桥苑一楼=康桥苑".parse::<MerchantAlias>()?]);
+//! assert_eq!(aliases.normalize(" 康桥苑一楼 "), "康桥苑");
+//! assert_eq!(aliases.normalize("梧桐苑（二楼）"), "梧桐苑（二楼）");
+//! ```
+
+use std::{collections::HashMap, str::FromStr};
+
+use color_eyre::eyre::{Report, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{libs::transactions::Transaction, utils::merchant_class::normalize_merchant_name};
+
+/// 一条别名配置，将 `alias` 映射为 `canonical`
+///
+/// 字符串形式为 `alias=canonical`。
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct MerchantAlias {
+    pub alias: String,
+    pub canonical: String,
+}
+
+impl FromStr for MerchantAlias {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((alias, canonical)) = s.split_once('=') else {
+            bail!("Invalid merchant alias \"{}\", expected ALIAS=CANONICAL", s);
+        };
+        let (alias, canonical) = (
+            normalize_merchant_name(alias),
+            normalize_merchant_name(canonical),
+        );
+        if alias.is_empty() || canonical.is_empty() {
+            bail!("Invalid merchant alias \"{}\", names must not be empty", s);
+        }
+        Ok(Self { alias, canonical })
+    }
+}
+
+impl TryFrom<String> for MerchantAlias {
+    type Error = Report;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MerchantAlias> for String {
+    fn from(value: MerchantAlias) -> Self {
+        format!("{}={}", value.alias, value.canonical)
+    }
+}
+
+/// 商家别名表
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct MerchantAliases {
+    map: HashMap<String, String>,
+}
+
+impl MerchantAliases {
+    /// 由别名配置构建别名表，同一别名出现多次时以最后一次为准
+    pub fn new<I: IntoIterator<Item = MerchantAlias>>(aliases: I) -> Self {
+        Self {
+            map: aliases
+                .into_iter()
+                .map(|a| (a.alias, a.canonical))
+                .collect(),
+        }
+    }
+
+    /// 返回规范化后的商家名称
+    ///
+    /// # 参数
+    ///
+    /// * `merchant` - 原始商家名称
+    ///
+    /// # 返回值
+    ///
+    /// 若在别名表中，返回对应的规范名称，否则返回原始名称
+    pub fn normalize(&self, merchant: &str) -> String {
+        match self.map.get(&normalize_merchant_name(merchant)) {
+            Some(canonical) => canonical.clone(),
+            None => merchant.to_string(),
+        }
+    }
+
+    /// 规范化一组交易记录的商家名称
+    pub fn apply(&self, transactions: Vec<Transaction>) -> Vec<Transaction> {
+        transactions
+            .into_iter()
+            .map(|t| Transaction {
+                merchant: self.normalize(&t.merchant),
+                ..t
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_alias() {
+        let alias: MerchantAlias = " 康桥苑一楼 = 康桥苑".parse().unwrap();
+        assert_eq!(alias.alias, "康桥苑一楼");
+        assert_eq!(alias.canonical, "康桥苑");
+        assert_eq!(String::from(alias), "康桥苑一楼=康桥苑");
+
+        assert!("康桥苑".parse::<MerchantAlias>().is_err());
+        assert!("=康桥苑".parse::<MerchantAlias>().is_err());
+        assert!("康桥苑= ".parse::<MerchantAlias>().is_err());
+    }
+
+    #[test]
+    fn normalize() {
+        let aliases = MerchantAliases::new([
+            "康桥苑一楼=康桥苑".parse().unwrap(),
+            "梧桐苑(二楼)=梧桐苑".parse().unwrap(),
+        ]);
+        assert_eq!(aliases.normalize("康桥苑一楼 "), "康桥苑");
+        assert_eq!(aliases.normalize("梧桐苑（二楼）"), "梧桐苑");
+        assert_eq!(aliases.normalize(" 文治书院超市"), " 文治书院超市");
+        assert_eq!(
+            MerchantAliases::default().normalize("康桥苑一楼"),
+            "康桥苑一楼"
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let aliases = MerchantAliases::new(["康桥苑一楼=康桥苑".parse().unwrap()]);
+        let transactions = vec![
+            Transaction::from_local(-10.0, "康桥苑一楼", "2025-03-01 12:00:00").unwrap(),
+            Transaction::from_local(-12.0, "康桥苑", "2025-03-02 12:00:00").unwrap(),
+        ];
+        let normalized = aliases.apply(transactions.clone());
+        assert!(normalized.iter().all(|t| t.merchant == "康桥苑"));
+        assert_eq!(normalized[0].amount, transactions[0].amount);
+        assert_eq!(normalized[0].time, transactions[0].time);
+    }
+}
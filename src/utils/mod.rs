@@ -7,16 +7,38 @@
 //!
 //! ```text
 //! utils/
+//! ├── amount.rs         - 金额格式化
+//! ├── clipboard.rs      - 通过 OSC 52 写入剪贴板
+//! ├── date.rs           - 截止时间参数解析
 //! ├── errors.rs         - 错误处理和 Panic Hook 配置
+//! ├── fuzzy.rs          - 商家名称模糊匹配与排序
 //! ├── help_msg.rs       - 帮助信息显示系统
+//! ├── json.rs           - JSON 输出格式
 //! ├── key_events.rs     - 键盘事件处理工具
 //! ├── logging.rs        - 日志记录配置
 //! ├── merchant_class.rs - 商家分类和识别
+//! ├── merchant_normalize.rs - 商家名称规范化与别名
 //! └── mod.rs           - 模块导出声明
 //! ```
 //!
 //! ## 功能模块说明
 //!
+//! ### 金额格式化 (`amount`)
+//! 金额的统一精度与展示格式：
+//! - 千位分隔符与货币符号
+//! - 可配置小数位数
+//! - 写入数据库前舍入到分，导出时固定两位小数
+//!
+//! ### 剪贴板 (`clipboard`)
+//! 通过终端转义序列复制文本：
+//! - 无需系统剪贴板依赖，SSH 会话中同样可用
+//! - 非终端环境下返回错误，由调用方提示用户
+//!
+//! ### 日期解析 (`date`)
+//! 命令行截止时间参数：
+//! - 绝对日期 `YYYY-MM-DD`
+//! - 相对天数 `Nd`，如 `30d`
+//!
 //! ### 错误处理 (`errors`)
 //! 配置全局错误处理机制，包括：
 //! - Color-eyre 错误报告
@@ -24,12 +46,22 @@
 //! - 终端恢复逻辑
 //! - 调试和生产环境的不同处理策略
 //!
+//! ### 模糊匹配 (`fuzzy`)
+//! 商家搜索的模糊匹配：
+//! - 按顺序包含查询字符即匹配，容忍漏字
+//! - 连续匹配、靠前匹配优先
+//!
 //! ### 帮助系统 (`help_msg`)
 //! 提供统一的帮助信息显示功能：
 //! - 快捷键说明格式化
 //! - 帮助信息组合和扩展
 //! - TUI 帮助面板渲染
 //!
+//! ### JSON 输出 (`json`)
+//! Web API 与命令行共用的 JSON 序列化：
+//! - 默认带缩进，便于阅读和调试
+//! - 可切换为紧凑格式，减少数据量
+//!
 //! ### 键盘事件 (`key_events`)
 //! 键盘输入的标准化处理：
 //! - 按键事件封装
@@ -48,6 +80,11 @@
 //! - 分类规则定义
 //! - 统计分析支持
 //!
+//! ### 商家名称规范化 (`merchant_normalize`)
+//! 分析前统一商家名称：
+//! - 清理空白与全角字符
+//! - 按配置的别名表合并同一商家
+//!
 //! ## 编译条件
 //!
 //! 部分模块使用条件编译：
@@ -72,10 +109,16 @@
 //! let category = classify_merchant("梧桐苑餐厅");
 //! ```
 
+pub(crate) mod amount;
+pub(crate) mod clipboard;
+pub(crate) mod date;
 #[cfg(not(tarpaulin_include))]
 pub(crate) mod errors;
+pub(crate) mod fuzzy;
 pub(crate) mod help_msg;
+pub(crate) mod json;
 pub(crate) mod key_events;
 #[cfg(not(tarpaulin_include))]
 pub(crate) mod logging;
 pub(crate) mod merchant_class;
+pub(crate) mod merchant_normalize;